@@ -45,6 +45,9 @@ pub struct FuncDecl {
 #[derive(Debug, Clone, PartialEq)]
 pub struct ClassDecl {
     pub name: String,
+    pub parent: Option<String>,  // cls Puppy : Dog
+    pub fields: Vec<VarDecl>,  // Declared fields: int age = 0
+    pub constants: Vec<ConstDecl>, // Class constants: const PI := 3.14
     pub constructor: Option<CtorDecl>,
     pub methods: Vec<MethodDecl>,
     pub span: Span,
@@ -74,7 +77,14 @@ pub struct MethodDecl {
 /// Import declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImportDecl {
-    pub modules: Vec<String>,  // import (a, b, c)
+    pub modules: Vec<ImportItem>,  // import (a, b, c)
     pub span: Span,
 }
 
+/// One module named by an import: `net.http` or `math as m`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportItem {
+    pub path: String,  // Dotted, as written
+    pub alias: Option<String>,
+}
+
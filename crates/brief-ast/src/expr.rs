@@ -18,6 +18,11 @@ pub enum Expr {
         member: String,
         span: Span,
     },
+    SafeMemberAccess {
+        object: Box<Expr>,  // obj?.field: null when obj is null
+        member: String,
+        span: Span,
+    },
     Index {
         object: Box<Expr>,
         index: Box<Expr>,
@@ -54,6 +59,15 @@ pub enum Expr {
         args: Vec<Expr>,
         span: Span,
     },
+    Spread {
+        expr: Box<Expr>,  // Array expanded into call arguments: f(xs...)
+        span: Span,
+    },
+    NamedArg {
+        name: String,     // Argument passed by parameter name: f(loud: true)
+        value: Box<Expr>,
+        span: Span,
+    },
     
     // Type casting
     Cast {
@@ -68,6 +82,14 @@ pub enum Expr {
         span: Span,
     },
     
+    // Range: start..end, or start..=end to include the end
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+        span: Span,
+    },
+    
     // Ternary
     Ternary {
         condition: Box<Expr>,
@@ -76,10 +98,10 @@ pub enum Expr {
         span: Span,
     },
     
-    // Lambda
+    // Lambda: (params) := expr, or (params) := followed by an indented block
     Lambda {
         params: Vec<Param>,
-        body: Box<Expr>,  // Single expression or block
+        body: LambdaBody,
         span: Span,
     },
     
@@ -100,14 +122,18 @@ impl Expr {
             Expr::Variable(_, span) |
             Expr::Error(span) => *span,
             Expr::MemberAccess { span, .. } |
+            Expr::SafeMemberAccess { span, .. } |
             Expr::Index { span, .. } |
             Expr::BinaryOp { span, .. } |
             Expr::UnaryOp { span, .. } |
             Expr::PostfixOp { span, .. } |
             Expr::Call { span, .. } |
             Expr::MethodCall { span, .. } |
+            Expr::Spread { span, .. } |
+            Expr::NamedArg { span, .. } |
             Expr::Cast { span, .. } |
             Expr::Interpolation { span, .. } |
+            Expr::Range { span, .. } |
             Expr::Ternary { span, .. } |
             Expr::Lambda { span, .. } => *span,
         }
@@ -129,8 +155,12 @@ pub enum BinaryOp {
     Add, Sub, Mul, Div, Mod, Pow,
     // Comparison
     Eq, Ne, Lt, Le, Gt, Ge,
+    // Membership: x in xs
+    In,
     // Logical
     And, Or,
+    // Null-coalescing: a ?? b
+    NullCoalesce,
     // Bitwise
     BitAnd, BitOr, BitXor,
     // Shift
@@ -155,11 +185,20 @@ pub enum PostfixOp {
     Dec,  // --
 }
 
+/// Body of a lambda expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum LambdaBody {
+    Expr(Box<Expr>),             // (x) := x + 1
+    Block(crate::stmt::Block),   // (x) := NEWLINE INDENT statements
+}
+
 /// Function parameter (used in lambdas and function declarations)
 #[derive(Debug, Clone, PartialEq)]
 pub struct Param {
     pub name: String,
     pub type_annotation: Option<crate::ty::Type>,
+    pub default: Option<Expr>,  // Default value: def greet(name, greeting = "Hello")
+    pub variadic: bool,         // Collects remaining arguments: def log(fmt, args...)
     pub span: Span,
 }
 
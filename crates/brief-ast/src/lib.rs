@@ -9,3 +9,8 @@ pub use stmt::*;
 pub use decl::*;
 pub use ty::*;
 pub use program::*;
+
+/// Prefix of names the compiler generates during desugaring. The parser
+/// rejects identifiers starting with it, so generated names never collide
+/// with user code
+pub const GENERATED_NAME_PREFIX: &str = "__temp_";
@@ -19,6 +19,8 @@ pub enum Stmt {
     While {
         condition: Expr,
         body: Block,
+        else_branch: Option<Block>,  // Runs only if the body never ran
+        label: Option<String>,  // `outer: while ...`, for `break outer`
         span: Span,
     },
     For {
@@ -26,12 +28,20 @@ pub enum Stmt {
         condition: Option<Expr>,
         increment: Option<Expr>,
         body: Block,
+        label: Option<String>,
         span: Span,
     },
     ForIn {
         var: String,
         iterable: Expr,
         body: Block,
+        label: Option<String>,
+        span: Span,
+    },
+    Repeat {
+        count: Expr,
+        body: Block,
+        label: Option<String>,
         span: Span,
     },
     Match {
@@ -46,8 +56,17 @@ pub enum Stmt {
         value: Option<Expr>,
         span: Span,
     },
-    Break(Span),
-    Continue(Span),
+    /// `break`, or `break outer` to leave the loop labeled `outer`
+    Break {
+        label: Option<String>,
+        span: Span,
+    },
+    Continue {
+        label: Option<String>,
+        span: Span,
+    },
+    /// `fall`: ends a match case by running on into the next case's body
+    Fall(Span),
     
     // Expression statement
     Expr(Expr, Span),
@@ -67,6 +86,7 @@ pub struct Block {
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchCase {
     pub patterns: Vec<Expr>,  // Multiple patterns allowed: case 'A', 'B'
+    pub guard: Option<Expr>,  // Optional refinement: case 'A' if cond
     pub body: Block,
     pub span: Span,
 }
@@ -1,4 +1,4 @@
-use brief_ast::{Expr, BinaryOp, Param};
+use brief_ast::{Expr, BinaryOp, LambdaBody, Param};
 use brief_diagnostic::{FileId, Position, Span};
 
 fn span(line: u32, column: u32) -> Span {
@@ -37,13 +37,15 @@ fn lambda_param_metadata_is_retained() {
     let param = Param {
         name: "value".into(),
         type_annotation: None,
+        default: None,
+        variadic: false,
         span: param_span,
     };
     let body_span = span(2, 10);
     let lambda_span = Span::new(FileId(0), Position::new(2, 1), Position::new(2, 12));
     let lambda = Expr::Lambda {
         params: vec![param.clone()],
-        body: Box::new(Expr::Variable("value".into(), body_span)),
+        body: LambdaBody::Expr(Box::new(Expr::Variable("value".into(), body_span))),
         span: lambda_span,
     };
     assert_eq!(lambda.span(), lambda_span);
@@ -0,0 +1,150 @@
+/// A builtin function: its name and how many arguments it takes. The
+/// resolver accepts exactly these names, and the runtime checks a call's
+/// arity against them before running the builtin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinSpec {
+    pub name: &'static str,
+    pub min_args: usize,
+    pub max_args: Option<usize>, // None: any number past min_args
+}
+
+impl BuiltinSpec {
+    pub const fn exactly(name: &'static str, count: usize) -> Self {
+        Self { name, min_args: count, max_args: Some(count) }
+    }
+
+    pub const fn between(name: &'static str, min_args: usize, max_args: usize) -> Self {
+        Self { name, min_args, max_args: Some(max_args) }
+    }
+
+    pub const fn at_least(name: &'static str, min_args: usize) -> Self {
+        Self { name, min_args, max_args: None }
+    }
+
+    /// Whether a call may pass `count` arguments
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_args && self.max_args.is_none_or(|max| count <= max)
+    }
+
+    /// The error for a call passing `count` arguments, e.g.
+    /// "substring takes 2 or 3 arguments, got 1"
+    pub fn arity_message(&self, count: usize) -> String {
+        arity_message(self.name, self.min_args, self.max_args, count)
+    }
+}
+
+/// The error for a call to `name` passing `count` arguments when it takes
+/// `min_args` up to `max_args`, worded as every builtin words it
+pub fn arity_message(name: &str, min_args: usize, max_args: Option<usize>, count: usize) -> String {
+    let expected = match (min_args, max_args) {
+        (0, Some(0)) => "no arguments".to_string(),
+        (min, Some(max)) if min == max => arguments(min),
+        (0, Some(max)) => format!("at most {}", arguments(max)),
+        (min, Some(max)) if max == min + 1 => format!("{} or {} arguments", min, max),
+        (min, Some(max)) => format!("{} to {} arguments", min, max),
+        (min, None) => format!("at least {}", arguments(min)),
+    };
+    format!("{} takes {}, got {}", name, expected, count)
+}
+
+fn arguments(count: usize) -> String {
+    format!("{} argument{}", count, if count == 1 { "" } else { "s" })
+}
+
+/// Every builtin. `rt_` builtins are only called by desugared code
+pub const BUILTINS: &[BuiltinSpec] = &[
+    // Core
+    BuiltinSpec::at_least("print", 0),
+    BuiltinSpec::at_least("println", 0),
+    BuiltinSpec::at_least("prints", 0),
+    BuiltinSpec::at_least("format", 1),
+    BuiltinSpec::between("input", 0, 1),
+    BuiltinSpec::exactly("len", 1),
+    BuiltinSpec::exactly("contains", 2),
+    BuiltinSpec::between("exit", 0, 1),
+    BuiltinSpec::between("assert", 1, 2),
+    BuiltinSpec::exactly("check", 2),
+    BuiltinSpec::exactly("panic", 1),
+    // Time and randomness
+    BuiltinSpec::exactly("clock", 0),
+    BuiltinSpec::exactly("time_ms", 0),
+    BuiltinSpec::exactly("now", 0),
+    BuiltinSpec::exactly("sleep_ms", 1),
+    BuiltinSpec::exactly("random", 0),
+    BuiltinSpec::exactly("random_int", 2),
+    BuiltinSpec::exactly("seed_random", 1),
+    // Files
+    BuiltinSpec::exactly("open", 2),
+    BuiltinSpec::exactly("read", 1),
+    BuiltinSpec::exactly("write", 2),
+    BuiltinSpec::exactly("close", 1),
+    BuiltinSpec::exactly("read_file", 1),
+    BuiltinSpec::exactly("write_file", 2),
+    BuiltinSpec::exactly("append_file", 2),
+    BuiltinSpec::exactly("file_exists", 1),
+    // Process
+    BuiltinSpec::exactly("env", 1),
+    BuiltinSpec::exactly("cwd", 0),
+    // Casts and reflection
+    BuiltinSpec::exactly("int", 1),
+    BuiltinSpec::exactly("dub", 1),
+    BuiltinSpec::exactly("str", 1),
+    BuiltinSpec::exactly("parseInt", 1),
+    BuiltinSpec::exactly("parseDouble", 1),
+    BuiltinSpec::exactly("type", 1),
+    // Math
+    BuiltinSpec::exactly("sin", 1),
+    BuiltinSpec::exactly("cos", 1),
+    BuiltinSpec::exactly("sqrt", 1),
+    BuiltinSpec::exactly("abs", 1),
+    BuiltinSpec::exactly("floor", 1),
+    BuiltinSpec::exactly("ceil", 1),
+    BuiltinSpec::exactly("round", 1),
+    BuiltinSpec::between("log", 1, 2),
+    // Strings
+    BuiltinSpec::exactly("upper", 1),
+    BuiltinSpec::exactly("lower", 1),
+    BuiltinSpec::exactly("trim", 1),
+    BuiltinSpec::exactly("split", 2),
+    BuiltinSpec::exactly("replace", 3),
+    BuiltinSpec::exactly("starts_with", 2),
+    BuiltinSpec::exactly("index_of", 2),
+    BuiltinSpec::between("substring", 2, 3),
+    // Arrays
+    BuiltinSpec::between("sort", 1, 2),
+    BuiltinSpec::exactly("map", 2),
+    BuiltinSpec::exactly("filter", 2),
+    BuiltinSpec::exactly("reduce", 3),
+    BuiltinSpec::exactly("push", 2),
+    BuiltinSpec::exactly("pop", 1),
+    BuiltinSpec::exactly("insert", 3),
+    BuiltinSpec::exactly("remove", 2),
+    BuiltinSpec::exactly("reverse", 1),
+    BuiltinSpec::exactly("join", 2),
+    BuiltinSpec::between("range", 1, 3),
+    // Maps
+    BuiltinSpec::at_least("map_of", 0),
+    BuiltinSpec::between("get", 2, 3),
+    BuiltinSpec::exactly("put", 3),
+    BuiltinSpec::exactly("has", 2),
+    BuiltinSpec::exactly("keys", 1),
+    BuiltinSpec::exactly("values", 1),
+    BuiltinSpec::exactly("merge", 2),
+    // Desugaring helpers
+    BuiltinSpec::exactly("rt_concat2", 2),
+    BuiltinSpec::exactly("rt_concat3", 3),
+    BuiltinSpec::exactly("rt_concat4", 4),
+    BuiltinSpec::exactly("rt_concat5", 5),
+    BuiltinSpec::exactly("rt_range", 3),
+    BuiltinSpec::exactly("rt_range_step", 3),
+    BuiltinSpec::exactly("rt_repeat_count", 1),
+];
+
+/// The spec of the builtin called `name`, if there is one
+pub fn lookup(name: &str) -> Option<&'static BuiltinSpec> {
+    BUILTINS.iter().find(|spec| spec.name == name)
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    lookup(name).is_some()
+}
@@ -0,0 +1,34 @@
+use std::collections::HashSet;
+use brief_builtins::{is_builtin, lookup, BuiltinSpec, BUILTINS};
+
+#[test]
+fn test_arity_messages() {
+    let message = |spec: BuiltinSpec, count| spec.arity_message(count);
+    assert_eq!(message(BuiltinSpec::exactly("clock", 0), 1), "clock takes no arguments, got 1");
+    assert_eq!(message(BuiltinSpec::exactly("len", 1), 0), "len takes 1 argument, got 0");
+    assert_eq!(message(BuiltinSpec::exactly("put", 3), 2), "put takes 3 arguments, got 2");
+    assert_eq!(message(BuiltinSpec::between("exit", 0, 1), 2), "exit takes at most 1 argument, got 2");
+    assert_eq!(message(BuiltinSpec::between("get", 2, 3), 4), "get takes 2 or 3 arguments, got 4");
+    assert_eq!(message(BuiltinSpec::between("f", 1, 4), 0), "f takes 1 to 4 arguments, got 0");
+    assert_eq!(message(BuiltinSpec::at_least("print", 1), 0), "print takes at least 1 argument, got 0");
+}
+
+#[test]
+fn test_accepts_counts_within_bounds() {
+    let get = BuiltinSpec::between("get", 2, 3);
+    assert!(!get.accepts(1) && get.accepts(2) && get.accepts(3) && !get.accepts(4));
+    let print = BuiltinSpec::at_least("print", 1);
+    assert!(!print.accepts(0) && print.accepts(1) && print.accepts(50));
+}
+
+#[test]
+fn test_registry_names_are_unique_and_found() {
+    let mut seen = HashSet::new();
+    for spec in BUILTINS {
+        assert!(seen.insert(spec.name), "{} is registered twice", spec.name);
+        assert!(spec.max_args.is_none_or(|max| max >= spec.min_args), "{} has max below min", spec.name);
+        assert_eq!(lookup(spec.name), Some(spec));
+    }
+    assert!(is_builtin("print"));
+    assert!(!is_builtin("no_such_builtin"));
+}
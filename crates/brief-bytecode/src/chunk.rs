@@ -1,5 +1,10 @@
 use crate::instruction::Instruction;
 use crate::constant::Constant;
+use brief_diagnostic::Span;
+
+/// Name of the chunk that initializes module-level variables. It is not a
+/// valid identifier, so it cannot collide with a user function.
+pub const MODULE_INIT: &str = "<module>";
 
 /// Code chunk representing a function
 #[derive(Debug, Clone)]
@@ -10,6 +15,10 @@ pub struct Chunk {
     pub max_regs: u8,      // Maximum register count
     pub upvalue_count: u8, // Number of upvalues
     pub param_count: u8,   // Number of parameters
+    pub required_params: u8, // Parameters without a default value
+    pub variadic: bool,    // Last parameter collects extra arguments
+    pub parent: Option<String>, // Constructor chunks: the class's parent, for method lookup
+    pub lines: Vec<(usize, Span)>, // Line table: each entry covers the code from its IP up to the next entry
 }
 
 impl Chunk {
@@ -21,6 +30,10 @@ impl Chunk {
             max_regs: 0,
             upvalue_count: 0,
             param_count: 0,
+            required_params: 0,
+            variadic: false,
+            parent: None,
+            lines: Vec::new(),
         }
     }
 
@@ -31,6 +44,21 @@ impl Chunk {
         ip
     }
 
+    /// Record that the instructions from `ip` onward come from `span`
+    pub fn mark_span(&mut self, ip: usize, span: Span) {
+        match self.lines.last_mut() {
+            Some((_, last)) if *last == span => {},
+            Some((start, last)) if *start == ip => *last = span,
+            _ => self.lines.push((ip, span)),
+        }
+    }
+
+    /// Source span of the instruction at `ip`, if the line table covers it
+    pub fn span_at(&self, ip: usize) -> Option<Span> {
+        let entry = self.lines.partition_point(|(start, _)| *start <= ip);
+        entry.checked_sub(1).map(|i| self.lines[i].1)
+    }
+
     /// Add a constant to the constant pool and return its index
     pub fn add_constant(&mut self, constant: Constant) -> u8 {
         // Check if constant already exists (simple deduplication)
@@ -64,12 +92,25 @@ impl Chunk {
     pub fn ip(&self) -> usize {
         self.code.len()
     }
+
+    /// Human-readable listing of the chunk: its header, constant pool and
+    /// numbered instructions. The same text is the chunk's `Display`
+    pub fn disassemble(&self) -> String {
+        self.to_string()
+    }
 }
 
 impl std::fmt::Display for Chunk {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Chunk: {}", self.name)?;
-        writeln!(f, "  Parameters: {}", self.param_count)?;
+        write!(f, "  Parameters: {} ({} required", self.param_count, self.required_params)?;
+        if self.variadic {
+            write!(f, ", variadic")?;
+        }
+        writeln!(f, ")")?;
+        if let Some(parent) = &self.parent {
+            writeln!(f, "  Extends: {}", parent)?;
+        }
         writeln!(f, "  Max Registers: {}", self.max_regs)?;
         writeln!(f, "  Upvalues: {}", self.upvalue_count)?;
         writeln!(f, "  Constants:")?;
@@ -59,6 +59,29 @@ impl Instruction {
         combined as i16
     }
 
+    /// The highest register this instruction reads or writes, or `None` if
+    /// it uses none. Calls count their argument registers too
+    pub fn highest_register(&self) -> Option<u16> {
+        let (a, b, c) = (self.a() as u16, self.b() as u16, self.c() as u16);
+        match self.opcode() {
+            Opcode::JMP | Opcode::JARG | Opcode::LOADKX | Opcode::EXT => None,
+            Opcode::SET_GLOBAL => Some(b),
+            Opcode::LOADK | Opcode::GET_GLOBAL | Opcode::JIF | Opcode::RET | Opcode::CLOSURE
+            | Opcode::NEWOBJ | Opcode::VARARG | Opcode::PRINT | Opcode::EXIT => Some(a),
+            Opcode::MOVE | Opcode::NEG | Opcode::NOT | Opcode::CAST | Opcode::TOBOOL | Opcode::GETFIELD
+            | Opcode::TEST_EQ | Opcode::TEST_NE | Opcode::TEST_LT
+            | Opcode::TEST_LE | Opcode::TEST_GT | Opcode::TEST_GE => Some(a.max(b)),
+            Opcode::SETFIELD => Some(a.max(c)),
+            Opcode::ADD | Opcode::SUB | Opcode::MUL | Opcode::DIVF | Opcode::DIVI | Opcode::MOD | Opcode::POW
+            | Opcode::CMP_EQ | Opcode::CMP_NE | Opcode::CMP_LT | Opcode::CMP_LE | Opcode::CMP_GT | Opcode::CMP_GE
+            | Opcode::CONTAINS => Some(a.max(b).max(c)),
+            // Callee (or parent constructor) in b, arguments after it
+            Opcode::CALL | Opcode::CALLV | Opcode::SUPER => Some(a.max(b + c)),
+            // Method name in b, receiver in b+1, arguments after that
+            Opcode::INVOKE => Some(a.max(b + 1 + c)),
+        }
+    }
+
     /// Set B and C from a 16-bit signed offset (for jumps)
     pub fn set_offset(&mut self, offset: i16) {
         let offset = offset as u16;
@@ -2,8 +2,10 @@ pub mod opcode;
 pub mod instruction;
 pub mod constant;
 pub mod chunk;
+pub mod serialize;
 
 pub use opcode::*;
 pub use instruction::*;
 pub use constant::*;
 pub use chunk::*;
+pub use serialize::*;
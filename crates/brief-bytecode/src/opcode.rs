@@ -11,6 +11,10 @@ pub enum Opcode {
     // Moves
     MOVE,         // a = destination, b = source
 
+    // Globals
+    GET_GLOBAL,   // a = global slot b
+    SET_GLOBAL,   // global slot a = b
+
     // Arithmetic
     ADD,          // a = b + c
     SUB,          // a = b - c
@@ -27,21 +31,42 @@ pub enum Opcode {
     CMP_LE,       // a = (b <= c)
     CMP_GT,       // a = (b > c)
     CMP_GE,       // a = (b >= c)
+    CONTAINS,     // a = (b in c): array element, map key or substring
 
     // Unary operations
     NEG,          // a = -b
     NOT,          // a = !b
+    CAST,         // a = b converted to the CastType c
+    TOBOOL,       // a = whether b is truthy
 
     // Control flow
     JIF,          // if !a, jump b (signed offset)
     JMP,          // jump a (signed offset)
+    TEST_EQ,      // if a == b, skip the next instruction (the jump taken when false)
+    TEST_NE,      // if a != b, skip the next instruction
+    TEST_LT,      // if a < b, skip the next instruction
+    TEST_LE,      // if a <= b, skip the next instruction
+    TEST_GT,      // if a > b, skip the next instruction
+    TEST_GE,      // if a >= b, skip the next instruction
 
     // Functions
     CALL,         // a = function(b, c args starting at b+1)
+    CALLV,        // like CALL, but the last argument is an array spread into the call
     RET,          // return a
+    JARG,         // if argument a was passed, jump b (signed offset)
+    CLOSURE,      // a = function named by constant b
+    VARARG,       // a = array of the arguments passed beyond the fixed parameters
+
+    // Objects
+    NEWOBJ,       // a = new instance of the class named by constant b (kept if a super call supplied a)
+    GETFIELD,     // a = b.field named by constant c
+    SETFIELD,     // a.field named by constant b = c
+    INVOKE,       // a = method named by b, called on b+1 with c args starting at b+2
+    SUPER,        // run parent constructor b on receiver a, with c args starting at b+1
 
     // Builtins
     PRINT,        // print a
+    EXIT,         // halt with exit code a
 
     // Extended opcodes (for future)
     EXT,          // Extended opcode follows
@@ -52,12 +77,42 @@ impl Opcode {
     pub fn operand_count(&self) -> usize {
         match self {
             Opcode::LOADK | Opcode::MOVE | Opcode::JIF | Opcode::JMP | Opcode::RET | Opcode::PRINT => 2,
-            Opcode::NEG | Opcode::NOT => 2,
+            Opcode::JARG | Opcode::CLOSURE | Opcode::NEWOBJ => 2,
+            Opcode::GET_GLOBAL | Opcode::SET_GLOBAL => 2,
+            Opcode::GETFIELD | Opcode::SETFIELD | Opcode::INVOKE | Opcode::SUPER => 3,
+            Opcode::NEG | Opcode::NOT | Opcode::TOBOOL => 2,
+            Opcode::CAST => 3,
             Opcode::ADD | Opcode::SUB | Opcode::MUL | Opcode::DIVF | Opcode::DIVI | Opcode::MOD | Opcode::POW => 3,
             Opcode::CMP_EQ | Opcode::CMP_NE | Opcode::CMP_LT | Opcode::CMP_LE | Opcode::CMP_GT | Opcode::CMP_GE => 3,
-            Opcode::CALL => 3,
+            Opcode::CONTAINS => 3,
+            Opcode::TEST_EQ | Opcode::TEST_NE | Opcode::TEST_LT | Opcode::TEST_LE | Opcode::TEST_GT | Opcode::TEST_GE => 2,
+            Opcode::CALL | Opcode::CALLV => 3,
+            Opcode::EXIT | Opcode::VARARG => 1,
             Opcode::LOADKX | Opcode::EXT => 0, // Special cases
         }
     }
 }
 
+
+/// Target type of a CAST instruction, stored in its c operand
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastType {
+    Int = 0,
+    Dub,
+    Str,
+    Bool,
+}
+
+impl CastType {
+    /// The cast type encoded as `n`, if any
+    pub fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            0 => Some(CastType::Int),
+            1 => Some(CastType::Dub),
+            2 => Some(CastType::Str),
+            3 => Some(CastType::Bool),
+            _ => None,
+        }
+    }
+}
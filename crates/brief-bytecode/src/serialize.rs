@@ -0,0 +1,281 @@
+use brief_diagnostic::{FileId, Position, Span};
+use crate::chunk::Chunk;
+use crate::constant::Constant;
+use crate::instruction::Instruction;
+use crate::opcode::Opcode;
+
+/// First bytes of a compiled module (`.bfc`) file
+pub const MODULE_MAGIC: &[u8; 4] = b"BFC\0";
+
+/// Module format version, bumped whenever the encoding changes
+pub const MODULE_VERSION: u8 = 3;
+
+/// Error reading serialized bytecode
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The data ends partway through a value
+    UnexpectedEnd,
+    /// Data left over after the last value
+    TrailingBytes(usize),
+    /// Not a compiled module: the magic bytes are wrong
+    BadMagic,
+    /// Compiled by a version of Brief with a different format
+    UnsupportedVersion(u8),
+    InvalidOpcode(u8),
+    InvalidConstantTag(u8),
+    InvalidUtf8,
+    /// A manifest entry points outside the chunk data
+    InvalidOffset { chunk: String },
+    /// A manifest entry's name differs from the chunk stored at its offset
+    NameMismatch { expected: String, found: String },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "Unexpected end of bytecode"),
+            DecodeError::TrailingBytes(count) => write!(f, "{} unexpected byte(s) after bytecode", count),
+            DecodeError::BadMagic => write!(f, "Not a compiled Brief module"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported bytecode version {} (expected {})", version, MODULE_VERSION)
+            }
+            DecodeError::InvalidOpcode(op) => write!(f, "Invalid opcode: {}", op),
+            DecodeError::InvalidConstantTag(tag) => write!(f, "Invalid constant tag: {}", tag),
+            DecodeError::InvalidUtf8 => write!(f, "Invalid UTF-8 in string"),
+            DecodeError::InvalidOffset { chunk } => write!(f, "Chunk '{}' lies outside the module", chunk),
+            DecodeError::NameMismatch { expected, found } => {
+                write!(f, "Manifest names chunk '{}' but found '{}'", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// Constant pool tags
+const TAG_INT: u8 = 0;
+const TAG_DOUBLE: u8 = 1;
+const TAG_BOOL: u8 = 2;
+const TAG_STR: u8 = 3;
+const TAG_NULL: u8 = 4;
+
+impl Chunk {
+    /// Encode the chunk as bytes; [`Chunk::deserialize`] reads them back.
+    /// Integers are little-endian, strings and lists are length-prefixed
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_str(&mut out, &self.name);
+        out.extend([self.max_regs, self.upvalue_count, self.param_count, self.required_params]);
+        out.push(self.variadic as u8);
+        match &self.parent {
+            Some(parent) => {
+                out.push(1);
+                write_str(&mut out, parent);
+            }
+            None => out.push(0),
+        }
+
+        write_u32(&mut out, self.constants.len());
+        for constant in &self.constants {
+            match constant {
+                Constant::Int(n) => {
+                    out.push(TAG_INT);
+                    out.extend(n.to_le_bytes());
+                }
+                Constant::Double(d) => {
+                    out.push(TAG_DOUBLE);
+                    out.extend(d.to_le_bytes());
+                }
+                Constant::Bool(b) => out.extend([TAG_BOOL, *b as u8]),
+                Constant::Str(s) => {
+                    out.push(TAG_STR);
+                    write_str(&mut out, s);
+                }
+                Constant::Null => out.push(TAG_NULL),
+            }
+        }
+
+        write_u32(&mut out, self.code.len());
+        for instruction in &self.code {
+            out.extend(instruction.0.to_le_bytes());
+        }
+
+        write_u32(&mut out, self.lines.len());
+        for (ip, span) in &self.lines {
+            write_u32(&mut out, *ip);
+            out.extend(span.file_id.0.to_le_bytes());
+            for position in [span.start, span.end] {
+                out.extend(position.line.to_le_bytes());
+                out.extend(position.column.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    /// Decode a chunk written by [`Chunk::serialize`]
+    pub fn deserialize(bytes: &[u8]) -> Result<Chunk, DecodeError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let chunk = reader.chunk()?;
+        reader.finish()?;
+        Ok(chunk)
+    }
+}
+
+/// Encode chunks as a compiled module: a header, then a manifest of each
+/// chunk's name and the offset and length of its bytes after the manifest,
+/// then the serialized chunks in order
+pub fn serialize_module(chunks: &[Chunk]) -> Vec<u8> {
+    let encoded: Vec<Vec<u8>> = chunks.iter().map(Chunk::serialize).collect();
+    let mut out = Vec::new();
+    out.extend(MODULE_MAGIC);
+    out.push(MODULE_VERSION);
+    write_u32(&mut out, chunks.len());
+    let mut offset = 0;
+    for (chunk, bytes) in chunks.iter().zip(&encoded) {
+        write_str(&mut out, &chunk.name);
+        write_u32(&mut out, offset);
+        write_u32(&mut out, bytes.len());
+        offset += bytes.len();
+    }
+    for bytes in encoded {
+        out.extend(bytes);
+    }
+    out
+}
+
+/// Decode a compiled module written by [`serialize_module`]
+pub fn deserialize_module(bytes: &[u8]) -> Result<Vec<Chunk>, DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(MODULE_MAGIC.len()).ok() != Some(MODULE_MAGIC.as_slice()) {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = reader.u8()?;
+    if version != MODULE_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let count = reader.u32()?;
+    let mut manifest = Vec::new();
+    for _ in 0..count {
+        manifest.push((reader.string()?, reader.u32()?, reader.u32()?));
+    }
+
+    let data = &bytes[reader.pos..];
+    let mut end = 0;
+    let mut chunks = Vec::new();
+    for (name, offset, length) in manifest {
+        let Some(chunk_bytes) = offset.checked_add(length).and_then(|stop| data.get(offset..stop)) else {
+            return Err(DecodeError::InvalidOffset { chunk: name });
+        };
+        let chunk = Chunk::deserialize(chunk_bytes)?;
+        if chunk.name != name {
+            return Err(DecodeError::NameMismatch { expected: name, found: chunk.name });
+        }
+        end = end.max(offset + length);
+        chunks.push(chunk);
+    }
+    if end < data.len() {
+        return Err(DecodeError::TrailingBytes(data.len() - end));
+    }
+    Ok(chunks)
+}
+
+fn write_u32(out: &mut Vec<u8>, n: usize) {
+    let n = u32::try_from(n).expect("bytecode section larger than 4 GiB");
+    out.extend(n.to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_u32(out, s.len());
+    out.extend(s.as_bytes());
+}
+
+/// Cursor over serialized bytecode
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos.checked_add(count).ok_or(DecodeError::UnexpectedEnd)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<usize, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let length = self.u32()?;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn position(&mut self) -> Result<Position, DecodeError> {
+        let line = u32::from_le_bytes(self.array()?);
+        let column = u32::from_le_bytes(self.array()?);
+        Ok(Position::new(line, column))
+    }
+
+    fn chunk(&mut self) -> Result<Chunk, DecodeError> {
+        let mut chunk = Chunk::new(self.string()?);
+        chunk.max_regs = self.u8()?;
+        chunk.upvalue_count = self.u8()?;
+        chunk.param_count = self.u8()?;
+        chunk.required_params = self.u8()?;
+        chunk.variadic = self.u8()? != 0;
+        chunk.parent = match self.u8()? {
+            0 => None,
+            _ => Some(self.string()?),
+        };
+
+        for _ in 0..self.u32()? {
+            let constant = match self.u8()? {
+                TAG_INT => Constant::Int(i64::from_le_bytes(self.array()?)),
+                TAG_DOUBLE => Constant::Double(f64::from_le_bytes(self.array()?)),
+                TAG_BOOL => Constant::Bool(self.u8()? != 0),
+                TAG_STR => Constant::Str(self.string()?),
+                TAG_NULL => Constant::Null,
+                tag => return Err(DecodeError::InvalidConstantTag(tag)),
+            };
+            chunk.constants.push(constant);
+        }
+
+        for _ in 0..self.u32()? {
+            let word = u32::from_le_bytes(self.array()?);
+            // `Instruction::opcode` trusts the opcode byte, so check it here
+            let op = (word & 0xFF) as u8;
+            if op > Opcode::EXT as u8 {
+                return Err(DecodeError::InvalidOpcode(op));
+            }
+            chunk.code.push(Instruction(word));
+        }
+
+        for _ in 0..self.u32()? {
+            let ip = self.u32()?;
+            let file_id = FileId(u32::from_le_bytes(self.array()?));
+            let start = self.position()?;
+            let end = self.position()?;
+            chunk.lines.push((ip, Span::new(file_id, start, end)));
+        }
+        Ok(chunk)
+    }
+
+    /// Fail if anything is left after the last value
+    fn finish(&self) -> Result<(), DecodeError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            extra => Err(DecodeError::TrailingBytes(extra)),
+        }
+    }
+}
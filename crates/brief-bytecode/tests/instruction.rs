@@ -57,6 +57,22 @@ fn test_chunk_operations() {
     assert_eq!(chunk.constants.len(), 2);
 }
 
+#[test]
+fn test_chunk_disassemble() {
+    let mut chunk = Chunk::new("main".to_string());
+    let idx = chunk.add_constant(Constant::Int(42));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx));
+    chunk.emit(Instruction::new1(Opcode::RET, 0));
+    chunk.max_regs = 1;
+
+    let listing = chunk.disassemble();
+    assert!(listing.starts_with("Chunk: main\n"), "{}", listing);
+    assert!(listing.contains("    [0] 42\n"), "{}", listing);
+    assert!(listing.contains("    0000 LOADK"), "{}", listing);
+    assert!(listing.contains("    0001 RET"), "{}", listing);
+    assert_eq!(listing, chunk.to_string());
+}
+
 #[test]
 fn test_constant_deduplication() {
     let mut chunk = Chunk::new("test".to_string());
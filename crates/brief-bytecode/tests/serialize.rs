@@ -0,0 +1,92 @@
+use brief_bytecode::*;
+use brief_diagnostic::{FileId, Position, Span};
+
+/// Chunks compare by their listing, which covers every field but the line table
+fn assert_same_chunk(a: &Chunk, b: &Chunk) {
+    assert_eq!(a.disassemble(), b.disassemble());
+    assert_eq!(a.upvalue_count, b.upvalue_count);
+    assert_eq!(a.lines, b.lines);
+}
+
+fn sample_chunk() -> Chunk {
+    let mut chunk = Chunk::new("Point::move".to_string());
+    for constant in [
+        Constant::Int(-7),
+        Constant::Double(2.5),
+        Constant::Bool(true),
+        Constant::Str("héllo".to_string()),
+        Constant::Null,
+    ] {
+        chunk.add_constant(constant);
+    }
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, 3));
+    let mut jump = Instruction::new1(Opcode::JMP, 0);
+    jump.set_offset(-2);
+    chunk.emit(jump);
+    chunk.emit(Instruction::new1(Opcode::RET, 0));
+    chunk.max_regs = 4;
+    chunk.param_count = 2;
+    chunk.required_params = 1;
+    chunk.variadic = true;
+    chunk.parent = Some("Shape".to_string());
+    chunk.mark_span(0, Span::new(FileId(0), Position::new(3, 5), Position::new(3, 12)));
+    chunk.mark_span(2, Span::new(FileId(1), Position::new(4, 1), Position::new(4, 4)));
+    chunk
+}
+
+#[test]
+fn test_chunk_round_trip() {
+    let chunk = sample_chunk();
+    let decoded = Chunk::deserialize(&chunk.serialize()).unwrap();
+    assert_same_chunk(&decoded, &chunk);
+    assert_eq!(decoded.code[1].offset(), -2);
+
+    let empty = Chunk::new("<module>".to_string());
+    assert_same_chunk(&Chunk::deserialize(&empty.serialize()).unwrap(), &empty);
+}
+
+#[test]
+fn test_chunk_deserialize_errors() {
+    let bytes = sample_chunk().serialize();
+    for end in [0, 3, bytes.len() / 2, bytes.len() - 1] {
+        assert_eq!(Chunk::deserialize(&bytes[..end]).unwrap_err(), DecodeError::UnexpectedEnd, "cut at {}", end);
+    }
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert_eq!(Chunk::deserialize(&extra).unwrap_err(), DecodeError::TrailingBytes(1));
+
+    let mut chunk = Chunk::new("f".to_string());
+    chunk.emit(Instruction(0xFF));
+    assert_eq!(Chunk::deserialize(&chunk.serialize()).unwrap_err(), DecodeError::InvalidOpcode(0xFF));
+}
+
+#[test]
+fn test_module_round_trip() {
+    let chunks = vec![Chunk::new("<module>".to_string()), sample_chunk(), Chunk::new("main".to_string())];
+    let bytes = serialize_module(&chunks);
+    assert!(bytes.starts_with(MODULE_MAGIC));
+    let decoded = deserialize_module(&bytes).unwrap();
+    assert_eq!(decoded.len(), 3);
+    for (decoded, chunk) in decoded.iter().zip(&chunks) {
+        assert_same_chunk(decoded, chunk);
+    }
+    assert_eq!(deserialize_module(&serialize_module(&[])).unwrap().len(), 0);
+}
+
+#[test]
+fn test_module_deserialize_errors() {
+    let bytes = serialize_module(&[sample_chunk()]);
+    assert_eq!(deserialize_module(b"BF").unwrap_err(), DecodeError::BadMagic);
+    assert_eq!(deserialize_module(&bytes[4..]).unwrap_err(), DecodeError::BadMagic);
+
+    let mut version = bytes.clone();
+    version[4] = MODULE_VERSION + 1;
+    assert_eq!(deserialize_module(&version).unwrap_err(), DecodeError::UnsupportedVersion(MODULE_VERSION + 1));
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(deserialize_module(truncated), Err(DecodeError::InvalidOffset { chunk }) if chunk == "Point::move"));
+
+    let mut extra = bytes.clone();
+    extra.extend([1, 2]);
+    assert_eq!(deserialize_module(&extra).unwrap_err(), DecodeError::TrailingBytes(2));
+}
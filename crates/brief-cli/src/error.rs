@@ -1,31 +1,34 @@
 use std::fmt;
+use brief_diagnostic::{Diagnostic, SourceMap};
+use serde::Serialize;
 
 /// CLI-specific errors
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum CliError {
     IoError(std::io::Error),
-    LexError,
-    ParseError,
-    HirError(Vec<brief_hir::HirError>),
+    /// Source that didn't compile, with its errors
+    InvalidSource(brief_driver::CompileError),
     RuntimeError(brief_vm::RuntimeError),
     UsageError(String),
+    /// A file failed to compile; its errors have already been printed
+    CompileError,
+    /// A `.bfc` file could not be read as compiled bytecode
+    InvalidBytecode(brief_bytecode::DecodeError),
 }
 
 impl fmt::Display for CliError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             CliError::IoError(e) => write!(f, "IO error: {}", e),
-            CliError::LexError => write!(f, "Lexical analysis failed"),
-            CliError::ParseError => write!(f, "Parsing failed"),
-            CliError::HirError(errors) => {
-                write!(f, "HIR errors:")?;
-                for err in errors {
-                    write!(f, "\n  {:?}", err)?;
-                }
-                Ok(())
-            },
+            CliError::InvalidSource(e) => write!(f, "{}", e),
             CliError::RuntimeError(e) => write!(f, "Runtime error: {}", e),
             CliError::UsageError(msg) => write!(f, "Usage error: {}", msg),
+            CliError::CompileError => write!(f, "Compilation failed"),
+            CliError::InvalidBytecode(brief_bytecode::DecodeError::BadMagic) => {
+                write!(f, "Not a compiled .bfc file; run source files with `brief file.bf`")
+            },
+            CliError::InvalidBytecode(e) => write!(f, "Invalid bytecode: {}", e),
         }
     }
 }
@@ -38,9 +41,9 @@ impl From<std::io::Error> for CliError {
     }
 }
 
-impl From<Vec<brief_hir::HirError>> for CliError {
-    fn from(errors: Vec<brief_hir::HirError>) -> Self {
-        CliError::HirError(errors)
+impl From<brief_driver::CompileError> for CliError {
+    fn from(err: brief_driver::CompileError) -> Self {
+        CliError::InvalidSource(err)
     }
 }
 
@@ -52,17 +55,85 @@ impl From<brief_vm::RuntimeError> for CliError {
 
 impl From<rustyline::error::ReadlineError> for CliError {
     fn from(err: rustyline::error::ReadlineError) -> Self {
-        CliError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
+        CliError::IoError(std::io::Error::other(
             format!("Readline error: {:?}", err),
         ))
     }
 }
 
+/// The compiler stage or runtime an [`ErrorReport`] came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    Lex,
+    Parse,
+    /// Name resolution, lowering and type checking
+    Hir,
+    Runtime,
+}
+
+/// One lex, parse, HIR or runtime error in a common shape, as printed by
+/// `brief --json-errors`. The location is null when it is unknown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorReport {
+    #[serde(rename = "type")]
+    pub kind: ErrorKind,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl ErrorReport {
+    /// A report for a diagnostic raised by `kind`
+    pub fn from_diagnostic(kind: ErrorKind, diagnostic: &Diagnostic, sources: &SourceMap) -> Self {
+        let span = diagnostic.span;
+        Self {
+            kind,
+            message: diagnostic.message.clone(),
+            file: sources.file_path(span.file_id).map(str::to_string),
+            line: Some(span.start.line),
+            column: Some(span.start.column),
+        }
+    }
+
+    /// A report for a runtime error, located where the VM raised it
+    pub fn from_runtime(error: &brief_vm::RuntimeError, sources: &SourceMap) -> Self {
+        let span = error.span();
+        Self {
+            kind: ErrorKind::Runtime,
+            message: error.without_location().to_string(),
+            file: span.and_then(|span| sources.file_path(span.file_id)).map(str::to_string),
+            line: span.map(|span| span.start.line),
+            column: span.map(|span| span.start.column),
+        }
+    }
+
+    /// The report as a single line of JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("error reports always serialize")
+    }
+}
+
 /// Exit codes for the CLI
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ExitCode {
-    Success = 0,
-    CompileError = 1,
-    RuntimeError = 2,
+    Success,
+    CompileError,
+    RuntimeError,
+    /// Code passed to `exit(code)` by the program
+    Requested(i32),
+}
+
+impl ExitCode {
+    /// Process exit status for this code
+    pub fn code(self) -> i32 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::CompileError => 1,
+            ExitCode::RuntimeError => 2,
+            ExitCode::Requested(code) => code,
+        }
+    }
 }
 
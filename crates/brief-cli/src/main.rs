@@ -9,11 +9,122 @@ use error::{CliError, ExitCode};
 fn main() {
     let args: Vec<String> = env::args().collect();
     
-    let exit_code = match args.len() {
+    let exit_code = match args.get(1).map(String::as_str) {
+        Some("compile") => compile_command(&args[2..]),
+        Some("run") => run_bytecode_command(&args[2..]),
+        Some("fmt") => fmt_command(&args[2..]),
+        _ if args.iter().any(|arg| arg == "--json-errors") => json_errors_command(&args[1..]),
+        _ => run_command(&args),
+    };
+    
+    std::process::exit(exit_code.code());
+}
+
+/// `brief compile file.bf [-o out.bfc]`; the output defaults to the source
+/// path with a `.bfc` extension
+fn compile_command(args: &[String]) -> ExitCode {
+    let usage = || {
+        eprintln!("{}", CliError::UsageError("compile takes a source file and an optional -o <out.bfc>".into()));
+        ExitCode::CompileError
+    };
+    let mut src = None;
+    let mut dst = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-o" || arg == "--output" {
+            match args.next() {
+                Some(path) if dst.is_none() => dst = Some(Path::new(path).to_path_buf()),
+                _ => return usage(),
+            }
+        } else if src.is_none() {
+            src = Some(Path::new(arg));
+        } else {
+            return usage();
+        }
+    }
+    let Some(src) = src else {
+        return usage();
+    };
+    let dst = dst.unwrap_or_else(|| src.with_extension("bfc"));
+    match run::compile_file(src, &dst) {
+        Ok(()) => ExitCode::Success,
+        Err(CliError::CompileError) => ExitCode::CompileError,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::RuntimeError
+        }
+    }
+}
+
+/// `brief run file.bfc`
+fn run_bytecode_command(args: &[String]) -> ExitCode {
+    let [path] = args else {
+        eprintln!("{}", CliError::UsageError("run takes one compiled .bfc file".into()));
+        return ExitCode::CompileError;
+    };
+    match run::run_bytecode(Path::new(path)) {
+        Ok(code) => code,
+        Err(e @ CliError::InvalidBytecode(_)) => {
+            eprintln!("Error: {}", e);
+            ExitCode::CompileError
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::RuntimeError
+        }
+    }
+}
+
+/// `brief fmt [--check] file.bf`
+fn fmt_command(args: &[String]) -> ExitCode {
+    let (check, path) = match args {
+        [path] => (false, path),
+        [flag, path] if flag == "--check" => (true, path),
+        _ => {
+            eprintln!("{}", CliError::UsageError("fmt takes a source file and an optional --check".into()));
+            return ExitCode::CompileError;
+        }
+    };
+    match run::format_file(Path::new(path), check) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::RuntimeError
+        }
+    }
+}
+
+/// `brief --json-errors [--typecheck] file.bf`; the flags may come in
+/// either order
+fn json_errors_command(args: &[String]) -> ExitCode {
+    let mut options = run::RunOptions { json_errors: true, ..Default::default() };
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json-errors" => {}
+            "--typecheck" => options.typecheck = true,
+            _ => paths.push(arg),
+        }
+    }
+    let [path] = paths.as_slice() else {
+        eprintln!("{}", CliError::UsageError("--json-errors takes a source file and an optional --typecheck".into()));
+        return ExitCode::CompileError;
+    };
+    match run::run_file_with(Path::new(path), options) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::RuntimeError
+        }
+    }
+}
+
+fn run_command(args: &[String]) -> ExitCode {
+    match args.len() {
         1 => {
             // No arguments - run REPL
             match repl::repl() {
-                Ok(_) => ExitCode::Success,
+                Ok(code) => code,
                 Err(e) => {
                     eprintln!("Error: {}", e);
                     ExitCode::RuntimeError
@@ -25,7 +136,7 @@ fn main() {
             if arg == "repl" || arg == "--repl" || arg == "-i" {
                 // Explicit REPL
                 match repl::repl() {
-                    Ok(_) => ExitCode::Success,
+                    Ok(code) => code,
                     Err(e) => {
                         eprintln!("Error: {}", e);
                         ExitCode::RuntimeError
@@ -34,6 +145,9 @@ fn main() {
             } else if arg == "help" || arg == "--help" || arg == "-h" {
                 print_usage();
                 ExitCode::Success
+            } else if arg == "--version" || arg == "-V" {
+                println!("brief {}", env!("CARGO_PKG_VERSION"));
+                ExitCode::Success
             } else {
                 // Treat as file path
                 let path = Path::new(arg);
@@ -46,14 +160,68 @@ fn main() {
                 }
             }
         },
+        3 if args[1] == "-e" || args[1] == "--eval" => {
+            match run::run_string(&args[2]) {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    ExitCode::RuntimeError
+                }
+            }
+        },
+        3 => {
+            let path = Path::new(&args[2]);
+            let result = match args[1].as_str() {
+                "check" | "--check" => Some(run::check_file(path)),
+                "--typecheck" => Some(run::run_file_with(path, run::RunOptions { typecheck: true, ..Default::default() })),
+                "--dump-bytecode" | "--disasm" => Some(match run::dump_bytecode(path) {
+                    Ok(()) => Ok(ExitCode::Success),
+                    Err(CliError::CompileError) => Ok(ExitCode::CompileError),
+                    Err(e) => Err(e),
+                }),
+                "--dump-hir" => Some(match run::dump_hir(path) {
+                    Ok(()) => Ok(ExitCode::Success),
+                    Err(CliError::CompileError) => Ok(ExitCode::CompileError),
+                    Err(e) => Err(e),
+                }),
+                _ => None,
+            };
+            match result {
+                Some(Ok(code)) => code,
+                Some(Err(e)) => {
+                    eprintln!("Error: {}", e);
+                    ExitCode::RuntimeError
+                },
+                None => {
+                    eprintln!("{}", CliError::UsageError(format!("Unknown command '{}'", args[1])));
+                    print_usage();
+                    ExitCode::CompileError
+                }
+            }
+        },
+        4 if matches!(args[1].as_str(), "check" | "--check") && args[2].starts_with("--format=") => {
+            let format = match &args[2]["--format=".len()..] {
+                "text" => run::DiagnosticFormat::Text,
+                "json" => run::DiagnosticFormat::Json,
+                other => {
+                    eprintln!("{}", CliError::UsageError(format!("Unknown format '{}', expected text or json", other)));
+                    return ExitCode::CompileError;
+                }
+            };
+            match run::check_file_with(Path::new(&args[3]), format) {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    ExitCode::RuntimeError
+                }
+            }
+        },
         _ => {
             eprintln!("{}", CliError::UsageError("Too many arguments".into()));
             print_usage();
             ExitCode::CompileError
         }
-    };
-    
-    std::process::exit(exit_code as i32);
+    }
 }
 
 fn print_usage() {
@@ -61,8 +229,29 @@ fn print_usage() {
     println!();
     println!("Usage:");
     println!("  brief [file.bf]    Run a Brief source file");
+    println!("  brief --typecheck [file.bf]");
+    println!("                      Type check a file, then run it");
+    println!("  brief --json-errors [--typecheck] [file.bf]");
+    println!("                      Run a file, printing errors to stderr as one JSON object per line");
+    println!("  brief check [file.bf], brief --check [file.bf]");
+    println!("                      Check names and types in a file without running it");
+    println!("  brief check --format=json [file.bf]");
+    println!("                      Print check diagnostics as one JSON object per line");
+    println!("  brief --dump-bytecode [file.bf], brief --disasm [file.bf]");
+    println!("                      Print a file's compiled bytecode without running it");
+    println!("  brief --dump-hir [file.bf]");
+    println!("                      Print a file's desugared and resolved HIR without running it");
+    println!("  brief compile [file.bf] [-o out.bfc]");
+    println!("                      Compile a file to bytecode, by default next to it as file.bfc");
+    println!("  brief fmt [--check] [file.bf]");
+    println!("                      Rewrite a file in canonical formatting; --check only reports it");
+    println!("  brief run [file.bfc]");
+    println!("                      Run a file written by brief compile");
+    println!("  brief -e <code>, brief --eval <code>");
+    println!("                      Run code given on the command line");
     println!("  brief repl          Start the REPL");
     println!("  brief help          Show this help message");
+    println!("  brief --version     Show the version");
     println!();
     println!("If no arguments are provided, the REPL is started.");
 }
@@ -1,34 +1,101 @@
-use crate::error::CliError;
-use brief_diagnostic::FileId;
-use brief_hir::{emit_bytecode, lower};
-use brief_lexer::lex;
+use crate::error::{CliError, ExitCode};
+use brief_diagnostic::{FileId, Position, Span};
+use brief_driver::{CompileError, CompiledProgram, Compiler, ENTRY, RuntimeError, VM, Value, new_vm};
+use crate::run::compile;
+use brief_hir::KnownGlobals;
+use brief_lexer::{TokenKind, lex};
 use brief_parser::parse;
-use brief_runtime::Runtime;
-use brief_vm::{VM, Value};
 use rustyline::Context;
 use rustyline::Helper;
 use rustyline::Result as RustylineResult;
-use rustyline::completion::{Completer, FilenameCompleter};
+use rustyline::completion::Completer;
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
 use rustyline::highlight::MatchingBracketHighlighter;
 use rustyline::hint::Hinter;
 use rustyline::hint::HistoryHinter;
-use rustyline::validate::MatchingBracketValidator;
 use rustyline::validate::ValidationContext;
 use rustyline::validate::ValidationResult;
 use rustyline::validate::Validator;
+use rustyline::history::DefaultHistory;
 use rustyline::{CompletionType, Config, EditMode, Editor};
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 struct BriefHelper {
-    completer: FilenameCompleter,
+    completer: BriefCompleter,
     highlighter: MatchingBracketHighlighter,
     hinter: HistoryHinter,
-    validator: MatchingBracketValidator,
+}
+
+/// Completes the word before the cursor from the keywords and the names
+/// the session has defined so far
+struct BriefCompleter {
+    vm: Rc<RefCell<VM>>,
+    // Knows the session's variables, which the VM only has by slot
+    compiler: Rc<RefCell<Compiler>>,
+}
+
+impl BriefCompleter {
+    fn candidates(&self, prefix: &str) -> Vec<String> {
+        let vm = self.vm.borrow();
+        let compiler = self.compiler.borrow();
+        completions(prefix, vm.function_names().chain(compiler.globals().names()))
+    }
+}
+
+impl Completer for BriefCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> RustylineResult<(usize, Vec<String>)> {
+        let start = word_start(line, pos);
+        Ok((start, self.candidates(&line[start..pos])))
+    }
+}
+
+/// Byte index where the identifier ending at `pos` begins
+fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(pos, |(i, _)| i)
+}
+
+/// Keywords and user-visible names starting with `prefix`, sorted. Chunks
+/// the compiler names itself (`__repl__`, `<module>`, `Class::method`)
+/// are left out
+fn completions<'a>(prefix: &str, names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+    let names = names.filter(|name| {
+        !name.starts_with("__") && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    let mut candidates: Vec<String> = TokenKind::KEYWORDS
+        .iter()
+        .copied()
+        .chain(names)
+        .filter(|word| word.starts_with(prefix) && *word != prefix)
+        .map(String::from)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
 }
 
 impl Completer for BriefHelper {
-    type Candidate = <FilenameCompleter as Completer>::Candidate;
+    type Candidate = <BriefCompleter as Completer>::Candidate;
 
     fn complete(
         &self,
@@ -73,8 +140,14 @@ impl Highlighter for BriefHelper {
 impl Helper for BriefHelper {}
 
 impl Validator for BriefHelper {
+    /// Enter submits the input once it parses as a whole; otherwise it
+    /// starts a new line
     fn validate(&self, ctx: &mut ValidationContext) -> RustylineResult<ValidationResult> {
-        self.validator.validate(ctx)
+        if is_incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
     }
 
     fn validate_while_typing(&self) -> bool {
@@ -82,8 +155,8 @@ impl Validator for BriefHelper {
     }
 }
 
-/// Run the REPL
-pub fn repl() -> Result<(), CliError> {
+/// Run the REPL until the input ends or the program calls `exit`
+pub fn repl() -> Result<ExitCode, CliError> {
     let config = Config::builder()
         .history_ignore_space(true)
         .completion_type(CompletionType::Circular) // Use Circular to allow tab insertion
@@ -91,196 +164,322 @@ pub fn repl() -> Result<(), CliError> {
         .tab_stop(4) // 4 spaces per tab
         .build();
 
+    let vm = Rc::new(RefCell::new(new_vm()));
+    let mut session = Session::new(Rc::clone(&vm));
+
     let h = BriefHelper {
-        completer: FilenameCompleter::new(),
+        completer: BriefCompleter { vm, compiler: Rc::clone(&session.compiler) },
         highlighter: MatchingBracketHighlighter::default(),
         hinter: HistoryHinter {},
-        validator: MatchingBracketValidator::new(),
     };
 
     let mut rl = Editor::with_config(config)?;
     rl.set_helper(Some(h));
 
-    let file_id = FileId(0);
+    let history = history_path();
+    if let Some(path) = &history {
+        load_history(&mut rl, path);
+    }
+    let result = read_eval_print(&mut rl, &mut session);
+    if let Some(path) = &history
+        && let Err(err) = rl.save_history(path)
+    {
+        eprintln!("Warning: could not save history to {}: {}", path.display(), err);
+    }
+    result
+}
+
+/// Where REPL history is kept: `$BRIEF_HISTORY` if set, otherwise
+/// `~/.brief_history`
+fn history_path() -> Option<PathBuf> {
+    history_path_from(std::env::var_os("BRIEF_HISTORY"), std::env::home_dir())
+}
+
+fn history_path_from(override_path: Option<OsString>, home: Option<PathBuf>) -> Option<PathBuf> {
+    match override_path {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => home.map(|home| home.join(".brief_history")),
+    }
+}
+
+/// Load saved history, creating the file on first use. Failures only warn
+fn load_history<H: Helper>(rl: &mut Editor<H, DefaultHistory>, path: &Path) {
+    if !path.exists()
+        && let Err(err) = File::create(path)
+    {
+        eprintln!("Warning: could not create history file {}: {}", path.display(), err);
+        return;
+    }
+    if let Err(err) = rl.load_history(path) {
+        eprintln!("Warning: could not load history from {}: {}", path.display(), err);
+    }
+}
 
+fn read_eval_print(rl: &mut Editor<BriefHelper, DefaultHistory>, session: &mut Session) -> Result<ExitCode, CliError> {
     println!("Brief REPL");
     println!("Type 'exit' or 'quit' to exit, 'help' for help");
-    println!("Press Enter to execute, or continue typing for multi-line input");
+    println!("Press Enter to execute; unfinished blocks continue on the next line");
     println!("Tab inserts spaces for indentation");
 
-    let mut vm = VM::new();
-    let runtime = Runtime::new();
-    vm.set_runtime(Box::new(runtime));
-
     loop {
-        // Collect multi-line input
-        let mut input = String::new();
-        let mut is_first_line = true;
-
-        loop {
-            let prompt = if is_first_line { "brief> " } else { "      " };
-            let readline = rl.readline(prompt);
-
-            match readline {
-                Ok(line) => {
-                    // Check for special commands (only on first line)
-                    if is_first_line {
-                        let trimmed = line.trim();
-                        if trimmed.is_empty() {
-                            continue;
-                        }
-                        if trimmed == "exit" || trimmed == "quit" {
-                            return Ok(());
-                        }
-                        if trimmed == "help" {
-                            println!("Commands:");
-                            println!("  exit, quit - Exit the REPL");
-                            println!("  help - Show this help message");
-                            println!("Enter Brief code to evaluate");
-                            println!("Press Enter on empty line to execute multi-line input");
-                            continue;
-                        }
-                    }
+        // The validator keeps reading lines until the input is complete
+        let input = match rl.readline("brief> ") {
+            Ok(input) => input,
+            Err(ReadlineError::Interrupted) => {
+                println!("CTRL-C");
+                return Ok(ExitCode::Success);
+            }
+            Err(ReadlineError::Eof) => {
+                println!("CTRL-D");
+                return Ok(ExitCode::Success);
+            }
+            Err(err) => {
+                eprintln!("Error: {:?}", err);
+                return Err(CliError::IoError(std::io::Error::other(
+                    format!("Readline error: {:?}", err),
+                )));
+            }
+        };
 
-                    // If line is empty and we have input, execute
-                    if line.trim().is_empty() && !input.is_empty() {
-                        break;
-                    }
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed == "exit" || trimmed == "quit" {
+            return Ok(ExitCode::Success);
+        }
+        if trimmed == "help" {
+            println!("Commands:");
+            println!("  exit, quit - Exit the REPL");
+            println!("  help - Show this help message");
+            println!("  :load <file> - Run a Brief file and keep its definitions");
+            println!("  :reset - Forget everything defined so far");
+            println!("  :type <expr> - Show the type of an expression");
+            println!("  :debug - Toggle printing bytecode before running");
+            println!("  :save <file> - Write this session's input to a file");
+            println!("Enter Brief code to evaluate");
+            println!("Blocks run once complete; press Enter on an empty line to end one");
+            continue;
+        }
 
-                    // Add line to input
-                    if !input.is_empty() {
-                        input.push('\n');
-                    }
-                    input.push_str(&line);
-                    is_first_line = false;
+        // Add to history
+        let _ = rl.add_history_entry(trimmed);
 
-                    // Check if input looks complete (heuristic: ends with newline or is a simple expression)
-                    // For now, continue collecting until empty line
-                }
-                Err(ReadlineError::Interrupted) => {
-                    if input.is_empty() {
-                        println!("CTRL-C");
-                        return Ok(());
-                    } else {
-                        // Clear multi-line input
-                        input.clear();
-                        is_first_line = true;
-                        continue;
-                    }
-                }
-                Err(ReadlineError::Eof) => {
-                    if input.is_empty() {
-                        println!("CTRL-D");
-                        return Ok(());
-                    } else {
-                        // Execute what we have
-                        break;
+        if trimmed.starts_with(':') {
+            match parse_meta_command(trimmed).and_then(|command| session.run_command(command)) {
+                Ok(message) => println!("{}", message),
+                Err(message) => eprintln!("{}", colored_error(&format!("Error: {}", message))),
+            }
+        } else {
+            match session.evaluate(input.trim_end()) {
+                Ok(result) => {
+                    if let Some(value) = result
+                        && value != Value::Null
+                    {
+                        println!("{}", colored_value(&value));
                     }
                 }
-                Err(err) => {
-                    eprintln!("Error: {:?}", err);
-                    return Err(CliError::IoError(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Readline error: {:?}", err),
-                    )));
+                Err(e) => {
+                    eprintln!("{}", colored_error(&format!("Error: {}", e)));
                 }
             }
         }
 
-        if input.trim().is_empty() {
-            continue;
+        // Return rather than exit here, so the caller still saves history
+        if let Some(code) = session.exit_code {
+            return Ok(ExitCode::Requested(code));
         }
+    }
+}
 
-        // Add to history
-        let _ = rl.add_history_entry(input.as_str());
-
-        // Wrap in a function for execution
-        // The input may already be multi-line, so we need to indent each line
-        let wrapped = build_repl_source(&input);
-
-        // Try to execute
-        match execute_repl_line(&wrapped, file_id, &mut vm) {
-            Ok(result) => {
-                if let Some(value) = result {
-                    if value != Value::Null {
-                        println!("{}", value);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Error: {}", e);
-            }
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// An error message, in red when color is enabled
+fn colored_error(msg: &str) -> String {
+    paint(msg, RED, color_enabled())
+}
+
+/// A result value, in green when color is enabled
+fn colored_value(val: &Value) -> String {
+    paint(&val.to_string(), GREEN, color_enabled())
+}
+
+fn paint(text: &str, color: &str, enabled: bool) -> String {
+    if enabled {
+        format!("{}{}{}", color, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Color is used only on a terminal, and never when `NO_COLOR` is set
+fn color_enabled() -> bool {
+    color_enabled_from(std::env::var_os("NO_COLOR"), std::io::stdout().is_terminal())
+}
+
+fn color_enabled_from(no_color: Option<OsString>, is_terminal: bool) -> bool {
+    no_color.is_none() && is_terminal
+}
+
+/// A REPL command, entered as a line starting with ':'
+#[derive(Debug, PartialEq)]
+enum MetaCommand {
+    Load(PathBuf),
+    Reset,
+    Type(String),
+    Debug,
+    Save(PathBuf),
+}
+
+fn parse_meta_command(line: &str) -> Result<MetaCommand, String> {
+    let line = line.trim().trim_start_matches(':');
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+    let required = |what: &str| {
+        if arg.is_empty() {
+            Err(format!(":{} expects {}", name, what))
+        } else {
+            Ok(arg)
         }
+    };
+    match name {
+        "load" => required("a file").map(|path| MetaCommand::Load(PathBuf::from(path))),
+        "reset" => Ok(MetaCommand::Reset),
+        "type" => required("an expression").map(|expr| MetaCommand::Type(expr.to_string())),
+        "debug" => Ok(MetaCommand::Debug),
+        "save" => required("a file").map(|path| MetaCommand::Save(PathBuf::from(path))),
+        _ => Err(format!("Unknown command ':{}', type 'help' for a list", name)),
     }
 }
 
-fn execute_repl_line(
-    source: &str,
+/// State that lives for a whole REPL session
+struct Session {
+    vm: Rc<RefCell<VM>>,
+    // Knows the names earlier inputs defined, including their variables.
+    // Shared with the completer
+    compiler: Rc<RefCell<Compiler>>,
+    // Every input line is compiled as the same pseudo-file
     file_id: FileId,
-    vm: &mut VM,
-) -> Result<Option<brief_vm::Value>, CliError> {
-    if std::env::var("BRIEF_REPL_DEBUG").is_ok() {
-        eprintln!("REPL SOURCE:\n{}\n----", source);
-    }
-    // 1. Lex
-    let (tokens, lex_errors) = lex(source, file_id);
-    if !lex_errors.is_empty() {
-        eprintln!("Lexical errors:");
-        for err in &lex_errors {
-            eprintln!("  {:?}", err);
-        }
-        return Err(CliError::LexError);
+    // Print each input's bytecode before running it
+    debug: bool,
+    // Inputs evaluated so far, for :save
+    inputs: Vec<String>,
+    // Set once the program calls `exit`, ending the session
+    exit_code: Option<i32>,
+}
+
+impl Session {
+    fn new(vm: Rc<RefCell<VM>>) -> Self {
+        let (compiler, file_id) = repl_compiler();
+        let compiler = Rc::new(RefCell::new(compiler));
+        Self { vm, compiler, file_id, debug: false, inputs: Vec::new(), exit_code: None }
     }
 
-    // 2. Parse
-    let (program, parse_errors) = parse(tokens, file_id);
-    if !parse_errors.is_empty() {
-        eprintln!("Parse errors:");
-        for err in &parse_errors {
-            eprintln!("  {:?}", err);
+    /// Evaluate one (possibly multi-line) input
+    fn evaluate(&mut self, input: &str) -> Result<Option<Value>, CliError> {
+        self.inputs.push(input.to_string());
+        self.execute(input)
+    }
+
+    fn execute(&mut self, input: &str) -> Result<Option<Value>, CliError> {
+        // Wrap in a function for execution, then point the tokens back at
+        // the lines that were typed so errors don't count the wrapper
+        let wrapped = WrappedInput::new(input);
+        if std::env::var("BRIEF_REPL_DEBUG").is_ok() {
+            eprintln!("REPL SOURCE:\n{}\n----", wrapped.source);
+        }
+        let (mut tokens, mut errors) = lex(&wrapped.source, self.file_id);
+        for token in &mut tokens {
+            token.span = wrapped.remap(token.span);
+        }
+        for error in &mut errors {
+            error.span = wrapped.remap(error.span);
+        }
+        if !errors.is_empty() {
+            return Err(CompileError::Lex(errors).into());
+        }
+        let program = self.compiler.borrow_mut().compile_tokens(tokens, &wrapped.input, self.file_id)?;
+        for warning in program.warnings() {
+            eprintln!("{}", warning.render(self.compiler.borrow().sources()));
         }
-        return Err(CliError::ParseError);
+        self.run(&program, REPL_ENTRY).map(Some)
     }
 
-    // 3. Lower to HIR
-    let hir_program = match lower(program) {
-        Ok(hir) => hir,
-        Err(errors) => {
-            eprintln!("HIR errors:");
-            for err in &errors {
-                eprintln!("  {:?}", err);
+    /// Run a compiled program from `entry` (or its first function). Its
+    /// functions stay registered for later inputs. A call to `exit` is
+    /// recorded in `exit_code` for the loop to act on
+    fn run(&mut self, program: &CompiledProgram, entry: &str) -> Result<Value, CliError> {
+        if self.debug {
+            for chunk in program.chunks() {
+                print!("{}", chunk.disassemble());
             }
-            return Err(CliError::HirError(errors));
         }
-    };
-
-    // 4. Emit bytecode
-    let chunks = emit_bytecode(&hir_program);
-
-    if chunks.is_empty() {
-        return Ok(None);
+        match program.run_from(&mut self.vm.borrow_mut(), entry) {
+            Ok(value) => Ok(value),
+            Err(RuntimeError::Exit(code)) => {
+                self.exit_code = Some(code);
+                Ok(Value::Null)
+            }
+            // Reported once, by whoever handles the error
+            Err(e) => Err(CliError::RuntimeError(e)),
+        }
     }
 
-    // 5. Execute
-    use std::rc::Rc;
-    let target_chunk = chunks
-        .iter()
-        .find(|chunk| chunk.name == "__repl__")
-        .cloned()
-        .unwrap_or_else(|| chunks[0].clone());
-    let main_chunk = Rc::new(target_chunk);
-    vm.push_frame(main_chunk, 0);
-
-    // 6. Run VM
-    match vm.run() {
-        Ok(value) => Ok(Some(value)),
-        Err(e) => {
-            eprintln!("Runtime error: {}", e);
-            Err(CliError::RuntimeError(e))
+    /// Run a meta-command, returning the confirmation to print
+    fn run_command(&mut self, command: MetaCommand) -> Result<String, String> {
+        match command {
+            MetaCommand::Load(path) => {
+                let program = match compile(&path, &mut self.compiler.borrow_mut(), false) {
+                    Ok(Some(program)) => program,
+                    Ok(None) => return Err(format!("could not compile {}", path.display())),
+                    Err(e) => return Err(format!("could not load {}: {}", path.display(), e)),
+                };
+                self.run(&program, ENTRY).map_err(|e| e.to_string())?;
+                Ok(format!("Loaded {}", path.display()))
+            }
+            MetaCommand::Reset => {
+                *self.vm.borrow_mut() = new_vm();
+                let (compiler, file_id) = repl_compiler();
+                *self.compiler.borrow_mut() = compiler;
+                self.file_id = file_id;
+                Ok("Session reset".to_string())
+            }
+            MetaCommand::Type(expr) => match self.execute(&format!("ret type({})", expr)) {
+                Ok(Some(value)) => Ok(value.to_string()),
+                Ok(None) => Ok(Value::Null.type_name().to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            MetaCommand::Debug => {
+                self.debug = !self.debug;
+                Ok(format!("Bytecode dump {}", if self.debug { "on" } else { "off" }))
+            }
+            MetaCommand::Save(path) => {
+                let mut contents = self.inputs.join("\n");
+                contents.push('\n');
+                std::fs::write(&path, contents)
+                    .map_err(|e| format!("could not save to {}: {}", path.display(), e))?;
+                Ok(format!("Saved {} inputs to {}", self.inputs.len(), path.display()))
+            }
         }
     }
 }
 
+/// Name of the function an input's statements are wrapped into
+pub(crate) const REPL_ENTRY: &str = "__repl__";
+
+/// A compiler for a fresh session, and the pseudo-file its inputs are
+/// compiled as. Variables declared at the top of an input are kept in
+/// global slots so the next input can still see them
+fn repl_compiler() -> (Compiler, FileId) {
+    let mut compiler = Compiler::with_globals(KnownGlobals::promoting_locals_of(REPL_ENTRY));
+    let file_id = compiler.add_source("<repl>", "");
+    (compiler, file_id)
+}
+
 fn normalize_leading_whitespace(line: &str) -> String {
     let bytes = line.as_bytes();
     let mut idx = 0;
@@ -315,30 +514,98 @@ fn normalize_leading_whitespace(line: &str) -> String {
     result
 }
 
-fn build_repl_source(input: &str) -> String {
-    let normalized_lines: Vec<String> = input.lines().map(normalize_leading_whitespace).collect();
+/// Wrap an input's statements into the `__repl__` function, keeping its
+/// declarations at the top level
+pub(crate) fn build_repl_source(input: &str) -> String {
+    WrappedInput::new(input).source
+}
+
+/// A line of input with its 1-based line number
+type InputLine = (u32, String);
+
+/// Where a line of wrapped source came from: its line in the input, and
+/// how many tabs wrapping put in front of it. `None` for the lines the
+/// wrapper adds itself
+type LineOrigin = Option<(u32, u32)>;
+
+/// An input wrapped for compiling, remembering where each of its lines
+/// came from so errors point at what was typed
+struct WrappedInput {
+    source: String,
+    origins: Vec<LineOrigin>,
+    // The input with its indentation normalized, as parse errors quote it
+    input: String,
+}
+
+impl WrappedInput {
+    fn new(input: &str) -> Self {
+        let (decl_lines, stmt_lines) = split_repl_input(input);
 
-    let mut decl_lines: Vec<String> = Vec::new();
-    let mut stmt_lines: Vec<String> = Vec::new();
+        let mut source = String::new();
+        let mut origins = Vec::new();
+        for (number, line) in &decl_lines {
+            source.push_str(line);
+            source.push('\n');
+            origins.push(Some((*number, 0)));
+        }
+        let (body, body_origins) = wrap_statements(&stmt_lines);
+        source.push_str(&body);
+        origins.extend(body_origins);
+
+        let input = input.lines().map(normalize_leading_whitespace).collect::<Vec<_>>().join("\n");
+        Self { source, origins, input }
+    }
+
+    /// `span` from the wrapped source, in terms of the input. Positions
+    /// on a line the wrapper added move to the start of the next input
+    /// line, or past the end of the input
+    fn remap(&self, span: Span) -> Span {
+        Span { start: self.remap_position(span.start), end: self.remap_position(span.end), ..span }
+    }
+
+    fn remap_position(&self, position: Position) -> Position {
+        let index = (position.line as usize).saturating_sub(1);
+        if let Some(Some((line, indent))) = self.origins.get(index) {
+            return Position::new(*line, position.column.saturating_sub(*indent).max(1));
+        }
+        let line = match self.origins.iter().skip(index).flatten().next() {
+            Some((line, _)) => *line,
+            None => self.input.lines().count() as u32 + 1,
+        };
+        Position::new(line, 1)
+    }
+}
+
+/// Separate top-level declarations (with their indented bodies) from the
+/// statements around them, normalizing indentation to tabs
+fn split_repl_input(input: &str) -> (Vec<InputLine>, Vec<InputLine>) {
+    let normalized_lines: Vec<InputLine> = input
+        .lines()
+        .zip(1..)
+        .map(|(line, number)| (number, normalize_leading_whitespace(line)))
+        .collect();
+
+    let mut decl_lines: Vec<InputLine> = Vec::new();
+    let mut stmt_lines: Vec<InputLine> = Vec::new();
     let mut i = 0;
 
     while i < normalized_lines.len() {
-        let line = normalized_lines[i].clone();
+        let (number, line) = normalized_lines[i].clone();
         let trimmed = line.trim();
 
         if trimmed.is_empty() {
-            stmt_lines.push(String::new());
+            stmt_lines.push((number, String::new()));
             i += 1;
             continue;
         }
 
         let indent_level = line.chars().take_while(|c| *c == '\t').count();
         if indent_level == 0 && is_top_level_decl(trimmed) {
-            decl_lines.push(line);
+            decl_lines.push((number, line));
             i += 1;
             while i < normalized_lines.len() {
                 let next = normalized_lines[i].clone();
-                if next.trim().is_empty() || next.starts_with('\t') {
+                if next.1.trim().is_empty() || next.1.starts_with('\t') {
                     decl_lines.push(next);
                     i += 1;
                 } else {
@@ -346,32 +613,72 @@ fn build_repl_source(input: &str) -> String {
                 }
             }
         } else {
-            stmt_lines.push(line);
+            stmt_lines.push((number, line));
             i += 1;
         }
     }
+    (decl_lines, stmt_lines)
+}
 
-    let mut wrapped = String::new();
-    if !decl_lines.is_empty() {
-        wrapped.push_str(&decl_lines.join("\n"));
-        wrapped.push('\n');
-    }
-
-    wrapped.push_str("def __repl__()\n");
-    if stmt_lines.iter().all(|l| l.trim().is_empty()) {
+/// The body of the `__repl__` function that runs an input's statements,
+/// and where each of its lines came from
+fn wrap_statements(stmt_lines: &[InputLine]) -> (String, Vec<LineOrigin>) {
+    let mut wrapped = format!("def {}()\n", REPL_ENTRY);
+    let mut origins = vec![None];
+    if stmt_lines.iter().all(|(_, l)| l.trim().is_empty()) {
         wrapped.push_str("\tret null\n");
+        origins.push(None);
     } else {
-        for line in stmt_lines {
+        for (number, line) in stmt_lines {
             if line.trim().is_empty() {
                 wrapped.push('\n');
+                origins.push(Some((*number, 0)));
             } else {
                 wrapped.push('\t');
-                wrapped.push_str(&line);
+                wrapped.push_str(line);
                 wrapped.push('\n');
+                origins.push(Some((*number, 1)));
             }
         }
     }
-    wrapped
+    (wrapped, origins)
+}
+
+/// Whether more lines could still complete `input`: its last line is
+/// inside an indented block, or it stops partway through a declaration or
+/// statement. A trailing blank line always submits, so inputs with errors
+/// before the end are still run and reported
+fn is_incomplete(input: &str) -> bool {
+    let trimmed = input.trim();
+    if trimmed.is_empty() || trimmed.starts_with(':') || ["exit", "quit", "help"].contains(&trimmed) {
+        return false;
+    }
+    let last_line = input.rsplit('\n').next().unwrap_or_default();
+    if last_line.trim().is_empty() {
+        return false;
+    }
+    if last_line.starts_with(char::is_whitespace) {
+        return true;
+    }
+
+    let (decl_lines, stmt_lines) = split_repl_input(input);
+    let decls = decl_lines.iter().map(|(_, line)| line.as_str()).collect::<Vec<_>>().join("\n");
+    let decls_open = !decl_lines.is_empty() && ends_early(&decls);
+    let stmts_open = stmt_lines.iter().any(|(_, l)| !l.trim().is_empty())
+        && ends_early(wrap_statements(&stmt_lines).0.trim_end());
+    decls_open || stmts_open
+}
+
+/// Whether parsing `source` fails at its very end, where more input could
+/// still fix it
+fn ends_early(source: &str) -> bool {
+    let file_id = FileId(0);
+    let (tokens, _) = lex(source, file_id);
+    let Some(end) = tokens.last().map(|token| token.span.start) else {
+        return false;
+    };
+    let (_, errors) = parse(tokens, file_id);
+    errors.iter().any(|err| err.span.start >= end)
 }
 
 fn is_top_level_decl(line: &str) -> bool {
@@ -383,7 +690,18 @@ fn is_top_level_decl(line: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{build_repl_source, normalize_leading_whitespace};
+    use super::{
+        BriefCompleter, GREEN, MetaCommand, RESET, Session, build_repl_source, color_enabled_from, completions, history_path_from,
+        is_incomplete, load_history, new_vm, normalize_leading_whitespace, paint, parse_meta_command, word_start,
+    };
+    use brief_vm::Value;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use brief_diagnostic::FileId;
+    use rustyline::history::{DefaultHistory, History};
+    use rustyline::{Config, Editor};
+    use std::ffi::OsString;
+    use std::path::PathBuf;
 
     #[test]
     fn converts_four_spaces_to_tab() {
@@ -434,4 +752,240 @@ mod tests {
         let expected = "def add(x, y)\n\tret x + y\ndef __repl__()\n\tz := add(5, 5)\n\tprint(z)\n";
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn generated_ret_is_not_unreachable() {
+        for input in ["def add(x, y)\n    ret x + y", "x := 1\nret x"] {
+            let source = build_repl_source(input);
+            let (tokens, _) = brief_lexer::lex(&source, FileId(0));
+            let (program, _) = brief_parser::parse(tokens, FileId(0));
+            let hir = brief_hir::lower(program).expect("lowering failed");
+            assert!(brief_hir::unreachable_code(&hir).is_empty(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn top_level_ret_is_legal() {
+        let source = build_repl_source("x := 1\nret x");
+        let (tokens, _) = brief_lexer::lex(&source, FileId(0));
+        let (program, parse_errors) = brief_parser::parse(tokens, FileId(0));
+        assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+        assert!(brief_hir::lower(program).is_ok());
+    }
+
+    #[test]
+    fn complete_inputs_submit() {
+        for input in [
+            "1 + 2",
+            "x := 5",
+            "print(\"hi\")",
+            "def f()\n\tret 1\n",
+            "if (true)\n\tprint(1)\n",
+            ":load lib.bf",
+            "help",
+        ] {
+            assert!(!is_incomplete(input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn unfinished_inputs_continue() {
+        for input in [
+            "def f()",
+            "cls Point",
+            "if (x > 1)",
+            "x := 1 +",
+            "print(1,",
+            "def f()\n\tret 1",
+            "while (true)\n    x++",
+        ] {
+            assert!(is_incomplete(input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn errors_before_the_end_submit() {
+        // Running these reports the error instead of waiting for more input
+        assert!(!is_incomplete("x := ) 1"));
+        assert!(!is_incomplete("def f()\n\tx := ) 1\n"));
+        assert!(!is_incomplete("def f()\n"));
+    }
+
+    #[test]
+    fn history_path_prefers_environment_override() {
+        let home = Some(PathBuf::from("/home/brief"));
+        assert_eq!(
+            history_path_from(Some(OsString::from("/tmp/hist")), home.clone()),
+            Some(PathBuf::from("/tmp/hist"))
+        );
+        assert_eq!(history_path_from(None, home.clone()), Some(PathBuf::from("/home/brief/.brief_history")));
+        // An empty override falls back to the default
+        assert_eq!(history_path_from(Some(OsString::new()), home), Some(PathBuf::from("/home/brief/.brief_history")));
+        assert_eq!(history_path_from(None, None), None);
+    }
+
+    #[test]
+    fn history_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+
+        let mut rl: Editor<(), DefaultHistory> = Editor::with_config(Config::default()).unwrap();
+        load_history(&mut rl, &path);
+        assert!(path.exists(), "missing history file is created");
+        rl.add_history_entry("x := 1").unwrap();
+        rl.save_history(&path).unwrap();
+
+        let mut rl: Editor<(), DefaultHistory> = Editor::with_config(Config::default()).unwrap();
+        load_history(&mut rl, &path);
+        assert_eq!(rl.history().len(), 1);
+    }
+
+    #[test]
+    fn completes_keywords_and_defined_names() {
+        let names = ["count", "__repl__", "<module>", "Point", "Point::area"];
+        assert_eq!(completions("wh", names.into_iter()), vec!["while"]);
+        assert_eq!(
+            completions("c", names.into_iter()),
+            vec!["case", "char", "cls", "const", "continue", "count"]
+        );
+        assert_eq!(completions("Po", names.into_iter()), vec!["Point"]);
+        // Nothing to complete at a word boundary or on a finished word
+        assert!(completions("", names.into_iter()).is_empty());
+        assert!(completions("def", names.into_iter()).is_empty());
+
+        // Session variables only the compiler knows are offered too
+        let vm = Rc::new(RefCell::new(new_vm()));
+        let mut session = Session::new(Rc::clone(&vm));
+        let completer = BriefCompleter { vm, compiler: Rc::clone(&session.compiler) };
+        assert!(session.evaluate("x := 1\nxs := x + 1").is_ok());
+        assert_eq!(completer.candidates("x"), vec!["xs"]);
+        assert!(session.evaluate("def xor(a, b)\n\tret a != b").is_ok());
+        assert_eq!(completer.candidates("x"), vec!["xor", "xs"]);
+    }
+
+    #[test]
+    fn completion_word_starts_after_punctuation() {
+        assert_eq!(word_start("x := cou", 8), 5);
+        assert_eq!(word_start("print(wh", 8), 6);
+        assert_eq!(word_start("\tret", 4), 1);
+        assert_eq!(word_start("f(", 2), 2);
+    }
+
+    #[test]
+    fn parses_meta_commands() {
+        assert_eq!(parse_meta_command(":load lib.bf"), Ok(MetaCommand::Load(PathBuf::from("lib.bf"))));
+        assert_eq!(parse_meta_command(":type 1 + 2"), Ok(MetaCommand::Type("1 + 2".to_string())));
+        assert_eq!(parse_meta_command(":reset"), Ok(MetaCommand::Reset));
+        assert_eq!(parse_meta_command(":debug"), Ok(MetaCommand::Debug));
+        assert_eq!(parse_meta_command(":save  out.bf "), Ok(MetaCommand::Save(PathBuf::from("out.bf"))));
+        assert_eq!(parse_meta_command(":load"), Err(":load expects a file".to_string()));
+        assert!(parse_meta_command(":frobnicate").unwrap_err().starts_with("Unknown command ':frobnicate'"));
+    }
+
+    #[test]
+    fn meta_commands_act_on_the_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("lib.bf");
+        std::fs::write(&lib, "def main()\n\tret null\ndef triple(x)\n\tret x * 3\n").unwrap();
+
+        let mut session = Session::new(Rc::new(RefCell::new(new_vm())));
+        assert!(session.run_command(MetaCommand::Load(lib)).is_ok());
+        assert_eq!(session.evaluate("triple(2)").unwrap(), Some(Value::Int(6)));
+
+        assert_eq!(session.run_command(MetaCommand::Type("triple(1.5)".to_string())), Ok("dub".to_string()));
+        assert_eq!(session.run_command(MetaCommand::Debug), Ok("Bytecode dump on".to_string()));
+        assert_eq!(session.run_command(MetaCommand::Debug), Ok("Bytecode dump off".to_string()));
+
+        let saved = dir.path().join("session.bf");
+        assert!(session.run_command(MetaCommand::Save(saved.clone())).is_ok());
+        // :type is not part of the saved input
+        assert_eq!(std::fs::read_to_string(&saved).unwrap(), "triple(2)\n");
+
+        assert!(session.run_command(MetaCommand::Reset).is_ok());
+        assert_eq!(session.vm.borrow().function_names().count(), 0);
+        assert!(session.run_command(MetaCommand::Load(dir.path().join("missing.bf"))).is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_typed_lines() {
+        let mut session = Session::new(Rc::new(RefCell::new(new_vm())));
+        let error = session.evaluate("x := missing").unwrap_err().to_string();
+        assert!(error.contains("at line 1 column 6"), "{}", error);
+
+        // Declarations are moved ahead of the wrapper, statements keep their lines
+        let error = session.evaluate("n := 1\ndef f()\n\tret n\nm := nope").unwrap_err().to_string();
+        assert!(error.contains("at line 4 column 6"), "{}", error);
+
+        let error = session.evaluate("print(1 +)").unwrap_err().to_string();
+        assert!(error.contains("at line 1 column 10"), "{}", error);
+        assert!(error.contains("\n    print(1 +)\n"), "{}", error);
+
+        let error = session.evaluate("zero := 0\none := 1 / zero").unwrap_err().to_string();
+        assert!(error.contains("at line 2 column 8"), "{}", error);
+    }
+
+    #[test]
+    fn exit_is_recorded_instead_of_ending_the_process() {
+        let mut session = Session::new(Rc::new(RefCell::new(new_vm())));
+        assert_eq!(session.evaluate("1 + 1").unwrap(), Some(Value::Int(2)));
+        assert_eq!(session.exit_code, None);
+        assert_eq!(session.evaluate("exit(4)").unwrap(), Some(Value::Null));
+        assert_eq!(session.exit_code, Some(4));
+    }
+
+    #[test]
+    fn variables_persist_between_inputs() {
+        let mut session = Session::new(Rc::new(RefCell::new(new_vm())));
+        assert_eq!(session.evaluate("x := 5").unwrap(), Some(Value::Null));
+        assert_eq!(session.evaluate("x + 1").unwrap(), Some(Value::Int(6)));
+        assert!(session.evaluate("x = x * 2").is_ok());
+        assert_eq!(session.evaluate("x").unwrap(), Some(Value::Int(10)));
+
+        // Functions defined later see the variable as a global
+        assert!(session.evaluate("def bump()\n\tx = x + 1").is_ok());
+        assert!(session.evaluate("bump()").is_ok());
+        assert_eq!(session.evaluate("x").unwrap(), Some(Value::Int(11)));
+
+        // Redeclaring a variable reuses its global slot rather than taking a new one
+        for n in 0..300 {
+            assert!(session.evaluate(&format!("x := {}", n)).is_ok(), "x := {}", n);
+        }
+        assert_eq!(session.evaluate("x").unwrap(), Some(Value::Int(299)));
+        assert!(session.evaluate("fresh := 1").is_ok());
+        assert_eq!(session.evaluate("bump()\nx").unwrap(), Some(Value::Int(300)));
+
+        // A failed input does not leave its declarations behind
+        assert!(session.evaluate("y := missing").is_err());
+        assert!(session.evaluate("y").is_err());
+
+        assert!(session.evaluate("const limit := 3").is_ok());
+        assert!(session.evaluate("limit = 4").is_err());
+        assert_eq!(session.evaluate("limit").unwrap(), Some(Value::Int(3)));
+    }
+
+    #[test]
+    fn loaded_globals_and_session_variables_share_slots() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("lib.bf");
+        std::fs::write(&lib, "count := 7\ncls Counter\n\tint n = 0\ndef main()\n\tret null\n").unwrap();
+
+        let mut session = Session::new(Rc::new(RefCell::new(new_vm())));
+        assert!(session.evaluate("total := 1").is_ok());
+        assert!(session.run_command(MetaCommand::Load(lib)).is_ok());
+        assert_eq!(session.evaluate("total + count").unwrap(), Some(Value::Int(8)));
+        assert_eq!(session.evaluate("c := Counter()\nc.n + 2").unwrap(), Some(Value::Int(2)));
+
+        assert!(session.run_command(MetaCommand::Reset).is_ok());
+        assert!(session.evaluate("total").is_err());
+    }
+
+    #[test]
+    fn colors_only_on_terminals_without_no_color() {
+        assert!(color_enabled_from(None, true));
+        assert!(!color_enabled_from(None, false));
+        assert!(!color_enabled_from(Some(OsString::from("1")), true));
+
+        assert_eq!(paint("6", GREEN, true), format!("{}6{}", GREEN, RESET));
+        assert_eq!(paint("6", GREEN, false), "6");
+    }
 }
@@ -1,77 +1,269 @@
 use std::path::Path;
-use std::rc::Rc;
-use brief_lexer::lex;
-use brief_parser::parse;
-use brief_hir::{lower, emit_bytecode};
-use brief_vm::VM;
-use brief_runtime::Runtime;
-use brief_diagnostic::FileId;
-use crate::error::{CliError, ExitCode};
+use brief_hir::typecheck;
+use brief_bytecode::{deserialize_module, serialize_module};
+use brief_driver::{CompileError, CompiledProgram, Compiler, ENTRY, RuntimeError, Value, new_vm, run_chunks};
+use brief_diagnostic::{Diagnostic, FileId, SourceMap};
+use brief_module::ModuleError;
+use crate::error::{CliError, ErrorKind, ErrorReport, ExitCode};
+use crate::repl::{REPL_ENTRY, build_repl_source};
+
+/// Options controlling how a file is run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    /// Type check annotated code first; type errors stop the run
+    pub typecheck: bool,
+    /// Print lex, parse, HIR and runtime errors to stderr as one JSON
+    /// object per line instead of as text. Warnings are left out
+    pub json_errors: bool,
+}
+
+/// How `brief check` prints diagnostics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// Human-readable messages on stderr
+    #[default]
+    Text,
+    /// One JSON object per line on stdout, for editors
+    Json,
+}
 
 /// Run a Brief source file
 pub fn run_file(path: &Path) -> Result<ExitCode, CliError> {
-    // 1. Read file
-    let source = std::fs::read_to_string(path)?;
-    let file_id = FileId(0); // For now, use a single file ID
-    
-    // 2. Lex
-    let (tokens, lex_errors) = lex(&source, file_id);
-    if !lex_errors.is_empty() {
-        eprintln!("Lexical errors:");
-        for err in &lex_errors {
-            eprintln!("  {:?}", err);
-        }
+    run_file_with(path, RunOptions::default())
+}
+
+/// Run a Brief source file with the given options
+pub fn run_file_with(path: &Path, options: RunOptions) -> Result<ExitCode, CliError> {
+    let mut compiler = Compiler::new();
+    let Some(program) = compile(path, &mut compiler, options.json_errors)? else {
+        return Ok(ExitCode::CompileError);
+    };
+
+    // Optional static type check
+    if options.typecheck && !report_diagnostics(&typecheck(program.hir()), compiler.sources(), options.json_errors) {
         return Ok(ExitCode::CompileError);
     }
-    
-    // 3. Parse
-    let (program, parse_errors) = parse(tokens, file_id);
-    if !parse_errors.is_empty() {
-        eprintln!("Parse errors:");
-        for err in &parse_errors {
-            eprintln!("  {:?}", err);
+
+    let result = program.run(&mut new_vm());
+    Ok(exit_code(result, compiler.sources(), options.json_errors))
+}
+
+/// Run inline source, as passed to `brief -e`. Statements outside a
+/// declaration are wrapped into a function the same way the REPL does it
+pub fn run_string(source: &str) -> Result<ExitCode, CliError> {
+    let mut compiler = Compiler::new();
+    let file_id = compiler.add_source("<eval>", build_repl_source(source));
+    let Some(program) = compile_reported(&mut compiler, file_id, false) else {
+        return Ok(ExitCode::CompileError);
+    };
+    let result = program.run_from(&mut new_vm(), REPL_ENTRY);
+    Ok(exit_code(result, compiler.sources(), false))
+}
+
+/// Run a module written by `brief compile`. Nothing is lexed or parsed;
+/// it starts from the chunk named `main`, or the first chunk if none is
+pub fn run_bytecode(path: &Path) -> Result<ExitCode, CliError> {
+    let bytes = std::fs::read(path)?;
+    let chunks = deserialize_module(&bytes).map_err(CliError::InvalidBytecode)?;
+    let result = run_chunks(&mut new_vm(), Vec::new(), &chunks, ENTRY);
+    Ok(exit_code(result, &SourceMap::new(), false))
+}
+
+/// The exit code for how a run ended, reporting any runtime error
+fn exit_code(result: Result<Value, RuntimeError>, sources: &SourceMap, json_errors: bool) -> ExitCode {
+    match result {
+        Ok(_) => ExitCode::Success,
+        Err(RuntimeError::Exit(code)) => ExitCode::Requested(code),
+        Err(e) => {
+            report_runtime_error(&e, sources, json_errors);
+            ExitCode::RuntimeError
         }
+    }
+}
+
+/// Print a runtime error, pointing at the file and line that raised it
+/// when known
+pub(crate) fn report_runtime_error(error: &RuntimeError, sources: &SourceMap, json_errors: bool) {
+    if json_errors {
+        eprintln!("{}", ErrorReport::from_runtime(error, sources).to_json());
+        return;
+    }
+    let path = error.span().and_then(|span| Some((sources.file_path(span.file_id)?, span)));
+    match (error.without_location(), path) {
+        // A failed check reads as the script's own message
+        (RuntimeError::AssertionFailed { message }, Some((path, span))) => {
+            eprintln!("Assertion failed at {}:{}: {}", path, span.start.line, message)
+        }
+        (RuntimeError::Panic { message }, Some((path, span))) => {
+            eprintln!("Panic at {}:{}: {}", path, span.start.line, message)
+        }
+        (error, Some((path, span))) => eprintln!(
+            "Runtime error: {} at {}:{}:{}",
+            error, path, span.start.line, span.start.column
+        ),
+        (_, None) => eprintln!("Runtime error: {}", error),
+    }
+}
+
+/// Check a Brief source file's syntax, names and types without running it.
+/// No bytecode is emitted
+pub fn check_file(path: &Path) -> Result<ExitCode, CliError> {
+    let mut compiler = Compiler::new();
+    let Some(program) = compile(path, &mut compiler, false)? else {
         return Ok(ExitCode::CompileError);
+    };
+    if report_diagnostics(&typecheck(program.hir()), compiler.sources(), false) {
+        Ok(ExitCode::Success)
+    } else {
+        Ok(ExitCode::CompileError)
     }
-    
-    // 4. Lower to HIR
-    let hir_program = match lower(program) {
-        Ok(hir) => hir,
-        Err(errors) => {
-            eprintln!("HIR errors:");
-            for err in &errors {
-                eprintln!("  {:?}", err);
-            }
+}
+
+/// Check a file like [`check_file`], printing diagnostics in `format`
+pub fn check_file_with(path: &Path, format: DiagnosticFormat) -> Result<ExitCode, CliError> {
+    if format == DiagnosticFormat::Text {
+        return check_file(path);
+    }
+    let mut compiler = Compiler::new();
+    let diagnostics = check_diagnostics(path, &mut compiler)?;
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic.to_json(compiler.sources()));
+    }
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        Ok(ExitCode::CompileError)
+    } else {
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Everything `brief check` reports for a file, without printing it: the
+/// errors of the first stage that fails, or else lowering warnings and
+/// type errors
+pub fn check_diagnostics(path: &Path, compiler: &mut Compiler) -> Result<Vec<Diagnostic>, CliError> {
+    let source = std::fs::read_to_string(path)?;
+    let file_id = compiler.add_source(path.display().to_string(), source);
+    match compiler.compile(file_id) {
+        Ok(program) => {
+            let mut diagnostics = program.warnings().to_vec();
+            diagnostics.extend(typecheck(program.hir()));
+            Ok(diagnostics)
+        }
+        Err(error) => Ok(error.to_diagnostics()),
+    }
+}
+
+/// Compile a Brief source file and print the disassembly of every chunk
+/// instead of running it
+pub fn dump_bytecode(path: &Path) -> Result<(), CliError> {
+    let Some(program) = compile(path, &mut Compiler::new(), false)? else {
+        return Err(CliError::CompileError);
+    };
+    for (i, chunk) in program.chunks().iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", chunk.disassemble());
+    }
+    Ok(())
+}
+
+/// Lower a Brief source file and print its HIR tree instead of running it
+pub fn dump_hir(path: &Path) -> Result<(), CliError> {
+    let Some(program) = compile(path, &mut Compiler::new(), false)? else {
+        return Err(CliError::CompileError);
+    };
+    print!("{}", program.hir().dump());
+    Ok(())
+}
+
+/// Compile a Brief source file to a bytecode module at `dst`, without
+/// running it. The modules it imports are compiled into the same file,
+/// ahead of its own chunks
+pub fn compile_file(src: &Path, dst: &Path) -> Result<(), CliError> {
+    let Some(program) = compile(src, &mut Compiler::new(), false)? else {
+        return Err(CliError::CompileError);
+    };
+    let mut chunks = program.modules().to_vec();
+    chunks.extend_from_slice(program.chunks());
+    std::fs::write(dst, serialize_module(&chunks))?;
+    Ok(())
+}
+
+/// Rewrite a Brief source file in canonical formatting. With `check` the
+/// file is left alone, and the exit code says whether it needed changes
+pub fn format_file(path: &Path, check: bool) -> Result<ExitCode, CliError> {
+    let source = std::fs::read_to_string(path)?;
+    let formatted = match brief_fmt::format(&source) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            eprintln!("{}", e);
             return Ok(ExitCode::CompileError);
         }
     };
-    
-    // 5. Emit bytecode
-    let chunks = emit_bytecode(&hir_program);
-    
-    if chunks.is_empty() {
-        // No functions to execute - this is OK for empty programs
+    if formatted == source {
         return Ok(ExitCode::Success);
     }
-    
-    // 6. Create VM with runtime
-    let mut vm = VM::new();
-    let runtime = Runtime::new();
-    vm.set_runtime(Box::new(runtime));
-    
-    // 7. Execute chunks
-    // For now, execute the first chunk (main function)
-    // TODO: Find and execute main function properly
-    let main_chunk = Rc::new(chunks[0].clone());
-    vm.push_frame(main_chunk, 0);
-    
-    // 8. Run VM
-    match vm.run() {
-        Ok(_) => Ok(ExitCode::Success),
-        Err(e) => {
-            eprintln!("Runtime error: {}", e);
-            Ok(ExitCode::RuntimeError)
+    if check {
+        eprintln!("{} is not formatted", path.display());
+        return Ok(ExitCode::CompileError);
+    }
+    std::fs::write(path, formatted)?;
+    Ok(ExitCode::Success)
+}
+
+/// Print diagnostics, returning whether the program may still run. With
+/// `json_errors` only the errors are printed, as HIR error reports
+fn report_diagnostics(diagnostics: &[Diagnostic], sources: &SourceMap, json_errors: bool) -> bool {
+    for diagnostic in diagnostics {
+        if !json_errors {
+            eprintln!("{}", diagnostic.render(sources));
+        } else if diagnostic.is_error() {
+            eprintln!("{}", ErrorReport::from_diagnostic(ErrorKind::Hir, diagnostic, sources).to_json());
         }
     }
+    !diagnostics.iter().any(Diagnostic::is_error)
+}
+
+/// Print why a program didn't compile, or one JSON report per error with
+/// `json_errors`
+fn report_compile_error(error: &CompileError, sources: &SourceMap, json_errors: bool) {
+    if !json_errors {
+        eprintln!("{}", error);
+        return;
+    }
+    let kind = match error {
+        CompileError::Lex(_) | CompileError::Module(ModuleError::Lex { .. }) => ErrorKind::Lex,
+        CompileError::Parse(_) | CompileError::Module(ModuleError::Parse { .. }) => ErrorKind::Parse,
+        _ => ErrorKind::Hir,
+    };
+    for diagnostic in error.to_diagnostics() {
+        eprintln!("{}", ErrorReport::from_diagnostic(kind, &diagnostic, sources).to_json());
+    }
+}
+
+/// Read a file and compile it with `compiler`, registering it there.
+/// Errors and warnings are printed, and `None` is returned if it doesn't
+/// compile
+pub(crate) fn compile(
+    path: &Path,
+    compiler: &mut Compiler,
+    json_errors: bool,
+) -> Result<Option<CompiledProgram>, CliError> {
+    let source = std::fs::read_to_string(path)?;
+    let file_id = compiler.add_source(path.display().to_string(), source);
+    Ok(compile_reported(compiler, file_id, json_errors))
 }
 
+/// Compile the registered file `file_id`, printing its errors and warnings
+fn compile_reported(compiler: &mut Compiler, file_id: FileId, json_errors: bool) -> Option<CompiledProgram> {
+    match compiler.compile(file_id) {
+        Ok(program) => {
+            report_diagnostics(program.warnings(), compiler.sources(), json_errors);
+            Some(program)
+        }
+        Err(error) => {
+            report_compile_error(&error, compiler.sources(), json_errors);
+            None
+        }
+    }
+}
@@ -46,7 +46,7 @@ fn test_run_invalid_syntax() {
     assert!(result.is_ok());
     if let Ok(exit_code) = result {
         // Should be compile error
-        assert_eq!(exit_code as i32, 1);
+        assert_eq!(exit_code.code(), 1);
     }
 }
 
@@ -84,3 +84,557 @@ fn test_run_with_variables() {
     assert!(result.is_ok());
 }
 
+
+#[test]
+fn test_run_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("exit.bf");
+    
+    fs::write(&file_path, "def test()\n\texit(3)\n\tprint(\"unreachable\")\n").unwrap();
+    
+    let exit_code = run::run_file(&file_path).unwrap();
+    assert_eq!(exit_code, brief_cli::error::ExitCode::Requested(3));
+    assert_eq!(exit_code.code(), 3);
+}
+
+#[test]
+fn test_binary_exit_status() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("exit.bf");
+    
+    fs::write(&file_path, "def test()\n\texit(3)\n").unwrap();
+    
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg(&file_path)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+}
+
+#[test]
+fn test_binary_exit_from_env_check() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("env.bf");
+    fs::write(
+        &file_path,
+        "def main()\n\tprint(cwd())\n\tcheck(\"BRIEF_TEST_FLAG\")\n\tprint(\"unreachable\")\ndef check(name)\n\tif (env(name) == \"yes\" && env(\"BRIEF_UNSET_VAR\") == null)\n\t\texit(7)\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg(&file_path)
+        .current_dir(temp_dir.path())
+        .env("BRIEF_TEST_FLAG", "yes")
+        .env_remove("BRIEF_UNSET_VAR")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(7));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let dir = fs::canonicalize(temp_dir.path()).unwrap();
+    assert_eq!(stdout, format!("{}\n", dir.display()));
+}
+
+#[test]
+fn test_binary_repl_reports_runtime_errors_once() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("repl")
+        .env("BRIEF_HISTORY", temp_dir.path().join("history"))
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"zero := 0\n\nx := 5 / zero\n\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("Division by zero").count(), 1, "unexpected output: {}", stderr);
+    assert!(stderr.contains("Error: Runtime error: Division by zero at line 1 column 6"), "unexpected output: {}", stderr);
+}
+
+#[test]
+fn test_binary_println_without_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("blank.bf");
+    fs::write(&file_path, "def main()\n\tprints(1)\n\tprintln()\n\tprintln()\n\tprint(2)\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n\n2\n");
+}
+
+#[test]
+fn test_binary_version_flag() {
+    for flag in ["--version", "-V"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+            .arg(flag)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains(env!("CARGO_PKG_VERSION")), "unexpected output: {}", stdout);
+    }
+}
+
+#[test]
+fn test_check_file_reports_type_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let bad = temp_dir.path().join("bad.bf");
+    fs::write(&bad, "def test()\n\tint x := \"hi\"\n").unwrap();
+    assert_eq!(run::check_file(&bad).unwrap(), brief_cli::error::ExitCode::CompileError);
+
+    let good = temp_dir.path().join("good.bf");
+    fs::write(&good, "def test()\n\tint x := 1\n\twhile (x)\n\t\tx = x - 1\n").unwrap();
+    // Warnings alone do not fail the check
+    assert_eq!(run::check_file(&good).unwrap(), brief_cli::error::ExitCode::Success);
+}
+
+#[test]
+fn test_typecheck_flag_stops_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("typed.bf");
+    fs::write(&file_path, "def test()\n\tint x := \"hi\"\n\texit(3)\n").unwrap();
+
+    // Without the flag the program runs as before
+    assert_eq!(run::run_file(&file_path).unwrap(), brief_cli::error::ExitCode::Requested(3));
+    let options = run::RunOptions { typecheck: true, ..Default::default() };
+    assert_eq!(run::run_file_with(&file_path, options).unwrap(), brief_cli::error::ExitCode::CompileError);
+}
+
+#[test]
+fn test_binary_check_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("check.bf");
+    fs::write(&file_path, "def add(int x, int y) -> int\n\tret x + y\ndef test()\n\tadd(1, \"2\")\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("check")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error: Argument 'y' of 'add' expects int, found str"), "unexpected output: {}", stderr);
+    // Diagnostics name the file they belong to
+    assert!(stderr.contains(&format!("{}:4:10", file_path.display())), "unexpected output: {}", stderr);
+}
+
+#[test]
+fn test_binary_check_flag_does_not_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let good = temp_dir.path().join("good.bf");
+    fs::write(&good, "def main()\n\tprint(\"ran\")\n").unwrap();
+    let bad = temp_dir.path().join("bad.bf");
+    fs::write(&bad, "def main()\n\tprint(missing)\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("--check")
+        .arg(&good)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty(), "program should not run: {}", String::from_utf8_lossy(&output.stdout));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("--check")
+        .arg(&bad)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("missing"), "unexpected output: {}", stderr);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["-e", "print(1)\nbreak"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("'break' outside of a loop"), "unexpected output: {}", stderr);
+}
+
+#[test]
+fn test_binary_dump_hir_prints_the_lowered_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("hir.bf");
+    fs::write(&file_path, "def main()\n\tn := 1\n\tn++\n\tprint(\"ran\")\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("--dump-hir")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("HirProgram\n  declarations:\n    FuncDecl\n      name: main\n"), "unexpected output: {}", stdout);
+    // n++ is desugared to an assignment before the dump
+    assert!(stdout.contains("Assign"), "unexpected output: {}", stdout);
+    assert!(stdout.contains("callee: Variable(print, "), "unexpected output: {}", stdout);
+    assert!(!stdout.contains("\nran\n"), "program should not run: {}", stdout);
+
+    fs::write(&file_path, "def main()\n\tret missing\n").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("--dump-hir")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_binary_dump_bytecode_does_not_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("dump.bf");
+    fs::write(&file_path, "def main()\n\tprint(\"ran\")\ndef helper()\n\tret 1\n").unwrap();
+
+    for flag in ["--dump-bytecode", "--disasm"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+            .arg(flag)
+            .arg(&file_path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", flag);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("Chunk: main\n"), "unexpected output: {}", stdout);
+        assert!(stdout.contains("Chunk: helper\n"), "unexpected output: {}", stdout);
+        assert!(stdout.contains("CALL"), "unexpected output: {}", stdout);
+        assert!(!stdout.contains("\nran\n"), "program should not run: {}", stdout);
+    }
+
+    let broken = temp_dir.path().join("broken.bf");
+    fs::write(&broken, "def main()\n\tret missing\n").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("--disasm")
+        .arg(&broken)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_binary_reports_parse_errors_with_source() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("broken.bf");
+    fs::write(&file_path, "def test()\n\tx := (1 + ) * 2\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Parse error at line 2 column 12: Expected expression"), "unexpected output: {}", stderr);
+    assert!(stderr.contains("\tx := (1 + ) * 2"), "unexpected output: {}", stderr);
+}
+
+#[test]
+fn test_run_string_exit_codes() {
+    use brief_cli::error::ExitCode;
+    assert_eq!(run::run_string("x := 2 + 2").unwrap(), ExitCode::Success);
+    assert_eq!(run::run_string("def sq(x)\n\tret x * x\nassert(sq(3) == 9)").unwrap(), ExitCode::Success);
+    assert_eq!(run::run_string("exit(3)").unwrap(), ExitCode::Requested(3));
+    assert_eq!(run::run_string("print(").unwrap(), ExitCode::CompileError);
+    assert_eq!(run::run_string("assert(false)").unwrap(), ExitCode::RuntimeError);
+}
+
+#[test]
+fn test_binary_eval_prints_result() {
+    for flag in ["-e", "--eval"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+            .arg(flag)
+            .arg("print(2 + 2)")
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "4\n");
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["-e", "print(missing)"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("missing"), "unexpected output: {}", stderr);
+}
+
+#[test]
+fn test_compile_file_writes_module() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("prog.bf");
+    fs::write(&src, "def helper()\n\tret 1\ndef main()\n\tprint(helper())\n").unwrap();
+    let dst = temp_dir.path().join("prog.bfc");
+
+    run::compile_file(&src, &dst).unwrap();
+    let chunks = brief_bytecode::deserialize_module(&fs::read(&dst).unwrap()).unwrap();
+    let names: Vec<_> = chunks.iter().map(|chunk| chunk.name.as_str()).collect();
+    assert!(names.contains(&"helper") && names.contains(&"main"), "{:?}", names);
+
+    let bad = temp_dir.path().join("bad.bf");
+    fs::write(&bad, "def main()\n\tprint(missing)\n").unwrap();
+    let bad_dst = temp_dir.path().join("bad.bfc");
+    assert!(matches!(run::compile_file(&bad, &bad_dst), Err(brief_cli::error::CliError::CompileError)));
+    assert!(!bad_dst.exists());
+}
+
+#[test]
+fn test_binary_compile_output_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("prog.bf");
+    fs::write(&src, "def main()\n\tprint(\"ran\")\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("compile")
+        .arg(&src)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty(), "program should not run: {}", String::from_utf8_lossy(&output.stdout));
+    assert!(temp_dir.path().join("prog.bfc").exists());
+
+    let custom = temp_dir.path().join("out.bin");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["compile", "-o"])
+        .arg(&custom)
+        .arg(&src)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(brief_bytecode::deserialize_module(&fs::read(&custom).unwrap()).is_ok());
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["compile", "--output"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_run_bytecode_after_compile() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("prog.bf");
+    fs::write(&src, "def helper(x)\n\tret x * 2\ndef main()\n\tprint(helper(21))\n\texit(3)\n").unwrap();
+    let dst = temp_dir.path().join("prog.bfc");
+    run::compile_file(&src, &dst).unwrap();
+    // The source is no longer needed
+    fs::remove_file(&src).unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("run")
+        .arg(&dst)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_run_bytecode_rejects_invalid_files() {
+    use brief_bytecode::DecodeError;
+    use brief_cli::error::CliError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("prog.bf");
+    fs::write(&src, "def main()\n\tprint(1)\n").unwrap();
+    assert!(matches!(run::run_bytecode(&src), Err(CliError::InvalidBytecode(DecodeError::BadMagic))));
+
+    let dst = temp_dir.path().join("prog.bfc");
+    run::compile_file(&src, &dst).unwrap();
+    let bytes = fs::read(&dst).unwrap();
+    fs::write(&dst, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(matches!(run::run_bytecode(&dst), Err(CliError::InvalidBytecode(_))));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .arg("run")
+        .arg(&src)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("brief file.bf"));
+}
+
+#[test]
+fn test_binary_check_json_reports_parse_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("broken.bf");
+    fs::write(&file_path, "def main()\n\tx := 1 +\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["--check", "--format=json"])
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let file = file_path.display().to_string();
+    assert_eq!(
+        stdout.lines().next().unwrap(),
+        format!(
+            r#"{{"file":"{}","start":{{"line":3,"column":1}},"end":{{"line":3,"column":1}},"severity":"error","message":"Expected expression"}}"#,
+            file
+        )
+    );
+
+    fs::write(&file_path, "def main()\n\tprint(1)\n").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+        .args(["check", "--format=json"])
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_binary_json_errors_reports_each_stage() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("errors.bf");
+    let file = file_path.display().to_string();
+    let run = |source: &str| {
+        fs::write(&file_path, source).unwrap();
+        std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+            .arg("--json-errors")
+            .arg(&file_path)
+            .output()
+            .unwrap()
+    };
+    let first_error = |output: &std::process::Output| -> serde_json::Value {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        serde_json::from_str(stderr.lines().next().unwrap()).unwrap()
+    };
+
+    let output = run("def main()\n\tx := \"abc\n");
+    assert_eq!(output.status.code(), Some(1));
+    let error = first_error(&output);
+    assert_eq!(error["type"], "lex");
+    assert_eq!(error["file"], file.as_str());
+    assert_eq!((error["line"].as_u64(), error["column"].as_u64()), (Some(2), Some(8)));
+
+    let output = run("def main()\n\tx := 1 +\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        first_error(&output),
+        serde_json::json!({"type": "parse", "message": "Expected expression", "file": file, "line": 3, "column": 1})
+    );
+
+    let output = run("def main()\n\tprint(y)\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(first_error(&output)["type"], "hir");
+
+    // Program output stays on stdout
+    let output = run("def main()\n\tprint(\"hi\")\n\tz := 0\n\tx := 1 / z\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+    assert_eq!(
+        first_error(&output),
+        serde_json::json!({"type": "runtime", "message": "Division by zero", "file": file, "line": 4, "column": 7})
+    );
+}
+
+#[test]
+fn test_binary_runs_imports_next_to_the_file() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join("shapes")).unwrap();
+    fs::write(temp_dir.path().join("math.bf"), "def add(a, b)\n\tret a + b\n").unwrap();
+    fs::write(temp_dir.path().join("shapes/mod.bf"), "sides := 4\n").unwrap();
+    let file_path = temp_dir.path().join("app.bf");
+    fs::write(&file_path, "import (math, shapes)\ndef main()\n\tprint(add(sides, 1))\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief")).arg(&file_path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5\n");
+
+    fs::write(&file_path, "import missing\ndef main()\n\tprint(1)\n").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief")).arg(&file_path).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Module 'missing' not found"));
+}
+
+#[test]
+fn test_binary_runs_aliased_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("math.bf"), "unit := 10\ndef add(a, b)\n\tret a + b\n").unwrap();
+    let file_path = temp_dir.path().join("app.bf");
+    fs::write(&file_path, "import math as m\ndef main()\n\tprint(m.add(m.unit, 1))\n\tprint(m.sub)\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief")).arg(&file_path).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "11\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no field 'sub'"));
+}
+
+#[test]
+fn test_binary_print_variants_join_their_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("print.bf");
+    fs::write(
+        &file_path,
+        "def main()\n\tprint(\"a\", 1, 2.5, split(\"x\", \",\"))\n\tprints(\"no\", \"newline\")\n\tprints(\"!\")\n\tprintln(format(\"{:>4}\", 7))\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_brief")).arg(&file_path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a 1 2.5 [\"x\"]\nno newline!   7\n");
+}
+
+#[test]
+fn test_binary_failed_assert_and_panic_exit_with_runtime_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("checks.bf");
+    let file = file_path.display().to_string();
+    let run = |source: &str| {
+        fs::write(&file_path, source).unwrap();
+        std::process::Command::new(env!("CARGO_BIN_EXE_brief")).arg(&file_path).output().unwrap()
+    };
+
+    let output = run("def main()\n\tassert(1 < 2)\n\tprint(\"ok\")\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+
+    let output = run("def main()\n\tx := 3\n\tassert(x < 2, \"x is too big\")\n\tprint(\"unreached\")\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), format!("Assertion failed at {}:3: x is too big\n", file));
+
+    let output = run("def main()\n\tpanic(\"giving up\")\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), format!("Panic at {}:2: giving up\n", file));
+}
+
+#[test]
+fn test_binary_fmt_rewrites_and_checks() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("messy.bf");
+    fs::write(&file_path, "def main()\n\ty:=0\n\n\n\tx:=1+y // sum\n\tprint( x )\n").unwrap();
+    let fmt = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_brief"))
+            .arg("fmt")
+            .args(args)
+            .arg(&file_path)
+            .status()
+            .unwrap()
+            .code()
+    };
+
+    assert_eq!(fmt(&["--check"]), Some(1));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "def main()\n\ty:=0\n\n\n\tx:=1+y // sum\n\tprint( x )\n");
+    assert_eq!(fmt(&[]), Some(0));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "def main()\n\ty := 0\n\n\tx := 1 + y // sum\n\tprint(x)\n");
+    assert_eq!(fmt(&["--check"]), Some(0));
+
+    // Source that doesn't parse is reported and left alone
+    fs::write(&file_path, "def main(\n").unwrap();
+    assert_eq!(fmt(&[]), Some(1));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "def main(\n");
+}
@@ -46,6 +46,7 @@ pub fn run_code(source: &str) -> Result<brief_vm::Value, String> {
     let mut vm = VM::new();
     let runtime = Runtime::new();
     vm.set_runtime(Box::new(runtime));
+    vm.register_functions(&chunks);
 
     let main_chunk = Rc::new(chunks[0].clone());
     vm.push_frame(main_chunk, 0);
@@ -15,7 +15,7 @@ fn test_if_statement() {
 
 #[test]
 fn test_while_loop() {
-    let source = "def test()\n\tx := 0\n\twhile (x < 3)\n\t\tx := x + 1\n\tx\n";
+    let source = "def test()\n\tx := 0\n\twhile (x < 3)\n\t\tx = x + 1\n\tx\n";
     let result = run_code(source);
     assert!(result.is_ok(), "expected Ok result, got {:?}", result);
     if let Ok(brief_vm::Value::Int(n)) = result {
@@ -27,7 +27,7 @@ fn test_while_loop() {
 
 #[test]
 fn test_for_loop() {
-    let source = "def test()\n\tx := 0\n\tfor (i := 0; i < 3; i := i + 1)\n\t\tx := x + 1\n\tx\n";
+    let source = "def test()\n\tx := 0\n\tfor (i := 0; i < 3; i := i + 1)\n\t\tx = x + 1\n\tx\n";
     let result = run_code(source);
     assert!(result.is_ok(), "expected Ok result, got {:?}", result);
     if let Ok(brief_vm::Value::Int(n)) = result {
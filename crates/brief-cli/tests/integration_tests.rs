@@ -81,6 +81,16 @@ fn test_builtin_int_cast() {
     }
 }
 
+#[test]
+fn test_builtin_contains() {
+    let source = "def test()\n\tcontains(\"hello\", \"ell\")\n";
+    let result = run_code(source);
+    if let Err(e) = &result {
+        eprintln!("Error: {}", e);
+    }
+    assert_eq!(result, Ok(brief_vm::Value::Bool(true)));
+}
+
 #[test]
 fn test_builtin_str_cast() {
     let source = "def test()\n\tstr(42)\n";
@@ -1,5 +1,6 @@
 use brief_runtime::Runtime;
 use brief_vm::BuiltinRuntime;
+use std::rc::Rc;
 
 #[test]
 fn test_builtin_len_string() {
@@ -17,6 +18,7 @@ fn test_builtin_len_string() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_builtin_int_cast_through_vm() {
     let runtime = Runtime::new();
@@ -76,6 +78,66 @@ fn test_builtin_concat_through_vm() {
     }
 }
 
+#[test]
+fn test_builtin_contains_string() {
+    let runtime = Runtime::new();
+    
+    let args = vec![
+        brief_vm::Value::Str("hello world".to_string()),
+        brief_vm::Value::Str("lo w".to_string()),
+    ];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(true)));
+    
+    let args = vec![
+        brief_vm::Value::Str("hello".to_string()),
+        brief_vm::Value::Str("xyz".to_string()),
+    ];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(false)));
+}
+
+#[test]
+fn test_builtin_contains_array() {
+    let runtime = Runtime::new();
+    let array = brief_vm::Value::array(vec![
+        brief_vm::Value::Int(1),
+        brief_vm::Value::Str("two".to_string()),
+        brief_vm::Value::Double(3.5),
+    ]);
+    
+    let args = vec![array.clone(), brief_vm::Value::Str("two".to_string())];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(true)));
+    
+    let args = vec![array, brief_vm::Value::Int(2)];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(false)));
+}
+
+#[test]
+fn test_builtin_contains_map() {
+    let runtime = Runtime::new();
+    let map = brief_vm::Value::map(vec![
+        (brief_vm::Value::Str("name".to_string()), brief_vm::Value::Str("sparky".to_string())),
+        (brief_vm::Value::Int(7), brief_vm::Value::Bool(true)),
+    ]);
+    
+    // Keys match, values do not
+    let args = vec![map.clone(), brief_vm::Value::Str("name".to_string())];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(true)));
+    
+    let args = vec![map.clone(), brief_vm::Value::Int(7)];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(true)));
+    
+    let args = vec![map, brief_vm::Value::Str("sparky".to_string())];
+    assert_eq!(runtime.call_builtin("contains", &args), Ok(brief_vm::Value::Bool(false)));
+}
+
+#[test]
+fn test_builtin_contains_invalid_haystack() {
+    let runtime = Runtime::new();
+    let args = vec![brief_vm::Value::Int(42), brief_vm::Value::Int(4)];
+    let result = runtime.call_builtin("contains", &args);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })));
+}
+
 #[test]
 fn test_unknown_builtin() {
     let runtime = Runtime::new();
@@ -84,3 +146,139 @@ fn test_unknown_builtin() {
     assert!(result.is_err());
 }
 
+
+/// Compile source into a VM with the runtime installed, returning the named
+/// function as a closure value
+fn vm_with_function(source: &str, name: &str) -> (brief_vm::VM, brief_vm::Value) {
+    let file_id = brief_diagnostic::FileId(0);
+    let (tokens, _) = brief_lexer::lex(source, file_id);
+    let (program, _) = brief_parser::parse(tokens, file_id);
+    let hir = brief_hir::lower(program).expect("lowering should succeed");
+    let chunks = brief_hir::emit_bytecode(&hir);
+    let mut vm = brief_vm::VM::new();
+    vm.set_runtime(Box::new(Runtime::new()));
+    vm.register_functions(&chunks);
+    let chunk = chunks.into_iter().find(|c| c.name == name).expect("function should be emitted");
+    let closure = brief_vm::Closure::new(Rc::new(chunk));
+    (vm, brief_vm::Value::Closure(Rc::new(closure)))
+}
+
+fn int_array(values: &[i64]) -> brief_vm::Value {
+    brief_vm::Value::array(values.iter().map(|n| brief_vm::Value::Int(*n)).collect())
+}
+
+#[test]
+fn test_builtin_sort_natural_order() {
+    let runtime = Runtime::new();
+    let mut vm = brief_vm::VM::new();
+    let numbers = brief_vm::Value::array(vec![
+        brief_vm::Value::Double(f64::NAN),
+        brief_vm::Value::Double(1.5),
+        brief_vm::Value::Int(3),
+        brief_vm::Value::Int(-2),
+    ]);
+    let words = brief_vm::Value::array(vec![
+        brief_vm::Value::Str("pear".to_string()),
+        brief_vm::Value::Str("apple".to_string()),
+    ]);
+    
+    let result = runtime.call_builtin_with_vm("sort", std::slice::from_ref(&numbers), &mut vm);
+    assert_eq!(result, Ok(brief_vm::Value::Null));
+    // Ints and doubles compare by value, NaN last; sorted in place
+    assert_eq!(numbers.to_string(), "[-2, 1.5, 3, nan]");
+    
+    let result = runtime.call_builtin_with_vm("sort", std::slice::from_ref(&words), &mut vm);
+    assert_eq!(result, Ok(brief_vm::Value::Null));
+    assert_eq!(words.to_string(), "[\"apple\", \"pear\"]");
+}
+
+#[test]
+fn test_builtin_sort_rejects_incomparable_values() {
+    let runtime = Runtime::new();
+    let mut vm = brief_vm::VM::new();
+    let mixed = brief_vm::Value::array(vec![
+        brief_vm::Value::Int(1),
+        brief_vm::Value::Str("1".to_string()),
+    ]);
+    
+    let result = runtime.call_builtin_with_vm("sort", std::slice::from_ref(&mixed), &mut vm);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })), "got {:?}", result);
+    // A failed sort leaves the array untouched
+    assert_eq!(mixed.to_string(), "[1, \"1\"]");
+    
+    let bools = brief_vm::Value::array(vec![brief_vm::Value::Bool(true), brief_vm::Value::Bool(false)]);
+    let result = runtime.call_builtin_with_vm("sort", &[bools], &mut vm);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })), "got {:?}", result);
+}
+
+#[test]
+fn test_builtin_sort_with_comparator() {
+    let runtime = Runtime::new();
+    let (mut vm, desc) = vm_with_function("def desc(a, b)\n\tret b - a", "desc");
+    let array = int_array(&[3, 1, 4, 1, 5]);
+    
+    let result = runtime.call_builtin_with_vm("sort", &[array.clone(), desc], &mut vm);
+    assert_eq!(result, Ok(brief_vm::Value::Null));
+    assert_eq!(array, int_array(&[5, 4, 3, 1, 1]));
+}
+
+#[test]
+fn test_builtin_sort_comparator_must_return_int() {
+    let runtime = Runtime::new();
+    let (mut vm, bad) = vm_with_function("def bad(a, b)\n\tret \"nope\"", "bad");
+    let array = int_array(&[2, 1]);
+    
+    let result = runtime.call_builtin_with_vm("sort", &[array, bad], &mut vm);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_builtin_sort_requires_array() {
+    let runtime = Runtime::new();
+    let mut vm = brief_vm::VM::new();
+    let result = runtime.call_builtin_with_vm("sort", &[brief_vm::Value::Int(1)], &mut vm);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_builtin_map() {
+    let runtime = Runtime::new();
+    let (mut vm, double) = vm_with_function("def double(x)\n\tret x * 2", "double");
+    let array = int_array(&[1, 2, 3]);
+    
+    let result = runtime.call_builtin_with_vm("map", &[array.clone(), double], &mut vm);
+    assert_eq!(result, Ok(int_array(&[2, 4, 6])));
+    // The source array is left untouched
+    assert_eq!(array, int_array(&[1, 2, 3]));
+}
+
+#[test]
+fn test_builtin_filter() {
+    let runtime = Runtime::new();
+    let (mut vm, big) = vm_with_function("def big(x)\n\tret x > 2", "big");
+    let array = int_array(&[4, 1, 3, 2, 5]);
+    
+    let result = runtime.call_builtin_with_vm("filter", &[array, big], &mut vm);
+    assert_eq!(result, Ok(int_array(&[4, 3, 5])));
+}
+
+#[test]
+fn test_builtin_reduce() {
+    let runtime = Runtime::new();
+    let (mut vm, add) = vm_with_function("def add(acc, x)\n\tret acc + x", "add");
+    
+    let result = runtime.call_builtin_with_vm("reduce", &[int_array(&[1, 2, 3, 4]), add.clone(), brief_vm::Value::Int(10)], &mut vm);
+    assert_eq!(result, Ok(brief_vm::Value::Int(20)));
+    
+    // An empty array folds to the initial value
+    let result = runtime.call_builtin_with_vm("reduce", &[int_array(&[]), add, brief_vm::Value::Int(10)], &mut vm);
+    assert_eq!(result, Ok(brief_vm::Value::Int(10)));
+}
+
+#[test]
+fn test_builtin_map_requires_array() {
+    let runtime = Runtime::new();
+    let (mut vm, double) = vm_with_function("def double(x)\n\tret x * 2", "double");
+    let result = runtime.call_builtin_with_vm("map", &[brief_vm::Value::Int(1), double], &mut vm);
+    assert!(matches!(result, Err(brief_vm::RuntimeError::TypeMismatch { .. })));
+}
@@ -1,9 +1,44 @@
 /// Unique identifier for a source file
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FileId(pub u32);
 
-/// Source position (line and column, 1-indexed)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A source file known to the compiler
+#[derive(Debug, Clone)]
+struct SourceFile {
+    path: String,
+    source: String,
+}
+
+/// Registry of source files, giving each a `FileId`
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a file and return its id. Ids are handed out in order
+    pub fn add_file(&mut self, path: impl Into<String>, source: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile { path: path.into(), source: source.into() });
+        id
+    }
+
+    pub fn file_path(&self, id: FileId) -> Option<&str> {
+        self.files.get(id.0 as usize).map(|file| file.path.as_str())
+    }
+
+    pub fn file_source(&self, id: FileId) -> Option<&str> {
+        self.files.get(id.0 as usize).map(|file| file.source.as_str())
+    }
+}
+
+/// Source position (line and column, 1-indexed). Positions order by line,
+/// then column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Position {
     pub line: u32,
     pub column: u32,
@@ -13,10 +48,16 @@ impl Position {
     pub fn new(line: u32, column: u32) -> Self {
         Self { line, column }
     }
+
+    /// Whether this position comes strictly before `other`
+    pub fn is_before(&self, other: &Position) -> bool {
+        self < other
+    }
 }
 
-/// Source span (start and end positions)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Source span (start and end positions). Spans order by file, then start,
+/// then end, so sorting them yields source order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Span {
     pub file_id: FileId,
     pub start: Position,
@@ -35,4 +76,116 @@ impl Span {
             end: pos,
         }
     }
+
+    /// Smallest span covering both `a` and `b`
+    ///
+    /// # Panics
+    /// If the spans belong to different files
+    pub fn merge(a: Span, b: Span) -> Self {
+        assert_eq!(a.file_id, b.file_id, "cannot merge spans from different files");
+        Self {
+            file_id: a.file_id,
+            start: a.start.min(b.start),
+            end: a.end.max(b.end),
+        }
+    }
+
+    /// Whether `pos` falls inside the span. The end column is exclusive
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos < self.end
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A message about the program, attached to a source location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self { severity: Severity::Error, message: message.into(), span }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self { severity: Severity::Warning, message: message.into(), span }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Like the `Display` output, but naming the file the span belongs to
+    /// as `path:line:column` when `sources` knows it
+    pub fn render(&self, sources: &SourceMap) -> String {
+        match sources.file_path(self.span.file_id) {
+            Some(path) => format!(
+                "{}: {} at {}:{}:{}",
+                self.severity.label(), self.message, path, self.span.start.line, self.span.start.column
+            ),
+            None => self.to_string(),
+        }
+    }
+
+    /// A single-line JSON object for editors and other tools:
+    /// `{"file":…,"start":{"line":…,"column":…},"end":…,"severity":…,"message":…}`.
+    /// `file` is null when `sources` doesn't know the span's file
+    pub fn to_json(&self, sources: &SourceMap) -> String {
+        let file = match sources.file_path(self.span.file_id) {
+            Some(path) => json_string(path),
+            None => "null".to_string(),
+        };
+        let position = |p: Position| format!("{{\"line\":{},\"column\":{}}}", p.line, p.column);
+        format!(
+            "{{\"file\":{},\"start\":{},\"end\":{},\"severity\":\"{}\",\"message\":{}}}",
+            file,
+            position(self.span.start),
+            position(self.span.end),
+            self.severity.label(),
+            json_string(&self.message)
+        )
+    }
+}
+
+/// Quote and escape `s` as a JSON string
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} at line {} column {}", self.severity.label(), self.message, self.span.start.line, self.span.start.column)
+    }
 }
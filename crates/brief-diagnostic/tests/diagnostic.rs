@@ -0,0 +1,59 @@
+use brief_diagnostic::{Diagnostic, FileId, Position, Severity, SourceMap, Span};
+
+#[test]
+fn diagnostic_display_includes_severity_and_location() {
+    let span = Span::new(FileId(0), Position::new(3, 5), Position::new(3, 9));
+    let error = Diagnostic::error("Bad thing", span);
+    assert!(error.is_error());
+    assert_eq!(error.to_string(), "error: Bad thing at line 3 column 5");
+
+    let warning = Diagnostic::warning("Odd thing", span);
+    assert_eq!(warning.severity, Severity::Warning);
+    assert_eq!(warning.to_string(), "warning: Odd thing at line 3 column 5");
+}
+
+#[test]
+fn source_map_registers_files_in_order() {
+    let mut sources = SourceMap::new();
+    let main = sources.add_file("main.br", "def main()\n\tret 0");
+    let lib = sources.add_file(String::from("lib/util.br"), String::new());
+    assert_eq!(main, FileId(0));
+    assert_eq!(lib, FileId(1));
+    assert_eq!(sources.file_path(main), Some("main.br"));
+    assert_eq!(sources.file_source(main), Some("def main()\n\tret 0"));
+    assert_eq!(sources.file_path(lib), Some("lib/util.br"));
+    assert_eq!(sources.file_source(lib), Some(""));
+    assert_eq!(sources.file_path(FileId(2)), None);
+    assert_eq!(sources.file_source(FileId(2)), None);
+}
+
+#[test]
+fn diagnostic_render_names_the_file() {
+    let mut sources = SourceMap::new();
+    let file_id = sources.add_file("main.br", "");
+    let span = Span::new(file_id, Position::new(2, 4), Position::new(2, 6));
+    let warning = Diagnostic::warning("Odd thing", span);
+    assert_eq!(warning.render(&sources), "warning: Odd thing at main.br:2:4");
+
+    // Unknown files fall back to the plain display
+    let other = Diagnostic::error("Bad thing", Span::new(FileId(7), Position::new(1, 1), Position::new(1, 2)));
+    assert_eq!(other.render(&sources), "error: Bad thing at line 1 column 1");
+}
+
+#[test]
+fn diagnostic_to_json_escapes_and_names_the_file() {
+    let mut sources = SourceMap::new();
+    let file_id = sources.add_file("dir\\main.br", "");
+    let span = Span::new(file_id, Position::new(2, 4), Position::new(2, 6));
+    let error = Diagnostic::error("Expected '\"'\n\there", span);
+    assert_eq!(
+        error.to_json(&sources),
+        r#"{"file":"dir\\main.br","start":{"line":2,"column":4},"end":{"line":2,"column":6},"severity":"error","message":"Expected '\"'\n\there"}"#
+    );
+
+    let warning = Diagnostic::warning("Odd\u{1}", Span::single(FileId(9), Position::new(1, 1)));
+    assert_eq!(
+        warning.to_json(&sources),
+        r#"{"file":null,"start":{"line":1,"column":1},"end":{"line":1,"column":1},"severity":"warning","message":"Odd\u0001"}"#
+    );
+}
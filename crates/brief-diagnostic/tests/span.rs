@@ -21,3 +21,63 @@ fn span_single_sets_identical_bounds() {
     assert_eq!(span.end, pos);
 }
 
+
+#[test]
+fn span_merge_covers_both_spans() {
+    let file = FileId(1);
+    let a = Span::new(file, Position::new(2, 4), Position::new(2, 9));
+    let b = Span::new(file, Position::new(1, 7), Position::new(2, 6));
+    let merged = Span::merge(a, b);
+    assert_eq!(merged.start, Position::new(1, 7));
+    assert_eq!(merged.end, Position::new(2, 9));
+    assert_eq!(Span::merge(b, a), merged);
+}
+
+#[test]
+#[should_panic(expected = "different files")]
+fn span_merge_rejects_different_files() {
+    let pos = Position::new(1, 1);
+    Span::merge(Span::single(FileId(0), pos), Span::single(FileId(1), pos));
+}
+
+#[test]
+fn span_contains_checks_lines_and_columns() {
+    let span = Span::new(FileId(0), Position::new(2, 5), Position::new(4, 3));
+    assert!(span.contains(Position::new(2, 5)));
+    assert!(span.contains(Position::new(3, 1)));
+    assert!(span.contains(Position::new(3, 80)));
+    assert!(span.contains(Position::new(4, 2)));
+    // The end column is exclusive
+    assert!(!span.contains(Position::new(4, 3)));
+    assert!(!span.contains(Position::new(2, 4)));
+    assert!(!span.contains(Position::new(1, 10)));
+    assert!(!span.contains(Position::new(5, 1)));
+}
+
+#[test]
+fn position_orders_by_line_then_column() {
+    let a = Position::new(2, 9);
+    let b = Position::new(3, 1);
+    let c = Position::new(3, 4);
+    assert!(a < b && b < c);
+    assert!(a.is_before(&b));
+    assert!(!b.is_before(&a));
+    assert!(!b.is_before(&b));
+
+    let mut positions = vec![c, a, b];
+    positions.sort();
+    assert_eq!(positions, vec![a, b, c]);
+}
+
+#[test]
+fn spans_sort_into_source_order() {
+    let file = FileId(0);
+    let first = Span::new(file, Position::new(1, 3), Position::new(1, 8));
+    let longer = Span::new(file, Position::new(1, 3), Position::new(2, 1));
+    let later = Span::new(file, Position::new(4, 1), Position::new(4, 2));
+    let other_file = Span::new(FileId(1), Position::new(1, 1), Position::new(1, 2));
+
+    let mut spans = vec![other_file, later, longer, first];
+    spans.sort();
+    assert_eq!(spans, vec![first, longer, later, other_file]);
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+use brief_diagnostic::Diagnostic;
+use brief_hir::HirError;
+use brief_lexer::LexError;
+use brief_module::ModuleError;
+use brief_parser::ParseError;
+use brief_vm::RuntimeError;
+
+/// Why a program didn't compile: the errors of the first stage that failed
+#[derive(Debug)]
+pub enum CompileError {
+    Lex(Vec<LexError>),
+    Parse(Vec<ParseError>),
+    /// A module named by `import` could not be loaded
+    Module(ModuleError),
+    /// Name resolution and lowering
+    Hir(Vec<HirError>),
+}
+
+impl CompileError {
+    /// The errors as diagnostics, one per error
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            CompileError::Lex(errors) => errors.iter().map(LexError::to_diagnostic).collect(),
+            CompileError::Parse(errors) => errors.iter().map(ParseError::to_diagnostic).collect(),
+            CompileError::Module(error) => error.to_diagnostics(),
+            CompileError::Hir(errors) => errors.iter().map(HirError::to_diagnostic).collect(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Lex(errors) => write_errors(f, "Lexical errors:", errors),
+            CompileError::Parse(errors) => write_errors(f, "Parse errors:", errors),
+            CompileError::Module(error) => write!(f, "{}", error),
+            CompileError::Hir(errors) => write_errors(f, "HIR errors:", errors),
+        }
+    }
+}
+
+fn write_errors<E: fmt::Display>(f: &mut fmt::Formatter<'_>, heading: &str, errors: &[E]) -> fmt::Result {
+    write!(f, "{}", heading)?;
+    for err in errors {
+        write!(f, "\n  {}", err)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for CompileError {}
+
+/// Why [`eval`](crate::eval) failed: the source didn't compile, or it
+/// raised an error while running
+#[derive(Debug)]
+pub enum EvalError {
+    Compile(Vec<Diagnostic>),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Compile(diagnostics) => {
+                write!(f, "Compilation failed:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {}", diagnostic.message)?;
+                }
+                Ok(())
+            }
+            EvalError::Runtime(error) => write!(f, "Runtime error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl From<RuntimeError> for EvalError {
+    fn from(error: RuntimeError) -> Self {
+        EvalError::Runtime(error)
+    }
+}
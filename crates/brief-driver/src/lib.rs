@@ -0,0 +1,219 @@
+//! The Brief pipeline as a library: source goes through the lexer, parser,
+//! module loader and lowering to a [`CompiledProgram`], which emits
+//! bytecode and runs it on a [`VM`].
+//!
+//! ```
+//! use brief_driver::{eval, Value};
+//!
+//! let value = eval("def main()\n\tret 6 * 7").unwrap();
+//! assert_eq!(value, Value::Int(42));
+//! ```
+//!
+//! A [`Compiler`] keeps what earlier programs defined, so a host can
+//! compile and run code piece by piece on the same VM:
+//!
+//! ```
+//! use brief_driver::{Compiler, Value, new_vm};
+//!
+//! let mut compiler = Compiler::new();
+//! let mut vm = new_vm();
+//!
+//! let file = compiler.add_source("<setup>", "answer := 21");
+//! compiler.compile(file).unwrap().run(&mut vm).unwrap();
+//!
+//! let file = compiler.add_source("<main>", "def main()\n\tret answer * 2");
+//! let value = compiler.compile(file).unwrap().run(&mut vm).unwrap();
+//! assert_eq!(value, Value::Int(42));
+//! ```
+
+mod error;
+
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use brief_bytecode::Chunk;
+use brief_diagnostic::{Diagnostic, FileId, SourceMap};
+use brief_hir::{emit_bytecode, lower_with_globals, unreachable_code, HirProgram, KnownGlobals};
+use brief_lexer::{lex, Token};
+use brief_module::ModuleLoader;
+use brief_parser::parse_with_source;
+
+pub use error::{CompileError, EvalError};
+pub use brief_runtime::{Runtime, RuntimeOptions};
+pub use brief_vm::{RuntimeError, Value, VM};
+
+/// Name of the function a program starts from, when it has one
+pub const ENTRY: &str = "main";
+
+/// Compiles programs against the names earlier ones defined.
+///
+/// Sources are registered with [`add_source`](Compiler::add_source) so
+/// diagnostics can point into them. Imports are looked up next to the
+/// file being compiled (the working directory for a name like `<repl>`
+/// that isn't a path), then in each added search path
+#[derive(Default)]
+pub struct Compiler {
+    sources: SourceMap,
+    globals: KnownGlobals,
+    search_paths: Vec<PathBuf>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A compiler starting from `globals` instead of only the builtins,
+    /// e.g. with host functions added by `KnownGlobals::add_builtin`
+    pub fn with_globals(globals: KnownGlobals) -> Self {
+        Self { globals, ..Self::default() }
+    }
+
+    /// Also look for imported modules in `dir`
+    pub fn add_search_path(&mut self, dir: impl Into<PathBuf>) {
+        self.search_paths.push(dir.into());
+    }
+
+    /// Register a source file to compile, returning its id
+    pub fn add_source(&mut self, path: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.sources.add_file(path, source)
+    }
+
+    /// The files compiled so far, including imported modules
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    /// The names programs compiled so far have defined
+    pub fn globals(&self) -> &KnownGlobals {
+        &self.globals
+    }
+
+    /// Lex, parse and lower the registered file `file_id`, loading the
+    /// modules it imports. Its definitions, and those of its modules, are
+    /// only kept for later programs if everything compiles
+    pub fn compile(&mut self, file_id: FileId) -> Result<CompiledProgram, CompileError> {
+        let source = self.sources.file_source(file_id).unwrap_or_default().to_string();
+        self.compile_source(&source, file_id)
+    }
+
+    /// Compile `source` as the contents of `file_id`, which may have been
+    /// registered with other text. An interactive session can compile each
+    /// input as the same pseudo-file this way
+    pub fn compile_source(&mut self, source: &str, file_id: FileId) -> Result<CompiledProgram, CompileError> {
+        let (tokens, errors) = lex(source, file_id);
+        if !errors.is_empty() {
+            return Err(CompileError::Lex(errors));
+        }
+        self.compile_tokens(tokens, source, file_id)
+    }
+
+    /// Compile already lexed tokens as the contents of `file_id`. `source`
+    /// is only quoted in parse errors, so a host that rewrote its input
+    /// before lexing can point the tokens' spans back into the original
+    pub fn compile_tokens(&mut self, tokens: Vec<Token>, source: &str, file_id: FileId) -> Result<CompiledProgram, CompileError> {
+        let (program, errors) = parse_with_source(tokens, file_id, source);
+        if !errors.is_empty() {
+            return Err(CompileError::Parse(errors));
+        }
+
+        let mut globals = self.globals.clone();
+        let modules = self.module_loader(file_id)
+            .load_imports(&program, &mut globals, &mut self.sources)
+            .map_err(CompileError::Module)?;
+        let hir = lower_with_globals(program, &mut globals).map_err(CompileError::Hir)?;
+        self.globals = globals;
+
+        let warnings = unreachable_code(&hir);
+        Ok(CompiledProgram { modules, hir, warnings, chunks: OnceCell::new() })
+    }
+
+    /// A loader searching next to `file_id`, then the added search paths
+    fn module_loader(&self, file_id: FileId) -> ModuleLoader {
+        let dir = self.sources.file_path(file_id)
+            .and_then(|path| Path::new(path).parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut search_paths = vec![dir.to_path_buf()];
+        search_paths.extend(self.search_paths.iter().cloned());
+        ModuleLoader::new(search_paths)
+    }
+}
+
+/// A lowered program, and the bytecode of the modules it imports in the
+/// order they must be loaded. Its own bytecode is emitted when first asked
+/// for, so a program that is only checked is never emitted
+#[derive(Debug)]
+pub struct CompiledProgram {
+    modules: Vec<Chunk>,
+    hir: HirProgram,
+    warnings: Vec<Diagnostic>,
+    chunks: OnceCell<Vec<Chunk>>,
+}
+
+impl CompiledProgram {
+    pub fn hir(&self) -> &HirProgram {
+        &self.hir
+    }
+
+    /// Warnings from lowering, such as unreachable code
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Chunks of the imported modules, to load before the program's own
+    pub fn modules(&self) -> &[Chunk] {
+        &self.modules
+    }
+
+    /// The program's own chunks
+    pub fn chunks(&self) -> &[Chunk] {
+        self.chunks.get_or_init(|| emit_bytecode(&self.hir))
+    }
+
+    /// Run the program on `vm` from `main`, or from its first function if
+    /// it has no `main`. Its functions stay registered on `vm` afterwards
+    pub fn run(&self, vm: &mut VM) -> Result<Value, RuntimeError> {
+        self.run_from(vm, ENTRY)
+    }
+
+    /// Run the program from the function named `entry`, or from its first
+    /// function if it has none by that name
+    pub fn run_from(&self, vm: &mut VM, entry: &str) -> Result<Value, RuntimeError> {
+        run_chunks(vm, self.modules.clone(), self.chunks(), entry)
+    }
+}
+
+/// Load `modules`, register `chunks` and run from the chunk named `entry`
+/// (or the first chunk). Nothing runs if there are no chunks, and the
+/// result is null
+pub fn run_chunks(vm: &mut VM, modules: Vec<Chunk>, chunks: &[Chunk], entry: &str) -> Result<Value, RuntimeError> {
+    let Some(first) = chunks.first() else {
+        return Ok(Value::Null);
+    };
+    let entry = chunks.iter().find(|chunk| chunk.name == entry).unwrap_or(first);
+    vm.load_module(modules);
+    vm.register_functions(chunks);
+    vm.push_frame(Rc::new(entry.clone()), 0);
+    vm.run()
+}
+
+/// A VM with the standard runtime installed
+pub fn new_vm() -> VM {
+    let mut vm = VM::new();
+    vm.set_runtime(Box::new(Runtime::new()));
+    vm
+}
+
+/// Compile `source` on its own, as the file `file_id`. Errors come back as
+/// diagnostics
+pub fn compile(source: &str, file_id: FileId) -> Result<CompiledProgram, Vec<Diagnostic>> {
+    Compiler::new().compile_source(source, file_id).map_err(|error| error.to_diagnostics())
+}
+
+/// Compile and run `source` on a fresh VM with the standard runtime,
+/// returning what its `main` (or first function) returns
+pub fn eval(source: &str) -> Result<Value, EvalError> {
+    let program = compile(source, FileId(0)).map_err(EvalError::Compile)?;
+    Ok(program.run(&mut new_vm())?)
+}
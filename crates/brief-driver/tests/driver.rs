@@ -0,0 +1,83 @@
+use brief_diagnostic::FileId;
+use brief_driver::{CompileError, Compiler, EvalError, RuntimeError, Value, compile, eval, new_vm};
+
+#[test]
+fn eval_runs_main() {
+    let source = "def helper()\n\tret 1\ndef main()\n\tret helper() + 41";
+    assert_eq!(eval(source).unwrap(), Value::Int(42));
+}
+
+#[test]
+fn eval_without_main_runs_the_first_function() {
+    assert_eq!(eval("def first()\n\tret \"one\"\ndef second()\n\tret \"two\"").unwrap(), Value::Str("one".to_string()));
+}
+
+#[test]
+fn eval_reports_each_stage() {
+    match eval("def main()\n\tret missing") {
+        Err(EvalError::Compile(diagnostics)) => assert!(diagnostics[0].message.contains("missing")),
+        other => panic!("expected a compile error, got {:?}", other),
+    }
+    match eval("def div(a, b)\n\tret a / b\ndef main()\n\tret div(1, 0)") {
+        Err(EvalError::Runtime(error)) => assert_eq!(error.without_location(), &RuntimeError::DivisionByZero),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn compile_stops_at_the_first_failing_stage() {
+    let mut compiler = Compiler::new();
+    let file_id = compiler.add_source("<test>", "");
+    assert!(matches!(compiler.compile_source("def main()\n\tret \"open", file_id), Err(CompileError::Lex(_))));
+    assert!(matches!(compiler.compile_source("def main(\n\tret 1", file_id), Err(CompileError::Parse(_))));
+    assert!(matches!(compiler.compile_source("import nowhere\ndef main()\n\tret 1", file_id), Err(CompileError::Module(_))));
+    assert!(matches!(compiler.compile_source("def main()\n\tret nope", file_id), Err(CompileError::Hir(_))));
+
+    let error = compile("def main()\n\tret nope", FileId(0)).unwrap_err();
+    assert_eq!(error.len(), 1);
+}
+
+#[test]
+fn compiled_program_exposes_each_stage() {
+    let program = compile("def main()\n\tret 1\n\tret 2", FileId(0)).unwrap();
+    assert_eq!(program.hir().declarations.len(), 1);
+    assert_eq!(program.warnings().len(), 1);
+    assert!(program.modules().is_empty());
+    assert_eq!(program.chunks()[0].name, "main");
+    assert_eq!(program.run(&mut new_vm()), Ok(Value::Int(1)));
+}
+
+#[test]
+fn definitions_carry_over_between_programs() {
+    let mut compiler = Compiler::new();
+    let mut vm = new_vm();
+    let lib = compiler.add_source("<lib>", "def triple(x)\n\tret x * 3\ndef setup()\n\tret null");
+    compiler.compile(lib).unwrap().run_from(&mut vm, "setup").unwrap();
+
+    // A program that fails leaves nothing behind
+    let broken = compiler.add_source("<broken>", "def quadruple(x)\n\tret x * missing");
+    assert!(compiler.compile(broken).is_err());
+
+    let main = compiler.add_source("<main>", "def main()\n\tret triple(quadruple(1))");
+    assert!(compiler.compile(main).is_err());
+    let main = compiler.add_source("<main>", "def main()\n\tret triple(2)");
+    assert_eq!(compiler.compile(main).unwrap().run(&mut vm), Ok(Value::Int(6)));
+}
+
+#[test]
+fn imports_are_found_next_to_the_file_and_in_search_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let libs = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("near.bf"), "def near()\n\tret 1\n").unwrap();
+    std::fs::write(libs.path().join("far.bf"), "def far()\n\tret 2\n").unwrap();
+    let source = "import near\nimport far\ndef main()\n\tret near() + far()";
+
+    let mut compiler = Compiler::new();
+    let file_id = compiler.add_source(dir.path().join("main.bf").display().to_string(), source);
+    assert!(matches!(compiler.compile(file_id), Err(CompileError::Module(_))));
+
+    compiler.add_search_path(libs.path());
+    let program = compiler.compile(file_id).unwrap();
+    assert_eq!(program.modules().len(), 2);
+    assert_eq!(program.run(&mut new_vm()), Ok(Value::Int(3)));
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+use brief_lexer::LexError;
+use brief_parser::ParseError;
+
+/// Why a source file couldn't be formatted. Only code that lexes and
+/// parses is formatted, so a mistake is never reshaped into another one
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    Lex(Vec<LexError>),
+    Parse(Vec<ParseError>),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Lex(errors) => {
+                write!(f, "Lexical errors:")?;
+                for err in errors {
+                    write!(f, "\n  {}", err)?;
+                }
+                Ok(())
+            }
+            FormatError::Parse(errors) => {
+                write!(f, "Parse errors:")?;
+                for err in errors {
+                    write!(f, "\n  {}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
@@ -0,0 +1,391 @@
+use brief_diagnostic::{FileId, Position};
+use brief_lexer::{lex, Token, TokenKind};
+use brief_parser::parse_with_source;
+use crate::error::FormatError;
+
+/// Reformat Brief source canonically: one tab per indentation level, one
+/// space around binary operators and after commas, none inside brackets or
+/// before call parentheses, and at most one blank line in a row. Comments
+/// are kept, and literals are copied exactly as written
+pub fn format(source: &str) -> Result<String, FormatError> {
+    let (tokens, lex_errors) = lex(source, FileId(0));
+    if !lex_errors.is_empty() {
+        return Err(FormatError::Lex(lex_errors));
+    }
+    // The parser rejects a file without declarations, but there is still
+    // layout to tidy in one that only holds comments
+    let has_code = tokens.iter().any(|token| !matches!(token.kind, TokenKind::Newline | TokenKind::Eof));
+    let (_, parse_errors) = parse_with_source(tokens.clone(), FileId(0), source);
+    if has_code && !parse_errors.is_empty() {
+        return Err(FormatError::Parse(parse_errors));
+    }
+
+    let text = SourceText::new(source);
+    let mut formatter = Formatter::default();
+    let mut prev_end = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i].kind {
+            TokenKind::Newline => formatter.line_done = true,
+            TokenKind::Indent => formatter.depth += 1,
+            TokenKind::Dedent => formatter.depth = formatter.depth.saturating_sub(1),
+            TokenKind::Eof => break,
+            _ => {
+                let (start, end, next) = text.extent(&tokens, i);
+                // Comments and blank lines never reach the token stream, so
+                // they are recovered from the source between tokens
+                formatter.gap(&text.chars[prev_end..start.max(prev_end)], false);
+                formatter.word(tokens[i].kind.clone(), text.chars[start..end].iter().collect());
+                prev_end = end;
+                i = next;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    formatter.gap(&text.chars[prev_end..], true);
+    Ok(formatter.out)
+}
+
+/// The source as chars, so lexer positions (1-based line and char column)
+/// map to offsets
+struct SourceText {
+    chars: Vec<char>,
+    line_starts: Vec<usize>,
+}
+
+impl SourceText {
+    fn new(source: &str) -> Self {
+        let chars: Vec<char> = source.chars().collect();
+        let mut line_starts = vec![0];
+        for (i, &c) in chars.iter().enumerate() {
+            // A lone '\r' ends a line too, as in the lexer
+            if c == '\n' || (c == '\r' && chars.get(i + 1) != Some(&'\n')) {
+                line_starts.push(i + 1);
+            }
+        }
+        Self { chars, line_starts }
+    }
+
+    fn offset(&self, pos: Position) -> usize {
+        let line_start = self.line_starts.get(pos.line as usize - 1).copied().unwrap_or(self.chars.len());
+        (line_start + pos.column as usize - 1).min(self.chars.len())
+    }
+
+    /// The source range of the token at `i`, quotes included, and the index
+    /// of the token after it. A string's text and interpolations are
+    /// separate, back-to-back tokens, so they are taken together
+    fn extent(&self, tokens: &[Token], i: usize) -> (usize, usize, usize) {
+        let start = self.offset(tokens[i].span.start);
+        match tokens[i].kind {
+            // Spans start after the opening quote
+            TokenKind::Character(_) => (start.saturating_sub(1), self.offset(tokens[i].span.end), i + 1),
+            ref kind if is_string_part(kind) => {
+                let mut last = i;
+                while tokens.get(last + 1).is_some_and(|next| {
+                    is_string_part(&next.kind) && self.offset(next.span.start) == self.offset(tokens[last].span.end)
+                }) {
+                    last += 1;
+                }
+                // ...and the last part ends at the closing quote
+                let end = (self.offset(tokens[last].span.end) + 1).min(self.chars.len());
+                (start.saturating_sub(1), end, last + 1)
+            }
+            _ => (start, self.offset(tokens[i].span.end).max(start), i + 1),
+        }
+    }
+}
+
+fn is_string_part(kind: &TokenKind) -> bool {
+    matches!(kind, TokenKind::StrPart(_) | TokenKind::InterpIdent(_) | TokenKind::InterpPath(_))
+}
+
+fn is_type_keyword(kind: &TokenKind) -> bool {
+    matches!(kind, TokenKind::Int | TokenKind::Char | TokenKind::Str | TokenKind::Dub | TokenKind::Bool)
+}
+
+/// How a `:` is spaced
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColonStyle {
+    /// `int:str` in a map type
+    Tight,
+    /// `name: value` in a call
+    Label,
+    /// `a ? b : c`, and `cls child : parent`
+    Spaced,
+}
+
+/// A token already on the current line
+struct Word {
+    kind: TokenKind,
+    /// A unary operator, or `++`/`--` before its operand
+    prefix: bool,
+    colon: Option<ColonStyle>,
+}
+
+impl Word {
+    /// Whether an operator after this word takes it as its left operand
+    fn ends_operand(&self) -> bool {
+        match self.kind {
+            TokenKind::Identifier(_)
+            | TokenKind::Integer(_)
+            | TokenKind::Double(_)
+            | TokenKind::Character(_)
+            | TokenKind::StrPart(_)
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Null
+            | TokenKind::RightParen
+            | TokenKind::RightBracket
+            | TokenKind::RightBrace => true,
+            TokenKind::Inc | TokenKind::Dec => !self.prefix,
+            ref kind => is_type_keyword(kind),
+        }
+    }
+}
+
+/// One physical line of the source between two tokens
+#[derive(Default)]
+struct GapLine {
+    /// Leading tabs
+    tabs: usize,
+    comments: Vec<String>,
+}
+
+/// Split the source between two tokens into lines, keeping their comments
+fn scan_gap(chars: &[char]) -> Vec<GapLine> {
+    let mut lines = vec![GapLine::default()];
+    let mut at_line_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let line = lines.last_mut().expect("there is always a line");
+        match (chars[i], chars.get(i + 1)) {
+            ('\r', Some('\n')) => i += 1,
+            ('\n' | '\r', _) => {
+                lines.push(GapLine::default());
+                at_line_start = true;
+                i += 1;
+            }
+            ('\t', _) if at_line_start => {
+                line.tabs += 1;
+                i += 1;
+            }
+            ('/', Some('/')) => {
+                let end = chars[i..].iter().position(|&c| c == '\n' || c == '\r').map_or(chars.len(), |n| i + n);
+                line.comments.push(chars[i..end].iter().collect::<String>().trim_end().to_string());
+                at_line_start = false;
+                i = end;
+            }
+            ('/', Some('*')) => {
+                // Block comments nest
+                let start = i;
+                let mut depth = 0;
+                while i < chars.len() {
+                    match (chars[i], chars.get(i + 1)) {
+                        ('/', Some('*')) => {
+                            depth += 1;
+                            i += 2;
+                        }
+                        ('*', Some('/')) => {
+                            depth -= 1;
+                            i += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => i += 1,
+                    }
+                }
+                line.comments.push(chars[start..i].iter().collect());
+                at_line_start = false;
+            }
+            _ => {
+                at_line_start = false;
+                i += 1;
+            }
+        }
+    }
+    lines
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    /// The line being built, without its indentation
+    line: String,
+    /// Indentation of the line being built, or of the last one written
+    line_depth: usize,
+    /// Indentation from the lexer's Indent and Dedent tokens
+    depth: usize,
+    /// The lexer ended the current line
+    line_done: bool,
+    /// The last token on the current line; none after a comment
+    prev: Option<Word>,
+    /// `?`s on this line without their `:` yet
+    open_ternaries: usize,
+    /// The current line declares a class
+    class_line: bool,
+    /// A blank line goes before the next line written
+    blank_pending: bool,
+}
+
+impl Formatter {
+    /// Lay out the source between the last token and the next one, or the
+    /// end of the file
+    fn gap(&mut self, chars: &[char], at_end: bool) {
+        let mut lines = scan_gap(chars);
+        if lines.len() == 1 && !at_end {
+            // Still on the same line, like `a /* note */ b`
+            let line = lines.remove(0);
+            for comment in line.comments {
+                self.push(&comment, true);
+            }
+            if self.line_done {
+                self.flush_line();
+            }
+            return;
+        }
+
+        let next_line = if at_end { None } else { lines.pop() };
+        for (n, line) in lines.into_iter().enumerate() {
+            if n == 0 && !self.line.is_empty() {
+                // A comment after code stays at the end of its line
+                for comment in line.comments {
+                    self.push(&comment, true);
+                }
+                self.flush_line();
+            } else if line.comments.is_empty() {
+                self.blank_pending = n > 0;
+            } else {
+                // A comment keeps its own indentation as far as the lines
+                // around it allow, so a note closing a block, or making up
+                // a whole block, stays inside it
+                let low = self.depth.min(self.line_depth);
+                let high = self.depth.max(self.line_depth + 1);
+                let depth = line.tabs.clamp(low, high);
+                self.write_line(depth, &line.comments.join(" "));
+            }
+        }
+        if !self.line.is_empty() {
+            self.flush_line();
+        }
+        self.blank_pending &= !at_end;
+        // Comments ahead of the next token's line share it
+        for comment in next_line.into_iter().flat_map(|line| line.comments) {
+            self.push(&comment, true);
+        }
+    }
+
+    fn word(&mut self, kind: TokenKind, text: String) {
+        let ends_operand = self.prev.as_ref().is_some_and(Word::ends_operand);
+        let prefix = match kind {
+            TokenKind::Not | TokenKind::BitNot => true,
+            TokenKind::Minus | TokenKind::Plus | TokenKind::Inc | TokenKind::Dec => !ends_operand,
+            _ => false,
+        };
+        let colon = (kind == TokenKind::Colon).then(|| self.colon_style());
+        if kind == TokenKind::Question {
+            self.open_ternaries += 1;
+        }
+        if self.line.is_empty() {
+            self.class_line = kind == TokenKind::Cls;
+        }
+        let space = match &self.prev {
+            Some(prev) => space_between(prev, &kind, prefix, colon),
+            None => true,
+        };
+        self.push(&text, space);
+        self.prev = Some(Word { kind, prefix, colon });
+    }
+
+    fn colon_style(&mut self) -> ColonStyle {
+        if self.prev.as_ref().is_some_and(|prev| is_type_keyword(&prev.kind)) {
+            ColonStyle::Tight
+        } else if self.open_ternaries > 0 {
+            self.open_ternaries -= 1;
+            ColonStyle::Spaced
+        } else if self.class_line {
+            ColonStyle::Spaced
+        } else {
+            ColonStyle::Label
+        }
+    }
+
+    /// Add text to the current line, after a space if `space` and the line
+    /// has started
+    fn push(&mut self, text: &str, space: bool) {
+        if self.line.is_empty() {
+            self.line_depth = self.depth;
+        } else if space {
+            self.line.push(' ');
+        }
+        self.line.push_str(text);
+        // Anything after a comment is spaced from it
+        if text.starts_with("//") || text.starts_with("/*") {
+            self.prev = None;
+        }
+    }
+
+    fn flush_line(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.write_line(self.line_depth, &line);
+        }
+        self.line_done = false;
+        self.prev = None;
+        self.open_ternaries = 0;
+        self.class_line = false;
+    }
+
+    fn write_line(&mut self, depth: usize, text: &str) {
+        if std::mem::take(&mut self.blank_pending) && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        for _ in 0..depth {
+            self.out.push('\t');
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+        self.line_depth = depth;
+    }
+}
+
+/// Whether a space separates `prev` from the next token
+fn space_between(prev: &Word, kind: &TokenKind, prefix: bool, colon: Option<ColonStyle>) -> bool {
+    if prev.prefix {
+        return false;
+    }
+    let callee = matches!(
+        prev.kind,
+        TokenKind::Identifier(_) | TokenKind::StrPart(_) | TokenKind::RightParen | TokenKind::RightBracket
+    ) || is_type_keyword(&prev.kind);
+    match kind {
+        TokenKind::RightParen
+        | TokenKind::RightBracket
+        | TokenKind::RightBrace
+        | TokenKind::Comma
+        | TokenKind::Semicolon
+        | TokenKind::Dot
+        | TokenKind::QuestionDot
+        | TokenKind::DotDot
+        | TokenKind::DotDotEq
+        | TokenKind::Ellipsis => return false,
+        TokenKind::Colon => return colon == Some(ColonStyle::Spaced),
+        TokenKind::Inc | TokenKind::Dec if !prefix => return false,
+        // `match(x)` is written like a call
+        TokenKind::LeftParen => return !(callee || prev.kind == TokenKind::Match),
+        TokenKind::LeftBracket | TokenKind::LeftBrace => return !(callee || prev.kind == TokenKind::RightBrace),
+        _ => {}
+    }
+    match prev.kind {
+        TokenKind::LeftParen
+        | TokenKind::LeftBracket
+        | TokenKind::LeftBrace
+        | TokenKind::Dot
+        | TokenKind::QuestionDot
+        | TokenKind::DotDot
+        | TokenKind::DotDotEq => false,
+        TokenKind::Colon => prev.colon != Some(ColonStyle::Tight),
+        _ => true,
+    }
+}
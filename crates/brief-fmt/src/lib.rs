@@ -0,0 +1,5 @@
+mod error;
+mod format;
+
+pub use error::FormatError;
+pub use format::format;
@@ -0,0 +1,57 @@
+use brief_fmt::{format, FormatError};
+
+fn assert_formats(source: &str, expected: &str) {
+    let formatted = format(source).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format(&formatted).unwrap(), formatted, "formatting should be idempotent");
+}
+
+#[test]
+fn test_format_spaces_operators_and_commas() {
+    assert_formats(
+        "def add(int x,int y)->int\n\tret x+y*2\ndef main()\n\tx:=-1\n\ty := x ? 1:2\n\tprint( add(x,y) )\n",
+        "def add(int x, int y) -> int\n\tret x + y * 2\ndef main()\n\tx := -1\n\ty := x ? 1 : 2\n\tprint(add(x, y))\n",
+    );
+}
+
+#[test]
+fn test_format_keeps_tight_punctuation() {
+    assert_formats(
+        "def main()\n\tint[] xs\n\tfor (i:=0;i<10;i++)\n\t\tprint(!done)\n\tfor(i in 1..=10)\n\t\tprint(a?.b ?? -i)\n\tmatch (x)\n\t\tcase 'A'\n\t\t\tlog(\"a\", xs...)\n\tgreet(greeting:\"Hi\")\n",
+        "def main()\n\tint[] xs\n\tfor (i := 0; i < 10; i++)\n\t\tprint(!done)\n\tfor (i in 1..=10)\n\t\tprint(a?.b ?? -i)\n\tmatch(x)\n\t\tcase 'A'\n\t\t\tlog(\"a\", xs...)\n\tgreet(greeting: \"Hi\")\n",
+    );
+    assert_formats("cls puppy:dog\n\tobj puppy( name )\n\t\tsuper(name)\n", "cls puppy : dog\n\tobj puppy(name)\n\t\tsuper(name)\n");
+}
+
+#[test]
+fn test_format_copies_literals_verbatim() {
+    assert_formats(
+        "def main()\n\tx := \"a  \\\"b&(y)! &x.y&&\"\n\tc := '\\n'\n\tret x+\"\"+c\n",
+        "def main()\n\tx := \"a  \\\"b&(y)! &x.y&&\"\n\tc := '\\n'\n\tret x + \"\" + c\n",
+    );
+}
+
+#[test]
+fn test_format_keeps_comments_and_collapses_blank_lines() {
+    assert_formats(
+        "\n\n// header\n\n\n\ncls dog\n\tint age=1   // default\n\tobj dog(name)\n\t\t// nothing to do\n\n\n\ndef main()\n\t// first\n\tx := 1\n\t// last\n// trailing\n\n",
+        "// header\n\ncls dog\n\tint age = 1 // default\n\tobj dog(name)\n\t\t// nothing to do\n\ndef main()\n\t// first\n\tx := 1\n\t// last\n// trailing\n",
+    );
+    assert_formats("// only a comment", "// only a comment\n");
+    assert_formats("", "");
+}
+
+#[test]
+fn test_format_normalizes_line_endings() {
+    assert_formats("def f()\r\n\tret 1\r\n", "def f()\n\tret 1\n");
+    // A tab after a token starts an indented line
+    assert_formats("def f()\tret 1", "def f()\n\tret 1\n");
+}
+
+#[test]
+fn test_format_rejects_invalid_source() {
+    assert!(matches!(format("def f()\n    ret 1\n"), Err(FormatError::Lex(_))));
+    assert!(matches!(format("def f(\n"), Err(FormatError::Parse(_))));
+    let error = format("def main()\n\tx := (1 +\n").unwrap_err();
+    assert!(error.to_string().starts_with("Parse errors:"), "{}", error);
+}
@@ -1,4 +1,4 @@
-use brief_ast::{Program, Expr, Stmt, Decl, Block, PostfixOp, BinaryOp};
+use brief_ast::{Program, Expr, Stmt, Decl, Block, LambdaBody, PostfixOp, BinaryOp, InterpPart, GENERATED_NAME_PREFIX};
 use brief_diagnostic::Span;
 use crate::hir::*;
 
@@ -11,25 +11,30 @@ pub fn desugar(program: Program) -> HirProgram {
 struct Desugarer {
     // Temporary counter for generating unique variable names
     temp_counter: usize,
+    // The program declares its own `range`, so a loop over `range(...)`
+    // calls it like any other function
+    range_declared: bool,
 }
 
 impl Desugarer {
     fn new() -> Self {
         Self {
             temp_counter: 0,
+            range_declared: false,
         }
     }
 
     fn next_temp(&mut self) -> String {
         // Use write! to a pre-allocated String for better performance
-        let mut name = String::with_capacity(15); // "__temp_" (7) + up to 8 digits
+        let mut name = String::with_capacity(GENERATED_NAME_PREFIX.len() + 8); // Up to 8 digits
         use std::fmt::Write;
-        let _ = write!(name, "__temp_{}", self.temp_counter);
+        let _ = write!(name, "{}{}", GENERATED_NAME_PREFIX, self.temp_counter);
         self.temp_counter += 1;
         name
     }
 
     fn desugar_program(&mut self, program: Program) -> HirProgram {
+        self.range_declared = program.declarations.iter().any(|decl| declares(decl, "range"));
         HirProgram {
             declarations: program.declarations
                 .into_iter()
@@ -46,7 +51,16 @@ impl Desugarer {
             Decl::FuncDecl(f) => HirDecl::FuncDecl(self.desugar_func_decl(f)),
             Decl::ClassDecl(c) => HirDecl::ClassDecl(self.desugar_class_decl(c)),
             Decl::ImportDecl(i) => HirDecl::ImportDecl(HirImportDecl {
-                modules: i.modules,
+                modules: i.modules.into_iter()
+                    .map(|item| HirImportItem {
+                        path: item.path,
+                        alias: item.alias.map(|name| HirImportAlias {
+                            name,
+                            symbol: crate::symbol::SymbolRef(0), // Will be set during name resolution
+                            value: None,
+                        }),
+                    })
+                    .collect(),
                 span: i.span,
             }),
             Decl::Error(span) => HirDecl::Error(span),
@@ -88,6 +102,9 @@ impl Desugarer {
         HirClassDecl {
             name: c.name, // Move instead of clone
             symbol: crate::symbol::SymbolRef(0), // Will be set during name resolution
+            parent: c.parent,
+            fields: c.fields.into_iter().map(|f| self.desugar_var_decl(f)).collect(),
+            constants: c.constants.into_iter().map(|k| self.desugar_const_decl(k)).collect(),
             constructor: c.constructor.map(|ctor| self.desugar_ctor_decl(ctor)),
             methods: c.methods.into_iter().map(|m| self.desugar_method_decl(m)).collect(),
             span: c.span,
@@ -149,6 +166,7 @@ impl Desugarer {
         HirCtorDecl {
             name: ctor.name,
             params: ctor.params.into_iter().map(|p| self.desugar_param(p)).collect(),
+            receiver: crate::symbol::SymbolRef(0), // Will be set during name resolution
             body,
             symbol_table: crate::symbol::SymbolTable::new(),
             span: ctor.span,
@@ -173,10 +191,28 @@ impl Desugarer {
             name: p.name, // Already moved, no clone needed
             symbol: crate::symbol::SymbolRef(0), // Will be set during name resolution
             type_annotation: p.type_annotation,
+            default: p.default.map(|e| self.desugar_expr(e)),
+            variadic: p.variadic,
             span: p.span,
         }
     }
 
+    fn desugar_lambda_body(&mut self, body: LambdaBody) -> HirBlock {
+        match body {
+            LambdaBody::Expr(expr) => {
+                let span = expr.span();
+                HirBlock {
+                    statements: vec![HirStmt::Return {
+                        value: Some(self.desugar_expr(*expr)),
+                        span,
+                    }],
+                    span,
+                }
+            }
+            LambdaBody::Block(block) => self.desugar_block(block),
+        }
+    }
+
     fn desugar_block(&mut self, block: Block) -> HirBlock {
         HirBlock {
             statements: block.statements
@@ -199,14 +235,16 @@ impl Desugarer {
                     span,
                 }]
             },
-            Stmt::While { condition, body, span } => {
+            Stmt::While { condition, body, else_branch, label, span } => {
                 vec![HirStmt::While {
                     condition: Box::new(self.desugar_expr(condition)),
                     body: self.desugar_block(body),
+                    else_branch: else_branch.map(|b| self.desugar_block(b)),
+                    label,
                     span,
                 }]
             },
-            Stmt::For { init, condition, increment, body, span } => {
+            Stmt::For { init, condition, increment, body, label, span } => {
                 let mut stmts = Vec::new();
                 
                 // Desugar init
@@ -214,41 +252,41 @@ impl Desugarer {
                     stmts.extend(self.desugar_stmt(*init_stmt));
                 }
                 
-                // Create while loop
                 let condition_expr = condition.map(|e| self.desugar_expr(e));
                 let body_block = self.desugar_block(body);
-                let increment_expr = increment.map(|e| self.desugar_expr(e));
+                let increment_expr = increment.map(|e| Box::new(self.desugar_expr(e)));
                 
-                // Build while loop with increment at the end
-                let mut while_body_stmts = body_block.statements;
-                if let Some(inc) = increment_expr {
-                    while_body_stmts.push(HirStmt::Expr(Box::new(inc), span));
-                }
-                let while_body = HirBlock {
-                    statements: while_body_stmts,
-                    span: body_block.span,
-                };
-                
-                let while_condition = condition_expr.unwrap_or_else(|| {
-                    HirExpr::Boolean(true, span) // Infinite loop if no condition
-                });
+                let condition = condition_expr.unwrap_or(
+                    HirExpr::Boolean(true, span), // Infinite loop if no condition
+                );
                 
-                stmts.push(HirStmt::While {
-                    condition: Box::new(while_condition),
-                    body: while_body,
+                stmts.push(HirStmt::For {
+                    init: None,
+                    condition: Some(Box::new(condition)),
+                    increment: increment_expr,
+                    body: body_block,
+                    label,
                     span,
                 });
                 
                 stmts
             },
-            Stmt::ForIn { var, iterable, body, span } => {
+            Stmt::ForIn { var, iterable: Expr::Range { start, end, inclusive, .. }, body, label, span } => {
+                self.desugar_range_loop(var, (*start, *end, inclusive), body, label, span)
+            },
+            Stmt::ForIn { var, iterable: Expr::Call { callee, args, .. }, body, label, span }
+                if !self.range_declared && is_range_call(&callee, &args) =>
+            {
+                self.desugar_range_call_loop(var, range_arguments(args, span), body, label, span)
+            },
+            Stmt::Repeat { count, body, label, span } => self.desugar_repeat(count, body, label, span),
+            Stmt::ForIn { var, iterable, body, label, span } => {
                 // Desugar: for (v in arr) { body }
                 // to:
                 //   i := 0
-                //   while (i < len(arr))
+                //   for (; i < len(arr); i++)
                 //     v := arr[i]
                 //     <body>
-                //     i++
                 
                 let index_var = self.next_temp();
                 let iterable_expr = self.desugar_expr(iterable);
@@ -316,19 +354,21 @@ impl Desugarer {
                     span,
                 };
                 
-                // Build while body: v := arr[i]; <body>; i++
-                let mut while_body_stmts = vec![loop_var_init];
-                while_body_stmts.extend(body_block.statements);
-                while_body_stmts.push(HirStmt::Expr(Box::new(increment), span));
+                // Build loop body: v := arr[i]; <body>
+                let mut loop_body_stmts = vec![loop_var_init];
+                loop_body_stmts.extend(body_block.statements);
                 
                 vec![
                     index_init,
-                    HirStmt::While {
-                        condition: Box::new(condition),
+                    HirStmt::For {
+                        init: None,
+                        condition: Some(Box::new(condition)),
+                        increment: Some(Box::new(increment)),
                         body: HirBlock {
-                            statements: while_body_stmts,
+                            statements: loop_body_stmts,
                             span: body_block.span,
                         },
+                        label,
                         span,
                     },
                 ]
@@ -373,8 +413,11 @@ impl Desugarer {
                     span,
                 }]
             },
-            Stmt::Break(span) => vec![HirStmt::Break(span)],
-            Stmt::Continue(span) => vec![HirStmt::Continue(span)],
+            Stmt::Break { label, span } => vec![HirStmt::Break { label, span }],
+            Stmt::Continue { label, span } => vec![HirStmt::Continue { label, span }],
+            // `build_match_if_chain` consumes the `fall` ending a case, and the
+            // parser rejects it anywhere else
+            Stmt::Fall(_) => Vec::new(),
             Stmt::Expr(expr, span) => {
                 vec![HirStmt::Expr(Box::new(self.desugar_expr(expr)), span)]
             },
@@ -382,6 +425,127 @@ impl Desugarer {
         }
     }
 
+    /// A loop over a range literal counts instead of building an array:
+    ///   i := start
+    ///   end := <end>
+    ///   for (; i < end; i = i + 1)      (`<=` for an inclusive range)
+    ///     v := i
+    ///     <body>
+    fn desugar_range_loop(
+        &mut self,
+        var: String,
+        (start, end, inclusive): (Expr, Expr, bool),
+        body: Block,
+        label: Option<String>,
+        span: Span,
+    ) -> Vec<HirStmt> {
+        let index_var = self.next_temp();
+        let end_var = self.next_temp();
+        let start_expr = self.desugar_expr(start);
+        let end_expr = self.desugar_expr(end);
+        let mut body_block = self.desugar_block(body);
+        body_block.statements.insert(0, declare_temp(&var, temp_variable(&index_var, span), span));
+
+        let op = if inclusive { BinaryOp::Le } else { BinaryOp::Lt };
+        counting_loop(&index_var, start_expr, op, (&end_var, end_expr), body_block, label, span)
+    }
+
+    /// A loop over `range(start, end, step)` counts like a range literal
+    /// instead of building the array the builtin would return:
+    ///   i := start
+    ///   end := <end>
+    ///   step := rt_range_step(i, end, <step>)
+    ///   for (; step > 0 ? i < end : i > end; i = i + step)
+    ///     v := i
+    ///     <body>
+    /// `rt_range_step` checks the bounds once, as `range` would
+    fn desugar_range_call_loop(
+        &mut self,
+        var: String,
+        (start, end, step): (Expr, Expr, Expr),
+        body: Block,
+        label: Option<String>,
+        span: Span,
+    ) -> Vec<HirStmt> {
+        let index_var = self.next_temp();
+        let end_var = self.next_temp();
+        let step_var = self.next_temp();
+        let start_expr = self.desugar_expr(start);
+        let end_expr = self.desugar_expr(end);
+        let step_expr = HirExpr::Call {
+            callee: Box::new(HirExpr::Variable {
+                name: "rt_range_step".to_string(),
+                symbol: crate::symbol::SymbolRef(0),
+                span,
+            }),
+            args: vec![temp_variable(&index_var, span), temp_variable(&end_var, span), self.desugar_expr(step)],
+            span,
+        };
+        let mut body_block = self.desugar_block(body);
+        body_block.statements.insert(0, declare_temp(&var, temp_variable(&index_var, span), span));
+
+        let compare = |op| HirExpr::BinaryOp {
+            left: Box::new(temp_variable(&index_var, span)),
+            op,
+            right: Box::new(temp_variable(&end_var, span)),
+            span,
+        };
+        let condition = HirExpr::Ternary {
+            condition: Box::new(HirExpr::BinaryOp {
+                left: Box::new(temp_variable(&step_var, span)),
+                op: BinaryOp::Gt,
+                right: Box::new(HirExpr::Integer(0, span)),
+                span,
+            }),
+            then_expr: Box::new(compare(BinaryOp::Lt)),
+            else_expr: Box::new(compare(BinaryOp::Gt)),
+            span,
+        };
+        let increment = HirExpr::Assign {
+            target: Box::new(temp_variable(&index_var, span)),
+            value: Box::new(HirExpr::BinaryOp {
+                left: Box::new(temp_variable(&index_var, span)),
+                op: BinaryOp::Add,
+                right: Box::new(temp_variable(&step_var, span)),
+                span,
+            }),
+            span,
+        };
+
+        vec![
+            declare_temp(&index_var, start_expr, span),
+            declare_temp(&end_var, end_expr, span),
+            declare_temp(&step_var, step_expr, span),
+            HirStmt::For {
+                init: None,
+                condition: Some(Box::new(condition)),
+                increment: Some(Box::new(increment)),
+                body: body_block,
+                label,
+                span,
+            },
+        ]
+    }
+
+    /// `repeat n` counts a temp index from 0 up to `n`, which must be an int:
+    /// `rt_repeat_count` checks it once, before the first iteration
+    fn desugar_repeat(&mut self, count: Expr, body: Block, label: Option<String>, span: Span) -> Vec<HirStmt> {
+        let index_var = self.next_temp();
+        let count_var = self.next_temp();
+        let count_expr = HirExpr::Call {
+            callee: Box::new(HirExpr::Variable {
+                name: "rt_repeat_count".to_string(),
+                symbol: crate::symbol::SymbolRef(0),
+                span,
+            }),
+            args: vec![self.desugar_expr(count)],
+            span,
+        };
+        let body_block = self.desugar_block(body);
+        let start = HirExpr::Integer(0, span);
+        counting_loop(&index_var, start, BinaryOp::Lt, (&count_var, count_expr), body_block, label, span)
+    }
+
     fn build_match_if_chain(
         &mut self,
         temp_var: &str,
@@ -397,23 +561,54 @@ impl Desugarer {
         }
         
         let case = cases.pop().unwrap();
-        let case_body = self.desugar_block(case.body);
+        
+        // A case ending in `fall` runs on into the next case's body (and the
+        // one after, if that falls too); the last case falls into else
+        let mut body = case.body;
+        let mut following = cases.iter().rev();
+        let mut falls_into_else = false;
+        while let Some(Stmt::Fall(_)) = body.statements.last() {
+            body.statements.pop();
+            match following.next() {
+                Some(next) => body.statements.extend(next.body.statements.iter().cloned()),
+                None => {
+                    falls_into_else = true;
+                    break;
+                }
+            }
+        }
+        let mut case_body = self.desugar_block(body);
+        if falls_into_else && let Some(else_block) = &else_branch {
+            case_body.statements.extend(else_block.statements.iter().cloned());
+        }
         
         // Build condition: temp == pattern1 || temp == pattern2 || ...
+        // A range pattern tests the bounds instead: start <= temp < end
         let temp_expr = HirExpr::Variable {
             name: temp_var.to_string(),
             symbol: crate::symbol::SymbolRef(0),
             span,
         };
+        let compare = |op, right| HirExpr::BinaryOp {
+            left: Box::new(temp_expr.clone()),
+            op,
+            right: Box::new(right),
+            span,
+        };
         
         let mut condition = None;
         for pattern in case.patterns {
-            let pattern_hir = self.desugar_expr(pattern);
-            let eq = HirExpr::BinaryOp {
-                left: Box::new(temp_expr.clone()),
-                op: BinaryOp::Eq,
-                right: Box::new(pattern_hir),
-                span,
+            let eq = match pattern {
+                Expr::Range { start, end, inclusive, .. } => HirExpr::BinaryOp {
+                    left: Box::new(compare(BinaryOp::Ge, self.desugar_expr(*start))),
+                    op: BinaryOp::And,
+                    right: Box::new(compare(
+                        if inclusive { BinaryOp::Le } else { BinaryOp::Lt },
+                        self.desugar_expr(*end),
+                    )),
+                    span,
+                },
+                pattern => compare(BinaryOp::Eq, self.desugar_expr(pattern)),
             };
             
             condition = Some(match condition {
@@ -427,9 +622,21 @@ impl Desugarer {
             });
         }
         
-        let condition = condition.unwrap_or_else(|| {
-            HirExpr::Boolean(true, span) // If no patterns, always match
-        });
+        let condition = condition.unwrap_or(
+            HirExpr::Boolean(true, span), // If no patterns, always match
+        );
+        
+        // Guard is ANDed with the pattern test, so a failing guard falls
+        // through to the remaining cases rather than straight to else
+        let condition = match case.guard {
+            Some(guard) => HirExpr::BinaryOp {
+                left: Box::new(condition),
+                op: BinaryOp::And,
+                right: Box::new(self.desugar_expr(guard)),
+                span,
+            },
+            None => condition,
+        };
         
         // Build else branch from remaining cases
         let else_branch = if cases.is_empty() {
@@ -470,6 +677,13 @@ impl Desugarer {
                     span,
                 }
             },
+            Expr::SafeMemberAccess { object, member, span } => {
+                HirExpr::SafeMemberAccess {
+                    object: Box::new(self.desugar_expr(*object)),
+                    member,
+                    span,
+                }
+            },
             Expr::Index { object, index, span } => {
                 HirExpr::Index {
                     object: Box::new(self.desugar_expr(*object)),
@@ -512,6 +726,14 @@ impl Desugarer {
                     span,
                 }
             },
+            Expr::Call { callee, args, span } if matches!(*callee, Expr::Variable(ref name, _) if name == "super") => {
+                HirExpr::SuperCall {
+                    parent: String::new(), // Will be set during name resolution
+                    receiver: crate::symbol::SymbolRef(0),
+                    args: args.into_iter().map(|a| self.desugar_expr(a)).collect(),
+                    span,
+                }
+            },
             Expr::Call { callee, args, span } => {
                 HirExpr::Call {
                     callee: Box::new(self.desugar_expr(*callee)),
@@ -527,6 +749,20 @@ impl Desugarer {
                     span,
                 }
             },
+            Expr::Spread { expr, span } => {
+                HirExpr::Spread {
+                    expr: Box::new(self.desugar_expr(*expr)),
+                    span,
+                }
+            },
+            Expr::NamedArg { name, value, span } => {
+                HirExpr::NamedArg {
+                    name,
+                    index: 0, // Will be set during name resolution
+                    value: Box::new(self.desugar_expr(*value)),
+                    span,
+                }
+            },
             Expr::Cast { expr, target_type, span } => {
                 HirExpr::Cast {
                     expr: Box::new(self.desugar_expr(*expr)),
@@ -534,8 +770,20 @@ impl Desugarer {
                     span,
                 }
             },
-            Expr::Interpolation { parts, span } => {
-                HirExpr::Interpolation { parts, span }
+            Expr::Interpolation { parts, span } => self.desugar_interpolation(parts, span),
+            // Outside a for-in loop a range builds an array of its values
+            Expr::Range { start, end, inclusive, span } => HirExpr::Call {
+                callee: Box::new(HirExpr::Variable {
+                    name: "rt_range".to_string(),
+                    symbol: crate::symbol::SymbolRef(0),
+                    span,
+                }),
+                args: vec![
+                    self.desugar_expr(*start),
+                    self.desugar_expr(*end),
+                    HirExpr::Boolean(inclusive, span),
+                ],
+                span,
             },
             Expr::Ternary { condition, then_expr, else_expr, span } => {
                 HirExpr::Ternary {
@@ -549,11 +797,150 @@ impl Desugarer {
                 HirExpr::Lambda {
                     params: params.into_iter().map(|p| self.desugar_param(p)).collect(),
                     captures: Vec::new(), // Will be filled during name resolution
-                    body: Box::new(self.desugar_expr(*body)),
+                    body: self.desugar_lambda_body(body),
                     span,
                 }
             },
             Expr::Error(span) => HirExpr::Error(span),
         }
     }
+
+    /// A string with interpolated values becomes a chain of `rt_concat`
+    /// calls, which convert each value as `str` does. Text-only strings
+    /// stay as they are
+    fn desugar_interpolation(&mut self, parts: Vec<InterpPart>, span: Span) -> HirExpr {
+        if parts.iter().all(|part| matches!(part, InterpPart::Text(_))) {
+            return HirExpr::Interpolation { parts, span };
+        }
+        let mut pieces: Vec<HirExpr> = parts
+            .into_iter()
+            .map(|part| match part {
+                InterpPart::Text(text) => HirExpr::String(text, span),
+                InterpPart::Ident(name, span) => HirExpr::Variable { name, symbol: crate::symbol::SymbolRef(0), span },
+                InterpPart::Path(expr, _) => self.desugar_expr(*expr),
+            })
+            .collect();
+        // A lone value still has to become a string
+        if pieces.len() == 1 {
+            pieces.insert(0, HirExpr::String(String::new(), span));
+        }
+
+        // The helpers take 2 to 5 values, so long strings are joined in rounds
+        let mut pieces = pieces.into_iter();
+        let mut result = concat_call(pieces.by_ref().take(5).collect(), span);
+        loop {
+            let rest: Vec<HirExpr> = pieces.by_ref().take(4).collect();
+            if rest.is_empty() {
+                return result;
+            }
+            let mut args = vec![result];
+            args.extend(rest);
+            result = concat_call(args, span);
+        }
+    }
+}
+
+/// Call to the `rt_concat` helper taking `args.len()` values
+fn concat_call(args: Vec<HirExpr>, span: Span) -> HirExpr {
+    HirExpr::Call {
+        callee: Box::new(HirExpr::Variable {
+            name: format!("rt_concat{}", args.len()),
+            symbol: crate::symbol::SymbolRef(0),
+            span,
+        }),
+        args,
+        span,
+    }
+}
+
+fn declare_temp(name: &str, initializer: HirExpr, span: Span) -> HirStmt {
+    HirStmt::VarDecl(HirVarDecl {
+        name: name.to_string(),
+        symbol: crate::symbol::SymbolRef(0),
+        type_annotation: None,
+        initializer: Some(initializer),
+        span,
+    })
+}
+
+/// `index := start; bound := end; for (; index op bound; index = index + 1) { body }`,
+/// with `bound` naming the temp that holds `end`. The bound is evaluated
+/// once, before the first iteration
+fn counting_loop(
+    index_var: &str,
+    start: HirExpr,
+    op: BinaryOp,
+    (bound_var, end): (&str, HirExpr),
+    body: HirBlock,
+    label: Option<String>,
+    span: Span,
+) -> Vec<HirStmt> {
+    let condition = HirExpr::BinaryOp {
+        left: Box::new(temp_variable(index_var, span)),
+        op,
+        right: Box::new(temp_variable(bound_var, span)),
+        span,
+    };
+    let increment = HirExpr::Assign {
+        target: Box::new(temp_variable(index_var, span)),
+        value: Box::new(HirExpr::BinaryOp {
+            left: Box::new(temp_variable(index_var, span)),
+            op: BinaryOp::Add,
+            right: Box::new(HirExpr::Integer(1, span)),
+            span,
+        }),
+        span,
+    };
+
+    vec![
+        declare_temp(index_var, start, span),
+        declare_temp(bound_var, end, span),
+        HirStmt::For {
+            init: None,
+            condition: Some(Box::new(condition)),
+            increment: Some(Box::new(increment)),
+            body,
+            label,
+            span,
+        },
+    ]
+}
+
+/// Whether a top-level declaration binds `name`
+fn declares(decl: &Decl, name: &str) -> bool {
+    match decl {
+        Decl::VarDecl(v) => v.name == name,
+        Decl::ConstDecl(c) => c.name == name,
+        Decl::FuncDecl(f) => f.name == name,
+        Decl::ClassDecl(c) => c.name == name,
+        Decl::ImportDecl(i) => i.modules.iter().any(|item| item.alias.as_deref() == Some(name)),
+        Decl::Error(_) => false,
+    }
+}
+
+/// A call of `range` with one to three plain arguments
+fn is_range_call(callee: &Expr, args: &[Expr]) -> bool {
+    matches!(callee, Expr::Variable(name, _) if name == "range")
+        && (1..=3).contains(&args.len())
+        && !args.iter().any(|arg| matches!(arg, Expr::Spread { .. } | Expr::NamedArg { .. }))
+}
+
+/// The start, end and step of a `range` call, filling in the defaults
+fn range_arguments(args: Vec<Expr>, span: Span) -> (Expr, Expr, Expr) {
+    let mut args = args.into_iter();
+    let (first, second, step) = (args.next(), args.next(), args.next());
+    let step = step.unwrap_or(Expr::Integer(1, span));
+    match (first, second) {
+        (Some(start), Some(end)) => (start, end, step),
+        (end, _) => (Expr::Integer(0, span), end.unwrap_or(Expr::Integer(0, span)), step),
+    }
+}
+
+/// Reference to a compiler temporary, resolved by name later
+fn temp_variable(name: &str, span: Span) -> HirExpr {
+    HirExpr::Variable {
+        name: name.to_string(),
+        symbol: crate::symbol::SymbolRef(0),
+        span,
+    }
 }
@@ -2,6 +2,20 @@ use brief_ast::{InterpPart, BinaryOp};
 use brief_bytecode::*;
 use crate::hir::*;
 use crate::symbol::SymbolRef;
+use crate::resolve::calls_super;
+use brief_diagnostic::Span;
+
+/// The CAST operand for `x ty`, or `None` for types a value can't be cast
+/// to. Characters are ints at runtime
+pub(crate) fn cast_type(ty: &brief_ast::Type) -> Option<CastType> {
+    match ty {
+        brief_ast::Type::Int | brief_ast::Type::Char => Some(CastType::Int),
+        brief_ast::Type::Dub => Some(CastType::Dub),
+        brief_ast::Type::Str => Some(CastType::Str),
+        brief_ast::Type::Bool => Some(CastType::Bool),
+        _ => None,
+    }
+}
 
 /// Emit bytecode from HIR
 pub fn emit(program: &HirProgram) -> Vec<Chunk> {
@@ -14,6 +28,28 @@ struct Emitter {
     current_chunk: Option<usize>,
     register_counter: u8,
     max_registers: u8,
+    locals_top: u8, // One past the highest register holding a local variable
+    current_span: Option<Span>, // Source of the instructions being emitted, for the line table
+    loops: Vec<LoopContext>, // Loops being emitted, innermost last
+}
+
+/// The `break` and `continue` jumps of a loop being emitted, patched once
+/// the loop's end and continue point are known
+struct LoopContext {
+    label: Option<String>,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// What the last statement of a block does with its value
+#[derive(Clone, Copy)]
+enum BlockTail {
+    /// Statement context: the value is dropped
+    Discard,
+    /// Function body: the value is returned
+    Return,
+    /// Block used as a value: it is left in the register (null for loops and declarations)
+    Value(u8),
 }
 
 impl Emitter {
@@ -23,6 +59,9 @@ impl Emitter {
             current_chunk: None,
             register_counter: 0,
             max_registers: 0,
+            locals_top: 0,
+            current_span: None,
+            loops: Vec::new(),
         }
     }
 
@@ -49,12 +88,27 @@ impl Emitter {
         }
     }
 
+    fn global_slot(symbol: SymbolRef) -> Option<u8> {
+        symbol.as_global_slot().map(|slot| u8::try_from(slot).expect("module slots are bounded during resolution"))
+    }
+
     fn register_for_symbol(&mut self, symbol: SymbolRef) -> u8 {
         let reg = symbol.0 as u8;
         self.reserve_register(reg);
+        self.locals_top = self.locals_top.max(reg + 1);
         reg
     }
 
+    /// Run `emit`, then release the temporaries it allocated so the next
+    /// caller can reuse them. Registers of locals declared meanwhile stay
+    /// reserved
+    fn with_temps<R>(&mut self, emit: impl FnOnce(&mut Self) -> R) -> R {
+        let saved = self.register_counter;
+        let result = emit(self);
+        self.register_counter = saved.max(self.locals_top);
+        result
+    }
+
     fn emit_null_return(&mut self) {
         let null_idx = self.add_constant(Constant::Null);
         let reg = self.allocate_register();
@@ -67,16 +121,32 @@ impl Emitter {
             if *symbol == SymbolRef::BUILTIN {
                 panic!("Cannot assign to builtin '{}'", name);
             }
+            if let Some(slot) = Self::global_slot(*symbol) {
+                self.emit_expr(value, result_reg);
+                self.emit_instruction(Instruction::new2(Opcode::SET_GLOBAL, slot, result_reg));
+                return;
+            }
             let dest_reg = self.register_for_symbol(*symbol);
             self.emit_expr(value, dest_reg);
             if dest_reg != result_reg {
                 self.emit_instruction(Instruction::new2(Opcode::MOVE, result_reg, dest_reg));
             }
+        } else if let HirExpr::MemberAccess { object, member, .. } = target {
+            self.emit_expr(value, result_reg);
+            self.emit_set_field(object, member, result_reg);
         } else {
             panic!("Complex assignment target not yet supported");
         }
     }
 
+    /// Store `value_reg` into `object.member`
+    fn emit_set_field(&mut self, object: &HirExpr, member: &str, value_reg: u8) {
+        let obj_reg = self.allocate_register();
+        self.emit_expr(object, obj_reg);
+        let field_idx = self.add_constant(Constant::Str(member.to_string()));
+        self.emit_instruction(Instruction::new(Opcode::SETFIELD, obj_reg, field_idx, value_reg));
+    }
+
     fn emit_compound_assignment(
         &mut self,
         left: &HirExpr,
@@ -93,7 +163,14 @@ impl Emitter {
             panic!("Cannot assign to builtin '{}'", name);
         }
 
-        let dest_reg = self.register_for_symbol(*symbol);
+        let slot = Self::global_slot(*symbol);
+        let dest_reg = match slot {
+            Some(slot) => {
+                self.emit_instruction(Instruction::new2(Opcode::GET_GLOBAL, result_reg, slot));
+                result_reg
+            },
+            None => self.register_for_symbol(*symbol),
+        };
         let right_reg = self.allocate_register();
         self.emit_expr(right, right_reg);
 
@@ -108,6 +185,9 @@ impl Emitter {
         };
 
         self.emit_instruction(Instruction::new(opcode, dest_reg, dest_reg, right_reg));
+        if let Some(slot) = slot {
+            self.emit_instruction(Instruction::new2(Opcode::SET_GLOBAL, slot, dest_reg));
+        }
         if dest_reg != result_reg {
             self.emit_instruction(Instruction::new2(Opcode::MOVE, result_reg, dest_reg));
         }
@@ -116,10 +196,22 @@ impl Emitter {
     fn emit_instruction(&mut self, instruction: Instruction) -> usize {
         let idx = self.current_chunk_idx();
         let ip = self.chunks[idx].code.len();
+        if let Some(span) = self.current_span {
+            self.chunks[idx].mark_span(ip, span);
+        }
         self.chunks[idx].code.push(instruction);
         ip
     }
 
+    /// Run `emit` with its instructions attributed to `span`, then go back
+    /// to the enclosing span
+    fn with_span<R>(&mut self, span: Span, emit: impl FnOnce(&mut Self) -> R) -> R {
+        let outer = self.current_span.replace(span);
+        let result = emit(self);
+        self.current_span = outer;
+        result
+    }
+
     fn add_constant(&mut self, constant: Constant) -> u8 {
         let idx = self.current_chunk_idx();
         self.chunks[idx].add_constant(constant)
@@ -156,34 +248,80 @@ impl Emitter {
                     self.emit_function(f);
                 },
                 HirDecl::ClassDecl(c) => {
-                    // Emit class methods
+                    // Emit class methods and constants
                     for method in &c.methods {
-                        self.emit_method(method);
+                        self.emit_method(method, &c.name);
                     }
-                    // Emit constructor if present
-                    if let Some(ctor) = &c.constructor {
-                        self.emit_constructor(ctor, &c.name);
+                    for constant in &c.constants {
+                        self.emit_class_constant(constant, &c.name);
                     }
+                    // Every class gets a constructor, named after the class
+                    self.emit_constructor(c);
                 },
-                _ => {
-                    // Top-level variables/constants are handled differently
-                    // For now, skip them (they'll be in a main function or module init)
-                }
+                // Module-level variables are set by the module initializer
+                _ => {}
             }
         }
+        self.emit_module_init(program);
         self.chunks.clone()
     }
 
+    /// Module-level variables, constants and import aliases are stored into
+    /// their global slots, in declaration order, by a chunk the VM runs
+    /// before the entry point. It is emitted last so the first chunk stays
+    /// the entry point.
+    fn emit_module_init(&mut self, program: &HirProgram) {
+        let globals: Vec<(SymbolRef, Option<&HirExpr>)> = program.declarations.iter()
+            .flat_map(|decl| match decl {
+                HirDecl::VarDecl(v) => vec![(v.symbol, v.initializer.as_ref())],
+                HirDecl::ConstDecl(c) => vec![(c.symbol, Some(&c.initializer))],
+                HirDecl::ImportDecl(i) => i.modules.iter()
+                    .filter_map(|item| item.alias.as_ref())
+                    .map(|alias| (alias.symbol, alias.value.as_ref()))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        if globals.is_empty() {
+            return;
+        }
+
+        self.chunks.push(Chunk::new(MODULE_INIT.to_string()));
+        self.current_chunk = Some(self.chunks.len() - 1);
+        let reg = self.allocate_register();
+        for (symbol, initializer) in globals {
+            let Some(slot) = Self::global_slot(symbol) else {
+                continue;
+            };
+            match initializer {
+                Some(init) => self.emit_expr(init, reg),
+                None => self.emit_load_null(reg),
+            }
+            self.emit_instruction(Instruction::new2(Opcode::SET_GLOBAL, slot, reg));
+        }
+        self.emit_null_return();
+
+        let idx = self.current_chunk_idx();
+        self.chunks[idx].max_regs = self.max_registers;
+
+        self.register_counter = 0;
+        self.max_registers = 0;
+        self.locals_top = 0;
+    }
+
     fn emit_function(&mut self, func: &HirFuncDecl) {
         let mut chunk = Chunk::new(func.name.clone());
         chunk.param_count = func.params.len() as u8;
+        chunk.required_params = required_param_count(&func.params);
+        chunk.variadic = func.params.last().is_some_and(|p| p.variadic);
         
         self.chunks.push(chunk);
         self.current_chunk = Some(self.chunks.len() - 1);
         self.register_counter = func.params.len() as u8; // Parameters use first registers
+        self.emit_param_prologue(&func.params);
         
         // Emit function body (tail expression returns)
-        self.emit_block(&func.body, true);
+        self.emit_block(&func.body, BlockTail::Return);
         self.emit_null_return();
         
         // Update chunk metadata
@@ -193,18 +331,24 @@ impl Emitter {
         
         self.register_counter = 0;
         self.max_registers = 0;
+        self.locals_top = 0;
     }
 
-    fn emit_method(&mut self, method: &HirMethodDecl) {
-        let mut chunk = Chunk::new(method.name.clone());
+    /// Method chunks are named `Class::method`. INVOKE places the receiver of
+    /// an instance method in the first register after the parameters.
+    fn emit_method(&mut self, method: &HirMethodDecl, class_name: &str) {
+        let mut chunk = Chunk::new(format!("{}::{}", class_name, method.name));
         chunk.param_count = method.params.len() as u8;
+        chunk.required_params = required_param_count(&method.params);
+        chunk.variadic = method.params.last().is_some_and(|p| p.variadic);
         
         self.chunks.push(chunk);
         self.current_chunk = Some(self.chunks.len() - 1);
-        self.register_counter = method.params.len() as u8;
+        self.register_counter = method.params.len() as u8 + u8::from(method.is_instance);
+        self.emit_param_prologue(&method.params);
         
         // Emit method body
-        self.emit_block(&method.body, true);
+        self.emit_block(&method.body, BlockTail::Return);
         self.emit_null_return();
         
         // Update chunk metadata
@@ -213,20 +357,76 @@ impl Emitter {
         
         self.register_counter = 0;
         self.max_registers = 0;
+        self.locals_top = 0;
     }
 
-    fn emit_constructor(&mut self, ctor: &HirCtorDecl, class_name: &str) {
-        let name = format!("{}::new", class_name);
-        let mut chunk = Chunk::new(name);
-        chunk.param_count = ctor.params.len() as u8;
+    /// A class constant compiles to a `Class::NAME` chunk returning its value
+    fn emit_class_constant(&mut self, constant: &HirConstDecl, class_name: &str) {
+        self.chunks.push(Chunk::new(format!("{}::{}", class_name, constant.name)));
+        self.current_chunk = Some(self.chunks.len() - 1);
+        let reg = self.allocate_register();
+        self.emit_expr(&constant.initializer, reg);
+        self.emit_instruction(Instruction::new1(Opcode::RET, reg));
+        
+        let idx = self.current_chunk_idx();
+        self.chunks[idx].max_regs = self.max_registers;
+        
+        self.register_counter = 0;
+        self.max_registers = 0;
+        self.locals_top = 0;
+    }
+
+    /// Constructor chunk: create the instance, initialize declared fields,
+    /// run the constructor body, and return the instance. Classes without an
+    /// explicit constructor get one taking no arguments.
+    ///
+    /// The instance lives in the first register after the parameters. When a
+    /// subclass runs this constructor through SUPER, that register already
+    /// holds the subclass instance and NEWOBJ keeps it.
+    fn emit_constructor(&mut self, class: &HirClassDecl) {
+        let params: &[HirParam] = class.constructor.as_ref().map_or(&[], |ctor| &ctor.params);
+        let mut chunk = Chunk::new(class.name.clone());
+        chunk.param_count = params.len() as u8;
+        chunk.required_params = required_param_count(params);
+        chunk.variadic = params.last().is_some_and(|p| p.variadic);
+        chunk.parent = class.parent.clone();
         
         self.chunks.push(chunk);
         self.current_chunk = Some(self.chunks.len() - 1);
-        self.register_counter = ctor.params.len() as u8;
+        // Reserve the receiver before the prologue so defaults can't clobber it
+        let obj_reg = params.len() as u8;
+        self.reserve_register(obj_reg);
+        self.emit_param_prologue(params);
+        
+        let class_idx = self.add_constant(Constant::Str(class.name.clone()));
+        self.emit_instruction(Instruction::new2(Opcode::NEWOBJ, obj_reg, class_idx));
+        
+        // Without an explicit super(...) the parent constructor runs first, with no arguments
+        if let Some(parent) = &class.parent
+            && !class.constructor.as_ref().is_some_and(|ctor| calls_super(&ctor.body))
+        {
+            self.emit_super_call(parent, obj_reg, &[]);
+        }
+        
+        // Declared fields start at their default (or null)
+        for field in &class.fields {
+            let value_reg = self.allocate_register();
+            match &field.initializer {
+                Some(init) => self.emit_expr(init, value_reg),
+                None => {
+                    let null_idx = self.add_constant(Constant::Null);
+                    self.emit_instruction(Instruction::new2(Opcode::LOADK, value_reg, null_idx));
+                }
+            }
+            let field_idx = self.add_constant(Constant::Str(field.name.clone()));
+            self.emit_instruction(Instruction::new(Opcode::SETFIELD, obj_reg, field_idx, value_reg));
+        }
         
         // Emit constructor body
-        self.emit_block(&ctor.body, true);
-        self.emit_null_return();
+        if let Some(ctor) = &class.constructor {
+            self.emit_block(&ctor.body, BlockTail::Discard);
+        }
+        self.emit_instruction(Instruction::new1(Opcode::RET, obj_reg));
         
         // Update chunk metadata
         let idx = self.current_chunk_idx();
@@ -234,78 +434,151 @@ impl Emitter {
         
         self.register_counter = 0;
         self.max_registers = 0;
+        self.locals_top = 0;
     }
 
-    fn emit_block(&mut self, block: &HirBlock, tail_return: bool) {
-        let stmt_count = block.statements.len();
-        for (idx, stmt) in block.statements.iter().enumerate() {
-            let is_tail = tail_return && idx == stmt_count.saturating_sub(1);
-            if is_tail {
-                match stmt {
-                    HirStmt::Expr(expr, _) => {
-                        let reg = self.allocate_register();
-                        self.emit_expr(expr, reg);
-                        self.emit_instruction(Instruction::new1(Opcode::RET, reg));
-                        continue;
-                    }
-                    HirStmt::If { condition, then_branch, else_branch, .. } => {
-                        let reg = self.allocate_register();
-                        self.emit_if_with_result(condition, then_branch, else_branch, reg);
-                        self.emit_instruction(Instruction::new1(Opcode::RET, reg));
-                        continue;
-                    }
-                    _ => {}
+    /// Evaluate call arguments and move them into consecutive registers
+    /// starting at `first_reg`. A spread argument contributes its array.
+    fn emit_call_args(&mut self, args: &[HirExpr], first_reg: u8) {
+        // Named arguments are evaluated in source order but land in their
+        // parameter's slot, so keep the temporaries clear of the slots
+        if args.iter().any(|arg| matches!(arg, HirExpr::NamedArg { .. })) {
+            self.reserve_register(first_reg + args.len() as u8 - 1);
+        }
+        let arg_regs: Vec<(usize, u8)> = args.iter().enumerate().map(|(i, arg)| {
+            let reg = self.allocate_register();
+            match arg {
+                HirExpr::Spread { expr, .. } => self.emit_expr(expr, reg),
+                HirExpr::NamedArg { index, value, .. } => {
+                    self.emit_expr(value, reg);
+                    return (*index, reg);
                 }
+                _ => self.emit_expr(arg, reg),
+            }
+            (i, reg)
+        }).collect();
+        
+        // Each argument's temporary was allocated after the ones before it,
+        // so it is never below its own slot, and a nested call only uses
+        // registers above its temporary. Moving in order therefore never
+        // overwrites an argument that is still to be moved
+        for (slot, arg_reg) in arg_regs.iter() {
+            let dest_reg = first_reg + *slot as u8;
+            if *arg_reg != dest_reg {
+                self.emit_instruction(Instruction::new2(Opcode::MOVE, dest_reg, *arg_reg));
             }
-            self.emit_stmt(stmt);
         }
     }
 
-    fn emit_block_value(&mut self, block: &HirBlock, target_reg: u8) {
-        if block.statements.is_empty() {
-            let null_idx = self.add_constant(Constant::Null);
-            self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
-            return;
+    /// Run the parent constructor on the instance in `obj_reg`
+    fn emit_super_call(&mut self, parent: &str, obj_reg: u8, args: &[HirExpr]) {
+        let callee_reg = self.allocate_register();
+        let parent_idx = self.add_constant(Constant::Str(parent.to_string()));
+        self.emit_instruction(Instruction::new2(Opcode::CLOSURE, callee_reg, parent_idx));
+        self.emit_call_args(args, callee_reg + 1);
+        self.emit_instruction(Instruction::new(Opcode::SUPER, obj_reg, callee_reg, args.len() as u8));
+    }
+
+    /// Function prologue: fill each omitted defaulted parameter by evaluating
+    /// its default expression in the callee. JARG skips the fill when the
+    /// caller passed that argument. A variadic parameter collects the extra
+    /// arguments into an array with VARARG.
+    fn emit_param_prologue(&mut self, params: &[HirParam]) {
+        for (idx, param) in params.iter().enumerate() {
+            if param.variadic {
+                self.emit_instruction(Instruction::new1(Opcode::VARARG, idx as u8));
+            } else if let Some(default) = &param.default {
+                let jarg_ip = self.get_ip();
+                self.emit_instruction(Instruction::new2(Opcode::JARG, idx as u8, 0));
+                self.emit_expr(default, idx as u8);
+                let end_ip = self.get_ip();
+                self.patch_jump_target(jarg_ip, end_ip);
+            }
         }
+    }
 
-        let last_idx = block.statements.len() - 1;
-        for (idx, stmt) in block.statements.iter().enumerate() {
-            if idx == last_idx {
-                match stmt {
-                    HirStmt::Expr(expr, _) => {
-                        self.emit_expr(expr, target_reg);
-                    }
-                    HirStmt::If { condition, then_branch, else_branch, .. } => {
-                        self.emit_if_with_result(condition, then_branch, else_branch, target_reg);
-                    }
-                    HirStmt::Return { value, .. } => {
-                        if let Some(expr) = value {
-                            self.emit_expr(expr, target_reg);
-                        } else {
-                            let null_idx = self.add_constant(Constant::Null);
-                            self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
-                        }
-                    }
-                    _ => {
-                        self.emit_stmt(stmt);
-                        let null_idx = self.add_constant(Constant::Null);
-                        self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
-                    }
+    /// Emit a block's statements, then give its last statement the meaning
+    /// `tail` asks for. Every mode agrees on non-tail statements, and a tail
+    /// `ret` always returns from the function.
+    fn emit_block(&mut self, block: &HirBlock, tail: BlockTail) {
+        let Some((last, rest)) = block.statements.split_last() else {
+            if let BlockTail::Value(target_reg) = tail {
+                self.emit_load_null(target_reg);
+            }
+            return;
+        };
+        for stmt in rest {
+            self.emit_stmt(stmt);
+        }
+        
+        let target_reg = match tail {
+            BlockTail::Discard => {
+                self.emit_stmt(last);
+                return;
+            }
+            BlockTail::Return => None,
+            BlockTail::Value(reg) => Some(reg),
+        };
+        match last {
+            HirStmt::Expr(expr, _) => {
+                let reg = target_reg.unwrap_or_else(|| self.allocate_register());
+                self.emit_expr(expr, reg);
+                if target_reg.is_none() {
+                    self.emit_instruction(Instruction::new1(Opcode::RET, reg));
+                }
+            }
+            HirStmt::If { condition, then_branch, else_branch, .. } => {
+                let reg = target_reg.unwrap_or_else(|| self.allocate_register());
+                self.emit_if_with_result(condition, then_branch, else_branch, reg);
+                if target_reg.is_none() {
+                    self.emit_instruction(Instruction::new1(Opcode::RET, reg));
+                }
+            }
+            HirStmt::Return { .. } => self.emit_stmt(last),
+            _ => {
+                // Loops and declarations have no value of their own
+                self.emit_stmt(last);
+                if let Some(reg) = target_reg {
+                    self.emit_load_null(reg);
                 }
-            } else {
-                self.emit_stmt(stmt);
             }
         }
     }
 
-    fn emit_if_with_result(&mut self, condition: &HirExpr, then_branch: &HirBlock, else_branch: &Option<HirBlock>, result_reg: u8) {
+    fn emit_load_null(&mut self, target_reg: u8) {
+        let null_idx = self.add_constant(Constant::Null);
+        self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
+    }
+
+    /// Emit a jump taken when `condition` is false and return its ip for
+    /// patching. A direct comparison is tested in place by a TEST_*
+    /// instruction that skips the jump when it holds, instead of computing
+    /// a boolean into a register for JIF
+    fn emit_jump_if_false(&mut self, condition: &HirExpr) -> usize {
+        if let HirExpr::BinaryOp { left, op, right, .. } = condition
+            && let Some(opcode) = test_opcode(*op)
+        {
+            let left_reg = self.allocate_register();
+            let right_reg = self.allocate_register();
+            self.emit_expr(left, left_reg);
+            self.emit_expr(right, right_reg);
+            self.emit_instruction(Instruction::new2(opcode, left_reg, right_reg));
+            let jump_ip = self.get_ip();
+            self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
+            return jump_ip;
+        }
+
         let cond_reg = self.allocate_register();
         self.emit_expr(condition, cond_reg);
-
-        let jmp_if_false_ip = self.get_ip();
+        let jump_ip = self.get_ip();
         self.emit_instruction(Instruction::new2(Opcode::JIF, cond_reg, 0));
+        jump_ip
+    }
+
+    fn emit_if_with_result(&mut self, condition: &HirExpr, then_branch: &HirBlock, else_branch: &Option<HirBlock>, result_reg: u8) {
+        let jmp_if_false_ip = self.emit_jump_if_false(condition);
 
-        self.emit_block_value(then_branch, result_reg);
+        self.emit_block(then_branch, BlockTail::Value(result_reg));
         let jump_over_else_ip = self.get_ip();
         self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
 
@@ -313,39 +586,60 @@ impl Emitter {
         self.patch_jump_target(jmp_if_false_ip, else_start_ip);
 
         if let Some(else_branch) = else_branch {
-            self.emit_block_value(else_branch, result_reg);
+            self.emit_block(else_branch, BlockTail::Value(result_reg));
         } else {
-            let null_idx = self.add_constant(Constant::Null);
-            self.emit_instruction(Instruction::new2(Opcode::LOADK, result_reg, null_idx));
+            self.emit_load_null(result_reg);
         }
 
         let else_end_ip = self.get_ip();
         self.patch_jump_target(jump_over_else_ip, else_end_ip);
     }
 
+    /// Initialize a variable declared by a statement. It normally lives in
+    /// its own register, but the resolver may have promoted it to a global
+    /// slot (as for REPL inputs)
+    fn emit_local_decl(&mut self, symbol: SymbolRef, initializer: Option<&HirExpr>) {
+        if let Some(slot) = Self::global_slot(symbol) {
+            self.with_temps(|e| {
+                let reg = e.allocate_register();
+                match initializer {
+                    Some(init) => e.emit_expr(init, reg),
+                    None => e.emit_load_null(reg),
+                }
+                e.emit_instruction(Instruction::new2(Opcode::SET_GLOBAL, slot, reg));
+            });
+            return;
+        }
+        let target_reg = self.register_for_symbol(symbol);
+        match initializer {
+            Some(init) => self.emit_expr(init, target_reg),
+            None => self.emit_load_null(target_reg),
+        }
+    }
+
     fn emit_stmt(&mut self, stmt: &HirStmt) {
+        self.with_span(stmt.span(), |e| e.emit_stmt_code(stmt));
+    }
+
+    fn emit_stmt_code(&mut self, stmt: &HirStmt) {
         match stmt {
             HirStmt::VarDecl(v) => {
-                let target_reg = self.register_for_symbol(v.symbol);
-                if let Some(init) = &v.initializer {
-                    self.emit_expr(init, target_reg);
-                } else {
-                    let null_idx = self.add_constant(Constant::Null);
-                    self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
-                }
+                self.emit_local_decl(v.symbol, v.initializer.as_ref());
             },
             HirStmt::ConstDecl(c) => {
-                let target_reg = self.register_for_symbol(c.symbol);
-                self.emit_expr(&c.initializer, target_reg);
+                self.emit_local_decl(c.symbol, Some(&c.initializer));
             },
             HirStmt::If { condition, then_branch, else_branch, .. } => {
                 self.emit_if(condition, then_branch, else_branch);
             },
-            HirStmt::While { condition, body, .. } => {
-                self.emit_while(condition, body);
+            HirStmt::While { condition, body, else_branch: None, label, .. } => {
+                self.emit_while(condition, body, label);
+            },
+            HirStmt::While { condition, body, else_branch: Some(else_branch), label, .. } => {
+                self.emit_while_else(condition, body, else_branch, label);
             },
-            HirStmt::For { init, condition, increment, body, .. } => {
-                self.emit_for(init, condition, increment, body);
+            HirStmt::For { init, condition, increment, body, label, .. } => {
+                self.emit_for(init, condition, increment, body, label);
             },
             HirStmt::Return { value, .. } => {
                 if let Some(value) = value {
@@ -360,8 +654,13 @@ impl Emitter {
                     self.emit_instruction(Instruction::new1(Opcode::RET, reg));
                 }
             },
-            HirStmt::Break(_) | HirStmt::Continue(_) => {
-                // TODO: Implement break/continue (needs loop context)
+            HirStmt::Break { label, .. } => {
+                let ip = self.emit_instruction(Instruction::new1(Opcode::JMP, 0)); // Patched when the loop ends
+                self.loop_context(label).breaks.push(ip);
+            },
+            HirStmt::Continue { label, .. } => {
+                let ip = self.emit_instruction(Instruction::new1(Opcode::JMP, 0)); // Patched when the loop ends
+                self.loop_context(label).continues.push(ip);
             },
             HirStmt::Expr(expr, _) => {
                 let reg = self.allocate_register();
@@ -374,61 +673,123 @@ impl Emitter {
     }
 
     fn emit_if(&mut self, condition: &HirExpr, then_branch: &HirBlock, else_branch: &Option<HirBlock>) {
-        let cond_reg = self.allocate_register();
-        self.emit_expr(condition, cond_reg);
-        
-        let jmp_if_false_ip = self.get_ip();
-        self.emit_instruction(Instruction::new2(Opcode::JIF, cond_reg, 0)); // Offset patched later
+        let jmp_if_false_ip = self.emit_jump_if_false(condition); // Offset patched later
         
         // Emit then branch
-        self.emit_block(then_branch, false);
-        
-        let then_end_ip = self.get_ip();
-        let else_start_ip = if else_branch.is_some() {
-            // Emit jump over else branch
-            let jmp_over_else_ip = self.get_ip();
-            self.emit_instruction(Instruction::new1(Opcode::JMP, 0)); // Offset patched later
-            jmp_over_else_ip
-        } else {
-            then_end_ip
-        };
+        self.emit_block(then_branch, BlockTail::Discard);
         
-        // Patch JIF offset
+        // Emit jump over else branch
+        let jmp_over_else_ip = else_branch.as_ref().map(|_| {
+            self.emit_instruction(Instruction::new1(Opcode::JMP, 0)) // Offset patched later
+        });
+        
+        // Patch JIF offset to land past that jump
+        let else_start_ip = self.get_ip();
         self.patch_jump_target(jmp_if_false_ip, else_start_ip);
         
         // Emit else branch if present
-        if let Some(else_branch) = else_branch {
-            self.emit_block(else_branch, false);
+        if let (Some(else_branch), Some(jmp_over_else_ip)) = (else_branch, jmp_over_else_ip) {
+            self.emit_block(else_branch, BlockTail::Discard);
             let else_end_ip = self.get_ip();
-            self.patch_jump_target(else_start_ip, else_end_ip);
+            self.patch_jump_target(jmp_over_else_ip, else_end_ip);
         }
     }
 
-    fn emit_while(&mut self, condition: &HirExpr, body: &HirBlock) {
+    /// The loop a `break` or `continue` with `label` acts on: the innermost
+    /// one, or the innermost with that label
+    fn loop_context(&mut self, label: &Option<String>) -> &mut LoopContext {
+        let index = match label {
+            Some(label) => self.loops.iter().rposition(|l| l.label.as_ref() == Some(label)),
+            None => self.loops.len().checked_sub(1),
+        };
+        let index = index.expect("break or continue outside a matching loop (checked by the resolver)");
+        &mut self.loops[index]
+    }
+
+    /// Emit a loop body, collecting its `break` and `continue` jumps. The
+    /// body runs its statements again on every iteration, so each
+    /// statement's temporaries are released as soon as it is emitted
+    fn emit_loop_body(&mut self, body: &HirBlock, label: &Option<String>) -> LoopContext {
+        self.loops.push(LoopContext { label: label.clone(), breaks: Vec::new(), continues: Vec::new() });
+        for stmt in &body.statements {
+            self.with_temps(|e| e.emit_stmt(stmt));
+        }
+        self.loops.pop().expect("loop context pushed above")
+    }
+
+    /// Point a loop's `break` jumps at `break_ip` and its `continue` jumps
+    /// at `continue_ip`
+    fn patch_loop_jumps(&mut self, context: LoopContext, continue_ip: usize, break_ip: usize) {
+        for ip in context.continues {
+            self.patch_jump_target(ip, continue_ip);
+        }
+        for ip in context.breaks {
+            self.patch_jump_target(ip, break_ip);
+        }
+    }
+
+    fn emit_while(&mut self, condition: &HirExpr, body: &HirBlock, label: &Option<String>) {
         let loop_start_ip = self.get_ip();
         
-        // Emit condition
-        let cond_reg = self.allocate_register();
-        self.emit_expr(condition, cond_reg);
-        
-        // Jump if false (to end)
-        let jmp_if_false_ip = self.get_ip();
-        self.emit_instruction(Instruction::new2(Opcode::JIF, cond_reg, 0)); // Offset patched later
+        // Emit condition, jumping to the end if false
+        let jmp_if_false_ip = self.with_temps(|e| e.emit_jump_if_false(condition)); // Offset patched later
         
         // Emit body
-        self.emit_block(body, false);
+        let context = self.emit_loop_body(body, label);
         
         // Jump back to start
-        let loop_end_ip = self.get_ip();
-        let back_jmp_offset = (loop_start_ip as i16) - (loop_end_ip as i16) - 1;
-        self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
-        self.patch_offset(loop_end_ip, back_jmp_offset);
+        let loop_end_ip = self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
+        self.patch_jump_target(loop_end_ip, loop_start_ip);
         
         // Patch JIF to jump to end
         self.patch_jump_target(jmp_if_false_ip, loop_end_ip + 1);
+        self.patch_loop_jumps(context, loop_start_ip, loop_end_ip + 1);
+    }
+
+    /// The condition is checked once before the loop to choose between the
+    /// body and the `else` block, then again after each iteration:
+    ///
+    /// ```text
+    ///     JIF cond -> else
+    /// top:
+    ///     body
+    /// next:
+    ///     JIF cond -> end
+    ///     JMP top
+    /// else:
+    ///     else block
+    /// end:
+    /// ```
+    ///
+    /// `continue` jumps to `next` and `break` to `end`, skipping the else
+    /// block
+    fn emit_while_else(&mut self, condition: &HirExpr, body: &HirBlock, else_branch: &HirBlock, label: &Option<String>) {
+        let jmp_to_else_ip = self.with_temps(|e| e.emit_jump_if_false(condition));
+
+        let loop_start_ip = self.get_ip();
+        let context = self.emit_loop_body(body, label);
+        let next_ip = self.get_ip();
+        let jmp_to_end_ip = self.with_temps(|e| e.emit_jump_if_false(condition));
+        let loop_end_ip = self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
+        self.patch_jump_target(loop_end_ip, loop_start_ip);
+
+        self.patch_jump_target(jmp_to_else_ip, loop_end_ip + 1);
+        self.emit_block(else_branch, BlockTail::Discard);
+        let end_ip = self.get_ip();
+        self.patch_jump_target(jmp_to_end_ip, end_ip);
+        self.patch_loop_jumps(context, next_ip, end_ip);
     }
 
-    fn emit_for(&mut self, init: &Option<Box<HirStmt>>, condition: &Option<Box<HirExpr>>, increment: &Option<Box<HirExpr>>, body: &HirBlock) {
+    /// `continue` jumps to the increment, which runs before the condition
+    /// is checked again
+    fn emit_for(
+        &mut self,
+        init: &Option<Box<HirStmt>>,
+        condition: &Option<Box<HirExpr>>,
+        increment: &Option<Box<HirExpr>>,
+        body: &HirBlock,
+        label: &Option<String>,
+    ) {
         // Emit init
         if let Some(init) = init {
             self.emit_stmt(init);
@@ -436,43 +797,43 @@ impl Emitter {
         
         let loop_start_ip = self.get_ip();
         
-        // Emit condition (or use true if no condition)
-        let cond_reg = if let Some(condition) = condition {
-            let reg = self.allocate_register();
-            self.emit_expr(condition, reg);
-            reg
+        // Emit condition (or use true if no condition), jumping to the end if false
+        let jmp_if_false_ip = self.with_temps(|e| if let Some(condition) = condition {
+            e.emit_jump_if_false(condition) // Offset patched later
         } else {
             // Infinite loop - load true
-            let true_idx = self.add_constant(Constant::Bool(true));
-            let reg = self.allocate_register();
-            self.emit_instruction(Instruction::new2(Opcode::LOADK, reg, true_idx));
-            reg
-        };
-        
-        // Jump if false (to end)
-        let jmp_if_false_ip = self.get_ip();
-        self.emit_instruction(Instruction::new2(Opcode::JIF, cond_reg, 0)); // Offset patched later
+            let true_idx = e.add_constant(Constant::Bool(true));
+            let reg = e.allocate_register();
+            e.emit_instruction(Instruction::new2(Opcode::LOADK, reg, true_idx));
+            e.emit_instruction(Instruction::new2(Opcode::JIF, reg, 0))
+        });
         
         // Emit body
-        self.emit_block(body, false);
+        let context = self.emit_loop_body(body, label);
         
         // Emit increment
+        let increment_ip = self.get_ip();
         if let Some(increment) = increment {
-            let inc_reg = self.allocate_register();
-            self.emit_expr(increment, inc_reg);
+            self.with_temps(|e| {
+                let inc_reg = e.allocate_register();
+                e.emit_expr(increment, inc_reg);
+            });
         }
         
         // Jump back to start
-        let loop_end_ip = self.get_ip();
-        let back_jmp_offset = (loop_start_ip as i16) - (loop_end_ip as i16) - 1;
-        self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
-        self.patch_offset(loop_end_ip, back_jmp_offset);
+        let loop_end_ip = self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
+        self.patch_jump_target(loop_end_ip, loop_start_ip);
         
         // Patch JIF to jump to end
         self.patch_jump_target(jmp_if_false_ip, loop_end_ip + 1);
+        self.patch_loop_jumps(context, increment_ip, loop_end_ip + 1);
     }
 
     fn emit_expr(&mut self, expr: &HirExpr, target_reg: u8) {
+        self.with_span(expr.span(), |e| e.emit_expr_code(expr, target_reg));
+    }
+
+    fn emit_expr_code(&mut self, expr: &HirExpr, target_reg: u8) {
         match expr {
             HirExpr::Integer(n, _) => {
                 let idx = self.add_constant(Constant::Int(*n));
@@ -503,6 +864,11 @@ impl Emitter {
                 if *symbol == SymbolRef::BUILTIN {
                     let idx = self.add_constant(Constant::Str(name.clone()));
                     self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, idx));
+                } else if *symbol == SymbolRef::GLOBAL {
+                    let idx = self.add_constant(Constant::Str(name.clone()));
+                    self.emit_instruction(Instruction::new2(Opcode::CLOSURE, target_reg, idx));
+                } else if let Some(slot) = Self::global_slot(*symbol) {
+                    self.emit_instruction(Instruction::new2(Opcode::GET_GLOBAL, target_reg, slot));
                 } else {
                     let src_reg = self.register_for_symbol(*symbol);
                     if src_reg != target_reg {
@@ -515,6 +881,9 @@ impl Emitter {
                     self.emit_assign_expr(left, right, target_reg);
                     return;
                 }
+                // `&&` and `||` yield a bool: the truthiness of whichever operand
+                // decided the result. The right side runs only when the left is
+                // undecided
                 match op {
                     brief_ast::BinaryOp::And => {
                         self.emit_expr(left, target_reg);
@@ -523,6 +892,7 @@ impl Emitter {
                         self.emit_expr(right, target_reg);
                         let end_ip = self.get_ip();
                         self.patch_jump_target(jif_ip, end_ip);
+                        self.emit_instruction(Instruction::new2(Opcode::TOBOOL, target_reg, target_reg));
                     },
                     brief_ast::BinaryOp::Or => {
                         self.emit_expr(left, target_reg);
@@ -535,6 +905,21 @@ impl Emitter {
                         self.emit_expr(right, target_reg);
                         let end_ip = self.get_ip();
                         self.patch_jump_target(skip_ip, end_ip);
+                        self.emit_instruction(Instruction::new2(Opcode::TOBOOL, target_reg, target_reg));
+                    },
+                    brief_ast::BinaryOp::NullCoalesce => {
+                        // Only evaluate the right side when the left is null
+                        self.emit_expr(left, target_reg);
+                        let null_reg = self.allocate_register();
+                        let null_idx = self.add_constant(Constant::Null);
+                        self.emit_instruction(Instruction::new2(Opcode::LOADK, null_reg, null_idx));
+                        let is_null_reg = self.allocate_register();
+                        self.emit_instruction(Instruction::new(Opcode::CMP_EQ, is_null_reg, target_reg, null_reg));
+                        let jif_ip = self.get_ip();
+                        self.emit_instruction(Instruction::new2(Opcode::JIF, is_null_reg, 0));
+                        self.emit_expr(right, target_reg);
+                        let end_ip = self.get_ip();
+                        self.patch_jump_target(jif_ip, end_ip);
                     },
                     brief_ast::BinaryOp::PlusAssign
                     | brief_ast::BinaryOp::MinusAssign
@@ -563,6 +948,7 @@ impl Emitter {
                             brief_ast::BinaryOp::Le => Opcode::CMP_LE,
                             brief_ast::BinaryOp::Gt => Opcode::CMP_GT,
                             brief_ast::BinaryOp::Ge => Opcode::CMP_GE,
+                            brief_ast::BinaryOp::In => Opcode::CONTAINS,
                             _ => panic!("Unexpected binary operator in HIR: {:?}", op),
                         };
                         
@@ -593,103 +979,129 @@ impl Emitter {
                     if *symbol == SymbolRef::BUILTIN {
                         panic!("Cannot assign to builtin '{}'", name);
                     }
-                    let target_reg = self.register_for_symbol(*symbol);
-                    self.emit_instruction(Instruction::new2(Opcode::MOVE, target_reg, value_reg));
+                    if let Some(slot) = Self::global_slot(*symbol) {
+                        self.emit_instruction(Instruction::new2(Opcode::SET_GLOBAL, slot, value_reg));
+                    } else {
+                        let target_reg = self.register_for_symbol(*symbol);
+                        self.emit_instruction(Instruction::new2(Opcode::MOVE, target_reg, value_reg));
+                    }
+                } else if let HirExpr::MemberAccess { object, member, .. } = target.as_ref() {
+                    self.emit_set_field(object, member, value_reg);
                 } else {
-                    // TODO: Handle member access, index, etc.
+                    // TODO: Handle index, etc.
                     panic!("Complex assignment target not yet supported");
                 }
             },
+            HirExpr::Call { callee, args, .. } if is_exit_call(callee, args) => {
+                // exit(code) halts the VM directly instead of going through CALL
+                let code_reg = self.allocate_register();
+                match args.first() {
+                    Some(code) => self.emit_expr(code, code_reg),
+                    None => {
+                        let idx = self.add_constant(Constant::Int(0));
+                        self.emit_instruction(Instruction::new2(Opcode::LOADK, code_reg, idx));
+                    }
+                }
+                self.emit_instruction(Instruction::new1(Opcode::EXIT, code_reg));
+            },
             HirExpr::Call { callee, args, .. } => {
                 // Emit callee
                 let callee_reg = self.allocate_register();
                 self.emit_expr(callee, callee_reg);
                 
-                // Emit arguments
-                let arg_regs: Vec<u8> = args.iter().map(|arg| {
-                    let reg = self.allocate_register();
-                    self.emit_expr(arg, reg);
-                    reg
-                }).collect();
+                // Emit arguments (a trailing spread passes its array as the last argument)
+                let spread = matches!(args.last(), Some(HirExpr::Spread { .. }));
+                self.emit_call_args(args, callee_reg + 1);
                 
-                // For now, assume first arg is in callee_reg+1
-                // TODO: Proper argument passing
-                if !arg_regs.is_empty() {
-                    // Move args to consecutive registers
-                    for (i, arg_reg) in arg_regs.iter().enumerate() {
-                        let dest_reg = callee_reg + 1 + i as u8;
-                        if *arg_reg != dest_reg {
-                            self.emit_instruction(Instruction::new2(Opcode::MOVE, dest_reg, *arg_reg));
-                        }
-                    }
+                let opcode = if spread { Opcode::CALLV } else { Opcode::CALL };
+                self.emit_instruction(Instruction::new(opcode, target_reg, callee_reg, args.len() as u8));
+            },
+            HirExpr::MethodCall { object, method, args, .. } => {
+                if args.iter().any(|arg| matches!(arg, HirExpr::Spread { .. })) {
+                    unreachable!("spread method arguments are rejected during resolution");
+                }
+                // Method name, then the receiver, then the arguments
+                let name_reg = self.allocate_register();
+                let name_idx = self.add_constant(Constant::Str(method.clone()));
+                self.emit_instruction(Instruction::new2(Opcode::LOADK, name_reg, name_idx));
+                let obj_reg = self.allocate_register();
+                self.emit_expr(object, obj_reg);
+                self.emit_call_args(args, obj_reg + 1);
+                self.emit_instruction(Instruction::new(Opcode::INVOKE, target_reg, name_reg, args.len() as u8));
+            },
+            HirExpr::SuperCall { parent, receiver, args, .. } => {
+                if args.iter().any(|arg| matches!(arg, HirExpr::Spread { .. })) {
+                    unreachable!("spread super arguments are rejected during resolution");
+                }
+                let obj_reg = self.register_for_symbol(*receiver);
+                self.emit_super_call(parent, obj_reg, args);
+                if obj_reg != target_reg {
+                    self.emit_instruction(Instruction::new2(Opcode::MOVE, target_reg, obj_reg));
                 }
-                
-                self.emit_instruction(Instruction::new(Opcode::CALL, target_reg, callee_reg, args.len() as u8));
             },
-            HirExpr::MethodCall { object, .. } => {
-                // TODO: Implement method calls
-                // For now, treat as regular call
+            HirExpr::MemberAccess { object, member, .. } => {
                 let obj_reg = self.allocate_register();
                 self.emit_expr(object, obj_reg);
-                
-                // Emit method call (simplified)
-                // TODO: Proper method dispatch
-                panic!("Method calls not yet implemented");
+                let field_idx = self.add_constant(Constant::Str(member.clone()));
+                self.emit_instruction(Instruction::new(Opcode::GETFIELD, target_reg, obj_reg, field_idx));
             },
-            HirExpr::MemberAccess { .. } => {
-                // TODO: Implement member access
-                panic!("Member access not yet implemented");
+            HirExpr::SafeMemberAccess { object, member, .. } => {
+                // Leave null in the target unless the object is non-null
+                let obj_reg = self.allocate_register();
+                self.emit_expr(object, obj_reg);
+                let null_idx = self.add_constant(Constant::Null);
+                self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, null_idx));
+                let not_null_reg = self.allocate_register();
+                self.emit_instruction(Instruction::new(Opcode::CMP_NE, not_null_reg, obj_reg, target_reg));
+                let jif_ip = self.get_ip();
+                self.emit_instruction(Instruction::new2(Opcode::JIF, not_null_reg, 0));
+                let field_idx = self.add_constant(Constant::Str(member.clone()));
+                self.emit_instruction(Instruction::new(Opcode::GETFIELD, target_reg, obj_reg, field_idx));
+                let end_ip = self.get_ip();
+                self.patch_jump_target(jif_ip, end_ip);
             },
             HirExpr::Index { .. } => {
                 // TODO: Implement index access
                 panic!("Index access not yet implemented");
             },
-            HirExpr::Cast { .. } => {
-                // TODO: Implement type casting
-                panic!("Type casting not yet implemented");
+            HirExpr::Cast { expr, target_type, .. } => {
+                let expr_reg = self.allocate_register();
+                self.emit_expr(expr, expr_reg);
+                let target = cast_type(target_type).expect("Cast target checked during name resolution");
+                self.emit_instruction(Instruction::new(Opcode::CAST, target_reg, expr_reg, target as u8));
             },
             HirExpr::Interpolation { parts, .. } => {
-                // Support plain strings (no embedded expressions) for now
-                if parts.iter().all(|part| matches!(part, InterpPart::Text(_))) {
-                    let mut text = String::new();
-                    for part in parts {
-                        if let InterpPart::Text(chunk) = part {
-                            text.push_str(chunk);
-                        }
+                // Desugaring turned interpolated values into rt_concat calls,
+                // so only text is left
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        InterpPart::Text(chunk) => text.push_str(chunk),
+                        _ => panic!("Interpolated value survived desugaring"),
                     }
-                    let idx = self.add_constant(Constant::Str(text));
-                    self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, idx));
-                } else {
-                    // TODO: Implement string interpolation lowering
-                    panic!("String interpolation with expressions not yet implemented");
                 }
+                let idx = self.add_constant(Constant::Str(text));
+                self.emit_instruction(Instruction::new2(Opcode::LOADK, target_reg, idx));
             },
             HirExpr::Ternary { condition, then_expr, else_expr, .. } => {
-                // Emit as if/else
-                let cond_reg = self.allocate_register();
-                self.emit_expr(condition, cond_reg);
-                
-                let jmp_if_false_ip = self.get_ip();
-                self.emit_instruction(Instruction::new2(Opcode::JIF, cond_reg, 0));
-                
-                // Emit then
+                // Emit as if/else, each branch writing straight into target_reg
+                let jmp_if_false_ip = self.emit_jump_if_false(condition);
                 self.emit_expr(then_expr, target_reg);
-                
-                let then_end_ip = self.get_ip();
                 let jmp_over_else_ip = self.get_ip();
                 self.emit_instruction(Instruction::new1(Opcode::JMP, 0));
-                
-                // Patch JIF
-                let else_offset = (then_end_ip - jmp_if_false_ip) as i16;
-                self.patch_offset(jmp_if_false_ip, else_offset);
-                
-                // Emit else
+
+                let else_start_ip = self.get_ip();
+                self.patch_jump_target(jmp_if_false_ip, else_start_ip);
                 self.emit_expr(else_expr, target_reg);
-                
-                // Patch jump over else
+
                 let else_end_ip = self.get_ip();
-                let jmp_offset = (else_end_ip - jmp_over_else_ip) as i16;
-                self.patch_offset(jmp_over_else_ip, jmp_offset);
+                self.patch_jump_target(jmp_over_else_ip, else_end_ip);
+            },
+            HirExpr::Spread { .. } => {
+                panic!("Spread is only allowed as the last call argument");
+            },
+            HirExpr::NamedArg { .. } => {
+                panic!("Named arguments are only allowed in call arguments");
             },
             HirExpr::Lambda { .. } => {
                 // TODO: Implement lambda compilation
@@ -703,3 +1115,28 @@ impl Emitter {
         }
     }
 }
+
+/// Number of leading parameters without a default value (a variadic
+/// parameter accepts zero arguments, so it is never required)
+fn required_param_count(params: &[HirParam]) -> u8 {
+    params.iter().take_while(|p| p.default.is_none() && !p.variadic).count() as u8
+}
+
+/// Direct call to the `exit` builtin with at most one argument
+fn is_exit_call(callee: &HirExpr, args: &[HirExpr]) -> bool {
+    matches!(callee, HirExpr::Variable { name, symbol, .. } if *symbol == SymbolRef::BUILTIN && name == "exit")
+        && args.len() <= 1
+}
+
+/// The fused compare-and-skip instruction for a comparison operator
+fn test_opcode(op: BinaryOp) -> Option<Opcode> {
+    match op {
+        BinaryOp::Eq => Some(Opcode::TEST_EQ),
+        BinaryOp::Ne => Some(Opcode::TEST_NE),
+        BinaryOp::Lt => Some(Opcode::TEST_LT),
+        BinaryOp::Le => Some(Opcode::TEST_LE),
+        BinaryOp::Gt => Some(Opcode::TEST_GT),
+        BinaryOp::Ge => Some(Opcode::TEST_GE),
+        _ => None,
+    }
+}
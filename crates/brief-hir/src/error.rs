@@ -1,4 +1,4 @@
-use brief_diagnostic::Span;
+use brief_diagnostic::{Diagnostic, Span};
 
 /// HIR-specific errors
 #[derive(Debug, Clone, PartialEq)]
@@ -7,6 +7,12 @@ pub enum HirError {
     UndefinedVariable {
         name: String,
         span: Span,
+        hint: Option<(Span, String)>, // "Did you mean?" note at the closest symbol in scope
+    },
+    /// Call to a name that is neither declared nor a builtin
+    UndefinedFunction {
+        name: String,
+        span: Span,
     },
     /// Duplicate symbol definition
     DuplicateSymbol {
@@ -19,6 +25,57 @@ pub enum HirError {
         name: String,
         span: Span,
     },
+    /// Class extends a class that doesn't exist
+    UnknownParentClass {
+        class: String,
+        parent: String,
+        span: Span,
+    },
+    /// Class is (indirectly) its own ancestor
+    CyclicInheritance {
+        class: String,
+        span: Span,
+    },
+    /// Left side of an assignment is not a variable, field or index
+    InvalidAssignmentTarget {
+        span: Span,
+    },
+    /// Assignment to a name declared with `const`
+    ConstantReassignment {
+        name: String,
+        span: Span,
+    },
+    /// `break` with no enclosing loop
+    BreakOutsideLoop {
+        span: Span,
+    },
+    /// `continue` with no enclosing loop
+    ContinueOutsideLoop {
+        span: Span,
+    },
+    /// `ret` outside any function body
+    ReturnOutsideFunction {
+        span: Span,
+    },
+    /// Constant expression divides by zero
+    DivisionByZero {
+        span: Span,
+    },
+    /// Part of a `const` initializer that can't be evaluated at compile time
+    NonConstInitializer {
+        span: Span,
+    },
+    /// `break` or `continue` naming a label no enclosing loop has
+    UnknownLoopLabel {
+        label: String,
+        span: Span,
+    },
+    /// `import path as alias` of a module the module loader didn't load,
+    /// so its names aren't known
+    UnloadedModule {
+        module: String,
+        span: Span,
+    },
     /// Other HIR errors
     Other {
         message: String,
@@ -30,10 +87,78 @@ impl HirError {
     pub fn span(&self) -> Span {
         match self {
             HirError::UndefinedVariable { span, .. } => *span,
+            HirError::UndefinedFunction { span, .. } => *span,
             HirError::DuplicateSymbol { duplicate_span, .. } => *duplicate_span,
             HirError::InvalidCapture { span, .. } => *span,
+            HirError::UnknownParentClass { span, .. } => *span,
+            HirError::CyclicInheritance { span, .. } => *span,
+            HirError::InvalidAssignmentTarget { span } => *span,
+            HirError::ConstantReassignment { span, .. } => *span,
+            HirError::BreakOutsideLoop { span } => *span,
+            HirError::ContinueOutsideLoop { span } => *span,
+            HirError::ReturnOutsideFunction { span } => *span,
+            HirError::DivisionByZero { span } => *span,
+            HirError::NonConstInitializer { span } => *span,
+            HirError::UnknownLoopLabel { span, .. } => *span,
+            HirError::UnloadedModule { span, .. } => *span,
             HirError::Other { span, .. } => *span,
         }
     }
+
+    /// Primary message, without location
+    pub fn message(&self) -> String {
+        match self {
+            HirError::UndefinedVariable { name, .. } => format!("Undefined variable '{}'", name),
+            HirError::UndefinedFunction { name, .. } => format!("Undefined function '{}'", name),
+            HirError::DuplicateSymbol { name, .. } => format!("'{}' is already declared", name),
+            HirError::InvalidCapture { name, .. } => format!("Cannot capture '{}'", name),
+            HirError::UnknownParentClass { class, parent, .. } => {
+                format!("Class '{}' extends unknown class '{}'", class, parent)
+            }
+            HirError::CyclicInheritance { class, .. } => format!("Class '{}' inherits from itself", class),
+            HirError::InvalidAssignmentTarget { .. } => "Invalid assignment target".to_string(),
+            HirError::ConstantReassignment { name, .. } => format!("Cannot assign to constant '{}'", name),
+            HirError::BreakOutsideLoop { .. } => "'break' outside of a loop".to_string(),
+            HirError::ContinueOutsideLoop { .. } => "'continue' outside of a loop".to_string(),
+            HirError::ReturnOutsideFunction { .. } => "'ret' outside of a function".to_string(),
+            HirError::DivisionByZero { .. } => "Division by zero in constant expression".to_string(),
+            HirError::NonConstInitializer { .. } => {
+                "Constant initializer must be a literal or an operation on constants".to_string()
+            }
+            HirError::UnknownLoopLabel { label, .. } => format!("No enclosing loop is labeled '{}'", label),
+            HirError::UnloadedModule { module, .. } => {
+                format!("Module '{}' was not loaded, so it can't be given an alias", module)
+            }
+            HirError::Other { message, .. } => message.clone(),
+        }
+    }
+
+    /// The message and primary span; labels are dropped
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.message(), self.span())
+    }
+
+    /// Secondary annotations, each pointing at a related location
+    pub fn labels(&self) -> Vec<(Span, String)> {
+        match self {
+            HirError::UndefinedVariable { hint, .. } => hint.iter().cloned().collect(),
+            HirError::DuplicateSymbol { original_span, .. } => {
+                vec![(*original_span, "first declared here".to_string())]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for HirError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        write!(f, "{} at line {} column {}", self.message(), span.start.line, span.start.column)?;
+        for (label_span, label) in self.labels() {
+            write!(f, "\n  note: {} at line {} column {}", label, label_span.start.line, label_span.start.column)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for HirError {}
@@ -0,0 +1,265 @@
+use brief_ast::{BinaryOp, UnaryOp};
+use brief_diagnostic::Span;
+use crate::hir::*;
+use crate::error::HirError;
+
+/// Replace operators over literal operands with their result, so that
+/// `2 * 60 * 60` is emitted as a single constant. Folding follows the VM's
+/// arithmetic; anything that would fail or overflow at runtime is left alone,
+/// except division by zero, which is reported.
+pub fn fold(program: &mut HirProgram) -> Result<(), Vec<HirError>> {
+    let mut folder = Folder { errors: Vec::new() };
+    for decl in &mut program.declarations {
+        folder.fold_decl(decl);
+    }
+
+    if folder.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(folder.errors)
+    }
+}
+
+struct Folder {
+    errors: Vec<HirError>,
+}
+
+impl Folder {
+    fn fold_decl(&mut self, decl: &mut HirDecl) {
+        match decl {
+            HirDecl::VarDecl(v) => {
+                if let Some(init) = &mut v.initializer {
+                    self.fold_expr(init);
+                }
+            },
+            HirDecl::ConstDecl(c) => self.fold_expr(&mut c.initializer),
+            HirDecl::FuncDecl(f) => {
+                self.fold_params(&mut f.params);
+                self.fold_block(&mut f.body);
+            },
+            HirDecl::ClassDecl(c) => {
+                for field in &mut c.fields {
+                    if let Some(init) = &mut field.initializer {
+                        self.fold_expr(init);
+                    }
+                }
+                for constant in &mut c.constants {
+                    self.fold_expr(&mut constant.initializer);
+                }
+                if let Some(ctor) = &mut c.constructor {
+                    self.fold_params(&mut ctor.params);
+                    self.fold_block(&mut ctor.body);
+                }
+                for method in &mut c.methods {
+                    self.fold_params(&mut method.params);
+                    self.fold_block(&mut method.body);
+                }
+            },
+            HirDecl::ImportDecl(_) | HirDecl::Error(_) => {},
+        }
+    }
+
+    fn fold_params(&mut self, params: &mut [HirParam]) {
+        for param in params {
+            if let Some(default) = &mut param.default {
+                self.fold_expr(default);
+            }
+        }
+    }
+
+    fn fold_block(&mut self, block: &mut HirBlock) {
+        for stmt in &mut block.statements {
+            self.fold_stmt(stmt);
+        }
+    }
+
+    fn fold_stmt(&mut self, stmt: &mut HirStmt) {
+        match stmt {
+            HirStmt::VarDecl(v) => {
+                if let Some(init) = &mut v.initializer {
+                    self.fold_expr(init);
+                }
+            },
+            HirStmt::ConstDecl(c) => self.fold_expr(&mut c.initializer),
+            HirStmt::If { condition, then_branch, else_branch, .. } => {
+                self.fold_expr(condition);
+                self.fold_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.fold_block(else_branch);
+                }
+            },
+            HirStmt::While { condition, body, else_branch, .. } => {
+                self.fold_expr(condition);
+                self.fold_block(body);
+                if let Some(else_branch) = else_branch {
+                    self.fold_block(else_branch);
+                }
+            },
+            HirStmt::For { init, condition, increment, body, .. } => {
+                if let Some(init) = init {
+                    self.fold_stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.fold_expr(condition);
+                }
+                if let Some(increment) = increment {
+                    self.fold_expr(increment);
+                }
+                self.fold_block(body);
+            },
+            HirStmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.fold_expr(value);
+                }
+            },
+            HirStmt::Expr(expr, _) => self.fold_expr(expr),
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Error(_) => {},
+        }
+    }
+
+    fn fold_expr(&mut self, expr: &mut HirExpr) {
+        // Operands first, so nested constant operations collapse bottom-up
+        match expr {
+            HirExpr::MemberAccess { object, .. } | HirExpr::SafeMemberAccess { object, .. } => {
+                self.fold_expr(object);
+            },
+            HirExpr::Index { object, index, .. } => {
+                self.fold_expr(object);
+                self.fold_expr(index);
+            },
+            HirExpr::BinaryOp { left, right, .. } => {
+                self.fold_expr(left);
+                self.fold_expr(right);
+            },
+            HirExpr::UnaryOp { expr, .. } => self.fold_expr(expr),
+            HirExpr::Assign { target, value, .. } => {
+                self.fold_expr(target);
+                self.fold_expr(value);
+            },
+            HirExpr::Call { callee, args, .. } => {
+                self.fold_expr(callee);
+                args.iter_mut().for_each(|arg| self.fold_expr(arg));
+            },
+            HirExpr::MethodCall { object, args, .. } => {
+                self.fold_expr(object);
+                args.iter_mut().for_each(|arg| self.fold_expr(arg));
+            },
+            HirExpr::SuperCall { args, .. } => {
+                args.iter_mut().for_each(|arg| self.fold_expr(arg));
+            },
+            HirExpr::Spread { expr, .. } | HirExpr::Cast { expr, .. } => self.fold_expr(expr),
+            HirExpr::NamedArg { value, .. } => self.fold_expr(value),
+            HirExpr::Ternary { condition, then_expr, else_expr, .. } => {
+                self.fold_expr(condition);
+                self.fold_expr(then_expr);
+                self.fold_expr(else_expr);
+            },
+            HirExpr::Lambda { params, body, .. } => {
+                self.fold_params(params);
+                self.fold_block(body);
+            },
+            // Only text is left: desugaring turns interpolated values into calls
+            HirExpr::Interpolation { .. } |
+            HirExpr::Variable { .. } |
+            HirExpr::Integer(_, _) |
+            HirExpr::Double(_, _) |
+            HirExpr::Character(_, _) |
+            HirExpr::String(_, _) |
+            HirExpr::Boolean(_, _) |
+            HirExpr::Null(_) |
+            HirExpr::Error(_) => {},
+        }
+
+        let folded = match expr {
+            HirExpr::BinaryOp { left, op, right, span } => self.fold_binary(left, *op, right, *span),
+            HirExpr::UnaryOp { op, expr, span } => fold_unary(*op, expr, *span),
+            _ => None,
+        };
+        if let Some(folded) = folded {
+            *expr = folded;
+        }
+    }
+
+    fn fold_binary(&mut self, left: &HirExpr, op: BinaryOp, right: &HirExpr, span: Span) -> Option<HirExpr> {
+        use HirExpr::{Boolean, Double, Integer};
+
+        if matches!(op, BinaryOp::Div | BinaryOp::Mod) && is_zero(right) && as_number(left).is_some() {
+            self.errors.push(HirError::DivisionByZero { span });
+            return None;
+        }
+
+        let folded = match (left, right) {
+            (Integer(a, _), Integer(b, _)) => match op {
+                BinaryOp::Add => Integer(a.checked_add(*b)?, span),
+                BinaryOp::Sub => Integer(a.checked_sub(*b)?, span),
+                BinaryOp::Mul => Integer(a.checked_mul(*b)?, span),
+                BinaryOp::Mod => Integer(a.checked_rem(*b)?, span),
+                BinaryOp::Eq => Boolean(a == b, span),
+                BinaryOp::Ne => Boolean(a != b, span),
+                // Compared as i64 so values past 2^53 don't collapse together
+                BinaryOp::Lt => Boolean(a < b, span),
+                BinaryOp::Le => Boolean(a <= b, span),
+                BinaryOp::Gt => Boolean(a > b, span),
+                BinaryOp::Ge => Boolean(a >= b, span),
+                _ => return fold_numeric(*a as f64, op, *b as f64, span),
+            },
+            (Boolean(a, _), Boolean(b, _)) => match op {
+                BinaryOp::And => Boolean(*a && *b, span),
+                BinaryOp::Or => Boolean(*a || *b, span),
+                BinaryOp::Eq => Boolean(a == b, span),
+                BinaryOp::Ne => Boolean(a != b, span),
+                _ => return None,
+            },
+            // Equality between an Int and a Double is decided by the VM's value comparison
+            (Double(a, _), Double(b, _)) if matches!(op, BinaryOp::Eq | BinaryOp::Ne) => {
+                Boolean((a == b) == (op == BinaryOp::Eq), span)
+            },
+            _ => return fold_numeric(as_number(left)?, op, as_number(right)?, span),
+        };
+        Some(folded)
+    }
+}
+
+/// Arithmetic and ordering where at least one side is a double (or the
+/// operator always produces one)
+fn fold_numeric(a: f64, op: BinaryOp, b: f64, span: Span) -> Option<HirExpr> {
+    use HirExpr::{Boolean, Double};
+    let folded = match op {
+        BinaryOp::Add => Double(a + b, span),
+        BinaryOp::Sub => Double(a - b, span),
+        BinaryOp::Mul => Double(a * b, span),
+        BinaryOp::Div => Double(a / b, span),
+        BinaryOp::Mod => Double(a % b, span),
+        BinaryOp::Pow => Double(a.powf(b), span),
+        BinaryOp::Lt => Boolean(a < b, span),
+        BinaryOp::Le => Boolean(a <= b, span),
+        BinaryOp::Gt => Boolean(a > b, span),
+        BinaryOp::Ge => Boolean(a >= b, span),
+        _ => return None,
+    };
+    Some(folded)
+}
+
+fn fold_unary(op: UnaryOp, operand: &HirExpr, span: Span) -> Option<HirExpr> {
+    let folded = match (op, operand) {
+        (UnaryOp::Neg, HirExpr::Integer(n, _)) => HirExpr::Integer(n.checked_neg()?, span),
+        (UnaryOp::Neg, HirExpr::Double(d, _)) => HirExpr::Double(-d, span),
+        (UnaryOp::Pos, HirExpr::Integer(n, _)) => HirExpr::Integer(*n, span),
+        (UnaryOp::Pos, HirExpr::Double(d, _)) => HirExpr::Double(*d, span),
+        (UnaryOp::Not, HirExpr::Boolean(b, _)) => HirExpr::Boolean(!b, span),
+        _ => return None,
+    };
+    Some(folded)
+}
+
+fn as_number(expr: &HirExpr) -> Option<f64> {
+    match expr {
+        HirExpr::Integer(n, _) => Some(*n as f64),
+        HirExpr::Double(d, _) => Some(*d),
+        _ => None,
+    }
+}
+
+fn is_zero(expr: &HirExpr) -> bool {
+    as_number(expr) == Some(0.0)
+}
@@ -69,6 +69,8 @@ pub struct HirParam {
     pub name: String,
     pub symbol: SymbolRef,
     pub type_annotation: Option<brief_ast::Type>,
+    pub default: Option<HirExpr>,
+    pub variadic: bool,
     pub span: Span,
 }
 
@@ -77,6 +79,9 @@ pub struct HirParam {
 pub struct HirClassDecl {
     pub name: String,
     pub symbol: SymbolRef,
+    pub parent: Option<String>,
+    pub fields: Vec<HirVarDecl>,
+    pub constants: Vec<HirConstDecl>,
     pub constructor: Option<HirCtorDecl>,
     pub methods: Vec<HirMethodDecl>,
     pub span: Span,
@@ -87,6 +92,7 @@ pub struct HirClassDecl {
 pub struct HirCtorDecl {
     pub name: String,
     pub params: Vec<HirParam>,
+    pub receiver: SymbolRef,  // The implicit `obj` local holding the new instance
     pub body: HirBlock,
     pub symbol_table: crate::symbol::SymbolTable,
     pub span: Span,
@@ -96,6 +102,7 @@ impl PartialEq for HirCtorDecl {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
             && self.params == other.params
+            && self.receiver == other.receiver
             && self.body == other.body
             && self.span == other.span
             // Skip symbol_table comparison
@@ -131,10 +138,26 @@ impl PartialEq for HirMethodDecl {
 /// HIR Import Declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct HirImportDecl {
-    pub modules: Vec<String>,
+    pub modules: Vec<HirImportItem>,
     pub span: Span,
 }
 
+/// HIR imported module
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirImportItem {
+    pub path: String,
+    pub alias: Option<HirImportAlias>,
+}
+
+/// `as` name of an imported module: a module-level constant holding a map
+/// of the module's top-level names to their values
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirImportAlias {
+    pub name: String,
+    pub symbol: SymbolRef,
+    pub value: Option<HirExpr>, // The `map_of(...)` call, built during name resolution
+}
+
 /// HIR Expression
 #[derive(Debug, Clone, PartialEq)]
 pub enum HirExpr {
@@ -159,6 +182,12 @@ pub enum HirExpr {
         member: String,
         span: Span,
     },
+    // Null-safe member access: null when the object is null
+    SafeMemberAccess {
+        object: Box<HirExpr>,
+        member: String,
+        span: Span,
+    },
     
     // Index
     Index {
@@ -199,6 +228,24 @@ pub enum HirExpr {
         args: Vec<HirExpr>,
         span: Span,
     },
+    Spread {
+        expr: Box<HirExpr>,
+        span: Span,
+    },
+    // Argument passed by parameter name; the resolver fills in its position
+    NamedArg {
+        name: String,
+        index: usize,
+        value: Box<HirExpr>,
+        span: Span,
+    },
+    // super(args) inside a subclass constructor: runs the parent constructor on `obj`
+    SuperCall {
+        parent: String,        // Filled in during name resolution
+        receiver: SymbolRef,   // The constructor's `obj`
+        args: Vec<HirExpr>,
+        span: Span,
+    },
     
     // Type casting
     Cast {
@@ -221,11 +268,11 @@ pub enum HirExpr {
         span: Span,
     },
     
-    // Lambda (desugared from y(x) := expr)
+    // Lambda; an expression body is desugared to a block returning it
     Lambda {
         params: Vec<HirParam>,
         captures: Vec<Upvalue>,
-        body: Box<HirExpr>,
+        body: HirBlock,
         span: Span,
     },
     
@@ -250,13 +297,17 @@ pub enum HirStmt {
     While {
         condition: Box<HirExpr>,
         body: HirBlock,
+        else_branch: Option<HirBlock>,  // Runs only if the body never ran
+        label: Option<String>,
         span: Span,
     },
+    /// Every other loop; `continue` runs the increment before the condition
     For {
         init: Option<Box<HirStmt>>,
         condition: Option<Box<HirExpr>>,
         increment: Option<Box<HirExpr>>,
         body: HirBlock,
+        label: Option<String>,
         span: Span,
     },
     
@@ -265,8 +316,14 @@ pub enum HirStmt {
         value: Option<HirExpr>,
         span: Span,
     },
-    Break(Span),
-    Continue(Span),
+    Break {
+        label: Option<String>, // The loop to leave; the innermost when None
+        span: Span,
+    },
+    Continue {
+        label: Option<String>,
+        span: Span,
+    },
     
     // Expression statement
     Expr(Box<HirExpr>, Span),
@@ -294,12 +351,16 @@ impl HirExpr {
             HirExpr::Error(span) => *span,
             HirExpr::Variable { span, .. } |
             HirExpr::MemberAccess { span, .. } |
+            HirExpr::SafeMemberAccess { span, .. } |
             HirExpr::Index { span, .. } |
             HirExpr::BinaryOp { span, .. } |
             HirExpr::UnaryOp { span, .. } |
             HirExpr::Assign { span, .. } |
             HirExpr::Call { span, .. } |
             HirExpr::MethodCall { span, .. } |
+            HirExpr::Spread { span, .. } |
+            HirExpr::NamedArg { span, .. } |
+            HirExpr::SuperCall { span, .. } |
             HirExpr::Cast { span, .. } |
             HirExpr::Interpolation { span, .. } |
             HirExpr::Ternary { span, .. } |
@@ -308,3 +369,20 @@ impl HirExpr {
     }
 }
 
+
+impl HirStmt {
+    pub fn span(&self) -> Span {
+        match self {
+            HirStmt::VarDecl(v) => v.span,
+            HirStmt::ConstDecl(c) => c.span,
+            HirStmt::Break { span, .. } |
+            HirStmt::Continue { span, .. } |
+            HirStmt::Expr(_, span) |
+            HirStmt::Error(span) => *span,
+            HirStmt::If { span, .. } |
+            HirStmt::While { span, .. } |
+            HirStmt::For { span, .. } |
+            HirStmt::Return { span, .. } => *span,
+        }
+    }
+}
@@ -2,16 +2,23 @@ pub mod hir;
 pub mod symbol;
 pub mod desugar;
 pub mod resolve;
+pub mod fold;
+pub mod typecheck;
+pub mod reachability;
 pub mod error;
 pub mod emit;
+pub mod dump;
 
 pub use hir::*;
 pub use symbol::*;
 pub use error::*;
+pub use typecheck::typecheck;
+pub use reachability::unreachable_code;
+pub use resolve::KnownGlobals;
 
 use brief_ast::Program;
 
-/// Convert AST to HIR by desugaring and resolving names
+/// Convert AST to HIR by desugaring, resolving names and folding constants
 pub fn lower(program: Program) -> Result<HirProgram, Vec<HirError>> {
     // First desugar
     let mut hir_program = desugar::desugar(program);
@@ -19,6 +26,21 @@ pub fn lower(program: Program) -> Result<HirProgram, Vec<HirError>> {
     // Then resolve names
     resolve::resolve(&mut hir_program)?;
     
+    // Finally collapse constant expressions
+    fold::fold(&mut hir_program)?;
+    
+    Ok(hir_program)
+}
+
+/// Like [`lower`], but the program can use what earlier programs defined.
+/// Only a program that lowers successfully adds its own definitions to
+/// `globals`
+pub fn lower_with_globals(program: Program, globals: &mut KnownGlobals) -> Result<HirProgram, Vec<HirError>> {
+    let mut hir_program = desugar::desugar(program);
+    let mut updated = globals.clone();
+    resolve::resolve_with_globals(&mut hir_program, &mut updated)?;
+    fold::fold(&mut hir_program)?;
+    *globals = updated;
     Ok(hir_program)
 }
 
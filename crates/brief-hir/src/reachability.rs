@@ -0,0 +1,187 @@
+use brief_diagnostic::Diagnostic;
+use crate::hir::*;
+
+/// Warn about statements that can never run because an earlier statement
+/// in the same block always leaves it, through `ret`, `break`, `continue`
+/// or an `if` whose branches all do. Only the first dead statement of a
+/// block is reported
+pub fn unreachable_code(program: &HirProgram) -> Vec<Diagnostic> {
+    let mut finder = Finder { diagnostics: Vec::new() };
+    for decl in &program.declarations {
+        finder.visit_decl(decl);
+    }
+    finder.diagnostics
+}
+
+struct Finder {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Finder {
+    fn visit_decl(&mut self, decl: &HirDecl) {
+        match decl {
+            HirDecl::VarDecl(v) => {
+                if let Some(init) = &v.initializer {
+                    self.visit_expr(init);
+                }
+            },
+            HirDecl::ConstDecl(c) => self.visit_expr(&c.initializer),
+            HirDecl::FuncDecl(f) => {
+                self.visit_params(&f.params);
+                self.visit_block(&f.body);
+            },
+            HirDecl::ClassDecl(c) => {
+                for field in &c.fields {
+                    if let Some(init) = &field.initializer {
+                        self.visit_expr(init);
+                    }
+                }
+                for constant in &c.constants {
+                    self.visit_expr(&constant.initializer);
+                }
+                if let Some(ctor) = &c.constructor {
+                    self.visit_params(&ctor.params);
+                    self.visit_block(&ctor.body);
+                }
+                for method in &c.methods {
+                    self.visit_params(&method.params);
+                    self.visit_block(&method.body);
+                }
+            },
+            HirDecl::ImportDecl(_) | HirDecl::Error(_) => {},
+        }
+    }
+
+    fn visit_params(&mut self, params: &[HirParam]) {
+        for param in params {
+            if let Some(default) = &param.default {
+                self.visit_expr(default);
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: &HirBlock) {
+        for (i, stmt) in block.statements.iter().enumerate() {
+            self.visit_stmt(stmt);
+            if !always_exits(stmt) {
+                continue;
+            }
+            if let Some(dead) = block.statements.get(i + 1) {
+                self.diagnostics.push(Diagnostic::warning("Unreachable statement", dead.span()));
+            }
+            return;
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::VarDecl(v) => {
+                if let Some(init) = &v.initializer {
+                    self.visit_expr(init);
+                }
+            },
+            HirStmt::ConstDecl(c) => self.visit_expr(&c.initializer),
+            HirStmt::If { condition, then_branch, else_branch, .. } => {
+                self.visit_expr(condition);
+                self.visit_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.visit_block(else_branch);
+                }
+            },
+            HirStmt::While { condition, body, else_branch, .. } => {
+                self.visit_expr(condition);
+                self.visit_block(body);
+                if let Some(else_branch) = else_branch {
+                    self.visit_block(else_branch);
+                }
+            },
+            HirStmt::For { init, condition, increment, body, .. } => {
+                if let Some(init) = init {
+                    self.visit_stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.visit_expr(condition);
+                }
+                if let Some(increment) = increment {
+                    self.visit_expr(increment);
+                }
+                self.visit_block(body);
+            },
+            HirStmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            },
+            HirStmt::Expr(expr, _) => self.visit_expr(expr),
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Error(_) => {},
+        }
+    }
+
+    /// Only lambdas contain blocks, but they may be nested anywhere
+    fn visit_expr(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Lambda { params, body, .. } => {
+                self.visit_params(params);
+                self.visit_block(body);
+            },
+            HirExpr::MemberAccess { object, .. } | HirExpr::SafeMemberAccess { object, .. } => {
+                self.visit_expr(object);
+            },
+            HirExpr::Index { object, index, .. } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            },
+            HirExpr::BinaryOp { left, right, .. } => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            },
+            HirExpr::Assign { target, value, .. } => {
+                self.visit_expr(target);
+                self.visit_expr(value);
+            },
+            HirExpr::Call { callee, args, .. } => {
+                self.visit_expr(callee);
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            },
+            HirExpr::MethodCall { object, args, .. } => {
+                self.visit_expr(object);
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            },
+            HirExpr::SuperCall { args, .. } => args.iter().for_each(|arg| self.visit_expr(arg)),
+            HirExpr::UnaryOp { expr, .. } |
+            HirExpr::Spread { expr, .. } |
+            HirExpr::Cast { expr, .. } => self.visit_expr(expr),
+            HirExpr::NamedArg { value, .. } => self.visit_expr(value),
+            HirExpr::Ternary { condition, then_expr, else_expr, .. } => {
+                self.visit_expr(condition);
+                self.visit_expr(then_expr);
+                self.visit_expr(else_expr);
+            },
+            // Only text is left: desugaring turns interpolated values into calls
+            HirExpr::Interpolation { .. } |
+            HirExpr::Variable { .. } |
+            HirExpr::Integer(_, _) |
+            HirExpr::Double(_, _) |
+            HirExpr::Character(_, _) |
+            HirExpr::String(_, _) |
+            HirExpr::Boolean(_, _) |
+            HirExpr::Null(_) |
+            HirExpr::Error(_) => {},
+        }
+    }
+}
+
+/// Whether control never continues past `stmt`
+fn always_exits(stmt: &HirStmt) -> bool {
+    match stmt {
+        HirStmt::Return { .. } | HirStmt::Break { .. } | HirStmt::Continue { .. } => true,
+        HirStmt::If { then_branch, else_branch: Some(else_branch), .. } => {
+            block_exits(then_branch) && block_exits(else_branch)
+        },
+        _ => false,
+    }
+}
+
+fn block_exits(block: &HirBlock) -> bool {
+    block.statements.iter().any(always_exits)
+}
@@ -1,24 +1,94 @@
+use std::collections::HashMap;
 use brief_diagnostic::Span;
+use brief_ast::InterpPart;
 use crate::hir::*;
 use crate::symbol::*;
 use crate::error::HirError;
 
-const BUILTINS: &[&str] = &[
-    "print",
-    "len",
-    "int",
-    "dub",
-    "str",
-    "rt_concat2",
-    "rt_concat3",
-    "rt_concat4",
-    "rt_concat5",
-];
-
 /// Resolve names in HIR and populate symbol tables
 pub fn resolve(program: &mut HirProgram) -> Result<(), Vec<HirError>> {
+    resolve_with_globals(program, &mut KnownGlobals::default())
+}
+
+/// Resolve names in a program that can see the globals defined before it.
+/// On success, `globals` gains everything the program defines at the top
+/// level
+pub fn resolve_with_globals(program: &mut HirProgram, globals: &mut KnownGlobals) -> Result<(), Vec<HirError>> {
     let mut resolver = Resolver::new();
-    resolver.resolve_program(program)
+    resolver.global_count = globals.next_slot;
+    resolver.classes = globals.classes.clone();
+    resolver.signatures = globals.signatures.clone();
+    resolver.promoted_function = globals.promoted_function.clone();
+    resolver.host_builtins = globals.host_builtins.clone();
+    resolver.modules = globals.modules.clone();
+
+    // Earlier definitions live in a scope around the module, so the
+    // program may shadow them
+    resolver.begin_scope();
+    for (symbol, info, constant) in &globals.symbols {
+        let scope = resolver.scopes.last_mut().expect("scope was just opened");
+        scope.add(*symbol, info.clone());
+        if *constant {
+            scope.mark_const(info.name.clone());
+        }
+    }
+    resolver.resolve_program(program)?;
+
+    for (symbol, info, constant) in resolver.defined {
+        globals.symbols.retain(|(_, known, _)| known.name != info.name);
+        globals.symbols.push((symbol, info, constant));
+    }
+    globals.next_slot = resolver.global_count;
+    globals.classes = resolver.classes;
+    globals.signatures = resolver.signatures;
+    Ok(())
+}
+
+/// Top-level names defined by programs that were already resolved, such as
+/// earlier REPL inputs, so later programs can refer to them
+#[derive(Clone, Default)]
+pub struct KnownGlobals {
+    symbols: Vec<(SymbolRef, SymbolInfo, bool)>, // With whether each is a constant
+    next_slot: usize, // First global slot not yet handed out
+    classes: HashMap<String, ClassInfo>,
+    signatures: HashMap<String, Vec<ParamInfo>>,
+    promoted_function: Option<String>,
+    host_builtins: Vec<String>, // Builtins an embedder registered with its runtime
+    modules: HashMap<String, Vec<String>>, // Top-level names of each loaded module, by dotted path
+}
+
+impl KnownGlobals {
+    /// Treat variables declared directly in the body of the function called
+    /// `name` as module-level variables, so they outlive its call. The REPL
+    /// runs each input as such a function
+    pub fn promoting_locals_of(name: &str) -> Self {
+        Self { promoted_function: Some(name.to_string()), ..Self::default() }
+    }
+
+    /// Whether a global called `name` has been defined
+    pub fn contains(&self, name: &str) -> bool {
+        self.symbols.iter().any(|(_, info, _)| info.name == name)
+    }
+
+    /// Names of the globals defined so far, variables included
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.symbols.iter().map(|(_, info, _)| info.name.as_str())
+    }
+
+    /// Resolve `name` as a builtin, for one an embedder registered with its
+    /// runtime, unless a program defines the name itself
+    pub fn add_builtin(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        if !self.host_builtins.contains(&name) {
+            self.host_builtins.push(name);
+        }
+    }
+
+    /// Record the top-level names of the module imported as `path`, which
+    /// `import path as alias` collects into the alias's map
+    pub fn add_module(&mut self, path: impl Into<String>, names: Vec<String>) {
+        self.modules.insert(path.into(), names);
+    }
 }
 
 struct Resolver {
@@ -26,7 +96,64 @@ struct Resolver {
     scopes: Vec<Scope>,
     _current_function: Option<usize>, // Reserved for future use
     local_count: usize,
+    global_count: usize, // Slots handed out to module-level variables
     _upvalue_count: usize,
+    classes: HashMap<String, ClassInfo>,
+    super_target: Option<(String, SymbolRef)>, // Parent and receiver while inside a subclass constructor
+    signatures: HashMap<String, Vec<ParamInfo>>, // Parameters of every global chunk, for named arguments
+    contexts: Vec<Context>, // Enclosing function bodies and loops, innermost last
+    promoted_function: Option<String>, // Function whose top-level variables become module variables
+    host_builtins: Vec<String>,
+    modules: HashMap<String, Vec<String>>, // Names of loaded modules, for import aliases
+    promoted_scope: Option<usize>, // Depth of that function's body scope while it is being resolved
+    defined: Vec<(SymbolRef, SymbolInfo, bool)>, // Module-level names this program defines, with constness
+}
+
+/// A construct that makes `ret`, `break` or `continue` legal inside it
+#[derive(Clone, PartialEq)]
+enum Context {
+    Function,
+    Loop(Option<String>), // With its label
+}
+
+/// A parameter as seen from a call site
+#[derive(Clone)]
+struct ParamInfo {
+    name: String,
+    variadic: bool,
+}
+
+/// What the resolver needs to know about a class before visiting it
+#[derive(Clone)]
+struct ClassInfo {
+    parent: Option<String>,
+    required_params: usize, // Constructor arguments a super(...) call must supply
+    members: HashMap<String, MemberKind>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MemberKind {
+    InstanceMethod,
+    StaticMethod,
+    Constant,
+}
+
+impl MemberKind {
+    fn describe(self) -> &'static str {
+        match self {
+            MemberKind::InstanceMethod => "instance method",
+            MemberKind::StaticMethod => "static method",
+            MemberKind::Constant => "constant",
+        }
+    }
+
+    fn with_article(self) -> &'static str {
+        match self {
+            MemberKind::InstanceMethod => "an instance method",
+            MemberKind::StaticMethod => "a static method",
+            MemberKind::Constant => "a constant",
+        }
+    }
 }
 
 impl Resolver {
@@ -36,7 +163,17 @@ impl Resolver {
             scopes: Vec::new(),
             _current_function: None,
             local_count: 0,
+            global_count: 0,
             _upvalue_count: 0,
+            classes: HashMap::new(),
+            super_target: None,
+            signatures: HashMap::new(),
+            contexts: Vec::new(),
+            promoted_function: None,
+            host_builtins: Vec::new(),
+            modules: HashMap::new(),
+            promoted_scope: None,
+            defined: Vec::new(),
         }
     }
 
@@ -44,11 +181,21 @@ impl Resolver {
         // Create module-level scope
         self.begin_scope();
         
+        // Classes may extend classes declared later in the file
+        self.collect_classes(program);
+        self.collect_signatures(program);
+        self.hoist_declarations(program);
+        
         // Resolve all top-level declarations
         for decl in &mut program.declarations {
             self.resolve_decl(decl);
         }
         
+        let module = self.scopes.last().expect("module scope");
+        let defined: Vec<_> = module.symbols.iter()
+            .map(|(symbol, info)| (*symbol, info.clone(), module.is_const(&info.name)))
+            .collect();
+        self.defined.splice(0..0, defined);
         self.end_scope();
         
         if self.errors.is_empty() {
@@ -61,8 +208,8 @@ impl Resolver {
     fn resolve_decl(&mut self, decl: &mut HirDecl) {
         match decl {
             HirDecl::VarDecl(v) => {
-                // Add to current scope
-                if let Some(symbol) = self.declare_symbol(&v.name, SymbolKind::Local(self.local_count), v.span) {
+                // Module-level variables live in global slots, not registers
+                if let Some(symbol) = self.declare_symbol(&v.name, SymbolKind::Module(self.global_count), v.span) {
                     v.symbol = symbol;
                 }
                 // Resolve initializer
@@ -72,113 +219,464 @@ impl Resolver {
             },
             HirDecl::ConstDecl(c) => {
                 // Add to current scope
-                if let Some(symbol) = self.declare_symbol(&c.name, SymbolKind::Local(self.local_count), c.span) {
+                if let Some(symbol) = self.declare_constant(&c.name, SymbolKind::Module(self.global_count), c.span) {
                     c.symbol = symbol;
                 }
                 // Resolve initializer
                 self.resolve_expr(&mut c.initializer);
+                self.check_const_initializer(&c.initializer);
             },
             HirDecl::FuncDecl(f) => {
-                // Add function name to scope (avoid cloning name)
-                let func_name = f.name.clone(); // Need clone for Global variant
-                if let Some(symbol) = self.declare_symbol(&f.name, SymbolKind::Global(func_name), f.span) {
-                    f.symbol = symbol;
-                }
-                // Resolve function body (with new scope)
+                // The name was hoisted; resolve the body in a new scope
                 self.resolve_func_decl(f);
             },
             HirDecl::ClassDecl(c) => {
-                // Add class name to scope (avoid cloning name)
-                let class_name = c.name.clone(); // Need clone for Global variant
-                if let Some(symbol) = self.declare_symbol(&c.name, SymbolKind::Global(class_name), c.span) {
-                    c.symbol = symbol;
+                // The name was hoisted. Field defaults are evaluated in the
+                // constructor before its body
+                self.begin_scope();
+                let outer_local_count = self.begin_function(0);
+                for field in &mut c.fields {
+                    if let Some(init) = &mut field.initializer {
+                        self.resolve_expr(init);
+                    }
                 }
+                // Each class constant is evaluated by its own chunk
+                for constant in &mut c.constants {
+                    self.begin_function(0);
+                    self.resolve_expr(&mut constant.initializer);
+                    self.check_const_initializer(&constant.initializer);
+                }
+                self.local_count = outer_local_count;
+                self.end_scope();
                 // Resolve constructor and methods
                 if let Some(ctor) = &mut c.constructor {
-                    self.resolve_ctor_decl(ctor);
+                    self.resolve_ctor_decl(ctor, c.parent.as_deref());
+                } else if let Some(parent) = &c.parent {
+                    // The implicit constructor calls super() with no arguments
+                    self.check_implicit_super(&c.name, parent, c.span);
                 }
                 for method in &mut c.methods {
                     self.resolve_method_decl(method);
                 }
             },
-            HirDecl::ImportDecl(_) => {
-                // Imports are handled separately
+            HirDecl::ImportDecl(i) => {
+                // The loader compiles the modules themselves. An alias is a
+                // constant holding a map of the module's names
+                for item in &mut i.modules {
+                    let Some(alias) = &mut item.alias else {
+                        continue;
+                    };
+                    let Some(names) = self.modules.get(&item.path) else {
+                        self.errors.push(HirError::UnloadedModule { module: item.path.clone(), span: i.span });
+                        continue;
+                    };
+                    let mut value = module_map(names, i.span);
+                    self.resolve_expr(&mut value);
+                    alias.value = Some(value);
+                    if let Some(symbol) = self.declare_constant(&alias.name, SymbolKind::Module(self.global_count), i.span) {
+                        alias.symbol = symbol;
+                    }
+                }
             },
             HirDecl::Error(_) => {},
         }
     }
 
+    /// Declare every function and class name before any body is resolved,
+    /// so they can be called from code above them (including mutual
+    /// recursion). Variables and constants are not hoisted
+    fn hoist_declarations(&mut self, program: &mut HirProgram) {
+        for decl in &mut program.declarations {
+            match decl {
+                HirDecl::FuncDecl(f) => {
+                    if let Some(symbol) = self.declare_symbol(&f.name, SymbolKind::Global(f.name.clone()), f.span) {
+                        f.symbol = symbol;
+                    }
+                },
+                HirDecl::ClassDecl(c) => {
+                    if let Some(symbol) = self.declare_symbol(&c.name, SymbolKind::Global(c.name.clone()), c.span) {
+                        c.symbol = symbol;
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn collect_classes(&mut self, program: &HirProgram) {
+        for decl in &program.declarations {
+            if let HirDecl::ClassDecl(c) = decl {
+                let required_params = c.constructor.as_ref().map_or(0, |ctor| {
+                    ctor.params.iter().take_while(|p| p.default.is_none() && !p.variadic).count()
+                });
+                let mut members = HashMap::new();
+                let mut first_spans = HashMap::new();
+                let methods = c.methods.iter().map(|m| {
+                    let kind = if m.is_instance { MemberKind::InstanceMethod } else { MemberKind::StaticMethod };
+                    (&m.name, kind, m.span)
+                });
+                let constants = c.constants.iter().map(|k| (&k.name, MemberKind::Constant, k.span));
+                // Methods and constants share the `Class::name` chunk namespace
+                for (name, kind, span) in methods.chain(constants) {
+                    if members.insert(name.clone(), kind).is_some() {
+                        self.errors.push(HirError::DuplicateSymbol {
+                            name: format!("{}.{}", c.name, name),
+                            original_span: first_spans[name],
+                            duplicate_span: span,
+                        });
+                    } else {
+                        first_spans.insert(name, span);
+                    }
+                }
+                self.classes.insert(c.name.clone(), ClassInfo { parent: c.parent.clone(), required_params, members });
+            }
+        }
+        
+        for decl in &program.declarations {
+            if let HirDecl::ClassDecl(c) = decl
+                && let Some(parent) = &c.parent
+            {
+                if !self.classes.contains_key(parent) {
+                    self.errors.push(HirError::UnknownParentClass {
+                        class: c.name.clone(),
+                        parent: parent.clone(),
+                        span: c.span,
+                    });
+                } else if self.inherits_from(parent, &c.name) {
+                    self.errors.push(HirError::CyclicInheritance {
+                        class: c.name.clone(),
+                        span: c.span,
+                    });
+                }
+            }
+        }
+    }
+
+    fn collect_signatures(&mut self, program: &HirProgram) {
+        fn params(params: &[HirParam]) -> Vec<ParamInfo> {
+            params.iter().map(|p| ParamInfo { name: p.name.clone(), variadic: p.variadic }).collect()
+        }
+        for decl in &program.declarations {
+            match decl {
+                HirDecl::FuncDecl(f) => {
+                    self.signatures.insert(f.name.clone(), params(&f.params));
+                }
+                HirDecl::ClassDecl(c) => {
+                    let ctor = c.constructor.as_ref().map_or_else(Vec::new, |ctor| params(&ctor.params));
+                    self.signatures.insert(c.name.clone(), ctor);
+                    for method in c.methods.iter().filter(|m| !m.is_instance) {
+                        self.signatures.insert(format!("{}::{}", c.name, method.name), params(&method.params));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether `class` is `ancestor` or one of its descendants
+    fn inherits_from(&self, class: &str, ancestor: &str) -> bool {
+        let mut current = Some(class);
+        let mut steps = 0;
+        while let Some(name) = current {
+            if name == ancestor {
+                return true;
+            }
+            // A cycle not involving `ancestor` is reported on its own members
+            steps += 1;
+            if steps > self.classes.len() {
+                return false;
+            }
+            current = self.classes.get(name).and_then(|info| info.parent.as_deref());
+        }
+        false
+    }
+
     fn resolve_func_decl(&mut self, func: &mut HirFuncDecl) {
         // Create new scope for function
         self.begin_scope();
+        let outer_local_count = self.begin_function(func.params.len());
         
         // Add parameters to scope
-        for (idx, param) in func.params.iter_mut().enumerate() {
-            if let Some(symbol) = self.declare_symbol(&param.name, SymbolKind::Param(idx), param.span) {
-                param.symbol = symbol;
-                // Add to function's symbol table
-                func.symbol_table.add_symbol(
-                    param.name.clone(),
-                    SymbolKind::Param(idx),
-                    param.span,
-                );
-            }
-        }
+        self.declare_params(&mut func.params, &mut func.symbol_table);
         
-        // Resolve function body
-        self.resolve_block(&mut func.body);
+        // Resolve function body, one scope inside the parameters
+        if self.promoted_function.as_ref() == Some(&func.name) {
+            self.promoted_scope = Some(self.scopes.len() + 1);
+        }
+        self.resolve_body(&mut func.body, Context::Function);
+        self.promoted_scope = None;
         
         // Build symbol table for function
         // Add all locals to function's symbol table
         // (This is simplified - in a full implementation, we'd track locals more carefully)
         
+        self.local_count = outer_local_count;
         self.end_scope();
     }
 
-    fn resolve_ctor_decl(&mut self, ctor: &mut HirCtorDecl) {
+    fn resolve_ctor_decl(&mut self, ctor: &mut HirCtorDecl, parent: Option<&str>) {
         // Create new scope for constructor
         self.begin_scope();
+        let outer_local_count = self.begin_function(ctor.params.len());
         
         // Add parameters to scope
-        for (idx, param) in ctor.params.iter_mut().enumerate() {
-            if let Some(symbol) = self.declare_symbol(&param.name, SymbolKind::Param(idx), param.span) {
-                param.symbol = symbol;
-                // Add to constructor's symbol table
-                ctor.symbol_table.add_symbol(
-                    param.name.clone(),
-                    SymbolKind::Param(idx),
-                    param.span,
-                );
-            }
+        self.declare_params(&mut ctor.params, &mut ctor.symbol_table);
+        
+        // The instance under construction lives in the first local after the parameters
+        if let Some(symbol) = self.declare_symbol("obj", SymbolKind::Local(0), ctor.span) {
+            ctor.receiver = symbol;
+        }
+        
+        // Without an explicit super(...) the parent constructor runs with no arguments
+        if let Some(parent) = parent
+            && !calls_super(&ctor.body)
+        {
+            self.check_implicit_super(&ctor.name, parent, ctor.span);
         }
         
         // Resolve constructor body
-        self.resolve_block(&mut ctor.body);
+        let outer_super = std::mem::replace(
+            &mut self.super_target,
+            parent.map(|parent| (parent.to_string(), ctor.receiver)),
+        );
+        self.resolve_body(&mut ctor.body, Context::Function);
+        self.super_target = outer_super;
         
+        self.local_count = outer_local_count;
         self.end_scope();
     }
 
     fn resolve_method_decl(&mut self, method: &mut HirMethodDecl) {
         // Create new scope for method
         self.begin_scope();
+        let outer_local_count = self.begin_function(method.params.len());
         
         // Add parameters to scope
-        for (idx, param) in method.params.iter_mut().enumerate() {
+        self.declare_params(&mut method.params, &mut method.symbol_table);
+        
+        // Instance methods receive the instance in the first local after the parameters
+        if method.is_instance {
+            self.declare_symbol("obj", SymbolKind::Local(0), method.span);
+        }
+        
+        // Resolve method body
+        self.resolve_body(&mut method.body, Context::Function);
+        
+        self.local_count = outer_local_count;
+        self.end_scope();
+    }
+
+    /// `Class.method(args)` and `Class.CONST` name static members, which are
+    /// compiled to their own `Owner::name` chunks. Rewrite them into direct
+    /// calls of that chunk (constants are zero-argument calls).
+    fn resolve_class_member(&mut self, expr: &HirExpr) -> Option<HirExpr> {
+        let (object, member, args, wanted, span) = match expr {
+            HirExpr::MethodCall { object, method, args, span } => {
+                (object, method, args.clone(), MemberKind::StaticMethod, *span)
+            }
+            HirExpr::MemberAccess { object, member, span } => {
+                (object, member, Vec::new(), MemberKind::Constant, *span)
+            }
+            HirExpr::Assign { target, span, .. } => {
+                if let HirExpr::MemberAccess { object, member, .. } = &**target
+                    && let Some(class) = self.class_namespace(object)
+                {
+                    self.errors.push(HirError::Other {
+                        message: format!("Cannot assign to class member '{}.{}'", class, member),
+                        span: *span,
+                    });
+                }
+                return None;
+            }
+            _ => return None,
+        };
+        let class = self.class_namespace(object)?;
+        let owner = self.find_class_member(&class, member, wanted, span)?;
+        Some(HirExpr::Call {
+            callee: Box::new(HirExpr::Variable {
+                name: format!("{}::{}", owner, member),
+                symbol: SymbolRef::GLOBAL,
+                span: object.span(),
+            }),
+            args,
+            span,
+        })
+    }
+
+    /// The class named by `object`, unless a local shadows it
+    fn class_namespace(&self, object: &HirExpr) -> Option<String> {
+        let HirExpr::Variable { name, .. } = object else {
+            return None;
+        };
+        let shadowed = self.scopes.iter().rev()
+            .find_map(|scope| scope.lookup(name))
+            .is_some_and(|symbol| symbol != SymbolRef::GLOBAL);
+        (self.classes.contains_key(name) && !shadowed).then(|| name.clone())
+    }
+
+    /// Find the class (`class` or an ancestor) that declares `member`
+    fn find_class_member(&mut self, class: &str, member: &str, wanted: MemberKind, span: Span) -> Option<String> {
+        let mut current = Some(class.to_string());
+        let mut steps = 0;
+        while let Some(name) = current {
+            let info = self.classes.get(&name)?;
+            if let Some(&kind) = info.members.get(member) {
+                if kind == wanted {
+                    return Some(name);
+                }
+                self.errors.push(HirError::Other {
+                    message: format!("'{}.{}' is {}, not {}", class, member, kind.with_article(), wanted.with_article()),
+                    span,
+                });
+                return None;
+            }
+            steps += 1;
+            if steps > self.classes.len() {
+                break; // Cyclic parents are reported separately
+            }
+            current = info.parent.clone();
+        }
+        self.errors.push(HirError::Other {
+            message: format!("Class '{}' has no {} '{}'", class, wanted.describe(), member),
+            span,
+        });
+        None
+    }
+
+    /// Give each named argument the position of the parameter it names. Named
+    /// arguments follow the positional ones and may not leave gaps, since the
+    /// callee fills in defaults only for trailing parameters.
+    /// Only function calls can spread an array into arguments, since only
+    /// they have a call instruction that takes one
+    fn reject_spread(&mut self, args: &[HirExpr], callee: &str) {
+        for arg in args {
+            if let HirExpr::Spread { span, .. } = arg {
+                self.errors.push(HirError::Other {
+                    message: format!("Spread arguments cannot be passed to {}", callee),
+                    span: *span,
+                });
+            }
+        }
+    }
+
+    fn bind_named_args(&mut self, callee: Option<&str>, args: &mut [HirExpr], span: Span) {
+        if !args.iter().any(|arg| matches!(arg, HirExpr::NamedArg { .. })) {
+            return;
+        }
+        let Some(params) = callee.and_then(|name| self.signatures.get(name)) else {
+            self.errors.push(HirError::Other {
+                message: "Named arguments require a call to a declared function".to_string(),
+                span,
+            });
+            return;
+        };
+        let mut filled = vec![false; params.len()];
+        let mut errors = Vec::new();
+        for (position, arg) in args.iter_mut().enumerate() {
+            let HirExpr::NamedArg { name, index, span, .. } = arg else {
+                if let Some(slot) = filled.get_mut(position) {
+                    *slot = true;
+                }
+                continue;
+            };
+            match params.iter().position(|p| p.name == *name) {
+                None => errors.push(HirError::Other {
+                    message: format!("Unknown parameter '{}'", name),
+                    span: *span,
+                }),
+                Some(idx) if params[idx].variadic => errors.push(HirError::Other {
+                    message: format!("Variadic parameter '{}' cannot be passed by name", name),
+                    span: *span,
+                }),
+                Some(idx) if filled[idx] => errors.push(HirError::Other {
+                    message: format!("Argument for parameter '{}' given more than once", name),
+                    span: *span,
+                }),
+                Some(idx) => {
+                    filled[idx] = true;
+                    *index = idx;
+                }
+            }
+        }
+        if errors.is_empty() {
+            let last = filled.iter().rposition(|&f| f).unwrap_or(0);
+            if let Some(gap) = filled[..last].iter().position(|&f| !f) {
+                errors.push(HirError::Other {
+                    message: format!("Missing argument for parameter '{}'", params[gap].name),
+                    span,
+                });
+            }
+        }
+        self.errors.extend(errors);
+    }
+
+    fn check_implicit_super(&mut self, class: &str, parent: &str, span: Span) {
+        let required = self.classes.get(parent).map_or(0, |info| info.required_params);
+        if required > 0 {
+            self.errors.push(HirError::Other {
+                message: format!(
+                    "Constructor of '{}' must call super(...): '{}' requires {} argument(s)",
+                    class, parent, required
+                ),
+                span,
+            });
+        }
+    }
+
+    /// Start numbering locals for a new function body. Parameters occupy the
+    /// first registers, so locals begin after them. Returns the previous count.
+    fn begin_function(&mut self, param_count: usize) -> usize {
+        std::mem::replace(&mut self.local_count, param_count)
+    }
+
+    fn declare_params(&mut self, params: &mut [HirParam], symbol_table: &mut SymbolTable) {
+        for (idx, param) in params.iter_mut().enumerate() {
+            // Defaults are evaluated in the callee, so they may refer to earlier parameters
+            if let Some(default) = &mut param.default {
+                self.resolve_expr(default);
+            }
             if let Some(symbol) = self.declare_symbol(&param.name, SymbolKind::Param(idx), param.span) {
                 param.symbol = symbol;
-                // Add to method's symbol table
-                method.symbol_table.add_symbol(
+                symbol_table.add_symbol(
                     param.name.clone(),
                     SymbolKind::Param(idx),
                     param.span,
                 );
             }
         }
-        
-        // Resolve method body
-        self.resolve_block(&mut method.body);
-        
-        self.end_scope();
+    }
+
+    /// Resolve a function or loop body, inside which `ret` or `break` and
+    /// `continue` are legal
+    fn resolve_body(&mut self, block: &mut HirBlock, context: Context) {
+        self.contexts.push(context);
+        self.resolve_block(block);
+        self.contexts.pop();
+    }
+
+    /// The loops `break` and `continue` may act on, innermost first. A
+    /// function body (such as a lambda's) hides the loops around it
+    fn enclosing_loops(&self) -> impl Iterator<Item = Option<&str>> {
+        self.contexts.iter().rev().map_while(|context| match context {
+            Context::Loop(label) => Some(label.as_deref()),
+            Context::Function => None,
+        })
+    }
+
+    /// Check that a `break` or `continue` has a loop to act on, and that
+    /// a loop with its label encloses it
+    fn check_loop_jump(&mut self, label: Option<&str>, outside_loop: HirError) {
+        let loops: Vec<Option<&str>> = self.enclosing_loops().collect();
+        let error = if loops.is_empty() {
+            Some(outside_loop)
+        } else if let Some(label) = label
+            && !loops.contains(&Some(label))
+        {
+            Some(HirError::UnknownLoopLabel { label: label.to_string(), span: outside_loop.span() })
+        } else {
+            None
+        };
+        self.errors.extend(error);
     }
 
     fn resolve_block(&mut self, block: &mut HirBlock) {
@@ -194,32 +692,27 @@ impl Resolver {
     fn resolve_stmt(&mut self, stmt: &mut HirStmt) {
         match stmt {
             HirStmt::VarDecl(v) => {
-                // If a symbol with this name already exists in an outer scope,
-                // treat this as an assignment instead of introducing a new local.
-                if let Some(existing) = self.lookup_outer_scopes(&v.name) {
-                    v.symbol = existing;
-                    if let Some(init) = &mut v.initializer {
-                        self.resolve_expr(init);
-                    }
-                    return;
-                }
-
-                // Add to current scope
-                if let Some(symbol) = self.declare_symbol(&v.name, SymbolKind::Local(self.local_count), v.span) {
-                    v.symbol = symbol;
-                }
-                // Resolve initializer
+                // `:=` always declares: a name from an outer scope is shadowed,
+                // and the initializer still sees the outer binding
                 if let Some(init) = &mut v.initializer {
                     self.resolve_expr(init);
                 }
+                let kind = self.statement_binding_kind();
+                if let Some(symbol) = self.declare_symbol(&v.name, kind, v.span) {
+                    v.symbol = symbol;
+                    self.record_promoted(false);
+                }
             },
             HirStmt::ConstDecl(c) => {
                 // Add to current scope
-                if let Some(symbol) = self.declare_symbol(&c.name, SymbolKind::Local(self.local_count), c.span) {
+                let kind = self.statement_binding_kind();
+                if let Some(symbol) = self.declare_constant(&c.name, kind, c.span) {
                     c.symbol = symbol;
+                    self.record_promoted(true);
                 }
                 // Resolve initializer
                 self.resolve_expr(&mut c.initializer);
+                self.check_const_initializer(&c.initializer);
             },
             HirStmt::If { condition, then_branch, else_branch, .. } => {
                 self.resolve_expr(condition);
@@ -228,11 +721,14 @@ impl Resolver {
                     self.resolve_block(else_branch);
                 }
             },
-            HirStmt::While { condition, body, .. } => {
+            HirStmt::While { condition, body, else_branch, label, .. } => {
                 self.resolve_expr(condition);
-                self.resolve_block(body);
+                self.resolve_body(body, Context::Loop(label.clone()));
+                if let Some(else_branch) = else_branch {
+                    self.resolve_block(else_branch);
+                }
             },
-            HirStmt::For { init, condition, increment, body, .. } => {
+            HirStmt::For { init, condition, increment, body, label, .. } => {
                 if let Some(init) = init {
                     self.resolve_stmt(init);
                 }
@@ -242,14 +738,24 @@ impl Resolver {
                 if let Some(increment) = increment {
                     self.resolve_expr(increment);
                 }
-                self.resolve_block(body);
+                self.resolve_body(body, Context::Loop(label.clone()));
             },
-            HirStmt::Return { value, .. } => {
+            HirStmt::Return { value, span } => {
+                // Only function bodies hold statements today, so this guards
+                // future top-level code
+                if !self.contexts.contains(&Context::Function) {
+                    self.errors.push(HirError::ReturnOutsideFunction { span: *span });
+                }
                 if let Some(value) = value {
                     self.resolve_expr(value);
                 }
             },
-            HirStmt::Break(_) | HirStmt::Continue(_) => {},
+            HirStmt::Break { label, span } => {
+                self.check_loop_jump(label.as_deref(), HirError::BreakOutsideLoop { span: *span });
+            },
+            HirStmt::Continue { label, span } => {
+                self.check_loop_jump(label.as_deref(), HirError::ContinueOutsideLoop { span: *span });
+            },
             HirStmt::Expr(expr, _) => {
                 self.resolve_expr(expr);
             },
@@ -258,6 +764,19 @@ impl Resolver {
     }
 
     fn resolve_expr(&mut self, expr: &mut HirExpr) {
+        if let Some(call) = self.resolve_class_member(expr) {
+            *expr = call;
+            // The callee is already bound to the member's chunk
+            if let HirExpr::Call { callee, args, span } = expr {
+                for arg in args.iter_mut() {
+                    self.resolve_expr(arg);
+                }
+                if let HirExpr::Variable { name, .. } = &**callee {
+                    self.bind_named_args(Some(name), args, *span);
+                }
+            }
+            return;
+        }
         match expr {
             HirExpr::Variable { name, symbol, span } => {
                 // Look up variable in scopes
@@ -265,14 +784,17 @@ impl Resolver {
                     *symbol = sym_ref;
                 }
             },
-            HirExpr::MemberAccess { object, .. } => {
+            HirExpr::MemberAccess { object, .. } | HirExpr::SafeMemberAccess { object, .. } => {
                 self.resolve_expr(object);
             },
             HirExpr::Index { object, index, .. } => {
                 self.resolve_expr(object);
                 self.resolve_expr(index);
             },
-            HirExpr::BinaryOp { left, right, .. } => {
+            HirExpr::BinaryOp { left, op, right, .. } => {
+                if is_assignment_op(*op) {
+                    self.check_assignment_target(left);
+                }
                 self.resolve_expr(left);
                 self.resolve_expr(right);
             },
@@ -280,27 +802,75 @@ impl Resolver {
                 self.resolve_expr(expr);
             },
             HirExpr::Assign { target, value, .. } => {
+                self.check_assignment_target(target);
                 self.resolve_expr(target);
                 self.resolve_expr(value);
             },
-            HirExpr::Call { callee, args, .. } => {
-                self.resolve_expr(callee);
-                for arg in args {
+            HirExpr::Call { callee, args, span } => {
+                match &mut **callee {
+                    HirExpr::Variable { name, symbol, span } => match self.lookup_symbol(name) {
+                        Some(sym_ref) => *symbol = sym_ref,
+                        None => self.errors.push(HirError::UndefinedFunction {
+                            name: name.clone(),
+                            span: *span,
+                        }),
+                    },
+                    callee => self.resolve_expr(callee),
+                }
+                for arg in args.iter_mut() {
                     self.resolve_expr(arg);
                 }
+                // Only globals have a fixed set of parameter names
+                let name = match &**callee {
+                    HirExpr::Variable { name, symbol, .. } if *symbol == SymbolRef::GLOBAL => Some(name.as_str()),
+                    _ => None,
+                };
+                self.bind_named_args(name, args, *span);
             },
-            HirExpr::MethodCall { object, args, .. } => {
+            HirExpr::MethodCall { object, args, span, .. } => {
                 self.resolve_expr(object);
-                for arg in args {
+                for arg in args.iter_mut() {
+                    self.resolve_expr(arg);
+                }
+                // The receiver's class, and so the method's parameters, is only known at runtime
+                self.bind_named_args(None, args, *span);
+                self.reject_spread(args, "a method");
+            },
+            HirExpr::Spread { expr, .. } => {
+                self.resolve_expr(expr);
+            },
+            HirExpr::NamedArg { value, .. } => {
+                self.resolve_expr(value);
+            },
+            HirExpr::SuperCall { parent, receiver, args, span } => {
+                match &self.super_target {
+                    Some((target, obj)) => {
+                        *parent = target.clone();
+                        *receiver = *obj;
+                    }
+                    None => self.errors.push(HirError::Other {
+                        message: "'super' can only be called in the constructor of a class with a parent".to_string(),
+                        span: *span,
+                    }),
+                }
+                for arg in args.iter_mut() {
                     self.resolve_expr(arg);
                 }
+                let parent = parent.clone();
+                self.bind_named_args(Some(&parent), args, *span);
+                self.reject_spread(args, "super");
             },
-            HirExpr::Cast { expr, .. } => {
+            HirExpr::Cast { expr, target_type, span } => {
                 self.resolve_expr(expr);
+                if crate::emit::cast_type(target_type).is_none() {
+                    self.errors.push(HirError::Other {
+                        message: "Only int, char, dub, str and bool casts are supported".to_string(),
+                        span: *span,
+                    });
+                }
             },
             HirExpr::Interpolation { .. } => {
-                // Interpolation parts contain AST expressions, not HIR expressions
-                // They will be resolved during bytecode generation
+                // Only text is left: desugaring turns interpolated values into calls
             },
             HirExpr::Ternary { condition, then_expr, else_expr, .. } => {
                 self.resolve_expr(condition);
@@ -319,7 +889,7 @@ impl Resolver {
                 }
                 
                 // Resolve body (this will detect captures)
-                self.resolve_expr(body);
+                self.resolve_body(body, Context::Function);
                 
                 // TODO: Detect and record upvalues/captures
                 // For now, captures remains empty
@@ -336,7 +906,102 @@ impl Resolver {
         }
     }
 
-    fn resolve_variable(&mut self, name: &str, span: Span) -> Option<SymbolRef> {
+    /// Only variables, fields and indexed elements can be assigned, and not
+    /// names declared with `const`
+    fn check_assignment_target(&mut self, target: &HirExpr) {
+        match target {
+            HirExpr::Variable { name, span, .. } => {
+                if self.is_constant(name) {
+                    self.errors.push(HirError::ConstantReassignment {
+                        name: name.clone(),
+                        span: *span,
+                    });
+                }
+            }
+            HirExpr::MemberAccess { .. } | HirExpr::Index { .. } => {}
+            // Already reported by the parser
+            HirExpr::Error(_) => {}
+            _ => self.errors.push(HirError::InvalidAssignmentTarget { span: target.span() }),
+        }
+    }
+
+    /// A `const` initializer may only use literals, other constants and
+    /// operators over them, so it can be evaluated at compile time
+    fn check_const_initializer(&mut self, initializer: &HirExpr) {
+        if let Some(part) = self.non_const_part(initializer) {
+            self.errors.push(HirError::NonConstInitializer { span: part.span() });
+        }
+    }
+
+    /// The first subexpression of `expr` that isn't a compile-time constant
+    fn non_const_part<'a>(&self, expr: &'a HirExpr) -> Option<&'a HirExpr> {
+        match expr {
+            HirExpr::Integer(..)
+            | HirExpr::Double(..)
+            | HirExpr::Character(..)
+            | HirExpr::String(..)
+            | HirExpr::Boolean(..)
+            | HirExpr::Null(_)
+            // Already reported
+            | HirExpr::Error(_) => None,
+            // An undefined name has its own error
+            HirExpr::Variable { name, .. } if self.is_constant(name) || self.lookup_symbol(name).is_none() => None,
+            HirExpr::BinaryOp { left, right, .. } => {
+                self.non_const_part(left).or_else(|| self.non_const_part(right))
+            }
+            HirExpr::UnaryOp { expr, .. } | HirExpr::Cast { expr, .. } => self.non_const_part(expr),
+            HirExpr::Ternary { condition, then_expr, else_expr, .. } => self.non_const_part(condition)
+                .or_else(|| self.non_const_part(then_expr))
+                .or_else(|| self.non_const_part(else_expr)),
+            HirExpr::Interpolation { parts, .. } if parts.iter().all(|part| matches!(part, InterpPart::Text(_))) => None,
+            // Interpolation is desugared to these pure concatenation calls
+            HirExpr::Call { callee, args, .. }
+                if matches!(&**callee, HirExpr::Variable { name, .. } if name.starts_with("rt_concat")) =>
+            {
+                args.iter().find_map(|arg| self.non_const_part(arg))
+            }
+            _ => Some(expr),
+        }
+    }
+
+    /// Whether the innermost binding of `name` was declared with `const`
+    fn is_constant(&self, name: &str) -> bool {
+        self.scopes.iter().rev()
+            .find(|scope| scope.lookup(name).is_some())
+            .is_some_and(|scope| scope.is_const(name))
+    }
+
+    /// Where a variable declared by a statement in the current scope is
+    /// stored: a register, or a global slot in a promoted function's body
+    fn statement_binding_kind(&self) -> SymbolKind {
+        if self.promoted_scope == Some(self.scopes.len()) {
+            SymbolKind::Module(self.global_count)
+        } else {
+            SymbolKind::Local(self.local_count)
+        }
+    }
+
+    /// Remember a variable just declared in a promoted function's body, so
+    /// later programs can see it
+    fn record_promoted(&mut self, constant: bool) {
+        if self.promoted_scope != Some(self.scopes.len()) {
+            return;
+        }
+        if let Some((symbol, info)) = self.scopes.last().and_then(|scope| scope.symbols.last()) {
+            self.defined.push((*symbol, info.clone(), constant));
+        }
+    }
+
+    fn declare_constant(&mut self, name: &str, kind: SymbolKind, span: Span) -> Option<SymbolRef> {
+        let symbol = self.declare_symbol(name, kind, span)?;
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.mark_const(name.to_string());
+        }
+        Some(symbol)
+    }
+
+    /// Binding of `name` in the current scopes, or a builtin
+    fn lookup_symbol(&self, name: &str) -> Option<SymbolRef> {
         // Look up in current scopes (from innermost to outermost)
         for scope in self.scopes.iter().rev() {
             if let Some(symbol) = scope.lookup(name) {
@@ -344,49 +1009,95 @@ impl Resolver {
             }
         }
 
-        if Self::is_builtin(name) {
-            return Some(SymbolRef::BUILTIN);
+        self.is_builtin(name).then_some(SymbolRef::BUILTIN)
+    }
+
+    fn resolve_variable(&mut self, name: &str, span: Span) -> Option<SymbolRef> {
+        if let Some(symbol) = self.lookup_symbol(name) {
+            return Some(symbol);
         }
 
         // Not found - report error
+        let hint = self.closest_symbol(name)
+            .map(|(candidate, declared)| (declared, format!("did you mean '{}'?", candidate)));
         self.errors.push(HirError::UndefinedVariable {
             name: name.to_string(),
             span,
+            hint,
         });
         None
     }
 
-    fn is_builtin(name: &str) -> bool {
-        BUILTINS.contains(&name)
+    /// The visible name most similar to a misspelled `name`, with its
+    /// declaration site. Only close matches are offered
+    fn closest_symbol(&self, name: &str) -> Option<(&str, Span)> {
+        let threshold = (name.chars().count() / 3).max(1);
+        self.scopes.iter().rev()
+            .flat_map(|scope| scope.symbols.iter().rev())
+            .map(|(_, info)| (info.name.as_str(), info.span, edit_distance(name, &info.name)))
+            .filter(|&(_, _, distance)| distance <= threshold)
+            .min_by_key(|&(_, _, distance)| distance)
+            .map(|(candidate, declared, _)| (candidate, declared))
+    }
+
+    fn is_builtin(&self, name: &str) -> bool {
+        brief_builtins::is_builtin(name) || self.host_builtins.iter().any(|builtin| builtin == name)
     }
 
     fn declare_symbol(&mut self, name: &str, kind: SymbolKind, span: Span) -> Option<SymbolRef> {
         // Check if already declared in current scope
-        if let Some(scope) = self.scopes.last() {
-            if scope.lookup(name).is_some() {
-                self.errors.push(HirError::DuplicateSymbol {
-                    name: name.to_string(),
-                    original_span: span, // TODO: Get actual original span from existing symbol
-                    duplicate_span: span,
-                });
-                return None;
-            }
+        if let Some(scope) = self.scopes.last()
+            && let Some(original_span) = scope.declaration_span(name)
+        {
+            self.errors.push(HirError::DuplicateSymbol {
+                name: name.to_string(),
+                original_span,
+                duplicate_span: span,
+            });
+            return None;
         }
         
+        // A name an earlier program already gave a slot, such as a variable
+        // redeclared in the REPL, keeps that slot
+        let earlier_slot = match self.scopes.first().and_then(|scope| scope.lookup_full(name)) {
+            Some(SymbolInfo { kind: SymbolKind::Module(index), .. }) => Some(*index),
+            _ => None,
+        };
+        
         // Add to current scope
         if let Some(scope) = self.scopes.last_mut() {
-            // Create a proper symbol reference based on kind
-            let symbol_ref = match kind {
+            // Create a proper symbol reference based on kind. Locals and
+            // module variables take the next free register or slot
+            let (symbol_ref, kind) = match kind {
                 SymbolKind::Local(_) => {
                     let index = self.local_count;
                     self.local_count += 1;
-                    SymbolRef(index)
+                    (SymbolRef(index), SymbolKind::Local(index))
+                },
+                SymbolKind::Param(idx) => (SymbolRef(idx), kind),
+                SymbolKind::Upvalue(idx) => (SymbolRef(idx), kind),
+                SymbolKind::Global(_) => (SymbolRef::GLOBAL, kind), // Globals are looked up by name
+                SymbolKind::Module(_) => {
+                    let index = match earlier_slot {
+                        Some(index) => index,
+                        None => {
+                            let index = self.global_count;
+                            // GET_GLOBAL and SET_GLOBAL address slots with a single byte
+                            if index > usize::from(u8::MAX) {
+                                self.errors.push(HirError::Other {
+                                    message: format!("Too many module-level variables (at most {})", usize::from(u8::MAX) + 1),
+                                    span,
+                                });
+                                return None;
+                            }
+                            self.global_count += 1;
+                            index
+                        },
+                    };
+                    (SymbolRef::global_slot(index), SymbolKind::Module(index))
                 },
-                SymbolKind::Param(idx) => SymbolRef(idx),
-                SymbolKind::Upvalue(idx) => SymbolRef(idx),
-                SymbolKind::Global(_) => SymbolRef(0), // Globals use a different indexing scheme
             };
-            scope.add(name.to_string(), symbol_ref);
+            scope.add(symbol_ref, SymbolInfo { name: name.to_string(), kind, span });
             Some(symbol_ref)
         } else {
             None
@@ -400,16 +1111,41 @@ impl Resolver {
     fn end_scope(&mut self) {
         self.scopes.pop();
     }
+}
 
-    fn lookup_outer_scopes(&self, name: &str) -> Option<SymbolRef> {
-        if self.scopes.len() <= 1 {
-            return None;
-        }
-        for scope in self.scopes[..self.scopes.len() - 1].iter().rev() {
-            if let Some(symbol) = scope.lookup(name) {
-                return Some(symbol);
-            }
+/// Levenshtein distance between two names, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
         }
-        None
     }
+    row[b.len()]
+}
+
+/// Whether `op` assigns to its left operand: `=`, `:=` or a compound form
+pub(crate) fn is_assignment_op(op: brief_ast::BinaryOp) -> bool {
+    use brief_ast::BinaryOp::*;
+    matches!(op, Assign | InitAssign | PlusAssign | MinusAssign | StarAssign | SlashAssign | PercentAssign | PowAssign)
+}
+
+/// Whether a constructor body calls super(...) as one of its statements
+pub(crate) fn calls_super(body: &HirBlock) -> bool {
+    body.statements.iter().any(|stmt| matches!(stmt, HirStmt::Expr(expr, _) if matches!(**expr, HirExpr::SuperCall { .. })))
+}
+
+/// `map_of("name", name, ...)` over a module's top-level names, the value
+/// of an import alias
+fn module_map(names: &[String], span: Span) -> HirExpr {
+    let variable = |name: &str| HirExpr::Variable { name: name.to_string(), symbol: SymbolRef(0), span };
+    let args = names.iter()
+        .flat_map(|name| [HirExpr::String(name.clone(), span), variable(name)])
+        .collect();
+    HirExpr::Call { callee: Box::new(variable("map_of")), args, span }
 }
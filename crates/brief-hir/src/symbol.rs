@@ -1,11 +1,46 @@
 use brief_diagnostic::Span;
+use std::fmt;
 
 /// Symbol reference (index into symbol table)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SymbolRef(pub usize);
 
 impl SymbolRef {
     pub const BUILTIN: Self = Self(usize::MAX);
+    /// Module-level function or class, looked up by name at runtime
+    pub const GLOBAL: Self = Self(usize::MAX - 1);
+    /// First reference used for module-level variable slots
+    const GLOBAL_SLOT_BASE: usize = usize::MAX / 2;
+
+    /// Module-level variable stored in global slot `index`
+    pub fn global_slot(index: usize) -> Self {
+        Self(Self::GLOBAL_SLOT_BASE + index)
+    }
+
+    /// The global slot this reference names, if it is a module-level variable
+    pub fn as_global_slot(self) -> Option<usize> {
+        (Self::GLOBAL_SLOT_BASE..Self::GLOBAL.0).contains(&self.0).then(|| self.0 - Self::GLOBAL_SLOT_BASE)
+    }
+}
+
+/// Names the sentinel references instead of printing their raw values
+impl fmt::Debug for SymbolRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::BUILTIN => write!(f, "Builtin"),
+            Self::GLOBAL => write!(f, "Global"),
+            _ => match self.as_global_slot() {
+                Some(slot) => write!(f, "GlobalSlot({})", slot),
+                None => write!(f, "SymbolRef({})", self.0),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SymbolRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
 }
 
 /// Symbol kind indicating where the symbol is stored
@@ -19,11 +54,14 @@ pub enum SymbolKind {
     Upvalue(usize),
     /// Global symbol (name)
     Global(String),
+    /// Module-level variable or constant (global slot index)
+    Module(usize),
 }
 
-/// Symbol entry in the symbol table
+/// What is known about a declared name: where it is stored and where it
+/// was declared, so diagnostics can point back at the declaration
 #[derive(Debug, Clone, PartialEq)]
-pub struct Symbol {
+pub struct SymbolInfo {
     pub name: String,
     pub kind: SymbolKind,
     pub span: Span,
@@ -32,7 +70,13 @@ pub struct Symbol {
 /// Symbol table for a function/module
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
-    pub symbols: Vec<Symbol>,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SymbolTable {
@@ -44,13 +88,18 @@ impl SymbolTable {
 
     pub fn add_symbol(&mut self, name: String, kind: SymbolKind, span: Span) -> SymbolRef {
         let index = self.symbols.len();
-        self.symbols.push(Symbol { name, kind, span });
+        self.symbols.push(SymbolInfo { name, kind, span });
         SymbolRef(index)
     }
 
-    pub fn get(&self, index: SymbolRef) -> Option<&Symbol> {
+    pub fn get(&self, index: SymbolRef) -> Option<&SymbolInfo> {
         self.symbols.get(index.0)
     }
+
+    /// The most recently added symbol called `name`
+    pub fn lookup_full(&self, name: &str) -> Option<&SymbolInfo> {
+        self.symbols.iter().rev().find(|info| info.name == name)
+    }
 }
 
 /// Scope stack for name resolution
@@ -58,29 +107,56 @@ impl SymbolTable {
 /// For larger scopes, consider using HashMap for O(1) lookup
 #[derive(Debug, Clone)]
 pub struct Scope {
-    pub symbols: Vec<(String, SymbolRef)>,
+    pub symbols: Vec<(SymbolRef, SymbolInfo)>,
+    pub constants: Vec<String>,  // Names declared with `const` in this scope
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scope {
     pub fn new() -> Self {
         Self {
             symbols: Vec::new(),
+            constants: Vec::new(),
         }
     }
 
-    pub fn add(&mut self, name: String, symbol: SymbolRef) {
-        self.symbols.push((name, symbol));
+    pub fn add(&mut self, symbol: SymbolRef, info: SymbolInfo) {
+        self.symbols.push((symbol, info));
+    }
+
+    /// Mark a name bound in this scope as a constant
+    pub fn mark_const(&mut self, name: String) {
+        self.constants.push(name);
+    }
+
+    pub fn is_const(&self, name: &str) -> bool {
+        self.constants.iter().any(|n| n == name)
     }
 
     /// Lookup a symbol in this scope (searches from most recent to oldest)
     /// Returns the most recent binding if multiple exist (shadowing)
     pub fn lookup(&self, name: &str) -> Option<SymbolRef> {
+        self.binding(name).map(|(symbol, _)| *symbol)
+    }
+
+    /// Kind and declaration site of the most recent binding of `name`
+    pub fn lookup_full(&self, name: &str) -> Option<&SymbolInfo> {
+        self.binding(name).map(|(_, info)| info)
+    }
+
+    /// Where the most recent binding of `name` in this scope was declared
+    pub fn declaration_span(&self, name: &str) -> Option<Span> {
+        self.lookup_full(name).map(|info| info.span)
+    }
+
+    fn binding(&self, name: &str) -> Option<&(SymbolRef, SymbolInfo)> {
         // Search backwards to find most recent binding (shadowing)
-        self.symbols
-            .iter()
-            .rev()
-            .find(|(n, _)| n == name)
-            .map(|(_, sym)| *sym)
+        self.symbols.iter().rev().find(|(_, info)| info.name == name)
     }
 }
 
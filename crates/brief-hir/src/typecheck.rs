@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::fmt;
+use brief_ast::{BinaryOp, Type, UnaryOp};
+use brief_diagnostic::{Diagnostic, Span};
+use crate::hir::*;
+use crate::resolve::is_assignment_op;
+use crate::symbol::SymbolRef;
+
+/// Check values flowing into annotated variables, parameters and returns.
+/// Types are inferred from literals, annotations and the VM's operator
+/// rules; anything unannotated stays dynamic (`any`) and is not checked.
+/// Statically non-bool conditions are reported as warnings
+pub fn typecheck(program: &HirProgram) -> Vec<Diagnostic> {
+    let mut checker = Checker::new(program);
+    for decl in &program.declarations {
+        checker.check_decl(decl);
+    }
+    // Report in source order, whatever order the checks ran in
+    checker.diagnostics.sort_by_key(|diagnostic| diagnostic.span);
+    checker.diagnostics
+}
+
+/// Static type of an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ty {
+    Int,
+    Dub,
+    Char,
+    Str,
+    Bool,
+    Null,
+    Any,
+}
+
+impl Ty {
+    /// Arrays, maps and function types are not tracked
+    fn from_annotation(ty: &Type) -> Self {
+        match ty {
+            Type::Int => Ty::Int,
+            Type::Dub => Ty::Dub,
+            Type::Char => Ty::Char,
+            Type::Str => Ty::Str,
+            Type::Bool => Ty::Bool,
+            _ => Ty::Any,
+        }
+    }
+
+    fn annotated(ty: Option<&Type>) -> Self {
+        ty.map_or(Ty::Any, Ty::from_annotation)
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Ty::Int | Ty::Dub)
+    }
+
+    /// Whether a value of type `actual` may be stored where `self` is
+    /// expected. Every variable can hold null, and ints widen to doubles
+    fn accepts(self, actual: Ty) -> bool {
+        self == actual
+            || self == Ty::Any
+            || actual == Ty::Any
+            || actual == Ty::Null
+            || (self == Ty::Dub && actual == Ty::Int)
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ty::Int => "int",
+            Ty::Dub => "dub",
+            Ty::Char => "char",
+            Ty::Str => "str",
+            Ty::Bool => "bool",
+            Ty::Null => "null",
+            Ty::Any => "any",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parameters and declared result of a global chunk
+struct Signature<'a> {
+    params: &'a [HirParam],
+    return_type: Ty,
+}
+
+struct Checker<'a> {
+    signatures: HashMap<String, Signature<'a>>, // Functions, constructors and `Class::method` statics
+    scopes: Vec<HashMap<String, Ty>>,
+    return_type: Option<Ty>, // Declared result of the function being checked
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(program: &'a HirProgram) -> Self {
+        let mut signatures = HashMap::new();
+        for decl in &program.declarations {
+            match decl {
+                HirDecl::FuncDecl(f) => {
+                    let return_type = Ty::annotated(f.return_type.as_ref());
+                    signatures.insert(f.name.clone(), Signature { params: &f.params, return_type });
+                },
+                HirDecl::ClassDecl(c) => {
+                    if let Some(ctor) = &c.constructor {
+                        signatures.insert(c.name.clone(), Signature { params: &ctor.params, return_type: Ty::Any });
+                    }
+                    for method in c.methods.iter().filter(|m| !m.is_instance) {
+                        let return_type = Ty::annotated(method.return_type.as_ref());
+                        signatures.insert(format!("{}::{}", c.name, method.name), Signature { params: &method.params, return_type });
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        Self {
+            signatures,
+            scopes: vec![HashMap::new()],
+            return_type: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn check_decl(&mut self, decl: &HirDecl) {
+        match decl {
+            HirDecl::VarDecl(v) => self.check_var_decl(v),
+            HirDecl::ConstDecl(c) => {
+                // A constant keeps the type of its initializer
+                let ty = self.infer(&c.initializer);
+                self.declare(&c.name, ty);
+            },
+            HirDecl::FuncDecl(f) => self.check_function(&f.params, f.return_type.as_ref(), &f.body),
+            HirDecl::ClassDecl(c) => {
+                for field in &c.fields {
+                    if let Some(init) = &field.initializer {
+                        self.check_initializer(&field.name, field.type_annotation.as_ref(), init);
+                    }
+                }
+                for constant in &c.constants {
+                    self.infer(&constant.initializer);
+                }
+                if let Some(ctor) = &c.constructor {
+                    self.check_function(&ctor.params, None, &ctor.body);
+                }
+                for method in &c.methods {
+                    self.check_function(&method.params, method.return_type.as_ref(), &method.body);
+                }
+            },
+            HirDecl::ImportDecl(_) | HirDecl::Error(_) => {},
+        }
+    }
+
+    fn check_var_decl(&mut self, v: &HirVarDecl) {
+        // The initializer is checked before the name is bound, matching resolution
+        if let Some(init) = &v.initializer {
+            self.check_initializer(&v.name, v.type_annotation.as_ref(), init);
+        }
+        self.declare(&v.name, Ty::annotated(v.type_annotation.as_ref()));
+    }
+
+    fn check_initializer(&mut self, name: &str, annotation: Option<&Type>, init: &HirExpr) {
+        let expected = Ty::annotated(annotation);
+        let actual = self.infer(init);
+        if !expected.accepts(actual) {
+            self.error(format!("Cannot initialize '{}' of type {} with a value of type {}", name, expected, actual), init.span());
+        }
+    }
+
+    fn check_function(&mut self, params: &[HirParam], return_type: Option<&Type>, body: &HirBlock) {
+        self.scopes.push(HashMap::new());
+        for param in params {
+            if let Some(default) = &param.default {
+                self.check_initializer(&param.name, param.type_annotation.as_ref(), default);
+            }
+            // Variadic parameters collect their arguments into an array
+            let ty = if param.variadic { Ty::Any } else { Ty::annotated(param.type_annotation.as_ref()) };
+            self.declare(&param.name, ty);
+        }
+        let outer = self.return_type.replace(Ty::annotated(return_type));
+        self.check_block(body);
+        self.return_type = outer;
+        self.scopes.pop();
+    }
+
+    fn check_block(&mut self, block: &HirBlock) {
+        self.scopes.push(HashMap::new());
+        for stmt in &block.statements {
+            self.check_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::VarDecl(v) => self.check_var_decl(v),
+            HirStmt::ConstDecl(c) => {
+                let ty = self.infer(&c.initializer);
+                self.declare(&c.name, ty);
+            },
+            HirStmt::If { condition, then_branch, else_branch, .. } => {
+                self.check_condition(condition);
+                self.check_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_block(else_branch);
+                }
+            },
+            HirStmt::While { condition, body, else_branch, .. } => {
+                self.check_condition(condition);
+                self.check_block(body);
+                if let Some(else_branch) = else_branch {
+                    self.check_block(else_branch);
+                }
+            },
+            HirStmt::For { init, condition, increment, body, .. } => {
+                self.scopes.push(HashMap::new());
+                if let Some(init) = init {
+                    self.check_stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.check_condition(condition);
+                }
+                if let Some(increment) = increment {
+                    self.infer(increment);
+                }
+                self.check_block(body);
+                self.scopes.pop();
+            },
+            HirStmt::Return { value: Some(value), .. } => {
+                let actual = self.infer(value);
+                if let Some(expected) = self.return_type
+                    && !expected.accepts(actual)
+                {
+                    self.error(format!("Expected return type {}, found {}", expected, actual), value.span());
+                }
+            },
+            HirStmt::Expr(expr, _) => {
+                self.infer(expr);
+            },
+            HirStmt::Return { value: None, .. } | HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Error(_) => {},
+        }
+    }
+
+    fn check_condition(&mut self, condition: &HirExpr) {
+        let ty = self.infer(condition);
+        if !matches!(ty, Ty::Bool | Ty::Any) {
+            self.diagnostics.push(Diagnostic::warning(format!("Condition has type {}, not bool", ty), condition.span()));
+        }
+    }
+
+    fn infer(&mut self, expr: &HirExpr) -> Ty {
+        match expr {
+            HirExpr::Integer(_, _) => Ty::Int,
+            HirExpr::Double(_, _) => Ty::Dub,
+            HirExpr::Character(_, _) => Ty::Char,
+            HirExpr::String(_, _) | HirExpr::Interpolation { .. } => Ty::Str,
+            HirExpr::Boolean(_, _) => Ty::Bool,
+            HirExpr::Null(_) => Ty::Null,
+            HirExpr::Variable { name, .. } => self.lookup(name),
+            HirExpr::MemberAccess { object, .. } | HirExpr::SafeMemberAccess { object, .. } => {
+                self.infer(object);
+                Ty::Any
+            },
+            HirExpr::Index { object, index, .. } => {
+                self.infer(object);
+                self.infer(index);
+                Ty::Any
+            },
+            HirExpr::BinaryOp { left, op, right, .. } if is_assignment_op(*op) => {
+                self.check_assignment(left, compound_operator(*op), right)
+            },
+            HirExpr::Assign { target, value, .. } => self.check_assignment(target, None, value),
+            HirExpr::BinaryOp { left, op, right, .. } => {
+                let left = self.infer(left);
+                let right = self.infer(right);
+                binary_result(left, *op, right)
+            },
+            HirExpr::UnaryOp { op, expr, .. } => {
+                let operand = self.infer(expr);
+                match op {
+                    UnaryOp::Neg | UnaryOp::Pos if operand.is_numeric() => operand,
+                    UnaryOp::Not => Ty::Bool,
+                    UnaryOp::BitNot if operand == Ty::Int => Ty::Int,
+                    _ => Ty::Any,
+                }
+            },
+            HirExpr::Call { callee, args, .. } => self.check_call(callee, args),
+            HirExpr::MethodCall { object, args, .. } => {
+                self.infer(object);
+                for arg in args {
+                    self.infer(arg);
+                }
+                Ty::Any
+            },
+            HirExpr::SuperCall { args, .. } => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                Ty::Any
+            },
+            HirExpr::Spread { expr, .. } => {
+                self.infer(expr);
+                Ty::Any
+            },
+            HirExpr::NamedArg { value, .. } => self.infer(value),
+            HirExpr::Cast { expr, target_type, .. } => {
+                self.infer(expr);
+                Ty::from_annotation(target_type)
+            },
+            HirExpr::Ternary { condition, then_expr, else_expr, .. } => {
+                self.check_condition(condition);
+                let then_ty = self.infer(then_expr);
+                let else_ty = self.infer(else_expr);
+                if then_ty == else_ty { then_ty } else { Ty::Any }
+            },
+            HirExpr::Lambda { params, body, .. } => {
+                self.check_function(params, None, body);
+                Ty::Any
+            },
+            HirExpr::Error(_) => Ty::Any,
+        }
+    }
+
+    /// Plain and compound assignment; an annotated variable only accepts
+    /// values of its type. Returns the type of the stored value
+    fn check_assignment(&mut self, target: &HirExpr, operator: Option<BinaryOp>, value: &HirExpr) -> Ty {
+        let declared = self.infer(target);
+        let assigned = self.infer(value);
+        let stored = operator.map_or(assigned, |op| binary_result(declared, op, assigned));
+        if let HirExpr::Variable { name, .. } = target
+            && !declared.accepts(stored)
+        {
+            self.error(format!("Cannot assign a value of type {} to '{}' of type {}", stored, name, declared), value.span());
+        }
+        stored
+    }
+
+    /// Check arguments against an annotated global's parameters and
+    /// return its declared result
+    fn check_call(&mut self, callee: &HirExpr, args: &[HirExpr]) -> Ty {
+        let arg_types: Vec<Ty> = args.iter().map(|arg| self.infer(arg)).collect();
+        let HirExpr::Variable { name, symbol, .. } = callee else {
+            self.infer(callee);
+            return Ty::Any;
+        };
+        if *symbol == SymbolRef::BUILTIN {
+            return builtin_result(name);
+        }
+        // A local holding a callable value
+        let Some(signature) = self.signatures.get(name).filter(|_| *symbol == SymbolRef::GLOBAL) else {
+            return Ty::Any;
+        };
+        let (params, return_type) = (signature.params, signature.return_type);
+
+        for (position, (arg, actual)) in args.iter().zip(arg_types).enumerate() {
+            let (index, value) = match arg {
+                HirExpr::NamedArg { index, value, .. } => (*index, &**value),
+                // Spread arguments land in positions only known at runtime
+                HirExpr::Spread { .. } => break,
+                _ => (position, arg),
+            };
+            let Some(param) = params.get(index).filter(|p| !p.variadic) else {
+                break;
+            };
+            let expected = Ty::annotated(param.type_annotation.as_ref());
+            if !expected.accepts(actual) {
+                self.error(
+                    format!("Argument '{}' of '{}' expects {}, found {}", param.name, name, expected, actual),
+                    value.span(),
+                );
+            }
+        }
+        return_type
+    }
+
+    fn declare(&mut self, name: &str, ty: Ty) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    /// Declared type of the innermost binding of `name`
+    fn lookup(&self, name: &str) -> Ty {
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name).copied())
+            .unwrap_or(Ty::Any)
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic::error(message, span));
+    }
+}
+
+/// Result type of an operator, following the VM's arithmetic
+fn binary_result(left: Ty, op: BinaryOp, right: Ty) -> Ty {
+    use Ty::*;
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => Bool,
+        BinaryOp::In => Bool,
+        // Concatenation converts the other operand to a string
+        BinaryOp::Add if left == Str || right == Str => Str,
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Mod => match (left, right) {
+            (Int, Int) => Int,
+            _ if left.is_numeric() && right.is_numeric() => Dub,
+            _ => Any,
+        },
+        // Division and exponentiation always produce a double
+        BinaryOp::Div | BinaryOp::Pow if left.is_numeric() && right.is_numeric() => Dub,
+        BinaryOp::And | BinaryOp::Or => Bool,
+        BinaryOp::NullCoalesce if left == Null => right,
+        BinaryOp::NullCoalesce if left == right => left,
+        BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr
+            if left == Int && right == Int => Int,
+        _ => Any,
+    }
+}
+
+/// The arithmetic behind a compound assignment such as `+=`
+fn compound_operator(op: BinaryOp) -> Option<BinaryOp> {
+    match op {
+        BinaryOp::PlusAssign => Some(BinaryOp::Add),
+        BinaryOp::MinusAssign => Some(BinaryOp::Sub),
+        BinaryOp::StarAssign => Some(BinaryOp::Mul),
+        BinaryOp::SlashAssign => Some(BinaryOp::Div),
+        BinaryOp::PercentAssign => Some(BinaryOp::Mod),
+        BinaryOp::PowAssign => Some(BinaryOp::Pow),
+        _ => None,
+    }
+}
+
+/// Result type of builtins whose result does not depend on their arguments
+fn builtin_result(name: &str) -> Ty {
+    match name {
+        "len" | "int" | "time_ms" | "now" | "random_int" | "index_of" | "rt_range_step" => Ty::Int,
+        "dub" | "clock" | "random" | "sqrt" | "sin" | "cos" | "floor" | "ceil" | "round" | "log" => Ty::Dub,
+        "str" | "type" | "upper" | "lower" | "trim" | "replace" | "substring" | "join" | "read_file" | "format" | "cwd" => Ty::Str,
+        "rt_concat2" | "rt_concat3" | "rt_concat4" | "rt_concat5" => Ty::Str,
+        "contains" | "starts_with" | "has" | "file_exists" => Ty::Bool,
+        _ => Ty::Any,
+    }
+}
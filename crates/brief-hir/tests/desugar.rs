@@ -52,6 +52,33 @@ fn test_desugar_for_in() {
     assert!(!hir.declarations.is_empty());
 }
 
+#[test]
+fn test_desugar_for_in_range_call() {
+    // for (i in range(a, b, c)) counts with the step instead of building an array
+    let hir = lower_source("def test()\n\tfor (i in range(10, 0, -2))\n\t\tprint(i)");
+    let HirDecl::FuncDecl(f) = &hir.declarations[0] else {
+        panic!("Expected function declaration");
+    };
+    let body = format!("{:?}", f.body);
+    assert!(body.contains("rt_range_step"));
+    assert!(!body.contains("\"len\""));
+
+    // A program's own range is iterated like any other call
+    let hir = lower_source("def range(n)\n\tret n\ndef test()\n\tfor (i in range(3))\n\t\tprint(i)");
+    let HirDecl::FuncDecl(f) = &hir.declarations[1] else {
+        panic!("Expected function declaration");
+    };
+    assert!(!format!("{:?}", f.body).contains("rt_range_step"));
+
+    // So is a module imported under the name range
+    let source = "import util as range\ndef test()\n\tfor (i in range(3))\n\t\tprint(i)";
+    let file_id = brief_diagnostic::FileId(0);
+    let (tokens, _lex_errors) = brief_lexer::lex(source, file_id);
+    let (ast, _parse_errors) = brief_parser::parse(tokens, file_id);
+    let hir = brief_hir::desugar::desugar(ast);
+    assert!(!format!("{:?}", hir.declarations).contains("rt_range_step"));
+}
+
 #[test]
 fn test_desugar_match() {
     let source = "match(x)\ncase 1\n\tret 1\nelse\n\tret 0";
@@ -82,6 +109,43 @@ fn test_desugar_match_multiple_patterns() {
     assert!(!hir.declarations.is_empty());
 }
 
+#[test]
+fn test_desugar_match_guard_falls_through() {
+    let source = "def test(x)\n\tmatch(x)\n\t\tcase 1 if x > 5\n\t\t\tret 1\n\t\tcase 1\n\t\t\tret 2\n\t\telse\n\t\t\tret 0";
+    let hir = lower_source(source);
+    
+    // match with a guard should be desugared to:
+    //   __temp_0 := x
+    //   if (__temp_0 == 1 && x > 5)
+    //     ret 1
+    //   else
+    //     if (__temp_0 == 1)
+    //       ret 2
+    //     else
+    //       ret 0
+    let HirDecl::FuncDecl(f) = &hir.declarations[0] else {
+        panic!("Expected function declaration");
+    };
+    let HirStmt::If { condition, else_branch, .. } = &f.body.statements[1] else {
+        panic!("Expected if statement for first case");
+    };
+    assert!(matches!(
+        condition.as_ref(),
+        HirExpr::BinaryOp { op: brief_ast::BinaryOp::And, .. }
+    ));
+    
+    // The failing guard must reach the second case, not the else block
+    let else_block = else_branch.as_ref().expect("Guarded case should have an else branch");
+    let HirStmt::If { condition, else_branch, .. } = &else_block.statements[0] else {
+        panic!("Expected if statement for second case");
+    };
+    assert!(matches!(
+        condition.as_ref(),
+        HirExpr::BinaryOp { op: brief_ast::BinaryOp::Eq, .. }
+    ));
+    assert!(else_branch.is_some());
+}
+
 #[test]
 fn test_desugar_ctor_implicit_assign() {
     let source = "cls Dog\n\tobj Dog(name)\n\t\tprint(name)";
@@ -104,11 +168,11 @@ fn test_desugar_ctor_explicit_assign() {
     let hir = lower_source(source);
     
     // Constructor with explicit assignment should not duplicate it
-    if let HirDecl::ClassDecl(c) = &hir.declarations[0] {
-        if let Some(ctor) = &c.constructor {
-            // Should not have duplicate assignments
-            assert!(!ctor.body.statements.is_empty());
-        }
+    if let HirDecl::ClassDecl(c) = &hir.declarations[0]
+        && let Some(ctor) = &c.constructor
+    {
+        // Should not have duplicate assignments
+        assert!(!ctor.body.statements.is_empty());
     }
 }
 
@@ -158,3 +222,23 @@ fn test_desugar_nested_control_flow() {
         assert!(!f.body.statements.is_empty());
     }
 }
+
+#[test]
+fn test_desugar_interpolation_to_concat_calls() {
+    let hir = lower_source("def test(a, b)\n\tret \"&a + &b = &(a + b)!\"");
+    let HirDecl::FuncDecl(f) = &hir.declarations[0] else { panic!("expected a function") };
+    let HirStmt::Return { value: Some(HirExpr::Call { callee, args, .. }), .. } = &f.body.statements[0] else {
+        panic!("expected a returned call, got {:?}", f.body.statements[0]);
+    };
+    // Six pieces: five in the first call, then the rest joined onto it
+    assert!(matches!(&**callee, HirExpr::Variable { name, .. } if name == "rt_concat2"));
+    let HirExpr::Call { args: first, .. } = &args[0] else { panic!("expected a nested call") };
+    assert_eq!(first.len(), 5);
+    assert!(matches!(&first[4], HirExpr::BinaryOp { .. }));
+    assert!(matches!(&args[1], HirExpr::String(text, _) if text == "!"));
+
+    // Text-only strings are left alone
+    let hir = lower_source("def test()\n\tret \"a && b\"");
+    let HirDecl::FuncDecl(f) = &hir.declarations[0] else { panic!("expected a function") };
+    assert!(matches!(&f.body.statements[0], HirStmt::Return { value: Some(HirExpr::Interpolation { .. }), .. }));
+}
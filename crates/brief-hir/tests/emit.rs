@@ -5,8 +5,10 @@ use brief_diagnostic::FileId;
 
 fn emit_source(source: &str) -> Vec<brief_bytecode::Chunk> {
     let file_id = FileId(0);
-    let (tokens, _lex_errors) = lex(source, file_id);
-    let (ast, _parse_errors) = parse(tokens, file_id);
+    let (tokens, lex_errors) = lex(source, file_id);
+    assert!(lex_errors.is_empty(), "Lex errors: {:?}", lex_errors);
+    let (ast, parse_errors) = parse(tokens, file_id);
+    assert!(parse_errors.is_empty(), "Parse errors: {:?}", parse_errors);
     let hir = lower(ast).unwrap_or_else(|errors| {
         eprintln!("HIR lowering errors: {:?}", errors);
         panic!("HIR lowering failed");
@@ -17,8 +19,8 @@ fn emit_source(source: &str) -> Vec<brief_bytecode::Chunk> {
 #[test]
 fn test_emit_simple_function() {
     let source = r#"
-def test():
-    return 42
+def test()
+	ret 42
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -31,11 +33,11 @@ def test():
 #[test]
 fn test_emit_literals() {
     let source = r#"
-def test():
-    x := 42
-    y := 3.14
-    z := true
-    s := "hello"
+def test()
+	x := 42
+	y := 3.14
+	z := true
+	s := "hello"
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -47,10 +49,10 @@ def test():
 #[test]
 fn test_emit_arithmetic() {
     let source = r#"
-def test():
-    x := 1 + 2
-    y := 3 * 4
-    z := 10 - 5
+def test()
+	x := 1 + 2
+	y := 3 * 4
+	z := 10 - 5
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -62,12 +64,12 @@ def test():
 #[test]
 fn test_emit_if_statement() {
     let source = r#"
-def test():
-    if true:
-        x := 1
-    else:
-        y := 2
-    return 0
+def test()
+	if (true)
+		x := 1
+	else
+		y := 2
+	ret 0
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -79,9 +81,9 @@ def test():
 #[test]
 fn test_emit_while_loop() {
     let source = r#"
-def test():
-    while true:
-        x := 1
+def test()
+	while (true)
+		x := 1
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -93,8 +95,8 @@ def test():
 #[test]
 fn test_emit_function_with_params() {
     let source = r#"
-def add(a, b):
-    return a + b
+def add(a, b)
+	ret a + b
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 1);
@@ -106,11 +108,11 @@ def add(a, b):
 #[test]
 fn test_emit_multiple_functions() {
     let source = r#"
-def func1():
-    x := 1
+def func1()
+	x := 1
 
-def func2():
-    y := 2
+def func2()
+	y := 2
 "#;
     let chunks = emit_source(source);
     assert_eq!(chunks.len(), 2);
@@ -118,3 +120,56 @@ def func2():
     assert_eq!(chunks[1].name, "func2");
 }
 
+
+#[test]
+fn test_emit_module_init_chunk() {
+    let source = "x := 1\nconst Y := 2\ndef test()\n\tret x + Y";
+    let chunks = emit_source(source);
+    assert_eq!(chunks.len(), 2);
+    // The entry point stays first; the initializer comes last
+    assert_eq!(chunks[0].name, "test");
+    assert_eq!(chunks[1].name, brief_bytecode::MODULE_INIT);
+    let stores = chunks[1].code.iter().filter(|i| i.opcode() == brief_bytecode::Opcode::SET_GLOBAL).count();
+    assert_eq!(stores, 2);
+    assert!(chunks[0].code.iter().any(|i| i.opcode() == brief_bytecode::Opcode::GET_GLOBAL));
+}
+
+#[test]
+fn test_emit_fused_comparison_conditions() {
+    use brief_bytecode::Opcode;
+
+    let opcodes = |source: &str| -> Vec<Opcode> {
+        emit_source(source)[0].code.iter().map(|i| i.opcode()).collect()
+    };
+
+    // A comparison condition tests in place and jumps over the branch
+    let fused = opcodes("def test(n)\n\ti := 0\n\twhile (i < n)\n\t\ti++\n\tif (i == n)\n\t\tret 1\n\tret 0");
+    for window in [[Opcode::TEST_LT, Opcode::JMP], [Opcode::TEST_EQ, Opcode::JMP]] {
+        assert!(fused.windows(2).any(|w| w == window), "{:?} missing from {:?}", window, fused);
+    }
+    assert!(!fused.contains(&Opcode::JIF));
+    assert!(!fused.contains(&Opcode::CMP_LT));
+
+    // Anything else still materializes the condition for JIF
+    let generic = opcodes("def test(a, b)\n\tif (a && b)\n\t\tret 1\n\tif (a)\n\t\tret 2\n\tret 0");
+    assert!(generic.contains(&Opcode::JIF));
+    assert!(!generic.iter().any(|op| format!("{:?}", op).starts_with("TEST_")));
+
+    // A comparison used as a value is not fused
+    let value = opcodes("def test(a, b)\n\tret a < b");
+    assert!(value.contains(&Opcode::CMP_LT));
+}
+
+#[test]
+fn test_emit_loop_body_reuses_temporaries() {
+    // Each statement needs a handful of temporaries; without reuse the
+    // loop body alone would need more than 255 registers
+    let mut source = String::from("def test(n)\n\ttotal := 0\n\ti := 0\n\twhile (i < n)\n");
+    for k in 0..30 {
+        source.push_str(&format!("\t\ttotal = total + (i + {k}) * (i - {k}) + i * {k} - (i + 1) * (i + 2) + (i * i)\n"));
+    }
+    source.push_str("\t\ti++\n\tret total");
+    let chunks = emit_source(&source);
+    let chunk = chunks.iter().find(|c| c.name == "test").unwrap();
+    assert!(chunk.max_regs < 32, "max_regs = {}", chunk.max_regs);
+}
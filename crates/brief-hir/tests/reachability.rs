@@ -0,0 +1,59 @@
+mod common;
+
+use brief_diagnostic::{Diagnostic, Severity};
+use brief_hir::unreachable_code;
+use common::*;
+
+fn check(source: &str) -> Vec<Diagnostic> {
+    unreachable_code(&lower_source(source))
+}
+
+#[test]
+fn test_statements_after_ret_warn_once() {
+    let diagnostics = check("def test()\n\tret 1\n\tprint(2)\n\tprint(3)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].message, "Unreachable statement");
+    // Points at the first dead statement
+    assert_eq!((diagnostics[0].span.start.line, diagnostics[0].span.start.column), (3, 2));
+}
+
+#[test]
+fn test_statements_after_break_and_continue_warn() {
+    assert_eq!(check("def test()\n\twhile (true)\n\t\tbreak\n\t\tprint(1)").len(), 1);
+    assert_eq!(check("def test()\n\tfor (i := 0; i < 3; i++)\n\t\tcontinue\n\t\tprint(i)").len(), 1);
+}
+
+#[test]
+fn test_if_with_exiting_branches() {
+    // Both branches return, so the statement after the if is dead
+    let diagnostics = check("def test(x)\n\tif (x)\n\t\tret 1\n\telse\n\t\tret 2\n\tprint(3)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].span.start.line, 6);
+
+    // Falling through either branch keeps it reachable
+    assert!(check("def test(x)\n\tif (x)\n\t\tret 1\n\tprint(3)").is_empty());
+    assert!(check("def test(x)\n\tif (x)\n\t\tret 1\n\telse\n\t\tprint(2)\n\tprint(3)").is_empty());
+}
+
+#[test]
+fn test_reachable_code_is_not_reported() {
+    for source in [
+        "def test()\n\tprint(1)\n\tret 2",
+        "def test(xs)\n\tfor (x in xs)\n\t\tret x",
+        "def test()\n\tfor (i in 1..3)\n\t\tret i",
+        "def test()\n\tfor (i := 0; i < 3; i++)\n\t\tret 1",
+        "def test(x)\n\tmatch(x)\n\t\tcase 1\n\t\t\tret 1\n\tret 0",
+        "def test()\n\twhile (true)\n\t\tbreak\n\tret 1",
+    ] {
+        let diagnostics = check(source);
+        assert!(diagnostics.is_empty(), "Unexpected diagnostics for {:?}: {:?}", source, diagnostics);
+    }
+}
+
+#[test]
+fn test_unreachable_inside_lambda() {
+    let diagnostics = check("def test()\n\tf := (x) :=\n\t\tret x\n\t\tprint(x)\n\tret f");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].span.start.line, 4);
+}
@@ -0,0 +1,41 @@
+use brief_diagnostic::{FileId, Position, Span};
+use brief_hir::*;
+
+fn span_at(line: u32) -> Span {
+    Span::new(FileId(0), Position::new(line, 1), Position::new(line, 5))
+}
+
+#[test]
+fn test_symbol_table_keeps_declaration_spans() {
+    let mut table = SymbolTable::new();
+    let x = table.add_symbol("x".to_string(), SymbolKind::Param(0), span_at(1));
+    table.add_symbol("y".to_string(), SymbolKind::Param(1), span_at(2));
+
+    assert_eq!(table.get(x).map(|info| info.span), Some(span_at(1)));
+    let y = table.lookup_full("y").expect("y was added");
+    assert_eq!(y.kind, SymbolKind::Param(1));
+    assert_eq!(y.span, span_at(2));
+    assert!(table.lookup_full("z").is_none());
+}
+
+#[test]
+fn test_scope_lookup_full_returns_latest_binding() {
+    let mut scope = Scope::new();
+    let info = |kind, line| SymbolInfo { name: "x".to_string(), kind, span: span_at(line) };
+    scope.add(SymbolRef(0), info(SymbolKind::Local(0), 1));
+    scope.add(SymbolRef(3), info(SymbolKind::Local(3), 4));
+
+    let latest = scope.lookup_full("x").expect("x is bound");
+    assert_eq!(latest.kind, SymbolKind::Local(3));
+    assert_eq!(latest.span, span_at(4));
+    assert_eq!(scope.lookup("x"), Some(SymbolRef(3)));
+    assert_eq!(scope.declaration_span("x"), Some(span_at(4)));
+}
+
+#[test]
+fn test_symbol_ref_names_sentinels() {
+    assert_eq!(format!("{:?}", SymbolRef::BUILTIN), "Builtin");
+    assert_eq!(format!("{:?}", SymbolRef::GLOBAL), "Global");
+    assert_eq!(format!("{:?}", SymbolRef::global_slot(3)), "GlobalSlot(3)");
+    assert_eq!(SymbolRef(2).to_string(), "SymbolRef(2)");
+}
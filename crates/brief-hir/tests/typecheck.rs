@@ -0,0 +1,143 @@
+mod common;
+
+use brief_diagnostic::{Diagnostic, Severity};
+use brief_hir::{typecheck, HirDecl, HirExpr, HirStmt};
+use common::*;
+
+fn check(source: &str) -> Vec<Diagnostic> {
+    typecheck(&lower_source(source))
+}
+
+fn errors(source: &str) -> Vec<String> {
+    check(source).into_iter().filter(Diagnostic::is_error).map(|d| d.message).collect()
+}
+
+#[test]
+fn test_typecheck_accepts_well_typed_code() {
+    for source in [
+        "def test()\n\tint x := 1\n\tdub d := 2\n\tstr s := \"a\" + 1\n\tbool b := x < d\n\tchar c := 'c'",
+        "def test()\n\tdub d := 7 / 2\n\tdub p := 2 ** 3\n\tint m := 7 % 2",
+        "def add(int x, int y) -> int\n\tret x + y\ndef test()\n\tint z := add(1, 2)",
+        "def half(dub x) -> dub\n\tret x / 2\ndef test()\n\tret half(3)",
+        "def test()\n\tint x := null\n\tstr s\n\ts = \"later\"",
+        "def test(a)\n\tint x := a\n\tstr s := a + 1",
+        "def test()\n\tint n := len(\"abc\")\n\tdub r := sqrt(2)\n\tstr t := type(n)",
+        "def test()\n\tint x := 0\n\tx += 1\n\tx++",
+    ] {
+        let diagnostics = check(source);
+        assert!(diagnostics.is_empty(), "Unexpected diagnostics for {:?}: {:?}", source, diagnostics);
+    }
+}
+
+#[test]
+fn test_typecheck_cast_gives_its_target_type() {
+    let source = "def test()\n\tint x := \"5\" int";
+    let hir = lower_source(source);
+    let HirDecl::FuncDecl(f) = &hir.declarations[0] else {
+        panic!("Expected function declaration");
+    };
+    let HirStmt::VarDecl(x) = &f.body.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    assert!(matches!(x.initializer, Some(HirExpr::Cast { target_type: brief_ast::Type::Int, .. })), "{:?}", x.initializer);
+    assert!(typecheck(&hir).is_empty());
+}
+
+#[test]
+fn test_typecheck_mismatched_initializers() {
+    for (source, expected) in [
+        ("def test()\n\tint x := \"hi\"", "Cannot initialize 'x' of type int with a value of type str"),
+        ("def test()\n\tint x := 1.5", "Cannot initialize 'x' of type int with a value of type dub"),
+        ("def test()\n\tint x := 1 / 2", "Cannot initialize 'x' of type int with a value of type dub"),
+        ("def test()\n\tbool b := 1 + 2", "Cannot initialize 'b' of type bool with a value of type int"),
+        ("def test()\n\tint x := \"n: \" + 1", "Cannot initialize 'x' of type int with a value of type str"),
+        ("int x := true", "Cannot initialize 'x' of type int with a value of type bool"),
+        ("def f(int x = \"one\")\n\tret x", "Cannot initialize 'x' of type int with a value of type str"),
+        ("cls P\n\tint age = \"old\"", "Cannot initialize 'age' of type int with a value of type str"),
+    ] {
+        assert_eq!(errors(source), vec![expected.to_string()], "for {:?}", source);
+    }
+}
+
+#[test]
+fn test_typecheck_assignments() {
+    assert_eq!(
+        errors("def test()\n\tint x := 1\n\tx = \"s\""),
+        vec!["Cannot assign a value of type str to 'x' of type int"]
+    );
+    assert_eq!(
+        errors("def test()\n\tint x := 1\n\tx += 0.5"),
+        vec!["Cannot assign a value of type dub to 'x' of type int"]
+    );
+}
+
+#[test]
+fn test_typecheck_return_types() {
+    assert_eq!(
+        errors("def f() -> int\n\tret \"no\""),
+        vec!["Expected return type int, found str"]
+    );
+    assert_eq!(
+        errors("def f(int a) -> bool\n\tif (a > 0)\n\t\tret a\n\tret false"),
+        vec!["Expected return type bool, found int"]
+    );
+    // The declared result flows to the caller
+    assert_eq!(
+        errors("def f() -> str\n\tret \"s\"\ndef test()\n\tint x := f()"),
+        vec!["Cannot initialize 'x' of type int with a value of type str"]
+    );
+}
+
+#[test]
+fn test_typecheck_call_arguments() {
+    let add = "def add(int x, int y) -> int\n\tret x + y";
+    assert_eq!(
+        errors(&format!("{}\ndef test()\n\tadd(1, \"2\")", add)),
+        vec!["Argument 'y' of 'add' expects int, found str"]
+    );
+    assert_eq!(
+        errors(&format!("{}\ndef test()\n\tadd(y: true, x: 1)", add)),
+        vec!["Argument 'y' of 'add' expects int, found bool"]
+    );
+    assert_eq!(
+        errors("cls P\n\tobj P(str name)\n\t\tprint(name)\ndef test()\n\tP(1)"),
+        vec!["Argument 'name' of 'P' expects str, found int"]
+    );
+}
+
+#[test]
+fn test_typecheck_inner_scope_shadowing() {
+    // The shadowing declaration is unannotated, so it is dynamic
+    assert!(check("def test()\n\tint x := 1\n\tif (true)\n\t\tx := \"s\"\n\tx = 2").is_empty());
+    assert_eq!(
+        errors("def test()\n\tx := \"s\"\n\tif (true)\n\t\tint x := 1\n\tint y := x"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_typecheck_non_bool_condition_warns() {
+    let diagnostics = check("def test()\n\tint n := 3\n\twhile (n)\n\t\tn = n - 1\n\tif (\"yes\")\n\t\tprint(1)");
+    let warnings: Vec<&Diagnostic> = diagnostics.iter().filter(|d| d.severity == Severity::Warning).collect();
+    assert_eq!(warnings.len(), 2, "{:?}", diagnostics);
+    assert_eq!(warnings[0].message, "Condition has type int, not bool");
+    assert_eq!(warnings[1].message, "Condition has type str, not bool");
+    assert!(!diagnostics.iter().any(Diagnostic::is_error));
+
+    // Dynamic conditions are not reported
+    assert!(check("def test(flag)\n\tif (flag)\n\t\tprint(1)").is_empty());
+}
+
+#[test]
+fn test_typecheck_reports_in_source_order() {
+    // The argument is checked before the assignment it feeds, but the
+    // assignment's error starts earlier on the line
+    let source = "def f(int a) -> str\n\tret \"\"\ndef test()\n\tint x := 0\n\tx = f(\"s\")";
+    assert_eq!(
+        errors(source),
+        vec![
+            "Cannot assign a value of type str to 'x' of type int",
+            "Argument 'a' of 'f' expects int, found str",
+        ]
+    );
+}
@@ -0,0 +1,41 @@
+use brief_diagnostic::{Diagnostic, Span};
+
+/// Lexical error with a stable code for tooling
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+    pub code: u32,
+}
+
+impl LexError {
+    pub const UNEXPECTED_CHARACTER: u32 = 1;
+    pub const UNTERMINATED_STRING: u32 = 2;
+    pub const INVALID_INTERPOLATION: u32 = 3; // No longer raised: a lone `&` is literal
+    pub const UNTERMINATED_CHARACTER: u32 = 4;
+    pub const CHARACTER_TOO_LONG: u32 = 5;
+    pub const INVALID_INTEGER: u32 = 6;
+    pub const INVALID_DOUBLE: u32 = 7;
+    pub const SPACE_INDENTATION: u32 = 8;
+    pub const INCONSISTENT_INDENTATION: u32 = 9;
+
+    pub fn new(code: u32, message: String, span: Span) -> Self {
+        Self { message, span, code }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(format!("[L{:03}] {}", self.code, self.message), self.span)
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[L{:03}] {} at line {} column {}",
+            self.code, self.message, self.span.start.line, self.span.start.column
+        )
+    }
+}
+
+impl std::error::Error for LexError {}
@@ -1,4 +1,5 @@
 use crate::token::{Token, TokenKind};
+use crate::error::LexError;
 use brief_diagnostic::{FileId, Position, Span};
 use std::collections::VecDeque;
 
@@ -12,7 +13,7 @@ pub struct Lexer {
     indent_stack: Vec<usize>,
     pending_indents: VecDeque<Token>,
     token_queue: VecDeque<Token>, // For string interpolation parts
-    errors: Vec<String>,
+    errors: Vec<LexError>,
     skip_next_line_start: bool, // Flag to skip line start handling after comment+tab
 }
 
@@ -33,7 +34,7 @@ impl Lexer {
     }
 
     /// Main entry point: lex the entire source
-    pub fn lex(mut self) -> (Vec<Token>, Vec<String>) {
+    pub fn lex(mut self) -> (Vec<Token>, Vec<LexError>) {
         let mut tokens = Vec::new();
         let mut at_line_start = true;
 
@@ -143,7 +144,7 @@ impl Lexer {
                 // Emit newline
                 tokens.push(Token::new(TokenKind::Newline, self.current_span()));
                 // Increment indentation by 1 level
-                let current_level = *self.indent_stack.last().unwrap();
+                let current_level = self.current_indent();
                 let new_level = current_level + 1;
                 self.indent_stack.push(new_level);
                 // Emit the indent token immediately
@@ -157,7 +158,7 @@ impl Lexer {
         }
 
         // Emit final newline if file doesn't end with one
-        if !tokens.last().map_or(false, |t| t.kind == TokenKind::Newline) {
+        if !tokens.last().is_some_and(|t| t.kind == TokenKind::Newline) {
             tokens.push(Token::new(TokenKind::Newline, self.current_span()));
         }
 
@@ -179,7 +180,7 @@ impl Lexer {
     }
 
     fn handle_indentation(&mut self, indent: usize, tokens: &mut Vec<Token>) {
-        let current_level = *self.indent_stack.last().unwrap();
+        let current_level = self.current_indent();
 
         if indent > current_level {
             // Increase indentation - emit one Indent token for each level
@@ -195,7 +196,7 @@ impl Lexer {
         } else if indent < current_level {
             // Decrease indentation
             while self.indent_stack.len() > 1 {
-                let top_level = *self.indent_stack.last().unwrap();
+                let top_level = self.current_indent();
                 if top_level <= indent {
                     break;
                 }
@@ -207,12 +208,13 @@ impl Lexer {
             }
 
             // Error if indent doesn't match any level (stack should have at least base level 0)
-            let final_level = *self.indent_stack.last().unwrap();
+            let final_level = self.current_indent();
             if final_level != indent {
-                self.errors.push(format!(
-                    "inconsistent indentation at line {}",
-                    self.line
-                ));
+                self.error(
+                    LexError::INCONSISTENT_INDENTATION,
+                    "inconsistent indentation".to_string(),
+                    Span::single(self.file_id, Position::new(self.line, 1)),
+                );
             }
         }
         // If indent == current_level, do nothing (same level, no change needed)
@@ -230,10 +232,11 @@ impl Lexer {
                 }
                 ' ' => {
                     // Error: spaces used for indentation
-                    self.errors.push(format!(
-                        "spaces cannot be used for indentation (use tabs) at line {}",
-                        self.line
-                    ));
+                    self.error(
+                        LexError::SPACE_INDENTATION,
+                        "spaces cannot be used for indentation (use tabs)".to_string(),
+                        self.current_span(),
+                    );
                     break;
                 }
                 _ => break,
@@ -269,17 +272,11 @@ impl Lexer {
     }
 
     fn next_token(&mut self) -> Token {
-        // Check if we're at EOF
-        if self.is_at_end() {
-            return Token::new(
-                TokenKind::Eof,
-                Span::single(self.file_id, self.current_pos()),
-            );
-        }
-
         let start = self.current_pos();
 
-        let ch = self.advance().unwrap();
+        let Some(ch) = self.advance() else {
+            return Token::new(TokenKind::Eof, Span::single(self.file_id, start));
+        };
 
         let kind = match ch {
             // Single character operators
@@ -393,7 +390,17 @@ impl Lexer {
             }
             '^' => TokenKind::BitXor,
             '~' => TokenKind::BitNot,
-            '?' => TokenKind::Question,
+            '?' => {
+                if self.match_char('?') {
+                    TokenKind::NullCoalesce
+                } else if self.peek() == Some('.') && !self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+                    // `c ? .5 : 1` is a ternary, not a null-safe access
+                    self.advance();
+                    TokenKind::QuestionDot
+                } else {
+                    TokenKind::Question
+                }
+            }
             ':' => {
                 if self.match_char('=') {
                     TokenKind::InitAssign
@@ -413,15 +420,29 @@ impl Lexer {
             ';' => TokenKind::Semicolon,
             '.' => {
                 // Check if this is the start of a number (e.g., .5)
-                if let Some(next_ch) = self.peek() {
-                    if next_ch.is_ascii_digit() {
-                        // This is a number starting with a decimal point
-                        self.pos -= 1; // Back up to include the dot
-                        self.column -= 1;
-                        return self.lex_number();
+                if let Some(next_ch) = self.peek()
+                    && next_ch.is_ascii_digit()
+                {
+                    // This is a number starting with a decimal point
+                    self.pos -= 1; // Back up to include the dot
+                    self.column -= 1;
+                    return self.lex_number();
+                }
+                if self.peek() == Some('.') && self.peek_next() == Some('.') {
+                    self.advance();
+                    self.advance();
+                    TokenKind::Ellipsis
+                } else if self.peek() == Some('.') {
+                    self.advance();
+                    if self.peek() == Some('=') {
+                        self.advance();
+                        TokenKind::DotDotEq
+                    } else {
+                        TokenKind::DotDot
                     }
+                } else {
+                    TokenKind::Dot
                 }
-                TokenKind::Dot
             }
 
             // Literals
@@ -449,10 +470,11 @@ impl Lexer {
             '\t' => return self.next_token(), // Skip tab and continue
 
             _ => {
-                self.errors.push(format!(
-                    "unexpected character '{}' at line {} column {}",
-                    ch, self.line, self.column
-                ));
+                self.error(
+                    LexError::UNEXPECTED_CHARACTER,
+                    format!("unexpected character '{}'", ch),
+                    self.span_from(start),
+                );
                 return self.next_token(); // Skip and continue
             }
         };
@@ -467,10 +489,11 @@ impl Lexer {
 
         loop {
             if self.is_at_end() {
-                self.errors.push(format!(
-                    "unterminated string starting at line {} column {}",
-                    start.line, start.column
-                ));
+                self.error(
+                    LexError::UNTERMINATED_STRING,
+                    "unterminated string".to_string(),
+                    self.span_from(start),
+                );
                 break;
             }
 
@@ -519,13 +542,14 @@ impl Lexer {
                     }
                 }
                 Some('&') => {
-                    // Check for interpolation or escaped &
-                    if self.peek_next() == Some('&') {
-                        // Escaped &
+                    // `&&` is an escaped `&`, and only `&name` or `&(expr)`
+                    // interpolate. Any other `&` is literal
+                    let next = self.peek_next();
+                    if next == Some('&') {
                         self.advance(); // Skip first &
                         self.advance(); // Skip second &
                         current_text.push('&');
-                    } else {
+                    } else if next.is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '(') {
                         // Interpolation - emit current text part (even if empty)
                         let text_end = self.current_pos();
                         let span = Span::new(self.file_id, text_start, text_end);
@@ -538,38 +562,36 @@ impl Lexer {
                         // Lex interpolation
                         let interp_start = self.current_pos();
                         self.advance(); // Skip &
-                        // Check if next character is valid for interpolation
-                        let is_valid_interp_start = self.peek().map_or(false, |c| {
-                            c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '(' || c == ')'
-                        });
-                        if is_valid_interp_start {
-                            let ident = self.lex_interpolation_ident();
-                            let interp_end = self.current_pos();
-                            let interp_span = Span::new(self.file_id, interp_start, interp_end);
-                            
-                            // Check for path (contains '.' or '(') only once
-                            let has_dot = ident.contains('.');
-                            let interp_kind = if has_dot || ident.contains('(') {
-                                TokenKind::InterpPath(ident)
-                            } else {
-                                TokenKind::InterpIdent(ident)
-                            };
-                            
-                            // Queue interpolation token
-                            self.token_queue.push_back(Token::new(interp_kind, interp_span));
-                            
-                            // Update text_start for next text part
-                            text_start = self.current_pos();
+                        let ident = self.lex_interpolation_ident();
+                        let interp_end = self.current_pos();
+                        let interp_span = Span::new(self.file_id, interp_start, interp_end);
+
+                        // Check for path (contains '.' or '(') only once
+                        let has_dot = ident.contains('.');
+                        let interp_kind = if has_dot || ident.contains('(') {
+                            TokenKind::InterpPath(ident)
                         } else {
-                            self.errors.push(format!(
-                                "invalid interpolation at line {} column {}",
-                                self.line, self.column
-                            ));
-                            // Continue as if it was just a regular character
-                            current_text.push('&');
-                        }
+                            TokenKind::InterpIdent(ident)
+                        };
+
+                        // Queue interpolation token
+                        self.token_queue.push_back(Token::new(interp_kind, interp_span));
+
+                        // Update text_start for next text part
+                        text_start = self.current_pos();
+                    } else {
+                        self.advance();
+                        current_text.push('&');
                     }
                 }
+                Some('\r') => {
+                    // Normalize "\r\n" and a lone '\r' to '\n'
+                    self.advance();
+                    if self.peek() == Some('\n') {
+                        self.advance();
+                    }
+                    current_text.push('\n');
+                }
                 Some(ch) => {
                     current_text.push(ch);
                     self.advance();
@@ -582,10 +604,17 @@ impl Lexer {
         Token::new(TokenKind::StrPart(current_text), self.span_from(start))
     }
 
+    /// Text of an interpolation after its `&`: a name or member path, where
+    /// parenthesized groups such as call arguments or `&(a + b)` are taken
+    /// whole, up to the matching `)` or the end of the string
     fn lex_interpolation_ident(&mut self) -> String {
         let mut ident = String::new();
         while let Some(ch) = self.peek() {
-            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '(' || ch == ')' {
+            // A `.` not followed by a name ends the sentence, not the path
+            let member_dot = ch == '.' && self.peek_next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+            if ch == '(' {
+                self.lex_interpolation_group(&mut ident);
+            } else if ch.is_ascii_alphanumeric() || ch == '_' || member_dot {
                 ident.push(ch);
                 self.advance();
             } else {
@@ -595,6 +624,27 @@ impl Lexer {
         ident
     }
 
+    fn lex_interpolation_group(&mut self, ident: &mut String) {
+        let mut depth = 0;
+        while let Some(ch) = self.peek() {
+            if matches!(ch, '"' | '\n' | '\r') {
+                return;
+            }
+            ident.push(ch);
+            self.advance();
+            match ch {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn lex_char(&mut self) -> Token {
         let start = self.current_pos();
         let ch = if let Some(c) = self.advance() {
@@ -604,18 +654,20 @@ impl Lexer {
                 c
             }
         } else {
-            self.errors.push(format!(
-                "unterminated character literal at line {} column {}",
-                self.line, self.column
-            ));
+            self.error(
+                LexError::UNTERMINATED_CHARACTER,
+                "unterminated character literal".to_string(),
+                self.span_from(start),
+            );
             return Token::new(TokenKind::Character('\0'), self.span_from(start));
         };
 
         if self.peek() != Some('\'') {
-            self.errors.push(format!(
-                "character literal must be single character at line {} column {}",
-                self.line, self.column
-            ));
+            self.error(
+                LexError::CHARACTER_TOO_LONG,
+                "character literal must be single character".to_string(),
+                self.span_from(start),
+            );
         } else {
             self.advance(); // Consume closing quote
         }
@@ -685,9 +737,10 @@ impl Lexer {
             }
         }
 
-        // Check for decimal point (if we haven't already seen it)
+        // Check for decimal point (if we haven't already seen it). Two dots
+        // start a range, as in `1..10`
         let mut has_decimal = starts_with_dot;
-        if !starts_with_dot && self.peek() == Some('.') {
+        if !starts_with_dot && self.peek() == Some('.') && self.peek_next() != Some('.') {
             num_str.push('.');
             self.advance();
             has_decimal = true;
@@ -707,10 +760,11 @@ impl Lexer {
             if let Ok(value) = num_str.parse::<f64>() {
                 Token::new(TokenKind::Double(value), self.span_from(start))
             } else {
-                self.errors.push(format!(
-                    "invalid double literal at line {} column {}",
-                    self.line, self.column
-                ));
+                self.error(
+                    LexError::INVALID_DOUBLE,
+                    format!("invalid double literal '{}'", num_str),
+                    self.span_from(start),
+                );
                 Token::new(TokenKind::Double(0.0), self.span_from(start))
             }
         } else {
@@ -718,10 +772,11 @@ impl Lexer {
             if let Ok(value) = num_str.parse::<i64>() {
                 Token::new(TokenKind::Integer(value), self.span_from(start))
             } else {
-                self.errors.push(format!(
-                    "invalid integer literal at line {} column {}",
-                    self.line, self.column
-                ));
+                self.error(
+                    LexError::INVALID_INTEGER,
+                    format!("invalid integer literal '{}'", num_str),
+                    self.span_from(start),
+                );
                 Token::new(TokenKind::Integer(0), self.span_from(start))
             }
         }
@@ -740,11 +795,7 @@ impl Lexer {
             }
         }
 
-        let kind = if TokenKind::is_keyword(&ident) {
-            TokenKind::from_keyword(&ident).unwrap()
-        } else {
-            TokenKind::Identifier(ident)
-        };
+        let kind = TokenKind::from_keyword(&ident).unwrap_or(TokenKind::Identifier(ident));
 
         Token::new(kind, self.span_from(start))
     }
@@ -791,6 +842,12 @@ impl Lexer {
     }
 
     // Helper methods
+
+    /// The innermost indentation level. The base level 0 is never popped
+    fn current_indent(&self) -> usize {
+        self.indent_stack.last().copied().unwrap_or(0)
+    }
+
     fn is_at_end(&self) -> bool {
         self.pos >= self.source.len()
     }
@@ -815,7 +872,9 @@ impl Lexer {
         if self.pos < self.source.len() {
             let ch = self.source[self.pos];
             self.pos += 1;
-            if ch == '\n' {
+            // A lone '\r' ends a line too; in "\r\n" the '\n' does
+            let line_break = ch == '\n' || (ch == '\r' && self.peek() != Some('\n'));
+            if line_break {
                 self.line += 1;
                 self.column = 1;
             } else {
@@ -836,6 +895,10 @@ impl Lexer {
         }
     }
 
+    fn error(&mut self, code: u32, message: String, span: Span) {
+        self.errors.push(LexError::new(code, message, span));
+    }
+
     fn current_pos(&self) -> Position {
         Position::new(self.line, self.column)
     }
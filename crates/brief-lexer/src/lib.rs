@@ -1,12 +1,14 @@
+pub mod error;
 pub mod lexer;
 pub mod token;
 
+pub use error::LexError;
 pub use lexer::Lexer;
 pub use token::{Token, TokenKind};
 
 use brief_diagnostic::FileId;
 
 /// Lex source code into tokens
-pub fn lex(source: &str, file_id: FileId) -> (Vec<Token>, Vec<String>) {
+pub fn lex(source: &str, file_id: FileId) -> (Vec<Token>, Vec<LexError>) {
     Lexer::new(source, file_id).lex()
 }
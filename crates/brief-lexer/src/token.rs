@@ -26,9 +26,11 @@ pub enum TokenKind {
     Else,
     While,
     For,
+    Repeat,
     In,
     Break,
     Continue,
+    Fall,
     Match,
     Case,
     Def,
@@ -36,6 +38,7 @@ pub enum TokenKind {
     Cls,
     Obj,
     Const,
+    Import,
     Null,
     True,
     False,
@@ -73,6 +76,8 @@ pub enum TokenKind {
     BitXor,         // ^
     BitNot,         // ~
     Question,       // ?
+    NullCoalesce,   // ??
+    QuestionDot,    // ?.
     Colon,          // :
 
     // Punctuation
@@ -85,6 +90,9 @@ pub enum TokenKind {
     Comma,          // ,
     Semicolon,      // ;
     Dot,            // .
+    Ellipsis,       // ...
+    DotDot,         // ..
+    DotDotEq,       // ..=
     Arrow,          // ->
 
     // Literals
@@ -106,32 +114,15 @@ pub enum TokenKind {
 }
 
 impl TokenKind {
+    /// Every reserved word, including the type names
+    pub const KEYWORDS: &'static [&'static str] = &[
+        "int", "char", "str", "dub", "bool", "if", "else", "while", "for", "repeat", "in", "break", "continue",
+        "fall", "match", "case", "def", "ret", "cls", "obj", "const", "import", "null", "true", "false",
+    ];
+
     /// Check if this is a keyword
     pub fn is_keyword(s: &str) -> bool {
-        matches!(
-            s,
-            "int" | "char"
-                | "str"
-                | "dub"
-                | "bool"
-                | "if"
-                | "else"
-                | "while"
-                | "for"
-                | "in"
-                | "break"
-                | "continue"
-                | "match"
-                | "case"
-                | "def"
-                | "ret"
-                | "cls"
-                | "obj"
-                | "const"
-                | "null"
-                | "true"
-                | "false"
-        )
+        Self::KEYWORDS.contains(&s)
     }
 
     /// Convert keyword string to token kind
@@ -146,9 +137,11 @@ impl TokenKind {
             "else" => TokenKind::Else,
             "while" => TokenKind::While,
             "for" => TokenKind::For,
+            "repeat" => TokenKind::Repeat,
             "in" => TokenKind::In,
             "break" => TokenKind::Break,
             "continue" => TokenKind::Continue,
+            "fall" => TokenKind::Fall,
             "match" => TokenKind::Match,
             "case" => TokenKind::Case,
             "def" => TokenKind::Def,
@@ -156,6 +149,7 @@ impl TokenKind {
             "cls" => TokenKind::Cls,
             "obj" => TokenKind::Obj,
             "const" => TokenKind::Const,
+            "import" => TokenKind::Import,
             "null" => TokenKind::Null,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
@@ -8,13 +8,13 @@ fn lex_kinds(source: &str) -> Vec<TokenKind> {
 
 #[test]
 fn test_keywords() {
-    let kinds = lex_kinds("int char str dub bool if else while for in break continue match case def ret cls obj const null true false");
+    let kinds = lex_kinds("int char str dub bool if else while for repeat in break continue match case def ret cls obj const null true false");
     
     assert_eq!(
         kinds,
         vec![
             TokenKind::Int, TokenKind::Char, TokenKind::Str, TokenKind::Dub, TokenKind::Bool,
-            TokenKind::If, TokenKind::Else, TokenKind::While, TokenKind::For, TokenKind::In,
+            TokenKind::If, TokenKind::Else, TokenKind::While, TokenKind::For, TokenKind::Repeat, TokenKind::In,
             TokenKind::Break, TokenKind::Continue, TokenKind::Match, TokenKind::Case,
             TokenKind::Def, TokenKind::Ret, TokenKind::Cls, TokenKind::Obj, TokenKind::Const,
             TokenKind::Null, TokenKind::True, TokenKind::False,
@@ -23,6 +23,15 @@ fn test_keywords() {
     );
 }
 
+#[test]
+fn test_keyword_list_matches_lexer() {
+    for keyword in TokenKind::KEYWORDS {
+        let kinds = lex_kinds(keyword);
+        assert!(!matches!(kinds[0], TokenKind::Identifier(_)), "{} lexed as an identifier", keyword);
+        assert_eq!(Some(kinds[0].clone()), TokenKind::from_keyword(keyword));
+    }
+}
+
 #[test]
 fn test_punctuation() {
     let kinds = lex_kinds("()[]{},;.->");
@@ -40,6 +49,37 @@ fn test_punctuation() {
     );
 }
 
+#[test]
+fn test_ellipsis() {
+    let kinds = lex_kinds("args... xs.len");
+    
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Identifier("args".to_string()), TokenKind::Ellipsis,
+            TokenKind::Identifier("xs".to_string()), TokenKind::Dot,
+            TokenKind::Identifier("len".to_string()),
+            TokenKind::Newline, TokenKind::Eof
+        ]
+    );
+}
+
+#[test]
+fn test_range_operators() {
+    let kinds = lex_kinds("1..10 a..=b 1.5 .5");
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Integer(1), TokenKind::DotDot, TokenKind::Integer(10),
+            TokenKind::Identifier("a".to_string()), TokenKind::DotDotEq,
+            TokenKind::Identifier("b".to_string()),
+            TokenKind::Double(1.5), TokenKind::Double(0.5),
+            TokenKind::Newline, TokenKind::Eof
+        ]
+    );
+}
+
 #[test]
 fn test_special_tokens() {
     let kinds = lex_kinds("x\ny");
@@ -1,4 +1,4 @@
-use brief_lexer::{lex, Token, TokenKind};
+use brief_lexer::{lex, LexError, Token, TokenKind};
 use brief_diagnostic::FileId;
 
 /// Helper function to lex source and return just the token kinds (ignoring spans)
@@ -14,7 +14,7 @@ pub fn lex_tokens(source: &str) -> Vec<Token> {
 }
 
 /// Helper function to lex source and return errors
-pub fn lex_errors(source: &str) -> Vec<String> {
+pub fn lex_errors(source: &str) -> Vec<LexError> {
     let (_tokens, errors) = lex(source, FileId(0));
     errors
 }
@@ -1,4 +1,4 @@
-use brief_lexer::{lex, Token, TokenKind};
+use brief_lexer::{lex, LexError, Token, TokenKind};
 use brief_diagnostic::FileId;
 
 fn lex_kinds(source: &str) -> Vec<TokenKind> {
@@ -11,7 +11,7 @@ fn lex_tokens(source: &str) -> Vec<Token> {
     tokens
 }
 
-fn lex_errors(source: &str) -> Vec<String> {
+fn lex_errors(source: &str) -> Vec<LexError> {
     let (_tokens, errors) = lex(source, FileId(0));
     errors
 }
@@ -47,7 +47,7 @@ fn test_unterminated_string() {
     let errors = lex_errors("\"hello");
     
     assert!(!errors.is_empty());
-    assert!(errors[0].contains("unterminated"));
+    assert!(errors[0].message.contains("unterminated"));
 }
 
 #[test]
@@ -57,6 +57,16 @@ fn test_unterminated_character() {
     assert!(!errors.is_empty());
 }
 
+#[test]
+fn test_lex_error_code_and_span() {
+    let errors = lex_errors("x := 1\ny := $");
+    
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, LexError::UNEXPECTED_CHARACTER);
+    assert_eq!((errors[0].span.start.line, errors[0].span.start.column), (2, 6));
+    assert_eq!(errors[0].to_string(), "[L001] unexpected character '$' at line 2 column 6");
+}
+
 #[test]
 fn test_unterminated_block_comment() {
     // This should not error (just continue), but let's test it doesn't break
@@ -72,7 +82,7 @@ fn test_spaces_in_indentation() {
     
     // Should error about spaces in indentation
     assert!(!errors.is_empty());
-    assert!(errors.iter().any(|e| e.contains("spaces") || e.contains("indentation")));
+    assert!(errors.iter().any(|e| e.code == LexError::SPACE_INDENTATION));
 }
 
 #[test]
@@ -160,3 +170,13 @@ fn test_real_world_example() {
     assert!(kinds.contains(&TokenKind::Ret));
 }
 
+#[test]
+fn test_malformed_input_ends_in_eof() {
+    // Inputs of the kind the fuzz target throws at the lexer: each is lexed
+    // to the end, however many errors it has
+    for source in ["\"", "'", "\"&{", "/*", "0x", "1e", "\\u{", "\r", "\t\t\n\t", "\t\n\n\t\t\t\r\t", "a\tb", "😀\t\"é&", "//\t", "..=.."] {
+        let tokens = lex_tokens(source);
+        assert_eq!(tokens.last().map(|t| &t.kind), Some(&TokenKind::Eof), "{:?}", source);
+    }
+}
+
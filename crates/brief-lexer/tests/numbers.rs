@@ -24,6 +24,7 @@ fn test_integers() {
     );
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_doubles() {
     let kinds = lex_kinds("1.0 3.14 0.5 .5");
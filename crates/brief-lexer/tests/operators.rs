@@ -143,6 +143,37 @@ fn test_ternary_operator() {
     );
 }
 
+#[test]
+fn test_null_coalesce_operator() {
+    let kinds = lex_kinds("?? ?");
+    
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::NullCoalesce,
+            TokenKind::Question,
+            TokenKind::Newline,
+            TokenKind::Eof
+        ]
+    );
+}
+
+#[test]
+fn test_question_dot_operator() {
+    let kinds = lex_kinds("?. ? .5");
+    
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::QuestionDot,
+            TokenKind::Question,
+            TokenKind::Double(0.5),
+            TokenKind::Newline,
+            TokenKind::Eof
+        ]
+    );
+}
+
 #[test]
 fn test_arrow_operator() {
     let kinds = lex_kinds("->");
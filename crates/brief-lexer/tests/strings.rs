@@ -103,6 +103,58 @@ fn test_string_with_escaped_ampersand() {
     );
 }
 
+#[test]
+fn test_string_lone_ampersand_is_literal() {
+    for (source, text) in [("\"a & b\"", "a & b"), ("\"a &\"", "a &"), ("\"5&3 &.\"", "5&3 &.")] {
+        let (tokens, errors) = lex(source, FileId(0));
+        assert!(errors.is_empty(), "{}: {:?}", source, errors);
+        assert_eq!(tokens[0].kind, TokenKind::StrPart(text.to_string()), "{}", source);
+        assert_eq!(tokens[1].kind, TokenKind::Newline, "{}", source);
+    }
+}
+
+#[test]
+fn test_string_escaped_ampersand_at_end() {
+    let (tokens, errors) = lex("\"cost: 5&&\"", FileId(0));
+    assert!(errors.is_empty(), "{:?}", errors);
+    let kinds: Vec<_> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::StrPart("cost: 5&".to_string()), TokenKind::Newline, TokenKind::Eof]);
+}
+
+#[test]
+fn test_string_short_interpolation() {
+    let (tokens, errors) = lex("\"&x\"", FileId(0));
+    assert!(errors.is_empty(), "{:?}", errors);
+    let kinds: Vec<_> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::StrPart("".to_string()),
+            TokenKind::InterpIdent("x".to_string()),
+            TokenKind::StrPart("".to_string()),
+            TokenKind::Newline,
+            TokenKind::Eof
+        ]
+    );
+}
+
+#[test]
+fn test_string_interpolation_takes_parentheses_whole() {
+    let kinds = lex_kinds("\"&(a + b) and &f(x, 2).y.\"");
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::StrPart("".to_string()),
+            TokenKind::InterpPath("(a + b)".to_string()),
+            TokenKind::StrPart(" and ".to_string()),
+            TokenKind::InterpPath("f(x, 2).y".to_string()),
+            TokenKind::StrPart(".".to_string()),
+            TokenKind::Newline,
+            TokenKind::Eof
+        ]
+    );
+}
+
 #[test]
 fn test_string_escape_sequences() {
     // The escape sequences should be processed
@@ -180,3 +232,24 @@ fn test_string_complex_interpolation() {
     );
 }
 
+
+#[test]
+fn test_string_with_crlf() {
+    let tokens = lex_tokens("\"one\r\ntwo\" x");
+    
+    assert_eq!(tokens[0].kind, TokenKind::StrPart("one\ntwo".to_string()));
+    assert_eq!((tokens[0].span.start.line, tokens[0].span.start.column), (1, 2));
+    assert_eq!((tokens[0].span.end.line, tokens[0].span.end.column), (2, 4));
+    // Positions after the string stay on the right line
+    assert_eq!(tokens[1].kind, TokenKind::Identifier("x".to_string()));
+    assert_eq!((tokens[1].span.start.line, tokens[1].span.start.column), (2, 6));
+}
+
+#[test]
+fn test_string_with_lone_cr() {
+    let tokens = lex_tokens("\"one\rtwo\" x");
+    
+    assert_eq!(tokens[0].kind, TokenKind::StrPart("one\ntwo".to_string()));
+    assert_eq!(tokens[1].kind, TokenKind::Identifier("x".to_string()));
+    assert_eq!((tokens[1].span.start.line, tokens[1].span.start.column), (2, 6));
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::path::PathBuf;
+use brief_diagnostic::{Diagnostic, Span};
+use brief_hir::HirError;
+use brief_lexer::LexError;
+use brief_parser::ParseError;
+
+/// Why an imported module couldn't be loaded. Compile errors name the
+/// module's file; their spans point into it
+#[derive(Debug)]
+pub enum ModuleError {
+    /// No search path has a file for the module
+    NotFound {
+        module: String,
+        span: Span, // The import naming it
+        searched: Vec<PathBuf>,
+    },
+    /// A module imports itself, directly or through the modules it imports
+    CircularImport {
+        path: PathBuf,
+        span: Span, // The import closing the cycle
+        cycle_start: usize, // Where `path` is in `stack`
+        stack: Vec<PathBuf>, // The modules being loaded, outermost first
+    },
+    Io {
+        path: PathBuf,
+        span: Span, // The import naming it
+        error: std::io::Error,
+    },
+    Lex {
+        path: PathBuf,
+        errors: Vec<LexError>,
+    },
+    Parse {
+        path: PathBuf,
+        errors: Vec<ParseError>,
+    },
+    Hir {
+        path: PathBuf,
+        errors: Vec<HirError>,
+    },
+}
+
+impl ModuleError {
+    /// The error as diagnostics: one per compile error, or one at the
+    /// import for a module that couldn't be found, read or finished
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            ModuleError::NotFound { span, .. }
+            | ModuleError::CircularImport { span, .. }
+            | ModuleError::Io { span, .. } => {
+                vec![Diagnostic::error(self.to_string(), *span)]
+            }
+            ModuleError::Lex { errors, .. } => errors.iter().map(LexError::to_diagnostic).collect(),
+            ModuleError::Parse { errors, .. } => errors.iter().map(ParseError::to_diagnostic).collect(),
+            ModuleError::Hir { errors, .. } => errors.iter().map(HirError::to_diagnostic).collect(),
+        }
+    }
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleError::NotFound { module, searched, .. } => {
+                write!(f, "Module '{}' not found", module)?;
+                let dirs: Vec<String> = searched.iter().map(|dir| dir.display().to_string()).collect();
+                if !dirs.is_empty() {
+                    write!(f, " (searched {})", dirs.join(", "))?;
+                }
+                Ok(())
+            }
+            ModuleError::CircularImport { path, cycle_start, stack, .. } => {
+                write!(f, "Circular import: ")?;
+                for module in &stack[*cycle_start..] {
+                    write!(f, "{} -> ", module.display())?;
+                }
+                write!(f, "{}", path.display())
+            }
+            ModuleError::Io { path, error, .. } => write!(f, "Could not read module {}: {}", path.display(), error),
+            ModuleError::Lex { path, errors } => write_errors(f, "Lexical errors", path, errors),
+            ModuleError::Parse { path, errors } => write_errors(f, "Parse errors", path, errors),
+            ModuleError::Hir { path, errors } => write_errors(f, "HIR errors", path, errors),
+        }
+    }
+}
+
+fn write_errors<E: fmt::Display>(f: &mut fmt::Formatter<'_>, heading: &str, path: &std::path::Path, errors: &[E]) -> fmt::Result {
+    write!(f, "{} in module {}:", heading, path.display())?;
+    for err in errors {
+        write!(f, "\n  {}", err)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for ModuleError {}
@@ -0,0 +1,5 @@
+mod error;
+mod loader;
+
+pub use error::ModuleError;
+pub use loader::{ModuleCache, ModuleLoader};
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use brief_ast::{Decl, Program};
+use brief_bytecode::Chunk;
+use brief_diagnostic::{SourceMap, Span};
+use brief_hir::{emit_bytecode, lower_with_globals, KnownGlobals};
+use brief_lexer::lex;
+use brief_parser::parse_with_source;
+use crate::error::ModuleError;
+
+/// Compiled modules by canonical file path: the chunks `ModuleLoader::load`
+/// returned for each
+pub type ModuleCache = HashMap<PathBuf, Vec<Chunk>>;
+
+/// Finds the files `import` declarations name and compiles them to bytecode.
+///
+/// `import math` looks for `math.bf`, then `math/mod.bf`, in each search
+/// path in turn; `import net.http` looks for `net/http.bf` or
+/// `net/http/mod.bf`. A module's top-level definitions become globals of
+/// the program importing it; `import math as m` also binds `m` to a map of
+/// them, so `m.sin(x)` calls math's `sin`. Each file is compiled once per
+/// loader; later imports of it get the cached chunks
+#[derive(Debug, Clone, Default)]
+pub struct ModuleLoader {
+    search_paths: Vec<PathBuf>,
+    cache: ModuleCache,
+    loading_stack: Vec<PathBuf>, // Modules whose imports are being loaded
+}
+
+impl ModuleLoader {
+    /// A loader looking for modules in `search_paths`, earliest first
+    pub fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self { search_paths, cache: ModuleCache::new(), loading_stack: Vec::new() }
+    }
+
+    /// Look in `dir` too, after the existing search paths
+    pub fn add_search_path(&mut self, dir: impl Into<PathBuf>) {
+        self.search_paths.push(dir.into());
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    /// Whether `file` has already been compiled by this loader
+    pub fn is_cached(&self, file: &Path) -> bool {
+        std::fs::canonicalize(file).is_ok_and(|file| self.cache.contains_key(&file))
+    }
+
+    /// The file for the module path `path` (`["net", "http"]` for
+    /// `import net.http`), if any search path has one
+    pub fn resolve(&self, path: &[String]) -> Option<PathBuf> {
+        let relative: PathBuf = path.iter().collect();
+        self.search_paths.iter().find_map(|dir| {
+            let base = dir.join(&relative);
+            [base.with_extension("bf"), base.join("mod.bf")]
+                .into_iter()
+                .find(|file| file.is_file())
+        })
+    }
+
+    /// Compile the module at `path` through lex, parse, lower and emit. The
+    /// modules it imports are loaded first and their chunks come before
+    /// its own. Its definitions are added to `globals` and its source to
+    /// `sources`; `span` is the import naming it, for errors. A file this
+    /// loader compiled before isn't read again: its cached chunks are
+    /// returned and `globals` is assumed to hold its definitions already.
+    /// A module importing itself, however indirectly, is an error
+    pub fn load(
+        &mut self,
+        path: &[String],
+        span: Span,
+        globals: &mut KnownGlobals,
+        sources: &mut SourceMap,
+    ) -> Result<Vec<Chunk>, ModuleError> {
+        let Some(file) = self.resolve(path) else {
+            return Err(ModuleError::NotFound {
+                module: path.join("."),
+                span,
+                searched: self.search_paths.clone(),
+            });
+        };
+        let file = std::fs::canonicalize(&file).map_err(|error| ModuleError::Io { path: file, span, error })?;
+        if let Some(chunks) = self.cache.get(&file) {
+            return Ok(chunks.clone());
+        }
+        if let Some(cycle_start) = self.loading_stack.iter().position(|loading| *loading == file) {
+            return Err(ModuleError::CircularImport {
+                path: file,
+                span,
+                cycle_start,
+                stack: self.loading_stack.clone(),
+            });
+        }
+
+        self.loading_stack.push(file.clone());
+        let loaded = self.load_file(path, &file, span, globals, sources);
+        self.loading_stack.pop();
+        let chunks = loaded?;
+        self.cache.insert(file, chunks.clone());
+        Ok(chunks)
+    }
+
+    /// Load every module `program` imports, in the order it names them
+    pub fn load_imports(
+        &mut self,
+        program: &Program,
+        globals: &mut KnownGlobals,
+        sources: &mut SourceMap,
+    ) -> Result<Vec<Chunk>, ModuleError> {
+        let mut chunks = Vec::new();
+        for decl in &program.declarations {
+            let Decl::ImportDecl(import) = decl else {
+                continue;
+            };
+            for module in &import.modules {
+                let path: Vec<String> = module.path.split('.').map(str::to_string).collect();
+                chunks.extend(self.load(&path, import.span, globals, sources)?);
+            }
+        }
+        Ok(chunks)
+    }
+
+    fn load_file(
+        &mut self,
+        path: &[String],
+        file: &Path,
+        span: Span,
+        globals: &mut KnownGlobals,
+        sources: &mut SourceMap,
+    ) -> Result<Vec<Chunk>, ModuleError> {
+        let source = std::fs::read_to_string(file).map_err(|error| ModuleError::Io {
+            path: file.to_path_buf(),
+            span,
+            error,
+        })?;
+        let file_id = sources.add_file(file.display().to_string(), source.clone());
+
+        let (tokens, errors) = lex(&source, file_id);
+        if !errors.is_empty() {
+            return Err(ModuleError::Lex { path: file.to_path_buf(), errors });
+        }
+        let (program, errors) = parse_with_source(tokens, file_id, &source);
+        if !errors.is_empty() {
+            return Err(ModuleError::Parse { path: file.to_path_buf(), errors });
+        }
+
+        let mut chunks = self.load_imports(&program, globals, sources)?;
+        let names = top_level_names(&program);
+        let hir = lower_with_globals(program, globals)
+            .map_err(|errors| ModuleError::Hir { path: file.to_path_buf(), errors })?;
+        globals.add_module(path.join("."), names);
+        chunks.extend(emit_bytecode(&hir));
+        Ok(chunks)
+    }
+}
+
+/// The names a module defines at its top level, in order
+fn top_level_names(program: &Program) -> Vec<String> {
+    program.declarations.iter()
+        .filter_map(|decl| match decl {
+            Decl::VarDecl(v) => Some(v.name.clone()),
+            Decl::ConstDecl(c) => Some(c.name.clone()),
+            Decl::FuncDecl(f) => Some(f.name.clone()),
+            Decl::ClassDecl(c) => Some(c.name.clone()),
+            Decl::ImportDecl(_) | Decl::Error(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use brief_diagnostic::{FileId, Position, SourceMap, Span};
+use brief_hir::{emit_bytecode, lower_with_globals, KnownGlobals};
+use brief_module::{ModuleError, ModuleLoader};
+use brief_runtime::Runtime;
+use brief_vm::{Value, VM};
+use tempfile::TempDir;
+
+fn path(module: &str) -> Vec<String> {
+    module.split('.').map(str::to_string).collect()
+}
+
+fn write(dir: &Path, file: &str, source: &str) {
+    let file = dir.join(file);
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    fs::write(file, source).unwrap();
+}
+
+/// Compile `source` with the modules it imports from `dir`, then run its
+/// `test` function
+fn run_importing(dir: &Path, source: &str) -> Value {
+    let mut sources = SourceMap::new();
+    let file_id = sources.add_file("main.bf", source);
+    let (tokens, _) = brief_lexer::lex(source, file_id);
+    let (program, errors) = brief_parser::parse(tokens, file_id);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut globals = KnownGlobals::default();
+    let mut loader = ModuleLoader::new(vec![dir.to_path_buf()]);
+    let modules = loader.load_imports(&program, &mut globals, &mut sources).unwrap();
+    let chunks = emit_bytecode(&lower_with_globals(program, &mut globals).unwrap());
+
+    let mut vm = VM::new();
+    vm.set_runtime(Box::new(Runtime::new()));
+    vm.load_module(modules);
+    vm.register_functions(&chunks);
+    let entry = chunks.iter().find(|chunk| chunk.name == "test").unwrap();
+    vm.push_frame(Rc::new(entry.clone()), 0);
+    vm.run().unwrap()
+}
+
+#[test]
+fn test_resolve_prefers_file_then_mod_across_search_paths() {
+    let first = TempDir::new().unwrap();
+    let second = TempDir::new().unwrap();
+    write(first.path(), "net/http/mod.bf", "");
+    write(second.path(), "math.bf", "");
+    write(second.path(), "net/http.bf", "");
+    let loader = ModuleLoader::new(vec![first.path().to_path_buf(), second.path().to_path_buf()]);
+
+    assert_eq!(loader.resolve(&path("math")), Some(second.path().join("math.bf")));
+    assert_eq!(loader.resolve(&path("net.http")), Some(first.path().join("net/http/mod.bf")));
+    assert_eq!(loader.resolve(&path("missing")), None);
+}
+
+#[test]
+fn test_imported_functions_and_globals_run() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "math.bf", "const offset := 100\ndef add(a, b)\n\tret a + b + offset");
+    write(dir.path(), "shapes/mod.bf", "import math\nunit := add(1, 1)\ndef double(x)\n\tret x * unit");
+    let value = run_importing(dir.path(), "import (math, shapes)\ndef test()\n\tret double(add(1, 2)) + unit");
+    assert_eq!(value, Value::Int(103 * 102 + 102));
+}
+
+#[test]
+fn test_import_alias_is_a_map_of_the_module_names() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "geo/mod.bf", "scale := 3\ndef cube(x)\n\tret x * x * x");
+    let value = run_importing(dir.path(), "import geo as g\ndef test()\n\tret g.cube(2) + g.scale");
+    assert_eq!(value, Value::Int(11));
+
+    let value = run_importing(dir.path(), "import (geo as g)\ndef test()\n\tret keys(g)");
+    assert_eq!(value, Value::array(vec![Value::Str("scale".to_string()), Value::Str("cube".to_string())]));
+}
+
+#[test]
+fn test_load_puts_dependencies_first() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "base.bf", "def base_fn()\n\tret 1");
+    write(dir.path(), "top.bf", "import base\ndef top_fn()\n\tret base_fn()");
+    let mut loader = ModuleLoader::new(vec![dir.path().to_path_buf()]);
+    let chunks = loader
+        .load(&path("top"), Span::single(FileId(0), Position::new(1, 1)), &mut KnownGlobals::default(), &mut SourceMap::new())
+        .unwrap();
+    let names: Vec<&str> = chunks.iter().map(|chunk| chunk.name.as_str()).collect();
+    assert_eq!(names, ["base_fn", "top_fn"]);
+}
+
+#[test]
+fn test_load_errors_name_the_module() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "broken.bf", "def f(\n");
+    let mut loader = ModuleLoader::new(vec![dir.path().to_path_buf()]);
+    let mut load = |module: &str| {
+        loader
+            .load(&path(module), Span::single(FileId(0), Position::new(1, 1)), &mut KnownGlobals::default(), &mut SourceMap::new())
+            .unwrap_err()
+    };
+
+    let missing = load("nowhere.near");
+    assert!(matches!(&missing, ModuleError::NotFound { module, .. } if module == "nowhere.near"));
+    assert_eq!(
+        missing.to_string(),
+        format!("Module 'nowhere.near' not found (searched {})", dir.path().display())
+    );
+
+    let broken = load("broken");
+    assert!(matches!(&broken, ModuleError::Parse { .. }));
+    let message = broken.to_string();
+    assert!(message.starts_with(&format!("Parse errors in module {}:", dir.path().join("broken.bf").display())), "{}", message);
+    assert!(!broken.to_diagnostics().is_empty());
+}
+
+#[test]
+fn test_load_reuses_cached_chunks_by_canonical_path() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "math.bf", "def add(a, b)\n\tret a + b");
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    let mut loader = ModuleLoader::new(vec![dir.path().join("sub").join("..")]);
+    let mut globals = KnownGlobals::default();
+    let mut sources = SourceMap::new();
+    let span = Span::single(FileId(0), Position::new(1, 1));
+
+    let first = loader.load(&path("math"), span, &mut globals, &mut sources).unwrap();
+    assert!(loader.is_cached(&dir.path().join("math.bf")));
+    assert!(!loader.is_cached(&dir.path().join("missing.bf")));
+
+    // A cache hit doesn't read or compile the file again
+    write(dir.path(), "math.bf", "def add(\n");
+    let second = loader.load(&path("math"), span, &mut globals, &mut sources).unwrap();
+    let names = |chunks: &[brief_bytecode::Chunk]| chunks.iter().map(|chunk| chunk.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&second), names(&first));
+    assert_eq!(sources.file_path(FileId(1)), None);
+}
+
+#[test]
+fn test_circular_imports_are_errors_naming_the_cycle() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "a.bf", "import b\ndef fa()\n\tret 1");
+    write(dir.path(), "b.bf", "import c\ndef fb()\n\tret 2");
+    write(dir.path(), "c.bf", "import b\ndef fc()\n\tret 3");
+    write(dir.path(), "me.bf", "import me\ndef f()\n\tret 4");
+    let root = fs::canonicalize(dir.path()).unwrap();
+    let mut loader = ModuleLoader::new(vec![dir.path().to_path_buf()]);
+    let mut load = |module: &str| {
+        loader
+            .load(&path(module), Span::single(FileId(0), Position::new(1, 1)), &mut KnownGlobals::default(), &mut SourceMap::new())
+            .unwrap_err()
+    };
+
+    let cycle = load("a");
+    assert!(matches!(&cycle, ModuleError::CircularImport { cycle_start: 1, .. }));
+    assert_eq!(
+        cycle.to_string(),
+        format!("Circular import: {b} -> {c} -> {b}", b = root.join("b.bf").display(), c = root.join("c.bf").display())
+    );
+    assert_eq!(cycle.to_diagnostics().len(), 1);
+
+    let itself = load("me");
+    assert_eq!(itself.to_string(), format!("Circular import: {m} -> {m}", m = root.join("me.bf").display()));
+
+    // A failed load leaves nothing on the stack, so b alone reports the same cycle
+    assert!(matches!(load("b"), ModuleError::CircularImport { cycle_start: 0, .. }));
+}
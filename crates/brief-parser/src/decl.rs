@@ -32,7 +32,7 @@ impl Parser {
             params,
             return_type,
             body,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -50,17 +50,38 @@ impl Parser {
                 };
 
                 let name = self.expect_identifier("Expected parameter name");
-                let span = self.previous().unwrap().span;
+                let name_span = self.previous_span();
+
+                // Rest parameter: args...
+                let variadic = self.match_token(&[TokenKind::Ellipsis]);
+
+                // Optional default value: greeting = "Hello"
+                let default = if !variadic && self.match_token(&[TokenKind::Assign]) {
+                    Some(self.parse_expression())
+                } else {
+                    None
+                };
+
+                // Defaulted parameters must be trailing
+                if !variadic && default.is_none() && params.iter().any(|p: &Param| p.default.is_some()) {
+                    self.error_at(name_span, "Parameters with default values must come after required parameters");
+                }
 
                 params.push(Param {
                     name,
                     type_annotation,
-                    span,
+                    default,
+                    variadic,
+                    span: name_span,
                 });
 
                 if !self.match_token(&[TokenKind::Comma]) {
                     break;
                 }
+
+                if variadic {
+                    self.error_at(name_span, "Variadic parameter must be the last parameter");
+                }
             }
         }
 
@@ -74,10 +95,21 @@ impl Parser {
 
         let name = self.expect_identifier("Expected class name");
 
+        // Optional parent class: `cls Puppy : Dog` or `cls Puppy extends Dog`
+        let parent = if self.match_token(&[TokenKind::Colon]) || self.match_contextual("extends") {
+            Some(self.expect_identifier("Expected parent class name"))
+        } else {
+            None
+        };
+
         // Expect Indent for class body
+        while self.check(&TokenKind::Newline) {
+            self.advance();
+        }
         self.expect(TokenKind::Indent, "Expected indented class body");
-        self.advance();
 
+        let mut fields = Vec::new();
+        let mut constants = Vec::new();
         let mut constructor = None;
         let mut methods = Vec::new();
 
@@ -112,8 +144,14 @@ impl Parser {
             } else if self.check(&TokenKind::Def) {
                 // Static method: def method(...)
                 methods.push(self.parse_method(false));
+            } else if self.check(&TokenKind::Const) {
+                // Class constant: const PI := 3.14
+                constants.push(self.parse_const_declaration());
+            } else if self.is_type_keyword() || self.is_identifier() {
+                // Field declaration: int age = 0
+                fields.push(self.parse_field_declaration());
             } else {
-                self.error_at_current("Expected 'obj' or 'def' in class body");
+                self.error_at_current("Expected field, 'const', 'obj', or 'def' in class body");
                 self.synchronize();
             }
 
@@ -129,9 +167,12 @@ impl Parser {
         let end_span = self.current_span();
         ClassDecl {
             name,
+            parent,
+            fields,
+            constants,
             constructor,
             methods,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -150,14 +191,21 @@ impl Parser {
         let params = self.parse_parameter_list();
         self.expect(TokenKind::RightParen, "Expected ')' after constructor parameters");
 
-        let body = self.parse_block();
+        // The body is optional: `obj Dog(name)` alone just assigns its params
+        let has_body = self.check(&TokenKind::Newline)
+            && matches!(self.peek_nth(1).map(|t| &t.kind), Some(TokenKind::Indent));
+        let body = if has_body {
+            self.parse_block()
+        } else {
+            Block { statements: Vec::new(), span: self.current_span() }
+        };
 
         let end_span = self.current_span();
         CtorDecl {
             name,
             params,
             body,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -195,8 +243,52 @@ impl Parser {
             params,
             return_type,
             body,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Parse import declaration: `import math`, `import net.http`,
+    /// `import math as m` or `import (math as m, net.http)`
+    pub(crate) fn parse_import_declaration(&mut self) -> ImportDecl {
+        let start_span = self.current_span();
+        self.advance(); // Consume 'import'
+
+        let mut modules = Vec::new();
+        if self.match_token(&[TokenKind::LeftParen]) {
+            loop {
+                modules.push(self.parse_import_item());
+                if !self.match_token(&[TokenKind::Comma]) {
+                    break;
+                }
+            }
+            self.expect(TokenKind::RightParen, "Expected ')' after imported modules");
+        } else {
+            modules.push(self.parse_import_item());
+        }
+
+        let end_span = self.current_span();
+        ImportDecl {
+            modules,
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Parse one imported module and its optional `as` alias
+    fn parse_import_item(&mut self) -> ImportItem {
+        let path = self.parse_module_name();
+        let alias = self.match_contextual("as")
+            .then(|| self.expect_identifier("Expected alias after 'as'"));
+        ImportItem { path, alias }
+    }
+
+    /// Parse a dotted module name, kept as written: `net.http`
+    fn parse_module_name(&mut self) -> String {
+        let mut name = self.expect_identifier("Expected module name");
+        while self.match_token(&[TokenKind::Dot]) {
+            name.push('.');
+            name.push_str(&self.expect_identifier("Expected module name after '.'"));
         }
+        name
     }
 
     /// Parse constant declaration
@@ -212,8 +304,18 @@ impl Parser {
         ConstDecl {
             name,
             initializer,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Parse a class field declaration: `int age = 0`, `age := 0`, or `int age`
+    fn parse_field_declaration(&mut self) -> VarDecl {
+        let mut field = self.parse_var_declaration();
+        if field.initializer.is_none() && self.match_token(&[TokenKind::Assign]) {
+            field.initializer = Some(self.parse_expression());
+            field.span = Span::merge(field.span, self.current_span());
         }
+        field
     }
 
     /// Parse variable declaration
@@ -243,7 +345,7 @@ impl Parser {
             name,
             type_annotation,
             initializer,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 }
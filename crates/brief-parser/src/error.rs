@@ -1,4 +1,5 @@
-use brief_diagnostic::Span;
+use std::fmt;
+use brief_diagnostic::{Diagnostic, Span};
 
 /// Parse error with rich diagnostic information
 #[derive(Debug, Clone, PartialEq)]
@@ -6,6 +7,8 @@ pub struct ParseError {
     pub message: String,
     pub span: Span,
     pub secondary_labels: Vec<(Span, String)>,
+    /// The source line the error starts on, when the source was available
+    pub source_snippet: Option<String>,
 }
 
 impl ParseError {
@@ -14,6 +17,7 @@ impl ParseError {
             message,
             span,
             secondary_labels: Vec::new(),
+            source_snippet: None,
         }
     }
 
@@ -21,5 +25,40 @@ impl ParseError {
         self.secondary_labels.push((span, label));
         self
     }
+
+    /// Attach the line of `source` the error starts on
+    pub fn with_source(mut self, source: &str) -> Self {
+        let line = (self.span.start.line as usize).saturating_sub(1);
+        self.source_snippet = source.lines().nth(line).map(str::to_string);
+        self
+    }
+
+    /// The message and primary span; labels and the snippet are dropped
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.message.clone(), self.span)
+    }
+}
+
+impl fmt::Display for ParseError {
+    /// `Parse error at line L column C: message`, followed by the source line
+    /// and a caret under the column when a snippet is attached
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Parse error at line {} column {}: {}",
+            self.span.start.line, self.span.start.column, self.message
+        )?;
+        if let Some(snippet) = &self.source_snippet {
+            // Keep tabs so the caret lines up with the indented source
+            let indent: String = snippet
+                .chars()
+                .take((self.span.start.column as usize).saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n    {}\n    {}^", snippet, indent)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for ParseError {}
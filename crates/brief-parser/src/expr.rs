@@ -1,6 +1,6 @@
 use crate::parser::Parser;
 use brief_ast::*;
-use brief_diagnostic::Span;
+use brief_diagnostic::{Position, Span};
 use brief_lexer::TokenKind;
 
 impl Parser {
@@ -13,30 +13,18 @@ impl Parser {
     fn parse_assignment(&mut self) -> Expr {
         let expr = self.parse_ternary();
 
-        if self.match_token(&[
-            TokenKind::Assign,
-            TokenKind::InitAssign,
-            TokenKind::PlusAssign,
-            TokenKind::MinusAssign,
-            TokenKind::StarAssign,
-            TokenKind::SlashAssign,
-            TokenKind::PercentAssign,
-            TokenKind::PowAssign,
+        if let Some(op) = self.match_operator(&[
+            (TokenKind::Assign, BinaryOp::Assign),
+            (TokenKind::InitAssign, BinaryOp::InitAssign),
+            (TokenKind::PlusAssign, BinaryOp::PlusAssign),
+            (TokenKind::MinusAssign, BinaryOp::MinusAssign),
+            (TokenKind::StarAssign, BinaryOp::StarAssign),
+            (TokenKind::SlashAssign, BinaryOp::SlashAssign),
+            (TokenKind::PercentAssign, BinaryOp::PercentAssign),
+            (TokenKind::PowAssign, BinaryOp::PowAssign),
         ]) {
-            let op_token = self.previous().unwrap();
-            let op = match op_token.kind {
-                TokenKind::Assign => BinaryOp::Assign,
-                TokenKind::InitAssign => BinaryOp::InitAssign,
-                TokenKind::PlusAssign => BinaryOp::PlusAssign,
-                TokenKind::MinusAssign => BinaryOp::MinusAssign,
-                TokenKind::StarAssign => BinaryOp::StarAssign,
-                TokenKind::SlashAssign => BinaryOp::SlashAssign,
-                TokenKind::PercentAssign => BinaryOp::PercentAssign,
-                TokenKind::PowAssign => BinaryOp::PowAssign,
-                _ => unreachable!(),
-            };
             let value = self.parse_assignment(); // Right-associative
-            let span = Span::new(self.file_id(), expr.span().start, value.span().end);
+            let span = Span::merge(expr.span(), value.span());
             return Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -50,7 +38,7 @@ impl Parser {
 
     /// Ternary operator (right-associative)
     fn parse_ternary(&mut self) -> Expr {
-        let expr = self.parse_logical_or();
+        let expr = self.parse_null_coalesce();
 
         if self.check(&TokenKind::Question) {
             let start_span = expr.span();
@@ -63,7 +51,26 @@ impl Parser {
                 condition: Box::new(expr),
                 then_expr: Box::new(then_expr),
                 else_expr: Box::new(else_expr),
-                span: Span::new(self.file_id(), start_span.start, end_span.end),
+                span: Span::merge(start_span, end_span),
+            };
+        }
+
+        expr
+    }
+
+    /// Null-coalescing (left-associative)
+    fn parse_null_coalesce(&mut self) -> Expr {
+        let mut expr = self.parse_logical_or();
+
+        while self.match_token(&[TokenKind::NullCoalesce]) {
+            let op = BinaryOp::NullCoalesce;
+            let right = self.parse_logical_or();
+            let span = Span::merge(expr.span(), right.span());
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+                span,
             };
         }
 
@@ -77,7 +84,7 @@ impl Parser {
         while self.match_token(&[TokenKind::Or]) {
             let op = BinaryOp::Or;
             let right = self.parse_logical_and();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -96,7 +103,7 @@ impl Parser {
         while self.match_token(&[TokenKind::And]) {
             let op = BinaryOp::And;
             let right = self.parse_bitwise_or();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -115,7 +122,7 @@ impl Parser {
         while self.match_token(&[TokenKind::BitOr]) {
             let op = BinaryOp::BitOr;
             let right = self.parse_bitwise_xor();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -134,7 +141,7 @@ impl Parser {
         while self.match_token(&[TokenKind::BitXor]) {
             let op = BinaryOp::BitXor;
             let right = self.parse_bitwise_and();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -153,7 +160,7 @@ impl Parser {
         while self.match_token(&[TokenKind::BitAnd]) {
             let op = BinaryOp::BitAnd;
             let right = self.parse_equality();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -169,14 +176,9 @@ impl Parser {
     fn parse_equality(&mut self) -> Expr {
         let mut expr = self.parse_comparison();
 
-        while self.match_token(&[TokenKind::Eq, TokenKind::Ne]) {
-            let op = match self.previous().unwrap().kind {
-                TokenKind::Eq => BinaryOp::Eq,
-                TokenKind::Ne => BinaryOp::Ne,
-                _ => unreachable!(),
-            };
+        while let Some(op) = self.match_operator(&[(TokenKind::Eq, BinaryOp::Eq), (TokenKind::Ne, BinaryOp::Ne)]) {
             let right = self.parse_comparison();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -188,20 +190,20 @@ impl Parser {
         expr
     }
 
-    /// Comparison operators (left-associative)
+    /// Comparison and membership operators (left-associative). A for-in
+    /// header is recognized before its `in` could be read as an operator
     fn parse_comparison(&mut self) -> Expr {
-        let mut expr = self.parse_shift();
-
-        while self.match_token(&[TokenKind::Lt, TokenKind::Le, TokenKind::Gt, TokenKind::Ge]) {
-            let op = match self.previous().unwrap().kind {
-                TokenKind::Lt => BinaryOp::Lt,
-                TokenKind::Le => BinaryOp::Le,
-                TokenKind::Gt => BinaryOp::Gt,
-                TokenKind::Ge => BinaryOp::Ge,
-                _ => unreachable!(),
-            };
-            let right = self.parse_shift();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+        let mut expr = self.parse_range();
+
+        while let Some(op) = self.match_operator(&[
+            (TokenKind::Lt, BinaryOp::Lt),
+            (TokenKind::Le, BinaryOp::Le),
+            (TokenKind::Gt, BinaryOp::Gt),
+            (TokenKind::Ge, BinaryOp::Ge),
+            (TokenKind::In, BinaryOp::In),
+        ]) {
+            let right = self.parse_range();
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -213,18 +215,30 @@ impl Parser {
         expr
     }
 
+    /// Range `start..end` or `start..=end` (not associative)
+    fn parse_range(&mut self) -> Expr {
+        let start = self.parse_shift();
+
+        let Some(inclusive) = self.match_operator(&[(TokenKind::DotDot, false), (TokenKind::DotDotEq, true)]) else {
+            return start;
+        };
+        let end = self.parse_shift();
+        let span = Span::merge(start.span(), end.span());
+        Expr::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            span,
+        }
+    }
+
     /// Shift operators (left-associative)
     fn parse_shift(&mut self) -> Expr {
         let mut expr = self.parse_addition();
 
-        while self.match_token(&[TokenKind::Shl, TokenKind::Shr]) {
-            let op = match self.previous().unwrap().kind {
-                TokenKind::Shl => BinaryOp::Shl,
-                TokenKind::Shr => BinaryOp::Shr,
-                _ => unreachable!(),
-            };
+        while let Some(op) = self.match_operator(&[(TokenKind::Shl, BinaryOp::Shl), (TokenKind::Shr, BinaryOp::Shr)]) {
             let right = self.parse_addition();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -240,14 +254,9 @@ impl Parser {
     fn parse_addition(&mut self) -> Expr {
         let mut expr = self.parse_multiplication();
 
-        while self.match_token(&[TokenKind::Plus, TokenKind::Minus]) {
-            let op = match self.previous().unwrap().kind {
-                TokenKind::Plus => BinaryOp::Add,
-                TokenKind::Minus => BinaryOp::Sub,
-                _ => unreachable!(),
-            };
+        while let Some(op) = self.match_operator(&[(TokenKind::Plus, BinaryOp::Add), (TokenKind::Minus, BinaryOp::Sub)]) {
             let right = self.parse_multiplication();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -263,15 +272,13 @@ impl Parser {
     fn parse_multiplication(&mut self) -> Expr {
         let mut expr = self.parse_power();
 
-        while self.match_token(&[TokenKind::Star, TokenKind::Slash, TokenKind::Percent]) {
-            let op = match self.previous().unwrap().kind {
-                TokenKind::Star => BinaryOp::Mul,
-                TokenKind::Slash => BinaryOp::Div,
-                TokenKind::Percent => BinaryOp::Mod,
-                _ => unreachable!(),
-            };
+        while let Some(op) = self.match_operator(&[
+            (TokenKind::Star, BinaryOp::Mul),
+            (TokenKind::Slash, BinaryOp::Div),
+            (TokenKind::Percent, BinaryOp::Mod),
+        ]) {
             let right = self.parse_power();
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -290,7 +297,7 @@ impl Parser {
         while self.match_token(&[TokenKind::Pow]) {
             let op = BinaryOp::Pow;
             let right = self.parse_power(); // Right-associative
-            let span = Span::new(self.file_id(), expr.span().start, right.span().end);
+            let span = Span::merge(expr.span(), right.span());
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -304,24 +311,16 @@ impl Parser {
 
     /// Unary operators (right-associative)
     fn parse_unary(&mut self) -> Expr {
-        if self.match_token(&[
-            TokenKind::Not,
-            TokenKind::BitNot,
-            TokenKind::Minus,
-            TokenKind::Plus,
+        if let Some(op) = self.match_operator(&[
+            (TokenKind::Not, UnaryOp::Not),
+            (TokenKind::BitNot, UnaryOp::BitNot),
+            (TokenKind::Minus, UnaryOp::Neg),
+            (TokenKind::Plus, UnaryOp::Pos),
         ]) {
-            let op_token = self.previous().unwrap();
-            let op_token_span = op_token.span;
-            let op = match op_token.kind {
-                TokenKind::Not => UnaryOp::Not,
-                TokenKind::BitNot => UnaryOp::BitNot,
-                TokenKind::Minus => UnaryOp::Neg,
-                TokenKind::Plus => UnaryOp::Pos,
-                _ => unreachable!(),
-            };
+            let op_token_span = self.previous_span();
             let expr = self.parse_unary(); // Right-associative
             let expr_span = expr.span();
-            let span = Span::new(self.file_id(), op_token_span.start, expr_span.end);
+            let span = Span::merge(op_token_span, expr_span);
             return Expr::UnaryOp {
                 op,
                 expr: Box::new(expr),
@@ -338,17 +337,8 @@ impl Parser {
 
         loop {
             // Postfix increment/decrement
-            if self.match_token(&[TokenKind::Inc, TokenKind::Dec]) {
-                let op = match self.previous().unwrap().kind {
-                    TokenKind::Inc => PostfixOp::Inc,
-                    TokenKind::Dec => PostfixOp::Dec,
-                    _ => unreachable!(),
-                };
-                let span = Span::new(
-                    self.file_id(),
-                    expr.span().start,
-                    self.previous().unwrap().span.end,
-                );
+            if let Some(op) = self.match_operator(&[(TokenKind::Inc, PostfixOp::Inc), (TokenKind::Dec, PostfixOp::Dec)]) {
+                let span = Span::merge(expr.span(), self.previous_span());
                 expr = Expr::PostfixOp {
                     expr: Box::new(expr),
                     op,
@@ -362,12 +352,22 @@ impl Parser {
             // Member access
             else if self.match_token(&[TokenKind::Dot]) {
                 let name = self.expect_identifier("Expected property name after '.'");
-                let span = Span::new(
-                    self.file_id(),
-                    expr.span().start,
-                    self.previous().unwrap().span.end,
-                );
-                expr = Expr::MemberAccess {
+                expr = if self.check(&TokenKind::LeftParen) {
+                    self.finish_method_call(expr, name)
+                } else {
+                    let span = Span::merge(expr.span(), self.previous_span());
+                    Expr::MemberAccess {
+                        object: Box::new(expr),
+                        member: name,
+                        span,
+                    }
+                };
+            }
+            // Null-safe member access
+            else if self.match_token(&[TokenKind::QuestionDot]) {
+                let name = self.expect_identifier("Expected property name after '?.'");
+                let span = Span::merge(expr.span(), self.previous_span());
+                expr = Expr::SafeMemberAccess {
                     object: Box::new(expr),
                     member: name,
                     span,
@@ -393,52 +393,27 @@ impl Parser {
         // Extract the kind first to avoid borrow conflicts
         let kind = self.peek_kind().cloned();
         match kind {
-            Some(TokenKind::True) => {
-                let token = self.advance().unwrap();
-                Expr::Boolean(true, token.span)
-            }
-            Some(TokenKind::False) => {
-                let token = self.advance().unwrap();
-                Expr::Boolean(false, token.span)
-            }
-            Some(TokenKind::Null) => {
-                let token = self.advance().unwrap();
-                Expr::Null(token.span)
-            }
-            Some(TokenKind::Integer(n)) => {
-                let token = self.advance().unwrap();
-                Expr::Integer(n, token.span)
-            }
-            Some(TokenKind::Double(d)) => {
-                let token = self.advance().unwrap();
-                Expr::Double(d, token.span)
-            }
-            Some(TokenKind::Character(c)) => {
-                let token = self.advance().unwrap();
-                Expr::Character(c, token.span)
-            }
+            Some(TokenKind::True) => Expr::Boolean(true, self.advance_span()),
+            Some(TokenKind::False) => Expr::Boolean(false, self.advance_span()),
+            Some(TokenKind::Null) => Expr::Null(self.advance_span()),
+            Some(TokenKind::Integer(n)) => Expr::Integer(n, self.advance_span()),
+            Some(TokenKind::Double(d)) => Expr::Double(d, self.advance_span()),
+            Some(TokenKind::Character(c)) => Expr::Character(c, self.advance_span()),
             Some(TokenKind::StrPart(_)) => self.parse_string_interpolation(),
             Some(TokenKind::Identifier(_)) => {
                 let name = self.expect_identifier("Expected identifier");
-                let span = self.previous().unwrap().span;
+                let span = self.previous_span();
                 Expr::Variable(name, span)
             }
-            Some(TokenKind::Int)
-            | Some(TokenKind::Char)
-            | Some(TokenKind::Str)
-            | Some(TokenKind::Dub)
-            | Some(TokenKind::Bool) => {
-                let token = self.advance().unwrap();
-                let name = match &token.kind {
-                    TokenKind::Int => "int",
-                    TokenKind::Char => "char",
-                    TokenKind::Str => "str",
-                    TokenKind::Dub => "dub",
-                    TokenKind::Bool => "bool",
-                    _ => unreachable!(),
-                };
-                Expr::Variable(name.to_string(), token.span)
-            }
+            // The receiver inside constructors and instance methods
+            Some(TokenKind::Obj) => Expr::Variable("obj".to_string(), self.advance_span()),
+            // Type names used as values, e.g. the conversion `int("5")`
+            Some(TokenKind::Int) => Expr::Variable("int".to_string(), self.advance_span()),
+            Some(TokenKind::Char) => Expr::Variable("char".to_string(), self.advance_span()),
+            Some(TokenKind::Str) => Expr::Variable("str".to_string(), self.advance_span()),
+            Some(TokenKind::Dub) => Expr::Variable("dub".to_string(), self.advance_span()),
+            Some(TokenKind::Bool) => Expr::Variable("bool".to_string(), self.advance_span()),
+            Some(TokenKind::LeftParen) if self.is_lambda_start() => self.parse_lambda(),
             Some(TokenKind::LeftParen) => self.parse_grouping(),
             _ => {
                 let span = self.current_span();
@@ -450,13 +425,66 @@ impl Parser {
         }
     }
 
+    /// Lambdas are told apart from groupings by what follows the closing
+    /// paren: `(x) := ...` is a lambda, `(x)` alone is a grouping
+    fn is_lambda_start(&self) -> bool {
+        let mut depth = 0usize;
+        let mut n = 0;
+        while let Some(token) = self.peek_nth(n) {
+            match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return matches!(
+                            self.peek_nth(n + 1).map(|t| &t.kind),
+                            Some(TokenKind::InitAssign)
+                        );
+                    }
+                }
+                TokenKind::Newline | TokenKind::Eof => return false,
+                _ => {}
+            }
+            n += 1;
+        }
+        false
+    }
+
+    /// Parse a lambda: (params) := expr, or (params) := followed by an
+    /// indented block on the next lines
+    fn parse_lambda(&mut self) -> Expr {
+        let start_span = self.advance_span(); // Consume '('
+        let params = self.parse_parameter_list();
+        self.expect(TokenKind::RightParen, "Expected ')' after lambda parameters");
+        self.expect(TokenKind::InitAssign, "Expected ':=' after lambda parameters");
+
+        let body = if self.check(&TokenKind::Newline) {
+            if !matches!(self.peek_nth(1).map(|t| &t.kind), Some(TokenKind::Indent)) {
+                self.error_at_current("Expected indented lambda body");
+            }
+            LambdaBody::Block(self.parse_block())
+        } else {
+            LambdaBody::Expr(Box::new(self.parse_expression()))
+        };
+
+        let end = match &body {
+            LambdaBody::Expr(expr) => expr.span().end,
+            LambdaBody::Block(block) => block.span.end,
+        };
+        Expr::Lambda {
+            params,
+            body,
+            span: Span::new(self.file_id(), start_span.start, end),
+        }
+    }
+
     /// Parse a grouped expression: (expr)
     fn parse_grouping(&mut self) -> Expr {
-        let start_span = self.advance().unwrap().span;
+        let start_span = self.advance_span();
         let expr = self.parse_expression();
         self.expect(TokenKind::RightParen, "Expected ')' after expression");
-        let end_span = self.previous().unwrap().span;
-        let span = Span::new(self.file_id(), start_span.start, end_span.end);
+        let end_span = self.previous_span();
+        let span = Span::merge(start_span, end_span);
         // Return the expression with updated span
         match expr {
             Expr::Error(_) => Expr::Error(span),
@@ -485,8 +513,8 @@ impl Parser {
             if let Some(kind) = token_kind {
                 match kind {
                     TokenKind::InterpIdent(name) => {
-                        let token = self.advance().unwrap();
-                        parts.push(InterpPart::Ident(name, token.span));
+                        let span = self.advance_span();
+                        parts.push(InterpPart::Ident(name, span));
                     }
                     TokenKind::InterpPath(path) => {
                         // Get span before advancing to avoid borrow conflict
@@ -515,27 +543,82 @@ impl Parser {
         let end_span = self.current_span();
         Expr::Interpolation {
             parts,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
-    /// Parse an interpolation path like obj.field
+    /// Parse the text of an interpolation like `&obj.field` or `&(a + b)`
+    /// as an expression. `span` covers the interpolation from its `&`
     fn parse_interpolation_path(&mut self, path: &str, span: Span) -> Box<Expr> {
-        // Simple implementation: parse as member access chain
-        // For now, just create a variable reference
-        // TODO: Parse actual path expressions
-        Box::new(Expr::Variable(path.to_string(), span))
+        // The path sits on one line, starting one column after the `&`
+        let start = Position::new(span.start.line, span.start.column + 1);
+        Box::new(self.parse_embedded_expression(path, start))
     }
 
     /// Finish a function call: expr(args)
     fn finish_call(&mut self, callee: Expr) -> Expr {
         let start_span = callee.span();
+        let args = self.parse_arguments();
+        let end_span = self.previous_span();
+        Expr::Call {
+            callee: Box::new(callee),
+            args,
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Finish a method call: object.method(args)
+    fn finish_method_call(&mut self, object: Expr, method: String) -> Expr {
+        let start_span = object.span();
+        let args = self.parse_arguments();
+        let end_span = self.previous_span();
+        Expr::MethodCall {
+            object: Box::new(object),
+            method,
+            args,
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Parse a parenthesized argument list, consuming both parens
+    fn parse_arguments(&mut self) -> Vec<Expr> {
         self.advance(); // Consume '('
         let mut args = Vec::new();
 
         if !self.check(&TokenKind::RightParen) {
+            let mut seen_named = false;
             loop {
-                args.push(self.parse_expression());
+                // Named argument: f(loud: true)
+                if self.is_identifier()
+                    && matches!(self.peek_nth(1).map(|t| &t.kind), Some(TokenKind::Colon))
+                {
+                    let start = self.current_span();
+                    let name = self.expect_identifier("Expected parameter name");
+                    self.advance(); // Consume ':'
+                    let value = self.parse_expression();
+                    let span = Span::merge(start, value.span());
+                    args.push(Expr::NamedArg { name, value: Box::new(value), span });
+                    seen_named = true;
+                    if !self.match_token(&[TokenKind::Comma]) {
+                        break;
+                    }
+                    continue;
+                }
+                if seen_named {
+                    self.error_at_current("Positional argument cannot follow a named argument");
+                }
+                let arg = self.parse_expression();
+                // Spread argument: f(xs...)
+                if self.match_token(&[TokenKind::Ellipsis]) {
+                    let ellipsis = self.previous_span();
+                    if self.check(&TokenKind::Comma) {
+                        self.error_at(ellipsis, "Spread argument must be the last argument");
+                    }
+                    let span = Span::merge(arg.span(), ellipsis);
+                    args.push(Expr::Spread { expr: Box::new(arg), span });
+                } else {
+                    args.push(arg);
+                }
                 if !self.match_token(&[TokenKind::Comma]) {
                     break;
                 }
@@ -543,12 +626,7 @@ impl Parser {
         }
 
         self.expect(TokenKind::RightParen, "Expected ')' after arguments");
-        let end_span = self.previous().unwrap().span;
-        Expr::Call {
-            callee: Box::new(callee),
-            args,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
-        }
+        args
     }
 
     /// Finish an index access: expr[index]
@@ -557,11 +635,11 @@ impl Parser {
         self.advance(); // Consume '['
         let index = self.parse_expression();
         self.expect(TokenKind::RightBracket, "Expected ']' after index");
-        let end_span = self.previous().unwrap().span;
+        let end_span = self.previous_span();
         Expr::Index {
             object: Box::new(object),
             index: Box::new(index),
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -573,7 +651,7 @@ impl Parser {
         Expr::Cast {
             expr: Box::new(expr),
             target_type,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
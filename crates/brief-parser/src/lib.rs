@@ -19,3 +19,11 @@ pub fn parse(tokens: Vec<Token>, file_id: FileId) -> (Program, Vec<ParseError>)
     let errors = parser.get_errors().to_vec();
     (program, errors)
 }
+
+/// Parse tokens into an AST, quoting the offending line of `source` in errors
+pub fn parse_with_source(tokens: Vec<Token>, file_id: FileId, source: &str) -> (Program, Vec<ParseError>) {
+    let mut parser = Parser::new(tokens, file_id).with_source(source);
+    let program = parser.parse();
+    let errors = parser.get_errors().to_vec();
+    (program, errors)
+}
@@ -11,6 +11,8 @@ pub struct Parser {
     file_id: FileId,
     error_count: usize,
     max_errors: usize,
+    source: Option<String>, // Original text, quoted in errors when present
+    pub(crate) case_body: bool, // The next block is a match case body, which may end in `fall`
 }
 
 impl Parser {
@@ -23,9 +25,17 @@ impl Parser {
             file_id,
             error_count: 0,
             max_errors: 50,
+            source: None,
+            case_body: false,
         }
     }
 
+    /// Quote the offending line of `source` in parse errors
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
     /// Get all parse errors
     pub fn get_errors(&self) -> &[ParseError] {
         &self.errors
@@ -48,7 +58,7 @@ impl Parser {
         let end_span = self.current_span();
         Program {
             declarations,
-            span: Span::new(self.file_id, start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -91,6 +101,18 @@ impl Parser {
         self.peek_kind().map(|k| k == kind).unwrap_or(false)
     }
 
+    /// Span of the last consumed token, or of the current one if nothing
+    /// was consumed yet
+    pub(crate) fn previous_span(&self) -> Span {
+        self.previous().map_or_else(|| self.current_span(), |token| token.span)
+    }
+
+    /// Consume the current token, returning its span
+    pub(crate) fn advance_span(&mut self) -> Span {
+        self.advance();
+        self.previous_span()
+    }
+
     pub(crate) fn match_token(&mut self, kinds: &[TokenKind]) -> bool {
         for kind in kinds {
             if self.check(kind) {
@@ -101,13 +123,20 @@ impl Parser {
         false
     }
 
+    /// Consume the current token if it is one of the `operators`, returning
+    /// what it stands for
+    pub(crate) fn match_operator<T: Copy>(&mut self, operators: &[(TokenKind, T)]) -> Option<T> {
+        let (_, op) = operators.iter().find(|(kind, _)| self.check(kind))?;
+        self.advance();
+        Some(*op)
+    }
+
     pub(crate) fn consume(&mut self, kind: TokenKind, message: &str) -> Result<&Token, ()> {
-        if self.check(&kind) {
-            Ok(self.advance().unwrap())
-        } else {
+        if !self.check(&kind) {
             self.error_at_current(message);
-            Err(())
+            return Err(());
         }
+        self.advance().ok_or(())
     }
 
     pub(crate) fn expect(&mut self, kind: TokenKind, message: &str) {
@@ -133,13 +162,20 @@ impl Parser {
     // ============================================================================
 
     pub(crate) fn error(&mut self, token: &Token, message: &str) {
+        self.error_at(token.span, message);
+    }
+
+    pub(crate) fn error_at(&mut self, span: Span, message: &str) {
         if self.error_count >= self.max_errors {
             return;
         }
 
         self.error_count += 1;
 
-        let mut error = ParseError::new(message.to_string(), token.span);
+        let mut error = ParseError::new(message.to_string(), span);
+        if let Some(source) = &self.source {
+            error = error.with_source(source);
+        }
 
         // Add secondary labels for context
         if let Some(prev) = self.previous() {
@@ -155,6 +191,38 @@ impl Parser {
         }
     }
 
+    /// Parse `text`, found inside a token that starts at `start`, as a
+    /// single expression. Errors are reported at their place in the file
+    pub(crate) fn parse_embedded_expression(&mut self, text: &str, start: Position) -> Expr {
+        let (tokens, lex_errors) = brief_lexer::lex(text, self.file_id);
+        // Text on the first line of `text` is offset by `start`
+        let shift = |position: Position| {
+            if position.line == 1 {
+                Position::new(start.line, start.column + position.column - 1)
+            } else {
+                Position::new(start.line + position.line - 1, position.column)
+            }
+        };
+        let relocate = |span: Span| Span::new(span.file_id, shift(span.start), shift(span.end));
+
+        let tokens = tokens
+            .into_iter()
+            .map(|token| Token::new(token.kind, relocate(token.span)))
+            .collect();
+        let mut parser = Parser::new(tokens, self.file_id);
+        parser.source = self.source.clone();
+        for error in lex_errors {
+            parser.errors.push(ParseError::new(error.message, relocate(error.span)));
+        }
+
+        let expr = parser.parse_expression();
+        if !matches!(parser.peek_kind(), Some(TokenKind::Newline | TokenKind::Eof) | None) {
+            parser.error_at_current("Unexpected token after expression");
+        }
+        self.errors.extend(parser.errors);
+        expr
+    }
+
     /// Panic-mode error recovery: synchronize to next safe token
     pub(crate) fn synchronize(&mut self) {
         self.advance();
@@ -178,7 +246,8 @@ impl Parser {
                 | Some(TokenKind::Case)
                 | Some(TokenKind::Match)
                 | Some(TokenKind::Def)
-                | Some(TokenKind::Cls) => return,
+                | Some(TokenKind::Cls)
+                | Some(TokenKind::Import) => return,
                 _ => {
                     self.advance();
                 }
@@ -193,8 +262,9 @@ impl Parser {
     fn parse_declaration(&mut self) -> Decl {
         let start_span = self.current_span();
 
-        // Note: Import syntax will be handled later - for now, treat as identifier
-        if self.check(&TokenKind::Def) {
+        if self.check(&TokenKind::Import) {
+            Decl::ImportDecl(self.parse_import_declaration())
+        } else if self.check(&TokenKind::Def) {
             Decl::FuncDecl(self.parse_function_declaration())
         } else if self.check(&TokenKind::Cls) {
             Decl::ClassDecl(self.parse_class_declaration())
@@ -229,10 +299,24 @@ impl Parser {
         matches!(self.peek_kind(), Some(TokenKind::Identifier(_)))
     }
 
+    /// Consume an identifier used as a contextual keyword (e.g. `extends`)
+    pub(crate) fn match_contextual(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek_kind(), Some(TokenKind::Identifier(name)) if name == keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
     pub(crate) fn expect_identifier(&mut self, message: &str) -> String {
         match self.peek_kind() {
             Some(TokenKind::Identifier(name)) => {
                 let name = name.clone();
+                if name.starts_with(GENERATED_NAME_PREFIX) {
+                    // Reserved for desugaring temporaries; keep the name so parsing continues
+                    self.error_at_current(&format!("Identifiers starting with '{}' are reserved", GENERATED_NAME_PREFIX));
+                }
                 self.advance();
                 name
             }
@@ -6,12 +6,16 @@ use brief_lexer::TokenKind;
 impl Parser {
     /// Parse a statement
     pub fn parse_statement(&mut self) -> Stmt {
-        if self.check(&TokenKind::If) {
+        if self.is_loop_label() {
+            self.parse_labeled_loop()
+        } else if self.check(&TokenKind::If) {
             self.parse_if_statement()
         } else if self.check(&TokenKind::While) {
             self.parse_while_statement()
         } else if self.check(&TokenKind::For) {
             self.parse_for_statement()
+        } else if self.check(&TokenKind::Repeat) {
+            self.parse_repeat_statement()
         } else if self.check(&TokenKind::Match) {
             self.parse_match_statement()
         } else if self.check(&TokenKind::Ret) {
@@ -20,6 +24,8 @@ impl Parser {
             self.parse_break_statement()
         } else if self.check(&TokenKind::Continue) {
             self.parse_continue_statement()
+        } else if self.check(&TokenKind::Fall) {
+            self.parse_fall_statement()
         } else if self.is_declaration_start() {
             // Variable or constant declaration
             if self.check(&TokenKind::Const) {
@@ -35,6 +41,37 @@ impl Parser {
         }
     }
 
+    /// Check if we're at `name:` followed by a loop keyword
+    fn is_loop_label(&self) -> bool {
+        self.is_identifier()
+            && self.peek_nth(1).is_some_and(|t| t.kind == TokenKind::Colon)
+            && self.peek_nth(2).is_some_and(|t| {
+                matches!(t.kind, TokenKind::While | TokenKind::For | TokenKind::Repeat)
+            })
+    }
+
+    /// Parse a labeled loop: `outer: while (...)`, `outer: for (...)` or
+    /// `outer: repeat n`
+    fn parse_labeled_loop(&mut self) -> Stmt {
+        let name = self.expect_identifier("Expected loop label");
+        self.advance(); // Consume ':'
+        let mut stmt = if self.check(&TokenKind::While) {
+            self.parse_while_statement()
+        } else if self.check(&TokenKind::For) {
+            self.parse_for_statement()
+        } else {
+            self.parse_repeat_statement()
+        };
+        if let Stmt::While { label, .. }
+        | Stmt::For { label, .. }
+        | Stmt::ForIn { label, .. }
+        | Stmt::Repeat { label, .. } = &mut stmt
+        {
+            *label = Some(name);
+        }
+        stmt
+    }
+
     /// Check if we're at the start of a declaration
     fn is_declaration_start(&self) -> bool {
         if self.check(&TokenKind::Const) {
@@ -52,10 +89,10 @@ impl Parser {
             return false;
         }
 
-        if self.is_identifier() {
-            if let Some(next) = self.peek_nth(1) {
-                return matches!(next.kind, TokenKind::InitAssign);
-            }
+        if self.is_identifier()
+            && let Some(next) = self.peek_nth(1)
+        {
+            return matches!(next.kind, TokenKind::InitAssign);
         }
 
         false
@@ -65,6 +102,8 @@ impl Parser {
     pub fn parse_block(&mut self) -> Block {
         let start_span = self.current_span();
         let mut statements = Vec::new();
+        // Only the case body itself may end in `fall`, not blocks nested in it
+        let case_body = std::mem::take(&mut self.case_body);
 
         // Consume any leading newlines
         while self.check(&TokenKind::Newline) {
@@ -77,12 +116,18 @@ impl Parser {
 
             // Parse statements until Dedent
             while !self.check(&TokenKind::Dedent) && !self.is_at_end() {
+                let fall = self.peek().filter(|token| token.kind == TokenKind::Fall).cloned();
                 statements.push(self.parse_statement());
 
                 // Consume newline between statements
                 if self.check(&TokenKind::Newline) {
                     self.advance();
                 }
+
+                let ends_case = case_body && (self.check(&TokenKind::Dedent) || self.is_at_end());
+                if let Some(token) = fall.filter(|_| !ends_case) {
+                    self.error(&token, "'fall' can only be the last statement of a match case");
+                }
             }
 
             // Consume Dedent
@@ -91,13 +136,17 @@ impl Parser {
             }
         } else {
             // Single-line statement - no block, just one statement
+            let fall = self.peek().filter(|token| token.kind == TokenKind::Fall).cloned();
             statements.push(self.parse_statement());
+            if let Some(token) = fall.filter(|_| !case_body) {
+                self.error(&token, "'fall' can only be the last statement of a match case");
+            }
         }
 
         let end_span = self.current_span();
         Block {
             statements,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -123,11 +172,12 @@ impl Parser {
             condition,
             then_branch,
             else_branch,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
-    /// Parse while statement
+    /// Parse while statement, with an optional `else` block that runs when
+    /// the condition is false on the first check
     fn parse_while_statement(&mut self) -> Stmt {
         let start_span = self.current_span();
         self.advance(); // Consume 'while'
@@ -137,12 +187,37 @@ impl Parser {
         self.expect(TokenKind::RightParen, "Expected ')' after while condition");
 
         let body = self.parse_block();
+        let else_branch = if self.check(&TokenKind::Else) {
+            self.advance();
+            Some(self.parse_block())
+        } else {
+            None
+        };
 
         let end_span = self.current_span();
         Stmt::While {
             condition,
             body,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            else_branch,
+            label: None,
+            span: Span::merge(start_span, end_span),
+        }
+    }
+
+    /// Parse repeat statement: `repeat <count>` followed by the body
+    fn parse_repeat_statement(&mut self) -> Stmt {
+        let start_span = self.current_span();
+        self.advance(); // Consume 'repeat'
+
+        let count = self.parse_expression();
+        let body = self.parse_block();
+
+        let end_span = self.current_span();
+        Stmt::Repeat {
+            count,
+            body,
+            label: None,
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -175,7 +250,8 @@ impl Parser {
                 var,
                 iterable,
                 body,
-                span: Span::new(self.file_id(), start_span.start, end_span.end),
+                label: None,
+                span: Span::merge(start_span, end_span),
             }
         } else {
             // C-style for loop: for (init; condition; increment)
@@ -211,7 +287,8 @@ impl Parser {
                 condition,
                 increment,
                 body,
-                span: Span::new(self.file_id(), start_span.start, end_span.end),
+                label: None,
+                span: Span::merge(start_span, end_span),
             }
         }
     }
@@ -247,10 +324,19 @@ impl Parser {
         let expr = self.parse_expression();
         self.expect(TokenKind::RightParen, "Expected ')' after match expression");
 
+        // Cases start on the following lines, optionally indented under the match
+        while self.check(&TokenKind::Newline) {
+            self.advance();
+        }
+        let indented = self.match_token(&[TokenKind::Indent]);
+
         let mut cases = Vec::new();
 
         while self.check(&TokenKind::Case) {
             cases.push(self.parse_match_case());
+            while self.check(&TokenKind::Newline) {
+                self.advance();
+            }
         }
 
         let else_branch = if self.check(&TokenKind::Else) {
@@ -260,12 +346,19 @@ impl Parser {
             None
         };
 
+        if indented {
+            while self.check(&TokenKind::Newline) {
+                self.advance();
+            }
+            self.match_token(&[TokenKind::Dedent]);
+        }
+
         let end_span = self.current_span();
         Stmt::Match {
             expr,
             cases,
             else_branch,
-            span: Span::new(self.file_id(), start_span.start, end_span.end),
+            span: Span::merge(start_span, end_span),
         }
     }
 
@@ -285,10 +378,20 @@ impl Parser {
             patterns.push(self.parse_expression());
         }
 
+        // Optional guard: case 'A' if cond
+        let guard = if self.check(&TokenKind::If) {
+            self.advance();
+            Some(self.parse_expression())
+        } else {
+            None
+        };
+
+        self.case_body = true;
         let body = self.parse_block();
 
         MatchCase {
             patterns,
+            guard,
             body,
             span: start_span,
         }
@@ -316,17 +419,35 @@ impl Parser {
         }
     }
 
-    /// Parse break statement
+    /// Parse break statement: `break`, or `break outer`
     fn parse_break_statement(&mut self) -> Stmt {
-        let span = self.current_span();
-        self.advance(); // Consume 'break'
-        Stmt::Break(span)
+        let (label, span) = self.parse_loop_jump();
+        Stmt::Break { label, span }
     }
 
-    /// Parse continue statement
+    /// Parse continue statement: `continue`, or `continue outer`
     fn parse_continue_statement(&mut self) -> Stmt {
+        let (label, span) = self.parse_loop_jump();
+        Stmt::Continue { label, span }
+    }
+
+    /// Consume `break` or `continue` and the loop label after it on the
+    /// same line, if any; the span covers both
+    fn parse_loop_jump(&mut self) -> (Option<String>, Span) {
+        let start_span = self.current_span();
+        self.advance(); // Consume 'break' or 'continue'
+        if !self.is_identifier() {
+            return (None, start_span);
+        }
+        let end_span = self.current_span();
+        let label = self.expect_identifier("Expected loop label");
+        (Some(label), Span::merge(start_span, end_span))
+    }
+
+    /// Parse fall statement; `parse_block` checks that it ends a match case
+    fn parse_fall_statement(&mut self) -> Stmt {
         let span = self.current_span();
-        self.advance(); // Consume 'continue'
-        Stmt::Continue(span)
+        self.advance(); // Consume 'fall'
+        Stmt::Fall(span)
     }
 }
@@ -54,6 +54,32 @@ fn test_constant_declaration() {
     }
 }
 
+#[test]
+fn test_import_declaration() {
+    let program = parse_source("import math\nimport (net.http, strings)");
+    let modules: Vec<Vec<&str>> = program.declarations.iter()
+        .map(|decl| match decl {
+            Decl::ImportDecl(i) => i.modules.iter().map(|item| item.path.as_str()).collect(),
+            _ => panic!("Expected import declaration"),
+        })
+        .collect();
+    assert_eq!(modules, [vec!["math"], vec!["net.http", "strings"]]);
+    assert!(!parse_errors("import (math,)").is_empty());
+}
+
+#[test]
+fn test_import_aliases() {
+    let program = parse_source("import math as m\nimport (net.http as http, strings)");
+    let items: Vec<(&str, Option<&str>)> = program.declarations.iter()
+        .flat_map(|decl| match decl {
+            Decl::ImportDecl(i) => i.modules.iter().map(|item| (item.path.as_str(), item.alias.as_deref())),
+            _ => panic!("Expected import declaration"),
+        })
+        .collect();
+    assert_eq!(items, [("math", Some("m")), ("net.http", Some("http")), ("strings", None)]);
+    assert!(!parse_errors("import math as").is_empty());
+}
+
 #[test]
 fn test_function_declaration() {
     let program = parse_source("def add(x, y)\n\tret x + y");
@@ -124,6 +150,51 @@ fn test_class_with_constructor() {
     }
 }
 
+#[test]
+fn test_class_with_fields() {
+    let program = parse_source("cls Dog\n\tint age = 3\n\tstr name\n\tobj Dog(name)");
+    match &program.declarations[0] {
+        Decl::ClassDecl(c) => {
+            assert_eq!(c.fields.len(), 2);
+            assert_eq!(c.fields[0].name, "age");
+            assert!(c.fields[0].initializer.is_some());
+            assert_eq!(c.fields[1].name, "name");
+            assert!(c.fields[1].initializer.is_none());
+            assert!(c.constructor.is_some());
+        }
+        _ => panic!("Expected class declaration"),
+    }
+}
+
+#[test]
+fn test_class_with_parent() {
+    for source in ["cls Puppy : Dog\n\tobj Puppy(name)", "cls Puppy extends Dog\n\tobj Puppy(name)"] {
+        let program = parse_source(source);
+        match &program.declarations[0] {
+            Decl::ClassDecl(c) => {
+                assert_eq!(c.name, "Puppy");
+                assert_eq!(c.parent.as_deref(), Some("Dog"));
+                assert!(c.constructor.is_some());
+            }
+            _ => panic!("Expected class declaration"),
+        }
+    }
+}
+
+#[test]
+fn test_class_with_constants() {
+    let program = parse_source("cls Math\n\tconst PI := 3.14\n\tdef square(x)\n\t\tret x * x");
+    match &program.declarations[0] {
+        Decl::ClassDecl(c) => {
+            assert_eq!(c.constants.len(), 1);
+            assert_eq!(c.constants[0].name, "PI");
+            assert_eq!(c.methods.len(), 1);
+            assert!(!c.methods[0].is_instance);
+        }
+        _ => panic!("Expected class declaration"),
+    }
+}
+
 #[test]
 fn test_class_with_instance_method() {
     let program = parse_source("cls Dog\n\tobj def greet()\n\t\tprint(\"hello\")");
@@ -131,17 +202,14 @@ fn test_class_with_instance_method() {
         Decl::ClassDecl(c) => {
             assert_eq!(c.name, "Dog");
             assert!(!c.methods.is_empty(), "Expected at least one method");
-            match &c.methods[0] {
-                MethodDecl { name, is_instance, .. } => {
-                    assert_eq!(name, "greet");
-                    // Instance method should have is_instance = true
-                    // If this fails, check the parser logic for obj def
-                    // For now, just verify we have the method
-                    if !*is_instance {
-                        // This might be a parser issue - log but don't fail
-                        eprintln!("Warning: Expected instance method, got static method");
-                    }
-                }
+            let MethodDecl { name, is_instance, .. } = &c.methods[0];
+            assert_eq!(name, "greet");
+            // Instance method should have is_instance = true
+            // If this fails, check the parser logic for obj def
+            // For now, just verify we have the method
+            if !*is_instance {
+                // This might be a parser issue - log but don't fail
+                eprintln!("Warning: Expected instance method, got static method");
             }
         }
         _ => panic!("Expected class declaration"),
@@ -155,12 +223,9 @@ fn test_class_with_static_method() {
         Decl::ClassDecl(c) => {
             assert_eq!(c.name, "Math");
             assert!(!c.methods.is_empty());
-            match &c.methods[0] {
-                MethodDecl { name, is_instance, .. } => {
-                    assert_eq!(name, "add");
-                    assert!(!*is_instance);
-                }
-            }
+            let MethodDecl { name, is_instance, .. } = &c.methods[0];
+            assert_eq!(name, "add");
+            assert!(!*is_instance);
         }
         _ => panic!("Expected class declaration"),
     }
@@ -180,3 +245,52 @@ fn test_function_parameters() {
     }
 }
 
+#[test]
+fn test_function_default_parameters() {
+    let (program, errors) = parse_with_errors("def greet(name, greeting = \"Hello\")\n\tret greeting");
+    assert!(errors.is_empty(), "Unexpected errors: {:?}", errors);
+    match &program.declarations[0] {
+        Decl::FuncDecl(f) => {
+            assert_eq!(f.params.len(), 2);
+            assert!(f.params[0].default.is_none());
+            assert!(matches!(&f.params[1].default, Some(Expr::Interpolation { .. }) | Some(Expr::String(..))));
+        }
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+#[test]
+fn test_function_variadic_parameter() {
+    let (program, errors) = parse_with_errors("def log(fmt, args...)\n\tret fmt");
+    assert!(errors.is_empty(), "Unexpected errors: {:?}", errors);
+    match &program.declarations[0] {
+        Decl::FuncDecl(f) => {
+            assert_eq!(f.params.len(), 2);
+            assert!(!f.params[0].variadic);
+            assert!(f.params[1].variadic);
+            assert_eq!(f.params[1].name, "args");
+        }
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+#[test]
+fn test_variadic_parameter_must_be_last() {
+    let errors = parse_errors("def log(args..., fmt)\n\tret fmt");
+    assert!(
+        errors.iter().any(|e| e.message.contains("Variadic parameter must be the last")),
+        "Expected variadic-position error, got {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_default_parameters_must_be_trailing() {
+    let errors = parse_errors("def greet(greeting = \"Hello\", name)\n\tret name");
+    assert!(
+        errors.iter().any(|e| e.message.contains("default values must come after")),
+        "Expected trailing-default error, got {:?}",
+        errors
+    );
+}
+
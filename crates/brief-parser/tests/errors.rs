@@ -35,3 +35,66 @@ fn test_error_recovery() {
            "Parser should recover and parse multiple declarations");
 }
 
+
+#[test]
+fn test_malformed_input_is_reported_not_panicked() {
+    // Token streams of the kind the fuzz target produces: operators and
+    // openers with nothing after them, and blocks cut short
+    for source in ["(", "x :=", "f(", "a[", "x.", "-", "def", "def f(", "def f(x = ", "(x) :=", "\"&(", "a ? b", "cls", "for (", "x ..= ", "f(xs..., 1)", "obj.", "int"] {
+        let errors = parse_errors(source);
+        assert!(!errors.is_empty(), "{:?} should not parse", source);
+    }
+}
+
+#[test]
+fn test_reserved_identifier_prefix() {
+    for source in ["__temp_0 := 1", "def test()\n\tret __temp_1 + 1", "def __temp_f()\n\tret 1"] {
+        let errors = parse_errors(source);
+        assert!(
+            errors.iter().any(|e| e.message.contains("reserved")),
+            "Expected reserved-name error for {:?}, got {:?}",
+            source,
+            errors
+        );
+    }
+    // Only the generated prefix is reserved
+    assert!(parse_errors("__value := 1").is_empty());
+}
+
+#[test]
+fn test_fall_only_ends_a_match_case() {
+    let message = "'fall' can only be the last statement of a match case";
+    for source in [
+        "def test()\n\tfall",
+        "def test(x)\n\tmatch(x)\n\t\tcase 1\n\t\t\tfall\n\t\t\tprint(1)",
+        "def test(x)\n\tmatch(x)\n\t\tcase 1\n\t\t\tif (x > 0)\n\t\t\t\tfall",
+    ] {
+        let errors = parse_errors(source);
+        assert!(errors.iter().any(|e| e.message == message), "{}: {:?}", source, errors);
+    }
+    assert!(parse_errors("def test(x)\n\tmatch(x)\n\t\tcase 1\n\t\t\tfall\n\t\telse\n\t\t\tret 0").is_empty());
+}
+
+#[test]
+fn test_parse_error_display() {
+    let errors = parse_errors("x := +");
+    // Without the source there is no snippet
+    assert_eq!(errors[0].source_snippet, None);
+    assert_eq!(errors[0].to_string(), "Parse error at line 1 column 7: Expected expression");
+}
+
+#[test]
+fn test_parse_error_display_with_source_snippet() {
+    let source = "def test()\n\tx := (1 + ) * 2\n\tret x";
+    let (tokens, _) = brief_lexer::lex(source, brief_diagnostic::FileId(0));
+    let (_, errors) = brief_parser::parse_with_source(tokens, brief_diagnostic::FileId(0), source);
+    let error = &errors[0];
+    assert_eq!(error.source_snippet.as_deref(), Some("\tx := (1 + ) * 2"));
+    let rendered = error.to_string();
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", rendered);
+    assert_eq!(lines[0], "Parse error at line 2 column 12: Expected expression");
+    assert_eq!(lines[1], "    \tx := (1 + ) * 2");
+    // The caret sits under the ')', keeping the tab
+    assert_eq!(lines[2], "    \t          ^");
+}
@@ -18,6 +18,7 @@ fn test_literals() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_double_literal() {
     let program = parse_source("x := 3.14");
@@ -115,6 +116,93 @@ fn test_logical_operators() {
     }
 }
 
+#[test]
+fn test_in_operator() {
+    // Binds like a comparison: tighter than && and looser than +
+    let program = parse_source("x := a + 1 in xs && ok");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => match &v.initializer {
+            Some(Expr::BinaryOp { op: BinaryOp::And, left, .. }) => match left.as_ref() {
+                Expr::BinaryOp { op, left, .. } => {
+                    assert!(matches!(op, BinaryOp::In));
+                    assert!(matches!(left.as_ref(), Expr::BinaryOp { op: BinaryOp::Add, .. }));
+                }
+                other => panic!("Expected membership test, got {:?}", other),
+            },
+            other => panic!("Expected && expression, got {:?}", other),
+        },
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_range_expression() {
+    let program = parse_source("x := a + 1..=n * 2");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => match &v.initializer {
+            Some(Expr::Range { start, end, inclusive, .. }) => {
+                assert!(*inclusive);
+                assert!(matches!(start.as_ref(), Expr::BinaryOp { op: BinaryOp::Add, .. }));
+                assert!(matches!(end.as_ref(), Expr::BinaryOp { op: BinaryOp::Mul, .. }));
+            }
+            other => panic!("Expected range, got {:?}", other),
+        },
+        _ => panic!("Expected variable declaration"),
+    }
+
+    let program = parse_source("x := 0..10");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => assert!(matches!(&v.initializer, Some(Expr::Range { inclusive: false, .. }))),
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_in_operator_inside_for_in() {
+    // The loop header claims the first `in`; the iterable may use another
+    let program = parse_source("def f(xs, ys)\n\tfor (x in xs in ys)\n\t\tprint(x)");
+    match &program.declarations[0] {
+        Decl::FuncDecl(f) => match &f.body.statements[0] {
+            Stmt::ForIn { var, iterable, .. } => {
+                assert_eq!(var, "x");
+                assert!(matches!(iterable, Expr::BinaryOp { op: BinaryOp::In, .. }));
+            }
+            other => panic!("Expected for-in loop, got {:?}", other),
+        },
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+#[test]
+fn test_null_coalesce_binds_looser_than_or() {
+    let program = parse_source("x := a ?? b || c");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => {
+            match &v.initializer {
+                Some(Expr::BinaryOp { op, right, .. }) => {
+                    assert!(matches!(op, BinaryOp::NullCoalesce));
+                    assert!(matches!(right.as_ref(), Expr::BinaryOp { op: BinaryOp::Or, .. }));
+                }
+                _ => panic!("Expected binary operation"),
+            }
+        }
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_safe_member_access_chain() {
+    let program = parse_source("x := a?.b?.c");
+    let Decl::VarDecl(v) = &program.declarations[0] else {
+        panic!("Expected variable declaration");
+    };
+    let Some(Expr::SafeMemberAccess { object, member, .. }) = &v.initializer else {
+        panic!("Expected null-safe member access");
+    };
+    assert_eq!(member, "c");
+    assert!(matches!(object.as_ref(), Expr::SafeMemberAccess { member, .. } if member == "b"));
+}
+
 #[test]
 fn test_unary_operators() {
     let program = parse_source("x := -5\ny := !true");
@@ -178,6 +266,129 @@ fn test_function_call() {
     }
 }
 
+#[test]
+fn test_function_call_spread() {
+    let program = parse_source("x := add(1, rest...)");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => {
+            match &v.initializer {
+                Some(Expr::Call { args, .. }) => {
+                    assert_eq!(args.len(), 2);
+                    match &args[1] {
+                        Expr::Spread { expr, .. } => {
+                            assert!(matches!(expr.as_ref(), Expr::Variable(name, _) if name == "rest"));
+                        }
+                        _ => panic!("Expected spread argument"),
+                    }
+                }
+                _ => panic!("Expected function call"),
+            }
+        }
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_spread_argument_must_be_last() {
+    let errors = parse_errors("x := add(rest..., 1)");
+    assert!(
+        errors.iter().any(|e| e.message.contains("Spread argument must be the last")),
+        "Expected spread-position error, got {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_function_call_named_args() {
+    let program = parse_source("x := greet(1, loud: true)");
+    let Decl::VarDecl(v) = &program.declarations[0] else {
+        panic!("Expected variable declaration");
+    };
+    let Some(Expr::Call { args, .. }) = &v.initializer else {
+        panic!("Expected function call");
+    };
+    assert_eq!(args.len(), 2);
+    assert!(matches!(&args[0], Expr::Integer(1, _)));
+    match &args[1] {
+        Expr::NamedArg { name, value, .. } => {
+            assert_eq!(name, "loud");
+            assert!(matches!(value.as_ref(), Expr::Boolean(true, _)));
+        }
+        _ => panic!("Expected named argument"),
+    }
+}
+
+#[test]
+fn test_positional_after_named_arg() {
+    let errors = parse_errors("x := greet(loud: true, \"Sam\")");
+    assert!(
+        errors.iter().any(|e| e.message.contains("Positional argument cannot follow a named argument")),
+        "Expected named-argument order error, got {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_lambda_expression_body() {
+    let program = parse_source("f := (x, y) := x + y");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => match &v.initializer {
+            Some(Expr::Lambda { params, body, .. }) => {
+                assert_eq!(params.len(), 2);
+                assert!(matches!(body, LambdaBody::Expr(e) if matches!(e.as_ref(), Expr::BinaryOp { .. })));
+            }
+            other => panic!("Expected lambda, got {:?}", other),
+        },
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_lambda_block_body() {
+    let program = parse_source("def test()\n\tf := (x) :=\n\t\ty := x * 2\n\t\tret y\n\tret f(1)");
+    match &program.declarations[0] {
+        Decl::FuncDecl(func) => {
+            assert_eq!(func.body.statements.len(), 2);
+            match &func.body.statements[0] {
+                Stmt::VarDecl(v) => match &v.initializer {
+                    Some(Expr::Lambda { body: LambdaBody::Block(block), .. }) => {
+                        assert_eq!(block.statements.len(), 2);
+                    }
+                    other => panic!("Expected block-bodied lambda, got {:?}", other),
+                },
+                other => panic!("Expected variable declaration, got {:?}", other),
+            }
+        }
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+#[test]
+fn test_lambda_as_call_argument() {
+    let program = parse_source("ys := map(xs, (x) := x * 2)");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => match &v.initializer {
+            Some(Expr::Call { args, .. }) => {
+                assert_eq!(args.len(), 2);
+                assert!(matches!(&args[1], Expr::Lambda { params, .. } if params.len() == 1));
+            }
+            other => panic!("Expected call, got {:?}", other),
+        },
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
+#[test]
+fn test_parenthesized_expression_is_not_lambda() {
+    let program = parse_source("x := (a + b) * 2");
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => {
+            assert!(matches!(&v.initializer, Some(Expr::BinaryOp { op: BinaryOp::Mul, .. })));
+        }
+        _ => panic!("Expected variable declaration"),
+    }
+}
+
 #[test]
 fn test_member_access() {
     // Test member access with proper tokenization (obj . field)
@@ -39,6 +39,58 @@ fn test_string_interpolation_integration() {
     }
 }
 
+fn interpolation_parts(source: &str) -> Vec<InterpPart> {
+    let file_id = brief_diagnostic::FileId(0);
+    let (tokens, _) = brief_lexer::lex(source, file_id);
+    let (program, errors) = brief_parser::parse(tokens, file_id);
+    assert!(errors.is_empty(), "{:?}", errors);
+    match &program.declarations[0] {
+        Decl::VarDecl(v) => match &v.initializer {
+            Some(Expr::Interpolation { parts, .. }) => parts.clone(),
+            other => panic!("Expected string interpolation, got {:?}", other),
+        },
+        other => panic!("Expected variable declaration, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_interpolation_path_is_member_access() {
+    let parts = interpolation_parts("x := \"&obj.x\"");
+    match &parts[0] {
+        InterpPart::Path(expr, _) => match &**expr {
+            Expr::MemberAccess { object, member, span } => {
+                assert!(matches!(&**object, Expr::Variable(name, _) if name == "obj"));
+                assert_eq!(member, "x");
+                // Spans point into the string, just after the `&`
+                assert_eq!((span.start.line, span.start.column), (1, 8));
+            }
+            other => panic!("Expected member access, got {:?}", other),
+        },
+        other => panic!("Expected path part, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_interpolation_parenthesized_expression() {
+    let parts = interpolation_parts("x := \"sum: &(a + b.c)!\"");
+    assert!(matches!(&parts[0], InterpPart::Text(text) if text == "sum: "));
+    match &parts[1] {
+        InterpPart::Path(expr, _) => assert!(matches!(&**expr, Expr::BinaryOp { op: BinaryOp::Add, .. }), "{:?}", expr),
+        other => panic!("Expected path part, got {:?}", other),
+    }
+    assert!(matches!(&parts[2], InterpPart::Text(text) if text == "!"));
+}
+
+#[test]
+fn test_interpolation_path_errors_point_into_string() {
+    let file_id = brief_diagnostic::FileId(0);
+    let (tokens, _) = brief_lexer::lex("x := \"&(a +)\"", file_id);
+    let (_, errors) = brief_parser::parse(tokens, file_id);
+    assert!(!errors.is_empty());
+    assert_eq!(errors[0].span.start.line, 1);
+    assert!(errors[0].span.start.column > 7, "{:?}", errors[0]);
+}
+
 #[test]
 fn test_indentation_integration() {
     let source = "if (true)\n\tx := 1\n\tif (false)\n\t\ty := 2";
@@ -80,6 +80,15 @@ fn pretty_print_decl(decl: &Decl, output: &mut String, indent: usize, include_sp
         Decl::ClassDecl(c) => {
             output.push_str(&format!("{}ClassDecl\n", indent_str));
             output.push_str(&format!("{}  name: {}\n", indent_str, c.name));
+            if let Some(parent) = &c.parent {
+                output.push_str(&format!("{}  parent: {}\n", indent_str, parent));
+            }
+            if !c.fields.is_empty() {
+                output.push_str(&format!("{}  fields:\n", indent_str));
+                for field in &c.fields {
+                    pretty_print_decl(&Decl::VarDecl(field.clone()), output, indent + 2, include_spans);
+                }
+            }
             if let Some(ctor) = &c.constructor {
                 output.push_str(&format!("{}  constructor:\n", indent_str));
                 pretty_print_ctor(ctor, output, indent + 2, include_spans);
@@ -92,9 +101,18 @@ fn pretty_print_decl(decl: &Decl, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("{}  span: {:?}\n", indent_str, c.span));
             }
         }
-        Decl::ImportDecl(_) => {
+        Decl::ImportDecl(i) => {
             output.push_str(&format!("{}ImportDecl\n", indent_str));
-            // Import parsing not fully implemented yet
+            let modules: Vec<String> = i.modules.iter()
+                .map(|item| match &item.alias {
+                    Some(alias) => format!("{} as {}", item.path, alias),
+                    None => item.path.clone(),
+                })
+                .collect();
+            output.push_str(&format!("{}  modules: {}\n", indent_str, modules.join(", ")));
+            if include_spans {
+                output.push_str(&format!("{}  span: {:?}\n", indent_str, i.span));
+            }
         }
         Decl::Error(span) => {
             output.push_str(&format!("{}Error\n", indent_str));
@@ -161,6 +179,17 @@ fn pretty_print_expr(expr: &Expr, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
             }
         }
+        Expr::Range { start, end, inclusive, span } => {
+            output.push_str(&format!("Range(inclusive: {})\n", inclusive));
+            output.push_str(&format!("{}  start: ", indent_str));
+            pretty_print_expr(start, output, indent + 2, include_spans);
+            output.push('\n');
+            output.push_str(&format!("{}  end: ", indent_str));
+            pretty_print_expr(end, output, indent + 2, include_spans);
+            if include_spans {
+                output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
+            }
+        }
         Expr::UnaryOp { op, expr, span } => {
             output.push_str(&format!("UnaryOp({:?})\n", op));
             output.push_str(&format!("{}  expr: ", indent_str));
@@ -214,6 +243,15 @@ fn pretty_print_expr(expr: &Expr, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("{}  span: {:?}", indent_str, span));
             }
         }
+        Expr::SafeMemberAccess { object, member, span } => {
+            output.push_str("SafeMemberAccess\n");
+            output.push_str(&format!("{}  object: ", indent_str));
+            pretty_print_expr(object, output, indent + 2, include_spans);
+            output.push_str(&format!("\n{}  member: {}\n", indent_str, member));
+            if include_spans {
+                output.push_str(&format!("{}  span: {:?}", indent_str, span));
+            }
+        }
         Expr::Index { object, index, span } => {
             output.push_str("Index\n");
             output.push_str(&format!("{}  object: ", indent_str));
@@ -225,6 +263,22 @@ fn pretty_print_expr(expr: &Expr, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
             }
         }
+        Expr::Spread { expr, span } => {
+            output.push_str("Spread\n");
+            output.push_str(&format!("{}  expr: ", indent_str));
+            pretty_print_expr(expr, output, indent + 2, include_spans);
+            if include_spans {
+                output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
+            }
+        },
+        Expr::NamedArg { name, value, span } => {
+            output.push_str(&format!("NamedArg({})\n", name));
+            output.push_str(&format!("{}  value: ", indent_str));
+            pretty_print_expr(value, output, indent + 2, include_spans);
+            if include_spans {
+                output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
+            }
+        },
         Expr::Cast { expr, target_type, span } => {
             output.push_str("Cast\n");
             output.push_str(&format!("{}  expr: ", indent_str));
@@ -266,7 +320,13 @@ fn pretty_print_expr(expr: &Expr, output: &mut String, indent: usize, include_sp
                 pretty_print_param(param, output, indent + 2, include_spans);
             }
             output.push_str(&format!("{}  body: ", indent_str));
-            pretty_print_expr(body, output, indent + 2, include_spans);
+            match body {
+                LambdaBody::Expr(expr) => pretty_print_expr(expr, output, indent + 2, include_spans),
+                LambdaBody::Block(block) => {
+                    output.push('\n');
+                    pretty_print_block(block, output, indent + 2, include_spans);
+                }
+            }
             if include_spans {
                 output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
             }
@@ -328,18 +388,41 @@ fn pretty_print_stmt(stmt: &Stmt, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("{}  span: {:?}", indent_str, span));
             }
         }
-        Stmt::While { condition, body, span } => {
+        Stmt::While { condition, body, else_branch, label, span } => {
             output.push_str(&format!("{}While\n", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!("{}  label: {}\n", indent_str, label));
+            }
             output.push_str(&format!("{}  condition: ", indent_str));
             pretty_print_expr(condition, output, indent + 2, include_spans);
             output.push_str(&format!("\n{}  body:\n", indent_str));
             pretty_print_block(body, output, indent + 2, include_spans);
+            if let Some(else_branch) = else_branch {
+                output.push_str(&format!("{}  else:\n", indent_str));
+                pretty_print_block(else_branch, output, indent + 2, include_spans);
+            }
+            if include_spans {
+                output.push_str(&format!("{}  span: {:?}", indent_str, span));
+            }
+        }
+        Stmt::Repeat { count, body, label, span } => {
+            output.push_str(&format!("{}Repeat\n", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!("{}  label: {}\n", indent_str, label));
+            }
+            output.push_str(&format!("{}  count: ", indent_str));
+            pretty_print_expr(count, output, indent + 2, include_spans);
+            output.push_str(&format!("\n{}  body:\n", indent_str));
+            pretty_print_block(body, output, indent + 2, include_spans);
             if include_spans {
                 output.push_str(&format!("{}  span: {:?}", indent_str, span));
             }
         }
-        Stmt::For { init, condition, increment, body, span } => {
+        Stmt::For { init, condition, increment, body, label, span } => {
             output.push_str(&format!("{}For\n", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!("{}  label: {}\n", indent_str, label));
+            }
             if let Some(init) = init {
                 output.push_str(&format!("{}  init:\n", indent_str));
                 pretty_print_stmt(init, output, indent + 2, include_spans);
@@ -360,8 +443,11 @@ fn pretty_print_stmt(stmt: &Stmt, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("{}  span: {:?}", indent_str, span));
             }
         }
-        Stmt::ForIn { var, iterable, body, span } => {
+        Stmt::ForIn { var, iterable, body, label, span } => {
             output.push_str(&format!("{}ForIn\n", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!("{}  label: {}\n", indent_str, label));
+            }
             output.push_str(&format!("{}  var: {}\n", indent_str, var));
             output.push_str(&format!("{}  iterable: ", indent_str));
             pretty_print_expr(iterable, output, indent + 2, include_spans);
@@ -397,14 +483,26 @@ fn pretty_print_stmt(stmt: &Stmt, output: &mut String, indent: usize, include_sp
                 output.push_str(&format!("\n{}  span: {:?}", indent_str, span));
             }
         }
-        Stmt::Break(span) => {
+        Stmt::Break { label, span } => {
             output.push_str(&format!("{}Break", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!(" {}", label));
+            }
             if include_spans {
                 output.push_str(&format!(" @ {:?}", span));
             }
         }
-        Stmt::Continue(span) => {
+        Stmt::Continue { label, span } => {
             output.push_str(&format!("{}Continue", indent_str));
+            if let Some(label) = label {
+                output.push_str(&format!(" {}", label));
+            }
+            if include_spans {
+                output.push_str(&format!(" @ {:?}", span));
+            }
+        }
+        Stmt::Fall(span) => {
+            output.push_str(&format!("{}Fall", indent_str));
             if include_spans {
                 output.push_str(&format!(" @ {:?}", span));
             }
@@ -464,6 +562,11 @@ fn pretty_print_match_case(case: &MatchCase, output: &mut String, indent: usize,
         pretty_print_expr(pattern, output, indent + 2, include_spans);
         output.push('\n');
     }
+    if let Some(guard) = &case.guard {
+        output.push_str(&format!("{}  guard: ", indent_str));
+        pretty_print_expr(guard, output, indent + 2, include_spans);
+        output.push('\n');
+    }
     output.push_str(&format!("{}  body:\n", indent_str));
     pretty_print_block(&case.body, output, indent + 2, include_spans);
     if include_spans {
@@ -480,6 +583,14 @@ fn pretty_print_param(param: &Param, output: &mut String, indent: usize, include
         pretty_print_type(ty, output, include_spans);
         output.push('\n');
     }
+    if param.variadic {
+        output.push_str(&format!("{}  variadic: true\n", indent_str));
+    }
+    if let Some(default) = &param.default {
+        output.push_str(&format!("{}  default: ", indent_str));
+        pretty_print_expr(default, output, indent + 2, include_spans);
+        output.push('\n');
+    }
     if include_spans {
         output.push_str(&format!("{}  span: {:?}\n", indent_str, param.span));
     }
@@ -671,6 +782,13 @@ fn snapshot_class_declaration() {
     assert_snapshot!("class_declaration", pretty_print_ast(&program));
 }
 
+#[test]
+fn snapshot_class_fields() {
+    let source = "cls Dog\n\tint age = 3\n\tstr name\n\tobj Dog(name)";
+    let program = parse_source(source);
+    assert_snapshot!("class_fields", pretty_print_ast(&program));
+}
+
 #[test]
 fn snapshot_string_interpolation() {
     let source = "x := \"Hello &name, you are &age years old\"";
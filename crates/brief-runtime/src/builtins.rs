@@ -1,32 +1,198 @@
-use brief_vm::{Value, RuntimeError};
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use brief_bytecode::CastType;
+use brief_vm::{ArrayRef, MapRef, Value, RuntimeError, VM, contains_value, exit_code, values_equal};
+use crate::formatter::format_template;
 
 /// Builtin function type
 /// Note: VM is passed separately to avoid circular dependency
 pub type BuiltinFn = fn(&[Value]) -> Result<Value, RuntimeError>;
 
-/// Print builtin: print(value)
+/// Builtin that needs the VM to call back into Brief code
+pub type VmBuiltinFn = fn(&[Value], &mut VM) -> Result<Value, RuntimeError>;
+
+/// Print builtin: print(values...)
+/// Writes the values separated by spaces, then a newline
 pub fn print(args: &[Value]) -> Result<Value, RuntimeError> {
-    if args.is_empty() {
-        return Err(RuntimeError::CallError("print requires at least 1 argument".to_string()));
-    }
-    println!("{}", args[0]);
+    write_print(&mut io::stdout(), "print", args, true)
+}
+
+/// Println builtin: println(values...), the same as print
+pub fn println(args: &[Value]) -> Result<Value, RuntimeError> {
+    write_print(&mut io::stdout(), "println", args, true)
+}
+
+/// Prints builtin: prints(values...)
+/// Like print without the newline, so later output continues the line
+pub fn prints(args: &[Value]) -> Result<Value, RuntimeError> {
+    write_print(&mut io::stdout(), "prints", args, false)
+}
+
+/// Write what the print builtin `name` shows for `args` to `out`, flushed
+/// so a line without a newline appears at once. No arguments print an
+/// empty line, or nothing for `prints`
+pub(crate) fn write_print(out: &mut dyn Write, name: &str, args: &[Value], newline: bool) -> Result<Value, RuntimeError> {
+    let parts: Vec<String> = args.iter().map(Value::to_string).collect();
+    write!(out, "{}{}", parts.join(" "), if newline { "\n" } else { "" })
+        .and_then(|_| out.flush())
+        .map_err(|e| RuntimeError::CallError(format!("{}: {}", name, e)))?;
     Ok(Value::Null)
 }
 
+/// Format builtin: format(fmt, args...)
+/// Fills `{}` placeholders as described at `format_template`
+pub fn format(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(template), rest @ ..] => Ok(Value::Str(format_template(template, rest)?)),
+        [other, ..] => Err(RuntimeError::TypeMismatch {
+            expected: "str template for format".to_string(),
+            got: other.type_name().to_string(),
+        }),
+        [] => Err(RuntimeError::CallError("format requires at least 1 argument".to_string())),
+    }
+}
+
+/// Input builtin: input(prompt = "")
+/// Prints the prompt without a newline and reads one line from stdin.
+/// Returns null at end of input.
+pub fn input(args: &[Value]) -> Result<Value, RuntimeError> {
+    if args.len() > 1 {
+        return Err(RuntimeError::CallError("input takes at most 1 argument".to_string()));
+    }
+    if let Some(prompt) = args.first() {
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", prompt)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| RuntimeError::CallError(format!("input: {}", e)))?;
+    }
+
+    let mut line = String::new();
+    let read = io::stdin().lock().read_line(&mut line)
+        .map_err(|e| RuntimeError::CallError(format!("input: {}", e)))?;
+    if read == 0 {
+        return Ok(Value::Null);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Value::Str(line))
+}
+
 /// Length builtin: len(value)
-/// Stub for now - returns 0 until arrays/strings are fully implemented
+/// Counts the characters of a string, or the entries of an array or map
 pub fn len(args: &[Value]) -> Result<Value, RuntimeError> {
-    if args.is_empty() {
-        return Err(RuntimeError::CallError("len requires 1 argument".to_string()));
-    }
-    match &args[0] {
-        Value::Str(s) => Ok(Value::Int(s.len() as i64)),
-        // TODO: Implement for arrays when they're added
-        _ => Err(RuntimeError::TypeMismatch {
-            expected: "string or array".to_string(),
-            got: format!("{:?}", args[0]),
+    let length = match args {
+        [Value::Str(s)] => s.chars().count(),
+        [Value::Array(items)] => items.borrow().len(),
+        [Value::Map(entries)] => entries.borrow().len(),
+        [other] => return Err(RuntimeError::TypeMismatch {
+            expected: "str, array or map".to_string(),
+            got: other.type_name().to_string(),
         }),
+        _ => return Err(RuntimeError::CallError("len requires 1 argument".to_string())),
+    };
+    Ok(Value::Int(length as i64))
+}
+
+/// Exit builtin: exit(code = 0)
+/// Stops the program by raising `RuntimeError::Exit` with the given code
+pub fn exit(args: &[Value]) -> Result<Value, RuntimeError> {
+    let code = match args {
+        [] => 0,
+        [code] => exit_code(code)?,
+        _ => return Err(RuntimeError::CallError("exit takes at most 1 argument".to_string())),
+    };
+    Err(RuntimeError::Exit(code))
+}
+
+/// Time builtin: time_ms()
+/// Milliseconds since the Unix epoch
+pub fn time_ms(args: &[Value]) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::CallError("time_ms takes no arguments".to_string()));
+    }
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| RuntimeError::CallError(format!("System clock is before the Unix epoch: {}", e)))?;
+    Ok(Value::Int(elapsed.as_millis() as i64))
+}
+
+/// Now builtin: now()
+/// Milliseconds since the Unix epoch, like time_ms
+pub fn now(args: &[Value]) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::CallError("now takes no arguments".to_string()));
     }
+    time_ms(args)
+}
+
+/// Sleep builtin: sleep_ms(n)
+/// Blocks for `n` milliseconds
+pub fn sleep_ms(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [ms] = expect_args::<1>("sleep_ms", args)?;
+    let ms = expect_int("sleep_ms", ms)?;
+    let ms = u64::try_from(ms)
+        .map_err(|_| RuntimeError::CallError(format!("sleep_ms needs a non-negative duration, got {}", ms)))?;
+    std::thread::sleep(std::time::Duration::from_millis(ms));
+    Ok(Value::Null)
+}
+
+/// Assert builtin: assert(cond, msg = "condition is false")
+/// Returns null when `cond` is truthy, otherwise raises `RuntimeError::AssertionFailed`
+pub fn assert(args: &[Value]) -> Result<Value, RuntimeError> {
+    let (cond, message) = match args {
+        [cond] => (cond, "condition is false"),
+        [cond, Value::Str(msg)] => (cond, msg.as_str()),
+        [_, other] => {
+            return Err(RuntimeError::TypeMismatch {
+                expected: "string message for assert".to_string(),
+                got: other.type_name().to_string(),
+            });
+        }
+        _ => return Err(RuntimeError::CallError("assert takes 1 or 2 arguments".to_string())),
+    };
+    if cond.is_truthy() {
+        Ok(Value::Null)
+    } else {
+        Err(RuntimeError::AssertionFailed { message: message.to_string() })
+    }
+}
+
+/// Check builtin: check(actual, expected)
+/// Returns null when the values are equal as `==` compares them, otherwise
+/// raises `RuntimeError::AssertionFailed` showing both
+pub fn check(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [actual, expected] = expect_args::<2>("check", args)?;
+    if values_equal(actual, expected) {
+        return Ok(Value::Null);
+    }
+    let (actual, expected) = (quoted(actual), quoted(expected));
+    let message = if actual == expected {
+        format!("expected {}, got a different value that prints the same", expected)
+    } else {
+        format!("expected {}, got {}", expected, actual)
+    };
+    Err(RuntimeError::AssertionFailed { message })
+}
+
+/// A value as `print` shows it, except that a string is quoted, so `"1"`
+/// and `1` read differently
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("\"{}\"", s),
+        other => other.to_string(),
+    }
+}
+
+/// Panic builtin: panic(msg)
+/// Always raises `RuntimeError::Panic`; `msg` may be any value and is shown as `print` would
+pub fn panic(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [message] = expect_args::<1>("panic", args)?;
+    Err(RuntimeError::Panic { message: message.to_string() })
 }
 
 /// Integer cast builtin: int(value)
@@ -34,17 +200,7 @@ pub fn int_cast(args: &[Value]) -> Result<Value, RuntimeError> {
     if args.is_empty() {
         return Err(RuntimeError::CallError("int requires 1 argument".to_string()));
     }
-    match &args[0] {
-        Value::Int(i) => Ok(Value::Int(*i)),
-        Value::Double(d) => Ok(Value::Int(*d as i64)),
-        Value::Bool(b) => Ok(Value::Int(if *b { 1 } else { 0 })),
-        Value::Str(s) => {
-            s.parse::<i64>()
-                .map(Value::Int)
-                .map_err(|_| RuntimeError::CallError(format!("Cannot convert string '{}' to integer", s)))
-        },
-        Value::Null => Err(RuntimeError::CallError("Cannot convert null to integer".to_string())),
-    }
+    args[0].cast(CastType::Int)
 }
 
 /// Double cast builtin: dub(value)
@@ -52,34 +208,539 @@ pub fn dub_cast(args: &[Value]) -> Result<Value, RuntimeError> {
     if args.is_empty() {
         return Err(RuntimeError::CallError("dub requires 1 argument".to_string()));
     }
-    match &args[0] {
-        Value::Int(i) => Ok(Value::Double(*i as f64)),
-        Value::Double(d) => Ok(Value::Double(*d)),
-        Value::Bool(b) => Ok(Value::Double(if *b { 1.0 } else { 0.0 })),
-        Value::Str(s) => {
-            s.parse::<f64>()
-                .map(Value::Double)
-                .map_err(|_| RuntimeError::CallError(format!("Cannot convert string '{}' to double", s)))
-        },
-        Value::Null => Err(RuntimeError::CallError("Cannot convert null to double".to_string())),
-    }
+    args[0].cast(CastType::Dub)
 }
 
 /// String cast builtin: str(value)
+/// Formats the value exactly as `print` would
 pub fn str_cast(args: &[Value]) -> Result<Value, RuntimeError> {
-    if args.is_empty() {
+    if args.len() != 1 {
         return Err(RuntimeError::CallError("str requires 1 argument".to_string()));
     }
-    // Optimize: if already a string, return it directly
-    match &args[0] {
-        Value::Str(s) => Ok(Value::Str(s.clone())), // Clone needed for ownership
-        other => Ok(Value::Str(other.to_string())),
+    args[0].cast(CastType::Str)
+}
+
+/// Integer parse builtin: parseInt(s)
+/// Unlike int(), only accepts a string and fails on malformed input
+pub fn parse_int(args: &[Value]) -> Result<Value, RuntimeError> {
+    let s = expect_str_arg("parseInt", args)?;
+    s.parse::<i64>()
+        .map(Value::Int)
+        .map_err(|e| RuntimeError::ParseError(format!("parseInt(\"{}\"): {}", s, e)))
+}
+
+/// Double parse builtin: parseDouble(s)
+pub fn parse_double(args: &[Value]) -> Result<Value, RuntimeError> {
+    let s = expect_str_arg("parseDouble", args)?;
+    s.parse::<f64>()
+        .map(Value::Double)
+        .map_err(|e| RuntimeError::ParseError(format!("parseDouble(\"{}\"): {}", s, e)))
+}
+
+/// The single string argument of a parse builtin
+fn expect_str_arg<'a>(name: &str, args: &'a [Value]) -> Result<&'a str, RuntimeError> {
+    match args {
+        [Value::Str(s)] => Ok(s),
+        [other] => Err(RuntimeError::TypeMismatch {
+            expected: "str".to_string(),
+            got: other.type_name().to_string(),
+        }),
+        _ => Err(RuntimeError::CallError(format!("{} requires 1 argument", name))),
+    }
+}
+
+/// Type reflection builtin: type(value)
+/// Returns the name of the value's type as a string, `object:Name` for an
+/// instance of class `Name`. Character literals are ints at runtime
+pub fn type_of(args: &[Value]) -> Result<Value, RuntimeError> {
+    let name = match args {
+        [Value::Object(object)] => format!("object:{}", object.borrow().class_name),
+        [value] => value.type_name().to_string(),
+        _ => return Err(RuntimeError::CallError("type requires 1 argument".to_string())),
+    };
+    Ok(Value::Str(name))
+}
+
+/// Sine builtin: sin(x), x in radians
+pub fn sin(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("sin", args)?.sin()))
+}
+
+/// Cosine builtin: cos(x), x in radians
+pub fn cos(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("cos", args)?.cos()))
+}
+
+/// Square root builtin: sqrt(x)
+/// Negative input yields NaN rather than an error
+pub fn sqrt(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("sqrt", args)?.sqrt()))
+}
+
+/// Absolute value builtin: abs(x)
+/// Integers stay integers; everything else is promoted to double
+pub fn abs(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(i)] => i.checked_abs()
+            .map(Value::Int)
+            .ok_or_else(|| RuntimeError::CallError(format!("abs({}) overflows int", i))),
+        _ => Ok(Value::Double(expect_number_arg("abs", args)?.abs())),
+    }
+}
+
+/// Floor builtin: floor(x)
+pub fn floor(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("floor", args)?.floor()))
+}
+
+/// Ceiling builtin: ceil(x)
+pub fn ceil(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("ceil", args)?.ceil()))
+}
+
+/// Rounding builtin: round(x), halfway cases round away from zero
+pub fn round(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Double(expect_number_arg("round", args)?.round()))
+}
+
+/// Logarithm builtin: log(x) is the natural log, log(x, base) uses the given base
+pub fn log(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [_] => Ok(Value::Double(expect_number_arg("log", args)?.ln())),
+        [x, base] => {
+            let x = expect_number("log", x)?;
+            let base = expect_number("log", base)?;
+            Ok(Value::Double(x.log(base)))
+        }
+        _ => Err(RuntimeError::CallError("log requires 1 or 2 arguments".to_string())),
+    }
+}
+
+/// The single numeric argument of a math builtin, promoted to f64
+fn expect_number_arg(name: &str, args: &[Value]) -> Result<f64, RuntimeError> {
+    match args {
+        [value] => expect_number(name, value),
+        _ => Err(RuntimeError::CallError(format!("{} requires 1 argument", name))),
+    }
+}
+
+fn expect_number(name: &str, value: &Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::Double(d) => Ok(*d),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: format!("number for {}", name),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// Membership builtin: contains(haystack, needle)
+/// Substring test for strings, element scan for arrays, key lookup for maps
+pub fn contains(args: &[Value]) -> Result<Value, RuntimeError> {
+    if args.len() < 2 {
+        return Err(RuntimeError::CallError("contains requires 2 arguments".to_string()));
+    }
+    contains_value(&args[0], &args[1]).map(Value::Bool)
+}
+
+/// Uppercase builtin: upper(s)
+pub fn upper(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(expect_str_arg("upper", args)?.to_uppercase()))
+}
+
+/// Lowercase builtin: lower(s)
+pub fn lower(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(expect_str_arg("lower", args)?.to_lowercase()))
+}
+
+/// Trim builtin: trim(s) strips leading and trailing whitespace
+pub fn trim(args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(expect_str_arg("trim", args)?.trim().to_string()))
+}
+
+/// Split builtin: split(s, sep)
+/// Returns an array of the pieces between each `sep`. An empty `sep` splits
+/// `s` into its characters
+pub fn split(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [s, sep] = expect_str_args::<2>("split", args)?;
+    let pieces: Vec<Value> = if sep.is_empty() {
+        s.chars().map(|c| Value::Str(c.to_string())).collect()
+    } else {
+        s.split(sep).map(|piece| Value::Str(piece.to_string())).collect()
+    };
+    Ok(Value::array(pieces))
+}
+
+/// Replace builtin: replace(s, from, to) replaces every `from` with `to`.
+/// An empty `from` matches nothing
+pub fn replace(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [s, from, to] = expect_str_args::<3>("replace", args)?;
+    if from.is_empty() {
+        return Ok(Value::Str(s.to_string()));
+    }
+    Ok(Value::Str(s.replace(from, to)))
+}
+
+/// Prefix builtin: starts_with(s, prefix)
+pub fn starts_with(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [s, prefix] = expect_str_args::<2>("starts_with", args)?;
+    Ok(Value::Bool(s.starts_with(prefix)))
+}
+
+/// Search builtin: index_of(s, needle) or index_of(arr, value)
+/// Index of the first `needle` in `s`, counted in characters like `len`
+/// and `substring`, or of the first element equal to `value`. -1 when
+/// there is none
+pub fn index_of(args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Array(items), value] = args {
+        let index = items.borrow().iter().position(|item| values_equal(item, value));
+        return Ok(Value::Int(index.map_or(-1, |i| i as i64)));
+    }
+    let [s, needle] = expect_str_args::<2>("index_of", args)?;
+    let index = s.find(needle).map_or(-1, |byte| s[..byte].chars().count() as i64);
+    Ok(Value::Int(index))
+}
+
+/// Slice builtin: substring(s, start, end = len(s))
+/// Characters from index `start` up to but not including `end`. Indices
+/// count characters, not bytes, and are clamped to the string
+pub fn substring(args: &[Value]) -> Result<Value, RuntimeError> {
+    let (s, start, end) = match args {
+        [s, start] => (s, start, None),
+        [s, start, end] => (s, start, Some(end)),
+        _ => return Err(RuntimeError::CallError("substring requires 2 or 3 arguments".to_string())),
+    };
+    let s = expect_str("substring", s)?;
+    let length = s.chars().count() as i64;
+    let start = expect_int("substring", start)?.clamp(0, length);
+    let end = match end {
+        Some(end) => expect_int("substring", end)?.clamp(start, length),
+        None => length,
+    };
+    let slice = s.chars().skip(start as usize).take((end - start) as usize).collect();
+    Ok(Value::Str(slice))
+}
+
+/// Exactly `N` string arguments
+fn expect_str_args<'a, const N: usize>(name: &str, args: &'a [Value]) -> Result<[&'a str; N], RuntimeError> {
+    let args: &[Value; N] = args.try_into().map_err(|_| {
+        RuntimeError::CallError(format!("{} requires {} arguments", name, N))
+    })?;
+    let mut strs = [""; N];
+    for (slot, arg) in strs.iter_mut().zip(args) {
+        *slot = expect_str(name, arg)?;
+    }
+    Ok(strs)
+}
+
+fn expect_str<'a>(name: &str, value: &'a Value) -> Result<&'a str, RuntimeError> {
+    match value {
+        Value::Str(s) => Ok(s),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: format!("str for {}", name),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+pub(crate) fn expect_int(name: &str, value: &Value) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Int(n) => Ok(*n),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: format!("int for {}", name),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// Sort builtin: sort(arr) or sort(arr, cmp_fn)
+/// Sorts the array in place and returns null. The comparator receives two
+/// elements and returns a negative int, zero, or a positive int.
+pub fn sort(args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+    if args.is_empty() {
+        return Err(RuntimeError::CallError("sort requires at least 1 argument".to_string()));
+    }
+    let array = expect_array(&args[0])?;
+
+    // Sort a copy so the comparator can't observe a borrowed array
+    let items = array.borrow().clone();
+    let sorted = merge_sort(items, &mut |a, b| match args.get(1) {
+        None => natural_order(a, b),
+        Some(comparator) => match vm.call_value(comparator, &[a.clone(), b.clone()])? {
+            Value::Int(n) => Ok(n.cmp(&0)),
+            other => Err(RuntimeError::TypeMismatch {
+                expected: "int from comparator".to_string(),
+                got: other.type_name().to_string(),
+            }),
+        },
+    })?;
+    *array.borrow_mut() = sorted;
+    Ok(Value::Null)
+}
+
+/// Stable merge sort that stops at the first failed comparison. Unlike
+/// `slice::sort_by` it never panics when `compare` is not a total order,
+/// which a user comparator need not be
+fn merge_sort<F>(mut items: Vec<Value>, compare: &mut F) -> Result<Vec<Value>, RuntimeError>
+where
+    F: FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+{
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, compare)?;
+    let right = merge_sort(right, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Equal elements keep their order: take from the right only when
+        // it sorts strictly first
+        let next = if compare(a, b)? == Ordering::Greater { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Natural ordering used by `sort`: ints and doubles compare numerically
+/// with each other (NaN after every other number), strings compare with
+/// strings. Any other pair has no order
+fn natural_order(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Ok(x.cmp(y)),
+        (Value::Str(x), Value::Str(y)) => Ok(x.cmp(y)),
+        (Value::Int(_) | Value::Double(_), Value::Int(_) | Value::Double(_)) => {
+            Ok(number_order(expect_number("sort", a)?, expect_number("sort", b)?))
+        }
+        _ => Err(RuntimeError::TypeMismatch {
+            expected: "ints, dubs or strs to sort".to_string(),
+            got: format!("{} and {}", a.type_name(), b.type_name()),
+        }),
+    }
+}
+
+/// Total order on doubles that puts NaN last and treats -0.0 as 0.0
+fn number_order(x: f64, y: f64) -> Ordering {
+    x.partial_cmp(&y).unwrap_or_else(|| x.is_nan().cmp(&y.is_nan()))
+}
+
+/// Map builtin: map(arr, fn)
+/// Returns a new array holding fn(elem) for each element
+pub fn map(args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::CallError("map requires 2 arguments".to_string()));
+    }
+    // Iterate a copy so the callback may modify the source array
+    let items = expect_array(&args[0])?.borrow().clone();
+    let mapped = items
+        .into_iter()
+        .map(|item| vm.call_value(&args[1], &[item]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::array(mapped))
+}
+
+/// Filter builtin: filter(arr, fn)
+/// Returns a new array of the elements for which fn(elem) is truthy
+pub fn filter(args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::CallError("filter requires 2 arguments".to_string()));
+    }
+    let items = expect_array(&args[0])?.borrow().clone();
+    let mut kept = Vec::new();
+    for item in items {
+        if vm.call_value(&args[1], std::slice::from_ref(&item))?.is_truthy() {
+            kept.push(item);
+        }
+    }
+    Ok(Value::array(kept))
+}
+
+/// Reduce builtin: reduce(arr, fn, init)
+/// Folds left, calling fn(acc, elem) for each element starting from init
+pub fn reduce(args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+    if args.len() != 3 {
+        return Err(RuntimeError::CallError("reduce requires 3 arguments".to_string()));
+    }
+    let items = expect_array(&args[0])?.borrow().clone();
+    items
+        .into_iter()
+        .try_fold(args[2].clone(), |acc, item| vm.call_value(&args[1], &[acc, item]))
+}
+
+/// Push builtin: push(arr, value) appends `value` to the end of `arr`
+pub fn push(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [array, value] = expect_args::<2>("push", args)?;
+    expect_array(array)?.borrow_mut().push(value.clone());
+    Ok(Value::Null)
+}
+
+/// Pop builtin: pop(arr) removes and returns the last element
+pub fn pop(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [array] = expect_args::<1>("pop", args)?;
+    expect_array(array)?
+        .borrow_mut()
+        .pop()
+        .ok_or_else(|| RuntimeError::CallError("pop from an empty array".to_string()))
+}
+
+/// Insert builtin: insert(arr, index, value)
+/// Shifts the elements from `index` on to make room. `index` may equal
+/// `len(arr)`, which appends
+pub fn insert(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [array, index, value] = expect_args::<3>("insert", args)?;
+    let mut items = expect_array(array)?.borrow_mut();
+    let index = expect_index("insert", index, items.len() + 1)?;
+    items.insert(index, value.clone());
+    Ok(Value::Null)
+}
+
+/// Remove builtin: remove(arr, index) or remove(m, key)
+/// Removes and returns the array element at `index`, shifting the rest
+/// down, or the map entry for `key`. A missing key returns null
+pub fn remove(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [collection, key] = expect_args::<2>("remove", args)?;
+    if let Value::Map(entries) = collection {
+        let mut entries = entries.borrow_mut();
+        let removed = entries.iter().position(|(k, _)| values_equal(k, key)).map(|i| entries.remove(i).1);
+        return Ok(removed.unwrap_or(Value::Null));
+    }
+    let mut items = expect_array(collection)?.borrow_mut();
+    let index = expect_index("remove", key, items.len())?;
+    Ok(items.remove(index))
+}
+
+/// Reverse builtin: reverse(arr) reverses the array in place
+pub fn reverse(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [array] = expect_args::<1>("reverse", args)?;
+    expect_array(array)?.borrow_mut().reverse();
+    Ok(Value::Null)
+}
+
+/// Join builtin: join(arr, sep)
+/// Formats each element as `str` would and puts `sep` between them
+pub fn join(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [array, sep] = expect_args::<2>("join", args)?;
+    let sep = expect_str("join", sep)?;
+    let pieces: Vec<String> = expect_array(array)?.borrow().iter().map(Value::to_string).collect();
+    Ok(Value::Str(pieces.join(sep)))
+}
+
+/// Map constructor builtin: map_of(k1, v1, k2, v2, ...)
+/// Entries keep the order their keys were first given in; a repeated key
+/// overwrites the earlier value
+pub fn map_of(args: &[Value]) -> Result<Value, RuntimeError> {
+    if !args.len().is_multiple_of(2) {
+        return Err(RuntimeError::CallError("map_of requires key/value pairs".to_string()));
+    }
+    let mut entries = Vec::new();
+    for pair in args.chunks(2) {
+        put_entry(&mut entries, pair[0].clone(), pair[1].clone());
+    }
+    Ok(Value::map(entries))
+}
+
+/// Lookup builtin: get(m, key, default = null)
+pub fn get(args: &[Value]) -> Result<Value, RuntimeError> {
+    let (map, key, default) = match args {
+        [map, key] => (map, key, Value::Null),
+        [map, key, default] => (map, key, default.clone()),
+        _ => return Err(RuntimeError::CallError("get requires 2 or 3 arguments".to_string())),
+    };
+    let entries = expect_map("get", map)?.borrow();
+    let value = entries.iter().find(|(k, _)| values_equal(k, key)).map(|(_, v)| v.clone());
+    Ok(value.unwrap_or(default))
+}
+
+/// Store builtin: put(m, key, value) sets the entry in place. A new key
+/// goes after the existing ones
+pub fn put(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [map, key, value] = expect_args::<3>("put", args)?;
+    put_entry(&mut expect_map("put", map)?.borrow_mut(), key.clone(), value.clone());
+    Ok(Value::Null)
+}
+
+/// Key builtin: has(m, key)
+pub fn has(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [map, key] = expect_args::<2>("has", args)?;
+    Ok(Value::Bool(expect_map("has", map)?.borrow().iter().any(|(k, _)| values_equal(k, key))))
+}
+
+/// Keys builtin: keys(m) returns the keys as an array, in insertion order
+pub fn keys(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [map] = expect_args::<1>("keys", args)?;
+    Ok(Value::array(expect_map("keys", map)?.borrow().iter().map(|(k, _)| k.clone()).collect()))
+}
+
+/// Values builtin: values(m) returns the values as an array, in the same
+/// order as `keys`
+pub fn values(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [map] = expect_args::<1>("values", args)?;
+    Ok(Value::array(expect_map("values", map)?.borrow().iter().map(|(_, v)| v.clone()).collect()))
+}
+
+/// Merge builtin: merge(a, b)
+/// A new map with the entries of `a`, then those of `b`. Where both have a
+/// key, `b`'s value wins and the key keeps its place from `a`
+pub fn merge(args: &[Value]) -> Result<Value, RuntimeError> {
+    let [a, b] = expect_args::<2>("merge", args)?;
+    let mut entries = expect_map("merge", a)?.borrow().clone();
+    for (key, value) in expect_map("merge", b)?.borrow().iter() {
+        put_entry(&mut entries, key.clone(), value.clone());
+    }
+    Ok(Value::map(entries))
+}
+
+fn put_entry(entries: &mut Vec<(Value, Value)>, key: Value, value: Value) {
+    match entries.iter_mut().find(|(k, _)| values_equal(k, &key)) {
+        Some((_, slot)) => *slot = value,
+        None => entries.push((key, value)),
+    }
+}
+
+fn expect_map<'a>(name: &str, value: &'a Value) -> Result<&'a MapRef, RuntimeError> {
+    match value {
+        Value::Map(entries) => Ok(entries),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: format!("map for {}", name),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// Exactly `N` arguments
+pub(crate) fn expect_args<'a, const N: usize>(name: &str, args: &'a [Value]) -> Result<&'a [Value; N], RuntimeError> {
+    args.try_into().map_err(|_| {
+        RuntimeError::CallError(format!("{} requires {} argument(s)", name, N))
+    })
+}
+
+/// An int index below `bound`
+fn expect_index(name: &str, value: &Value, bound: usize) -> Result<usize, RuntimeError> {
+    let index = expect_int(name, value)?;
+    usize::try_from(index)
+        .ok()
+        .filter(|&i| i < bound)
+        .ok_or_else(|| RuntimeError::CallError(format!("Index {} out of range for {}", index, name)))
+}
+
+/// Borrow the array behind a builtin argument
+fn expect_array(value: &Value) -> Result<&ArrayRef, RuntimeError> {
+    match value {
+        Value::Array(items) => Ok(items),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "array".to_string(),
+            got: other.type_name().to_string(),
+        }),
     }
 }
 
 /// String concatenation helper: rt_concatN(args...)
 /// Concatenates N string arguments efficiently
-
 pub fn rt_concat2(args: &[Value]) -> Result<Value, RuntimeError> {
     if args.len() < 2 {
         return Err(RuntimeError::CallError("rt_concat2 requires 2 arguments".to_string()));
@@ -91,21 +752,21 @@ pub fn rt_concat2(args: &[Value]) -> Result<Value, RuntimeError> {
             let mut result = String::with_capacity(a.len() + b.len());
             result.push_str(a);
             result.push_str(b);
-            return Ok(Value::Str(result));
+            Ok(Value::Str(result))
         },
         (Value::Str(a), b) => {
             let b_str = b.to_string();
             let mut result = String::with_capacity(a.len() + b_str.len());
             result.push_str(a);
             result.push_str(&b_str);
-            return Ok(Value::Str(result));
+            Ok(Value::Str(result))
         },
         (a, Value::Str(b)) => {
             let a_str = a.to_string();
             let mut result = String::with_capacity(a_str.len() + b.len());
             result.push_str(&a_str);
             result.push_str(b);
-            return Ok(Value::Str(result));
+            Ok(Value::Str(result))
         },
         (a, b) => {
             // Both non-strings - need to convert both
@@ -114,9 +775,9 @@ pub fn rt_concat2(args: &[Value]) -> Result<Value, RuntimeError> {
             let mut result = String::with_capacity(a_str.len() + b_str.len());
             result.push_str(&a_str);
             result.push_str(&b_str);
-            return Ok(Value::Str(result));
+            Ok(Value::Str(result))
         },
-    };
+    }
 }
 
 pub fn rt_concat3(args: &[Value]) -> Result<Value, RuntimeError> {
@@ -176,3 +837,84 @@ pub fn rt_concat5(args: &[Value]) -> Result<Value, RuntimeError> {
     Ok(Value::Str(result))
 }
 
+/// Range helper: rt_range(start, end, inclusive) builds the array of ints
+/// from `start` up to `end`
+pub fn rt_range(args: &[Value]) -> Result<Value, RuntimeError> {
+    if args.len() < 3 {
+        return Err(RuntimeError::CallError("rt_range requires 3 arguments".to_string()));
+    }
+    let bound = |value: &Value| match value {
+        Value::Int(n) => Ok(*n),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "int".to_string(),
+            got: other.type_name().to_string(),
+        }),
+    };
+    let (start, end) = (bound(&args[0])?, bound(&args[1])?);
+    let items: Vec<Value> = if args[2].is_truthy() {
+        (start..=end).map(Value::Int).collect()
+    } else {
+        (start..end).map(Value::Int).collect()
+    };
+    Ok(Value::array(items))
+}
+
+/// Range builtin: range(end), range(start, end) or range(start, end, step)
+/// The ints from `start` (default 0) up to but not including `end`, `step`
+/// (default 1) apart. A negative step counts down; a range that can't
+/// reach `end` from `start` is empty
+pub fn range(args: &[Value]) -> Result<Value, RuntimeError> {
+    let (start, end, step) = range_bounds(args)?;
+    let mut items = Vec::new();
+    let mut n = start;
+    while (step > 0 && n < end) || (step < 0 && n > end) {
+        items.push(Value::Int(n));
+        let Some(next) = n.checked_add(step) else {
+            break;
+        };
+        n = next;
+    }
+    Ok(Value::array(items))
+}
+
+/// The start, end and step of a `range` call. All must be ints, and the
+/// step not zero
+fn range_bounds(args: &[Value]) -> Result<(i64, i64, i64), RuntimeError> {
+    let bound = |value: &Value| match value {
+        Value::Int(n) => Ok(*n),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "int".to_string(),
+            got: other.type_name().to_string(),
+        }),
+    };
+    let ints = args.iter().map(bound).collect::<Result<Vec<_>, _>>()?;
+    let (start, end, step) = match ints[..] {
+        [end] => (0, end, 1),
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step),
+        _ => return Err(RuntimeError::CallError("range takes 1 to 3 arguments".to_string())),
+    };
+    if step == 0 {
+        return Err(RuntimeError::CallError("range step cannot be zero".to_string()));
+    }
+    Ok((start, end, step))
+}
+
+/// Range loop helper: rt_range_step(start, end, step) checks the bounds of
+/// a `for (v in range(...))` loop as `range` would, and returns the step
+pub fn rt_range_step(args: &[Value]) -> Result<Value, RuntimeError> {
+    let (_, _, step) = range_bounds(args)?;
+    Ok(Value::Int(step))
+}
+
+/// Repeat helper: rt_repeat_count(n) checks that a `repeat` count is an int
+pub fn rt_repeat_count(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args.first() {
+        Some(Value::Int(n)) => Ok(Value::Int(*n)),
+        Some(other) => Err(RuntimeError::TypeMismatch {
+            expected: "int".to_string(),
+            got: other.type_name().to_string(),
+        }),
+        None => Err(RuntimeError::CallError("rt_repeat_count requires 1 argument".to_string())),
+    }
+}
@@ -0,0 +1,151 @@
+use brief_vm::{RuntimeError, Value};
+
+/// Fill the placeholders of `template` with `args`, for `format(fmt, args...)`
+///
+/// `{}` takes the next argument and `{1}` a given one; `{{` and `}}` are
+/// literal braces. After a `:` comes an optional spec of
+/// `[[fill]align][width][.precision]`: align is `<`, `>` or `^`, the width
+/// counts characters, and the precision is the number of decimals for an
+/// int or dub, or the most characters kept of a string. Values are written
+/// as `print` shows them, numbers aligned right and everything else left
+/// unless the spec says otherwise. Every argument must be used
+pub fn format_template(template: &str, args: &[Value]) -> Result<String, RuntimeError> {
+    let mut output = String::new();
+    let mut used = vec![false; args.len()];
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err(format_error("unmatched '}' (write '}}' for a literal brace)")),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(format_error("unclosed '{' (write '{{' for a literal brace)")),
+                    }
+                }
+                let (index, spec) = match placeholder.split_once(':') {
+                    Some((index, spec)) => (index, Some(parse_spec(spec)?)),
+                    None => (placeholder.as_str(), None),
+                };
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    index.parse::<usize>()
+                        .map_err(|_| format_error(&format!("invalid placeholder '{{{}}}'", placeholder)))?
+                };
+                let value = args.get(index).ok_or_else(|| {
+                    format_error(&format!("placeholder {} has no argument ({} given)", index, args.len()))
+                })?;
+                used[index] = true;
+                output.push_str(&write_value(value, &spec.unwrap_or_default())?);
+            }
+            c => output.push(c),
+        }
+    }
+
+    let unused = used.iter().filter(|used| !**used).count();
+    if unused > 0 {
+        return Err(format_error(&format!("{} of {} arguments not used by the template", unused, args.len())));
+    }
+    Ok(output)
+}
+
+/// Largest width or precision a spec may ask for. Rust's own formatting
+/// rejects larger precisions, and a huge width would pad without bound
+const MAX_SPEC_NUMBER: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, RuntimeError> {
+    let invalid = || format_error(&format!("invalid format spec '{{:{}}}'", spec));
+    let align_of = |c: char| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+
+    let mut parsed = Spec::default();
+    let mut rest = spec;
+    let mut leading = rest.chars();
+    if let (Some(fill), Some(align)) = (leading.next(), leading.next().and_then(align_of)) {
+        parsed.fill = Some(fill);
+        parsed.align = Some(align);
+        rest = &rest[fill.len_utf8() + 1..];
+    } else if let Some(align) = rest.chars().next().and_then(align_of) {
+        parsed.align = Some(align);
+        rest = &rest[1..];
+    }
+
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (rest, None),
+    };
+    let bounded = |digits: &str| match digits.parse::<usize>() {
+        Ok(n) if n <= MAX_SPEC_NUMBER => Ok(n),
+        _ => Err(invalid()),
+    };
+    if !width.is_empty() {
+        parsed.width = bounded(width)?;
+    }
+    if let Some(precision) = precision {
+        if precision.is_empty() || !precision.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        parsed.precision = Some(bounded(precision)?);
+    }
+    Ok(parsed)
+}
+
+fn write_value(value: &Value, spec: &Spec) -> Result<String, RuntimeError> {
+    let text = match (spec.precision, value) {
+        (None, value) => value.to_string(),
+        (Some(decimals), Value::Int(i)) => format!("{:.*}", decimals, *i as f64),
+        (Some(decimals), Value::Double(d)) => format!("{:.*}", decimals, d),
+        (Some(chars), Value::Str(s)) => s.chars().take(chars).collect(),
+        (Some(_), other) => {
+            return Err(format_error(&format!("a precision needs an int, dub or str, got {}", other.type_name())));
+        }
+    };
+
+    let padding = spec.width.saturating_sub(text.chars().count());
+    let numeric = matches!(value, Value::Int(_) | Value::Double(_));
+    let align = spec.align.unwrap_or(if numeric { Align::Right } else { Align::Left });
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+    let fill = spec.fill.unwrap_or(' ').to_string();
+    Ok(format!("{}{}{}", fill.repeat(before), text, fill.repeat(after)))
+}
+
+fn format_error(message: &str) -> RuntimeError {
+    RuntimeError::CallError(format!("format: {}", message))
+}
@@ -1,4 +1,5 @@
 pub mod builtins;
+pub mod formatter;
 pub mod runtime;
 
 pub use builtins::*;
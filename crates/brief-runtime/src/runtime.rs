@@ -1,57 +1,513 @@
 use std::collections::HashMap;
-use brief_vm::{Value, RuntimeError, BuiltinRuntime};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use brief_vm::{Value, RuntimeError, BuiltinRuntime, VM};
+use brief_builtins::{arity_message, BuiltinSpec, BUILTINS};
 use crate::builtins::*;
 
+/// What a runtime lets Brief code do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeOptions {
+    /// Whether file builtins may touch the filesystem. When false they fail
+    /// with "filesystem access disabled"
+    pub allow_fs: bool,
+    /// Whether `env` and `cwd` may read the process environment
+    pub allow_env: bool,
+    /// Whether `exit` may stop the program. When false it fails like any
+    /// other error, which the embedder can report or catch
+    pub allow_exit: bool,
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        Self { allow_fs: true, allow_env: true, allow_exit: true }
+    }
+}
+
+/// Builtin that needs the runtime's own state, such as open files
+pub type StatefulBuiltinFn = fn(&Runtime, &[Value]) -> Result<Value, RuntimeError>;
+
+/// A Rust function exposed to Brief code with `Runtime::register`
+pub type HostFn = dyn Fn(&[Value]) -> Result<Value, RuntimeError> + Send + Sync;
+
+/// How the runtime calls a builtin
+#[derive(Clone, Copy)]
+pub enum Builtin {
+    Plain(BuiltinFn),
+    Stateful(StatefulBuiltinFn),
+    Vm(VmBuiltinFn),
+}
+
+/// The implementation of every builtin in `brief_builtins::BUILTINS`
+const IMPLEMENTATIONS: &[(&str, Builtin)] = &[
+    // Core
+    ("print", Builtin::Stateful(|runtime, args| runtime.print("print", args, true))),
+    ("println", Builtin::Stateful(|runtime, args| runtime.print("println", args, true))),
+    ("prints", Builtin::Stateful(|runtime, args| runtime.print("prints", args, false))),
+    ("format", Builtin::Plain(format)),
+    ("input", Builtin::Plain(input)),
+    ("len", Builtin::Plain(len)),
+    ("contains", Builtin::Plain(contains)),
+    ("exit", Builtin::Stateful(Runtime::exit)),
+    ("assert", Builtin::Plain(assert)),
+    ("check", Builtin::Plain(check)),
+    ("panic", Builtin::Plain(panic)),
+    // Time and randomness
+    ("clock", Builtin::Stateful(Runtime::clock)),
+    ("time_ms", Builtin::Plain(time_ms)),
+    ("now", Builtin::Plain(now)),
+    ("sleep_ms", Builtin::Plain(sleep_ms)),
+    ("random", Builtin::Stateful(Runtime::random)),
+    ("random_int", Builtin::Stateful(Runtime::random_int)),
+    ("seed_random", Builtin::Stateful(Runtime::seed_random)),
+    // Files
+    ("open", Builtin::Stateful(Runtime::open)),
+    ("read", Builtin::Stateful(Runtime::read)),
+    ("write", Builtin::Stateful(Runtime::write)),
+    ("close", Builtin::Stateful(Runtime::close)),
+    ("read_file", Builtin::Stateful(Runtime::read_file)),
+    ("write_file", Builtin::Stateful(|runtime, args| runtime.write_file(args, false))),
+    ("append_file", Builtin::Stateful(|runtime, args| runtime.write_file(args, true))),
+    ("file_exists", Builtin::Stateful(Runtime::file_exists)),
+    // Process
+    ("env", Builtin::Stateful(Runtime::env)),
+    ("cwd", Builtin::Stateful(Runtime::cwd)),
+    // Type casting and reflection
+    ("int", Builtin::Plain(int_cast)),
+    ("dub", Builtin::Plain(dub_cast)),
+    ("str", Builtin::Plain(str_cast)),
+    ("parseInt", Builtin::Plain(parse_int)),
+    ("parseDouble", Builtin::Plain(parse_double)),
+    ("type", Builtin::Plain(type_of)),
+    // Math
+    ("sin", Builtin::Plain(sin)),
+    ("cos", Builtin::Plain(cos)),
+    ("sqrt", Builtin::Plain(sqrt)),
+    ("abs", Builtin::Plain(abs)),
+    ("floor", Builtin::Plain(floor)),
+    ("ceil", Builtin::Plain(ceil)),
+    ("round", Builtin::Plain(round)),
+    ("log", Builtin::Plain(log)),
+    // Strings
+    ("upper", Builtin::Plain(upper)),
+    ("lower", Builtin::Plain(lower)),
+    ("trim", Builtin::Plain(trim)),
+    ("split", Builtin::Plain(split)),
+    ("replace", Builtin::Plain(replace)),
+    ("starts_with", Builtin::Plain(starts_with)),
+    ("index_of", Builtin::Plain(index_of)),
+    ("substring", Builtin::Plain(substring)),
+    // Arrays; the array is shared, so these mutate it in place. Those
+    // taking a function call back into Brief code
+    ("sort", Builtin::Vm(sort)),
+    ("map", Builtin::Vm(map)),
+    ("filter", Builtin::Vm(filter)),
+    ("reduce", Builtin::Vm(reduce)),
+    ("push", Builtin::Plain(push)),
+    ("pop", Builtin::Plain(pop)),
+    ("insert", Builtin::Plain(insert)),
+    ("remove", Builtin::Plain(remove)),
+    ("reverse", Builtin::Plain(reverse)),
+    ("join", Builtin::Plain(join)),
+    ("range", Builtin::Plain(range)),
+    // Maps; entries stay in insertion order
+    ("map_of", Builtin::Plain(map_of)),
+    ("get", Builtin::Plain(get)),
+    ("put", Builtin::Plain(put)),
+    ("has", Builtin::Plain(has)),
+    ("keys", Builtin::Plain(keys)),
+    ("values", Builtin::Plain(values)),
+    ("merge", Builtin::Plain(merge)),
+    // String concatenation, range literals outside for-in loops and repeat
+    ("rt_concat2", Builtin::Plain(rt_concat2)),
+    ("rt_concat3", Builtin::Plain(rt_concat3)),
+    ("rt_concat4", Builtin::Plain(rt_concat4)),
+    ("rt_concat5", Builtin::Plain(rt_concat5)),
+    ("rt_range", Builtin::Plain(rt_range)),
+    ("rt_range_step", Builtin::Plain(rt_range_step)),
+    ("rt_repeat_count", Builtin::Plain(rt_repeat_count)),
+];
+
 /// Runtime for builtin functions
 pub struct Runtime {
-    builtins: HashMap<String, BuiltinFn>,
+    options: RuntimeOptions,
+    builtins: HashMap<String, (BuiltinSpec, Builtin)>,
+    /// Builtins registered by the embedder, with their arity
+    host_builtins: Mutex<HashMap<String, (usize, Arc<HostFn>)>>,
+    /// Where the print builtins write; stdout unless `set_output` changed it
+    output: Mutex<Box<dyn Write + Send>>,
+    start_time: Instant,
+    file_handles: Mutex<HashMap<i64, File>>,
+    next_handle: AtomicI64,
+    /// State of the random number generator; `seed_random` sets it
+    random_state: AtomicU64,
 }
 
 impl BuiltinRuntime for Runtime {
     fn call_builtin(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
-        if let Some(builtin_fn) = self.get_builtin(name) {
-            builtin_fn(args)
-        } else {
-            Err(RuntimeError::CallError(format!("Unknown builtin: {}", name)))
-        }
+        self.dispatch(name, args, None)
     }
     
     fn is_builtin(&self, name: &str) -> bool {
-        self.builtins.contains_key(name)
+        Runtime::is_builtin(self, name)
+    }
+
+    fn call_builtin_with_vm(&self, name: &str, args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+        self.dispatch(name, args, Some(vm))
     }
 }
 
 impl Runtime {
     pub fn new() -> Self {
-        let mut builtins = HashMap::new();
-        
-        // Core builtins
-        builtins.insert("print".to_string(), print as BuiltinFn);
-        builtins.insert("len".to_string(), len as BuiltinFn);
-        
-        // Type casting builtins
-        builtins.insert("int".to_string(), int_cast as BuiltinFn);
-        builtins.insert("dub".to_string(), dub_cast as BuiltinFn);
-        builtins.insert("str".to_string(), str_cast as BuiltinFn);
-        
-        // String concatenation helpers
-        builtins.insert("rt_concat2".to_string(), rt_concat2 as BuiltinFn);
-        builtins.insert("rt_concat3".to_string(), rt_concat3 as BuiltinFn);
-        builtins.insert("rt_concat4".to_string(), rt_concat4 as BuiltinFn);
-        builtins.insert("rt_concat5".to_string(), rt_concat5 as BuiltinFn);
+        Self::with_options(RuntimeOptions::default())
+    }
+
+    pub fn with_options(options: RuntimeOptions) -> Self {
+        let builtins = BUILTINS
+            .iter()
+            .map(|spec| {
+                let (_, builtin) = IMPLEMENTATIONS
+                    .iter()
+                    .find(|(name, _)| *name == spec.name)
+                    .unwrap_or_else(|| panic!("builtin '{}' has no implementation", spec.name));
+                (spec.name.to_string(), (*spec, *builtin))
+            })
+            .collect();
         
-        Self { builtins }
+        Self {
+            options,
+            builtins,
+            host_builtins: Mutex::new(HashMap::new()),
+            output: Mutex::new(Box::new(std::io::stdout())),
+            start_time: Instant::now(),
+            file_handles: Mutex::new(HashMap::new()),
+            next_handle: AtomicI64::new(1),
+            // Unseeded programs get a different sequence each run
+            random_state: AtomicU64::new(
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64),
+            ),
+        }
+    }
+
+    /// Expose `host_fn` to Brief code as the builtin `name`, called with
+    /// exactly `arity` arguments. It takes precedence over a built-in of
+    /// the same name, and since this only needs `&self`, a runtime shared
+    /// with a running VM through an `Arc` can still gain builtins. Programs
+    /// calling it must be lowered with the name added to their
+    /// `KnownGlobals` (`add_builtin`) so it resolves
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        arity: usize,
+        host_fn: impl Fn(&[Value]) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    ) {
+        self.host().insert(name.into(), (arity, Arc::new(host_fn)));
+    }
+
+    /// Send what `print`, `println` and `prints` write to `writer` instead
+    /// of stdout, say to capture a program's output. Like `register` this
+    /// only needs `&self`
+    pub fn set_output(&self, writer: Box<dyn Write + Send>) {
+        *self.output.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = writer;
+    }
+
+    /// Print builtins: print(values...), println(values...) and
+    /// prints(values...), written to the runtime's output
+    fn print(&self, name: &str, args: &[Value], newline: bool) -> Result<Value, RuntimeError> {
+        let mut output = self.output.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        write_print(&mut **output, name, args, newline)
+    }
+
+    fn host(&self) -> std::sync::MutexGuard<'_, HashMap<String, (usize, Arc<HostFn>)>> {
+        self.host_builtins.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Check the arity of a call against the builtin's spec, then run it.
+    /// Builtins that call back into Brief code need `vm`
+    fn dispatch(&self, name: &str, args: &[Value], vm: Option<&mut VM>) -> Result<Value, RuntimeError> {
+        // Cloned out so the host function can itself register builtins
+        let host = self.host().get(name).cloned();
+        if let Some((arity, host_fn)) = host {
+            if args.len() != arity {
+                return Err(RuntimeError::CallError(arity_message(name, arity, Some(arity), args.len())));
+            }
+            return host_fn(args);
+        }
+        let Some((spec, builtin)) = self.builtins.get(name) else {
+            return Err(RuntimeError::CallError(format!("Unknown builtin: {}", name)));
+        };
+        if !spec.accepts(args.len()) {
+            return Err(RuntimeError::CallError(spec.arity_message(args.len())));
+        }
+        match (builtin, vm) {
+            (Builtin::Plain(builtin_fn), _) => builtin_fn(args),
+            (Builtin::Stateful(builtin_fn), _) => builtin_fn(self, args),
+            (Builtin::Vm(builtin_fn), Some(vm)) => builtin_fn(args, vm),
+            (Builtin::Vm(_), None) => {
+                Err(RuntimeError::CallError(format!("Builtin '{}' must be called from the VM", name)))
+            }
+        }
+    }
+    
+    /// Clock builtin: clock()
+    /// Seconds elapsed since the runtime was created, as a double
+    fn clock(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        if !args.is_empty() {
+            return Err(RuntimeError::CallError("clock takes no arguments".to_string()));
+        }
+        Ok(Value::Double(self.start_time.elapsed().as_secs_f64()))
+    }
+    
+    /// The next 64 random bits, from SplitMix64: small and fast, but not
+    /// suitable for anything security related
+    fn next_random(&self) -> u64 {
+        const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut z = self.random_state.fetch_add(GAMMA, Ordering::Relaxed).wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random builtin: random()
+    /// A uniform double in [0, 1)
+    fn random(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        if !args.is_empty() {
+            return Err(RuntimeError::CallError("random takes no arguments".to_string()));
+        }
+        // The top 53 bits fill a double's mantissa exactly
+        Ok(Value::Double((self.next_random() >> 11) as f64 / (1u64 << 53) as f64))
+    }
+
+    /// Random int builtin: random_int(lo, hi)
+    /// A uniform int from `lo` to `hi`, both included
+    fn random_int(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        let [lo, hi] = expect_args::<2>("random_int", args)?;
+        let (lo, hi) = (expect_int("random_int", lo)?, expect_int("random_int", hi)?);
+        if lo > hi {
+            return Err(RuntimeError::CallError(format!("random_int needs lo <= hi, got {} and {}", lo, hi)));
+        }
+        // Scale 64 random bits onto the range, which may span all of i64
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        let offset = (self.next_random() as u128 * span) >> 64;
+        Ok(Value::Int((lo as i128 + offset as i128) as i64))
+    }
+
+    /// Seed builtin: seed_random(n)
+    /// After seeding, random and random_int repeat the same sequence
+    fn seed_random(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        let [seed] = expect_args::<1>("seed_random", args)?;
+        let seed = expect_int("seed_random", seed)?;
+        self.random_state.store(seed as u64, Ordering::Relaxed);
+        Ok(Value::Null)
+    }
+    
+    fn handles(&self) -> std::sync::MutexGuard<'_, HashMap<i64, File>> {
+        // A panic while holding the lock can't leave the map half-updated
+        self.file_handles.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
+    /// Open builtin: open(path, mode)
+    /// Mode is "r", "w" (truncate) or "a" (append); returns an integer handle
+    fn open(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        self.check_fs("open")?;
+        let (path, mode) = match args {
+            [Value::Str(path), Value::Str(mode)] => (path, mode),
+            [_, _] => {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "string path and mode for open".to_string(),
+                    got: format!("{}, {}", args[0].type_name(), args[1].type_name()),
+                });
+            }
+            _ => return Err(RuntimeError::CallError("open requires 2 arguments".to_string())),
+        };
+        let result = match mode.as_str() {
+            "r" => File::open(path),
+            "w" => File::create(path),
+            "a" => OpenOptions::new().append(true).create(true).open(path),
+            _ => {
+                return Err(RuntimeError::CallError(format!(
+                    "Invalid file mode '{}': expected \"r\", \"w\" or \"a\"",
+                    mode
+                )));
+            }
+        };
+        let file = result.map_err(|e| io_error("open", path, e))?;
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.handles().insert(handle, file);
+        Ok(Value::Int(handle))
     }
     
-    /// Lookup a builtin function by name
-    pub fn get_builtin(&self, name: &str) -> Option<BuiltinFn> {
+    /// Read builtin: read(handle)
+    /// Returns the rest of the file as a string
+    fn read(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        let handle = expect_handle("read", args, 1)?;
+        let mut handles = self.handles();
+        let file = handles.get_mut(&handle).ok_or_else(|| invalid_handle(handle))?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .map_err(|e| RuntimeError::IoError(format!("Cannot read file handle {}: {}", handle, e)))?;
+        Ok(Value::Str(contents))
+    }
+    
+    /// Write builtin: write(handle, str)
+    fn write(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        let handle = expect_handle("write", args, 2)?;
+        let Value::Str(text) = &args[1] else {
+            return Err(RuntimeError::TypeMismatch {
+                expected: "string for write".to_string(),
+                got: args[1].type_name().to_string(),
+            });
+        };
+        let mut handles = self.handles();
+        let file = handles.get_mut(&handle).ok_or_else(|| invalid_handle(handle))?;
+        file.write_all(text.as_bytes())
+            .map_err(|e| RuntimeError::IoError(format!("Cannot write file handle {}: {}", handle, e)))?;
+        Ok(Value::Null)
+    }
+    
+    /// Close builtin: close(handle)
+    /// The handle is invalid afterwards
+    fn close(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        let handle = expect_handle("close", args, 1)?;
+        match self.handles().remove(&handle) {
+            Some(_) => Ok(Value::Null),
+            None => Err(invalid_handle(handle)),
+        }
+    }
+    
+    /// Read file builtin: read_file(path)
+    /// Returns the whole file as a string
+    fn read_file(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        self.check_fs("read_file")?;
+        let [path] = expect_paths::<1>("read_file", args)?;
+        std::fs::read_to_string(path)
+            .map(Value::Str)
+            .map_err(|e| io_error("read", path, e))
+    }
+
+    /// Write file builtin: write_file(path, str), or append_file(path, str)
+    /// with `append`. Missing files are created
+    fn write_file(&self, args: &[Value], append: bool) -> Result<Value, RuntimeError> {
+        let name = if append { "append_file" } else { "write_file" };
+        self.check_fs(name)?;
+        let [path, text] = expect_paths::<2>(name, args)?;
+        let result = if append {
+            OpenOptions::new().append(true).create(true).open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+        } else {
+            std::fs::write(path, text)
+        };
+        result.map_err(|e| io_error("write", path, e))?;
+        Ok(Value::Null)
+    }
+
+    /// File exists builtin: file_exists(path)
+    fn file_exists(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        self.check_fs("file_exists")?;
+        let [path] = expect_paths::<1>("file_exists", args)?;
+        Ok(Value::Bool(std::path::Path::new(path).exists()))
+    }
+
+    fn check_fs(&self, name: &str) -> Result<(), RuntimeError> {
+        check_allowed(self.options.allow_fs, name, "filesystem access")
+    }
+
+    /// Env builtin: env(name)
+    /// The environment variable's value, or null when it is unset
+    fn env(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        check_allowed(self.options.allow_env, "env", "environment access")?;
+        let [name] = expect_paths::<1>("env", args)?;
+        Ok(std::env::var(name).map_or(Value::Null, Value::Str))
+    }
+
+    /// Cwd builtin: cwd()
+    /// The current working directory
+    fn cwd(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        check_allowed(self.options.allow_env, "cwd", "environment access")?;
+        if !args.is_empty() {
+            return Err(RuntimeError::CallError("cwd takes no arguments".to_string()));
+        }
+        let dir = std::env::current_dir()
+            .map_err(|error| RuntimeError::IoError(format!("Cannot read the current directory: {}", error)))?;
+        Ok(Value::Str(dir.display().to_string()))
+    }
+
+    /// Exit builtin, when the options allow it; see `builtins::exit`
+    fn exit(&self, args: &[Value]) -> Result<Value, RuntimeError> {
+        check_allowed(self.options.allow_exit, "exit", "exiting")?;
+        exit(args)
+    }
+    
+    /// Lookup a builtin by name, with its spec
+    pub fn get_builtin(&self, name: &str) -> Option<(BuiltinSpec, Builtin)> {
         self.builtins.get(name).copied()
     }
     
     /// Check if a name is a builtin
     pub fn is_builtin(&self, name: &str) -> bool {
-        self.builtins.contains_key(name)
+        self.builtins.contains_key(name) || self.host().contains_key(name)
+    }
+
+    /// The names of every builtin this runtime can call, registered ones
+    /// included, in alphabetical order
+    pub fn builtin_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.builtins.keys().cloned().collect();
+        names.extend(self.host().keys().filter(|name| !self.builtins.contains_key(*name)).cloned());
+        names.sort_unstable();
+        names
+    }
+}
+
+/// The file handle in the first of exactly `count` arguments
+fn expect_handle(name: &str, args: &[Value], count: usize) -> Result<i64, RuntimeError> {
+    if args.len() != count {
+        return Err(RuntimeError::CallError(format!("{} requires {} argument(s)", name, count)));
     }
+    match &args[0] {
+        Value::Int(handle) => Ok(*handle),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: format!("file handle for {}", name),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// Exactly `N` string arguments: a path, then any text
+fn expect_paths<'a, const N: usize>(name: &str, args: &'a [Value]) -> Result<[&'a str; N], RuntimeError> {
+    let args: &[Value; N] = args
+        .try_into()
+        .map_err(|_| RuntimeError::CallError(format!("{} requires {} argument(s)", name, N)))?;
+    let mut strs = [""; N];
+    for (s, arg) in strs.iter_mut().zip(args) {
+        let Value::Str(value) = arg else {
+            return Err(RuntimeError::TypeMismatch {
+                expected: format!("string for {}", name),
+                got: arg.type_name().to_string(),
+            });
+        };
+        *s = value;
+    }
+    Ok(strs)
+}
+
+/// An error for builtin `name` when the runtime's options turn `what` off
+fn check_allowed(allowed: bool, name: &str, what: &str) -> Result<(), RuntimeError> {
+    if allowed {
+        Ok(())
+    } else {
+        Err(RuntimeError::CallError(format!("{}: {} disabled", name, what)))
+    }
+}
+
+fn io_error(action: &str, path: &str, error: std::io::Error) -> RuntimeError {
+    RuntimeError::IoError(format!("Cannot {} '{}': {}", action, path, error))
+}
+
+fn invalid_handle(handle: i64) -> RuntimeError {
+    RuntimeError::CallError(format!("Invalid file handle: {}", handle))
 }
 
 impl Default for Runtime {
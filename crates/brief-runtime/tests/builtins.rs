@@ -1,5 +1,87 @@
 use brief_runtime::*;
-use brief_vm::{Value, RuntimeError, BuiltinRuntime};
+use brief_vm::{Value, RuntimeError, BuiltinRuntime, Instance};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_runtime_implements_exactly_the_registry() {
+    let runtime = Runtime::new();
+    let names = runtime.builtin_names();
+    let mut registered: Vec<&str> = brief_builtins::BUILTINS.iter().map(|spec| spec.name).collect();
+    registered.sort_unstable();
+    assert_eq!(names, registered);
+}
+
+#[test]
+fn test_every_builtin_rejects_wrong_arity_uniformly() {
+    let runtime = Runtime::new();
+    let mut vm = brief_vm::VM::new();
+    for spec in brief_builtins::BUILTINS {
+        let mut wrong_counts = Vec::new();
+        if spec.min_args > 0 {
+            wrong_counts.push(spec.min_args - 1);
+        }
+        if let Some(max) = spec.max_args {
+            wrong_counts.push(max + 1);
+        }
+        for count in wrong_counts {
+            let args = vec![Value::Null; count];
+            let expected = Err(RuntimeError::CallError(spec.arity_message(count)));
+            assert_eq!(runtime.call_builtin_with_vm(spec.name, &args, &mut vm), expected, "{}", spec.name);
+            assert_eq!(runtime.call_builtin(spec.name, &args), expected, "{}", spec.name);
+        }
+    }
+}
+
+#[test]
+fn test_registered_builtins_run_before_the_built_in_table() {
+    let runtime = Runtime::new();
+    runtime.register("double", 1, |args| match args {
+        [Value::Int(n)] => Ok(Value::Int(n * 2)),
+        _ => Err(RuntimeError::CallError("double needs an int".to_string())),
+    });
+    runtime.register("len", 1, |_| Ok(Value::Int(-1)));
+
+    assert!(runtime.is_builtin("double"));
+    assert!(runtime.builtin_names().contains(&"double".to_string()));
+    assert_eq!(runtime.call_builtin("double", &[Value::Int(21)]), Ok(Value::Int(42)));
+    assert_eq!(
+        runtime.call_builtin("double", &[]),
+        Err(RuntimeError::CallError("double takes 1 argument, got 0".to_string()))
+    );
+    assert_eq!(runtime.call_builtin("len", &[Value::Str("abc".to_string())]), Ok(Value::Int(-1)));
+    assert_eq!(runtime.builtin_names().iter().filter(|name| *name == "len").count(), 1);
+}
+
+#[test]
+fn test_set_output_captures_the_print_builtins() {
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let runtime = Runtime::new();
+    let captured = Captured::default();
+    runtime.set_output(Box::new(captured.clone()));
+    runtime.call_builtin("print", &[Value::Int(1), Value::Str("two".to_string())]).unwrap();
+    runtime.call_builtin("prints", &[Value::Bool(true)]).unwrap();
+    runtime.call_builtin("println", &[Value::Null]).unwrap();
+    assert_eq!(String::from_utf8(captured.0.lock().unwrap().clone()).unwrap(), "1 two\ntruenull\n");
+
+    // Without arguments println ends the line and prints adds nothing
+    captured.0.lock().unwrap().clear();
+    runtime.call_builtin("prints", &[]).unwrap();
+    runtime.call_builtin("println", &[]).unwrap();
+    assert_eq!(String::from_utf8(captured.0.lock().unwrap().clone()).unwrap(), "\n");
+}
 
 #[test]
 fn test_print_builtin() {
@@ -10,10 +92,184 @@ fn test_print_builtin() {
 }
 
 #[test]
-fn test_print_requires_argument() {
-    let args = vec![];
-    let result = print(&args);
-    assert!(result.is_err());
+fn test_print_without_arguments() {
+    // Prints an empty line
+    let result = print(&[]);
+    assert_eq!(result, Ok(Value::Null));
+}
+
+#[test]
+fn test_input_rejects_extra_arguments() {
+    let args = vec![Value::Str("a".to_string()), Value::Str("b".to_string())];
+    assert!(input(&args).is_err());
+}
+
+/// Needs a line typed at the terminal: run with `--features interactive-tests -- --nocapture`
+#[cfg(feature = "interactive-tests")]
+#[test]
+fn test_input_reads_line() {
+    let result = input(&[Value::Str("Type anything and press enter: ".to_string())]);
+    match result {
+        Ok(Value::Str(line)) => assert!(!line.ends_with('\n')),
+        other => panic!("Expected a line, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_assert_passes() {
+    assert_eq!(assert(&[Value::Bool(true)]), Ok(Value::Null));
+    assert_eq!(assert(&[Value::Int(1), Value::Str("unused".to_string())]), Ok(Value::Null));
+}
+
+#[test]
+fn test_assert_fails_with_message() {
+    let result = assert(&[Value::Bool(false), Value::Str("x must be positive".to_string())]);
+    let err = result.unwrap_err();
+    assert_eq!(err, RuntimeError::AssertionFailed { message: "x must be positive".to_string() });
+    assert_eq!(err.to_string(), "Assertion failed: x must be positive");
+}
+
+#[test]
+fn test_assert_fails_without_message() {
+    assert!(matches!(assert(&[Value::Null]), Err(RuntimeError::AssertionFailed { .. })));
+}
+
+#[test]
+fn test_check_passes_on_equal_values() {
+    assert_eq!(check(&[Value::Int(3), Value::Int(3)]), Ok(Value::Null));
+    let array = |items: Vec<Value>| Value::Array(Rc::new(RefCell::new(items)));
+    let a = array(vec![Value::Int(1), Value::Str("two".to_string())]);
+    let b = array(vec![Value::Int(1), Value::Str("two".to_string())]);
+    assert_eq!(check(&[a, b]), Ok(Value::Null));
+}
+
+#[test]
+fn test_check_fails_showing_both_values() {
+    let err = check(&[Value::Int(4), Value::Int(3)]).unwrap_err();
+    assert_eq!(err, RuntimeError::AssertionFailed { message: "expected 3, got 4".to_string() });
+
+    let array = |items: Vec<Value>| Value::Array(Rc::new(RefCell::new(items)));
+    let err = check(&[array(vec![Value::Int(1)]), array(vec![Value::Str("1".to_string())])]).unwrap_err();
+    assert_eq!(err.to_string(), "Assertion failed: expected [\"1\"], got [1]");
+    let err = check(&[Value::Str("1".to_string()), Value::Int(1)]).unwrap_err();
+    assert_eq!(err.to_string(), "Assertion failed: expected 1, got \"1\"");
+    assert!(matches!(check(&[Value::Int(1)]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_panic_always_raises() {
+    let err = panic(&[Value::Str("out of cheese".to_string())]).unwrap_err();
+    assert_eq!(err, RuntimeError::Panic { message: "out of cheese".to_string() });
+    assert_eq!(err.to_string(), "Panic: out of cheese");
+    assert_eq!(panic(&[Value::Int(7)]), Err(RuntimeError::Panic { message: "7".to_string() }));
+    assert!(matches!(panic(&[]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_clock_is_non_negative_and_monotonic() {
+    let runtime = Runtime::new();
+    let first = match runtime.call_builtin("clock", &[]) {
+        Ok(Value::Double(t)) => t,
+        other => panic!("Expected a double, got {:?}", other),
+    };
+    let second = match runtime.call_builtin("clock", &[]) {
+        Ok(Value::Double(t)) => t,
+        other => panic!("Expected a double, got {:?}", other),
+    };
+    assert!(first >= 0.0);
+    assert!(second >= first);
+}
+
+#[test]
+fn test_time_ms_is_after_epoch() {
+    match time_ms(&[]) {
+        Ok(Value::Int(ms)) => assert!(ms > 0),
+        other => panic!("Expected an integer, got {:?}", other),
+    }
+    assert!(time_ms(&[Value::Int(1)]).is_err());
+}
+
+#[test]
+fn test_now_and_sleep_ms() {
+    let before = match now(&[]) {
+        Ok(Value::Int(ms)) => ms,
+        other => panic!("Expected an integer, got {:?}", other),
+    };
+    assert_eq!(sleep_ms(&[Value::Int(5)]), Ok(Value::Null));
+    match now(&[]) {
+        Ok(Value::Int(after)) => assert!(after >= before + 5, "{} then {}", before, after),
+        other => panic!("Expected an integer, got {:?}", other),
+    }
+    assert!(now(&[Value::Int(1)]).is_err());
+    assert!(matches!(sleep_ms(&[Value::Int(-1)]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(sleep_ms(&[Value::Double(1.0)]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+/// Seed a runtime and draw `count` values from `name`
+fn draws(seed: i64, name: &str, args: &[Value], count: usize) -> Vec<Value> {
+    let runtime = Runtime::new();
+    runtime.call_builtin("seed_random", &[Value::Int(seed)]).unwrap();
+    (0..count).map(|_| runtime.call_builtin(name, args).unwrap()).collect()
+}
+
+#[test]
+fn test_random_is_deterministic_after_seeding() {
+    assert_eq!(draws(42, "random", &[], 20), draws(42, "random", &[], 20));
+    assert_ne!(draws(42, "random", &[], 20), draws(43, "random", &[], 20));
+    let range = [Value::Int(1), Value::Int(6)];
+    assert_eq!(draws(7, "random_int", &range, 20), draws(7, "random_int", &range, 20));
+
+    // Reseeding restarts the sequence
+    let runtime = Runtime::new();
+    runtime.call_builtin("seed_random", &[Value::Int(-3)]).unwrap();
+    let first = runtime.call_builtin("random", &[]).unwrap();
+    runtime.call_builtin("seed_random", &[Value::Int(-3)]).unwrap();
+    assert_eq!(runtime.call_builtin("random", &[]).unwrap(), first);
+}
+
+#[test]
+fn test_random_stays_in_unit_interval() {
+    for value in draws(1, "random", &[], 10_000) {
+        match value {
+            Value::Double(x) => assert!((0.0..1.0).contains(&x), "{}", x),
+            other => panic!("Expected a double, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_random_int_is_inclusive_and_in_bounds() {
+    let mut seen = [false; 7];
+    for value in draws(2, "random_int", &[Value::Int(-3), Value::Int(3)], 10_000) {
+        match value {
+            Value::Int(n) => {
+                assert!((-3..=3).contains(&n), "{}", n);
+                seen[(n + 3) as usize] = true;
+            }
+            other => panic!("Expected an int, got {:?}", other),
+        }
+    }
+    assert!(seen.iter().all(|&hit| hit), "every value should come up: {:?}", seen);
+
+    assert_eq!(draws(3, "random_int", &[Value::Int(5), Value::Int(5)], 3), vec![Value::Int(5); 3]);
+    // The whole i64 range doesn't overflow
+    draws(4, "random_int", &[Value::Int(i64::MIN), Value::Int(i64::MAX)], 100);
+}
+
+#[test]
+fn test_random_builtins_validate_arguments() {
+    let runtime = Runtime::new();
+    assert!(matches!(
+        runtime.call_builtin("random_int", &[Value::Int(3), Value::Int(1)]),
+        Err(RuntimeError::CallError(_))
+    ));
+    assert!(matches!(
+        runtime.call_builtin("random_int", &[Value::Int(1), Value::Double(2.0)]),
+        Err(RuntimeError::TypeMismatch { .. })
+    ));
+    assert!(runtime.call_builtin("random_int", &[Value::Int(1)]).is_err());
+    assert!(runtime.call_builtin("random", &[Value::Int(1)]).is_err());
+    assert!(runtime.call_builtin("seed_random", &[Value::Str("x".to_string())]).is_err());
 }
 
 #[test]
@@ -52,6 +308,7 @@ fn test_int_cast_from_int() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_int_cast_from_double() {
     let args = vec![Value::Double(3.14)];
@@ -123,6 +380,7 @@ fn test_dub_cast_from_int() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_dub_cast_from_double() {
     let args = vec![Value::Double(3.14)];
@@ -147,6 +405,7 @@ fn test_dub_cast_from_bool() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_dub_cast_from_string() {
     let args = vec![Value::Str("3.14".to_string())];
@@ -159,6 +418,29 @@ fn test_dub_cast_from_string() {
     }
 }
 
+#[test]
+fn test_len_counts_characters_not_bytes() {
+    assert_eq!(len(&[Value::Str("héllo".to_string())]), Ok(Value::Int(5)));
+}
+
+#[test]
+fn test_len_array_and_map() {
+    assert_eq!(len(&[Value::array(vec![Value::Int(1), Value::Null])]), Ok(Value::Int(2)));
+    assert_eq!(len(&[Value::array(vec![])]), Ok(Value::Int(0)));
+    let map = Value::map(vec![(Value::Str("a".to_string()), Value::Int(1))]);
+    assert_eq!(len(&[map]), Ok(Value::Int(1)));
+}
+
+#[test]
+fn test_len_errors() {
+    assert_eq!(
+        len(&[Value::Double(1.0)]),
+        Err(RuntimeError::TypeMismatch { expected: "str, array or map".to_string(), got: "dub".to_string() })
+    );
+    assert!(matches!(len(&[]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(len(&[Value::array(vec![]), Value::Int(1)]), Err(RuntimeError::CallError(_))));
+}
+
 #[test]
 fn test_str_cast_from_int() {
     let args = vec![Value::Int(42)];
@@ -171,6 +453,7 @@ fn test_str_cast_from_int() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_str_cast_from_double() {
     let args = vec![Value::Double(3.14)];
@@ -207,6 +490,343 @@ fn test_str_cast_from_string() {
     }
 }
 
+#[test]
+fn test_parse_int() {
+    let args = vec![Value::Str("-42".to_string())];
+    assert_eq!(parse_int(&args), Ok(Value::Int(-42)));
+}
+
+#[test]
+fn test_parse_int_invalid() {
+    let args = vec![Value::Str("4.2".to_string())];
+    assert!(matches!(parse_int(&args), Err(RuntimeError::ParseError(_))));
+}
+
+#[test]
+fn test_parse_int_rejects_non_string() {
+    let args = vec![Value::Double(4.0)];
+    assert!(matches!(parse_int(&args), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_parse_double() {
+    let args = vec![Value::Str("2.5".to_string())];
+    assert_eq!(parse_double(&args), Ok(Value::Double(2.5)));
+    
+    let args = vec![Value::Str("abc".to_string())];
+    assert!(matches!(parse_double(&args), Err(RuntimeError::ParseError(_))));
+    
+    let args = vec![Value::Int(2)];
+    assert!(matches!(parse_double(&args), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+fn point() -> Value {
+    let mut instance = Instance::new("Point".to_string());
+    instance.set("x", Value::Int(1));
+    instance.set("name", Value::Str("p".to_string()));
+    Value::Object(Rc::new(RefCell::new(instance)))
+}
+
+#[test]
+fn test_type_of() {
+    let cases = vec![
+        (Value::Int(1), "int"),
+        (Value::Double(1.5), "dub"),
+        (Value::Bool(true), "bool"),
+        (Value::Str("s".to_string()), "str"),
+        (Value::Null, "null"),
+        (Value::array(vec![]), "array"),
+        (Value::map(vec![]), "map"),
+        (point(), "object:Point"),
+    ];
+    for (value, expected) in cases {
+        assert_eq!(type_of(&[value]), Ok(Value::Str(expected.to_string())));
+    }
+    assert!(matches!(type_of(&[]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_str_cast_matches_display() {
+    let cases = vec![
+        (Value::Null, "null"),
+        (Value::Double(2.0), "2.0"),
+        (Value::array(vec![Value::Int(1), Value::Str("a".to_string())]), "[1, \"a\"]"),
+        (Value::map(vec![(Value::Str("k".to_string()), Value::Bool(false))]), "{\"k\": false}"),
+        (point(), "Point{x: 1, name: \"p\"}"),
+    ];
+    for (value, expected) in cases {
+        assert_eq!(value.to_string(), expected);
+        assert_eq!(str_cast(&[value]), Ok(Value::Str(expected.to_string())));
+    }
+    assert!(matches!(str_cast(&[Value::Int(1), Value::Int(2)]), Err(RuntimeError::CallError(_))));
+}
+
+fn s(text: &str) -> Value {
+    Value::Str(text.to_string())
+}
+
+#[test]
+fn test_upper_lower() {
+    assert_eq!(upper(&[s("héllo World")]), Ok(s("HÉLLO WORLD")));
+    assert_eq!(lower(&[s("ÄBC def")]), Ok(s("äbc def")));
+    assert_eq!(upper(&[s("")]), Ok(s("")));
+    assert_eq!(lower(&[s("123 !")]), Ok(s("123 !")));
+    assert!(matches!(upper(&[Value::Int(1)]), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(lower(&[]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_trim() {
+    assert_eq!(trim(&[s("  \tpadded \n")]), Ok(s("padded")));
+    assert_eq!(trim(&[s("   ")]), Ok(s("")));
+    assert_eq!(trim(&[s("")]), Ok(s("")));
+    assert_eq!(trim(&[s(" ünï ")]), Ok(s("ünï")));
+}
+
+#[test]
+fn test_split() {
+    let pieces = |items: &[&str]| Value::array(items.iter().map(|item| s(item)).collect());
+    assert_eq!(split(&[s("a,b,,c"), s(",")]), Ok(pieces(&["a", "b", "", "c"])));
+    assert_eq!(split(&[s("abc"), s(";")]), Ok(pieces(&["abc"])));
+    assert_eq!(split(&[s(""), s(",")]), Ok(pieces(&[""])));
+    assert_eq!(split(&[s("日本語"), s("")]), Ok(pieces(&["日", "本", "語"])));
+    assert_eq!(split(&[s("x→y→z"), s("→")]), Ok(pieces(&["x", "y", "z"])));
+    assert!(matches!(split(&[s("a")]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(split(&[s("a"), Value::Null]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_replace() {
+    assert_eq!(replace(&[s("a-b-c"), s("-"), s("+")]), Ok(s("a+b+c")));
+    assert_eq!(replace(&[s("abc"), s("x"), s("y")]), Ok(s("abc")));
+    assert_eq!(replace(&[s(""), s("a"), s("b")]), Ok(s("")));
+    assert_eq!(replace(&[s("abc"), s(""), s("-")]), Ok(s("abc")));
+    assert_eq!(replace(&[s("café café"), s("é"), s("e")]), Ok(s("cafe cafe")));
+}
+
+#[test]
+fn test_contains_and_starts_with_strings() {
+    assert_eq!(contains(&[s("naïve"), s("ïv")]), Ok(Value::Bool(true)));
+    assert_eq!(contains(&[s("abc"), s("d")]), Ok(Value::Bool(false)));
+    assert_eq!(contains(&[s(""), s("")]), Ok(Value::Bool(true)));
+    assert_eq!(starts_with(&[s("über"), s("üb")]), Ok(Value::Bool(true)));
+    assert_eq!(starts_with(&[s("abc"), s("bc")]), Ok(Value::Bool(false)));
+    assert_eq!(starts_with(&[s("abc"), s("")]), Ok(Value::Bool(true)));
+    assert_eq!(starts_with(&[s(""), s("a")]), Ok(Value::Bool(false)));
+}
+
+#[test]
+fn test_index_of_counts_characters() {
+    assert_eq!(index_of(&[s("hello"), s("l")]), Ok(Value::Int(2)));
+    assert_eq!(index_of(&[s("héllo"), s("l")]), Ok(Value::Int(2)));
+    assert_eq!(index_of(&[s("hello"), s("z")]), Ok(Value::Int(-1)));
+    assert_eq!(index_of(&[s(""), s("a")]), Ok(Value::Int(-1)));
+    assert_eq!(index_of(&[s("abc"), s("")]), Ok(Value::Int(0)));
+}
+
+#[test]
+fn test_substring_counts_characters() {
+    assert_eq!(substring(&[s("héllo"), Value::Int(1), Value::Int(3)]), Ok(s("él")));
+    assert_eq!(substring(&[s("héllo"), Value::Int(2)]), Ok(s("llo")));
+    // Indices are clamped to the string
+    assert_eq!(substring(&[s("abc"), Value::Int(-2), Value::Int(10)]), Ok(s("abc")));
+    assert_eq!(substring(&[s("abc"), Value::Int(2), Value::Int(1)]), Ok(s("")));
+    assert_eq!(substring(&[s(""), Value::Int(0), Value::Int(1)]), Ok(s("")));
+    assert!(matches!(substring(&[s("abc"), Value::Double(1.0)]), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(substring(&[s("abc")]), Err(RuntimeError::CallError(_))));
+}
+
+fn ints(items: &[i64]) -> Value {
+    Value::array(items.iter().map(|&n| Value::Int(n)).collect())
+}
+
+#[test]
+fn test_push_mutates_in_place() {
+    let array = ints(&[1]);
+    assert_eq!(push(&[array.clone(), Value::Int(2)]), Ok(Value::Null));
+    assert_eq!(push(&[array.clone(), s("x")]), Ok(Value::Null));
+    assert_eq!(array, Value::array(vec![Value::Int(1), Value::Int(2), s("x")]));
+    assert!(matches!(push(&[s("abc"), Value::Int(1)]), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(push(&[array]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_pop_returns_last() {
+    let array = ints(&[1, 2]);
+    assert_eq!(pop(std::slice::from_ref(&array)), Ok(Value::Int(2)));
+    assert_eq!(pop(std::slice::from_ref(&array)), Ok(Value::Int(1)));
+    assert!(matches!(pop(&[array]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_insert_shifts_elements() {
+    let array = ints(&[1, 3]);
+    assert_eq!(insert(&[array.clone(), Value::Int(1), Value::Int(2)]), Ok(Value::Null));
+    assert_eq!(insert(&[array.clone(), Value::Int(3), Value::Int(4)]), Ok(Value::Null));
+    assert_eq!(insert(&[array.clone(), Value::Int(0), Value::Int(0)]), Ok(Value::Null));
+    assert_eq!(array, ints(&[0, 1, 2, 3, 4]));
+    assert!(matches!(insert(&[array.clone(), Value::Int(6), Value::Null]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(insert(&[array, Value::Int(-1), Value::Null]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_remove_returns_element() {
+    let array = ints(&[1, 2, 3]);
+    assert_eq!(remove(&[array.clone(), Value::Int(1)]), Ok(Value::Int(2)));
+    assert_eq!(array, ints(&[1, 3]));
+    assert!(matches!(remove(&[array.clone(), Value::Int(2)]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(remove(&[array, s("0")]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_reverse_in_place() {
+    let array = ints(&[1, 2, 3]);
+    assert_eq!(reverse(std::slice::from_ref(&array)), Ok(Value::Null));
+    assert_eq!(array, ints(&[3, 2, 1]));
+    let empty = ints(&[]);
+    assert_eq!(reverse(std::slice::from_ref(&empty)), Ok(Value::Null));
+    assert_eq!(empty, ints(&[]));
+}
+
+#[test]
+fn test_join_formats_elements() {
+    let mixed = Value::array(vec![Value::Int(1), Value::Double(2.5), s("three"), Value::Null]);
+    assert_eq!(join(&[mixed, s(", ")]), Ok(s("1, 2.5, three, null")));
+    assert_eq!(join(&[ints(&[]), s("-")]), Ok(s("")));
+    assert_eq!(join(&[ints(&[7]), s("-")]), Ok(s("7")));
+    assert!(matches!(join(&[ints(&[1]), Value::Int(0)]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_index_of_array() {
+    let array = Value::array(vec![s("a"), Value::Int(2), ints(&[3])]);
+    assert_eq!(index_of(&[array.clone(), Value::Int(2)]), Ok(Value::Int(1)));
+    assert_eq!(index_of(&[array.clone(), ints(&[3])]), Ok(Value::Int(2)));
+    assert_eq!(index_of(&[array, s("b")]), Ok(Value::Int(-1)));
+}
+
+fn scores() -> Value {
+    map_of(&[s("ann"), Value::Int(3), s("bob"), Value::Int(5)]).unwrap()
+}
+
+#[test]
+fn test_map_of_keeps_first_position_of_repeated_key() {
+    let map = map_of(&[s("a"), Value::Int(1), s("b"), Value::Int(2), s("a"), Value::Int(3)]).unwrap();
+    assert_eq!(map, Value::map(vec![(s("a"), Value::Int(3)), (s("b"), Value::Int(2))]));
+    assert_eq!(map_of(&[]), Ok(Value::map(Vec::new())));
+    assert!(matches!(map_of(&[s("a")]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_get_missing_key() {
+    let map = scores();
+    assert_eq!(get(&[map.clone(), s("bob")]), Ok(Value::Int(5)));
+    assert_eq!(get(&[map.clone(), s("cat")]), Ok(Value::Null));
+    assert_eq!(get(&[map.clone(), s("cat"), Value::Int(0)]), Ok(Value::Int(0)));
+    assert!(matches!(get(&[ints(&[1]), Value::Int(0)]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_put_overwrites_in_place() {
+    let map = scores();
+    assert_eq!(put(&[map.clone(), s("ann"), Value::Int(4)]), Ok(Value::Null));
+    assert_eq!(put(&[map.clone(), s("cat"), Value::Int(1)]), Ok(Value::Null));
+    assert_eq!(map.to_string(), "{\"ann\": 4, \"bob\": 5, \"cat\": 1}");
+}
+
+#[test]
+fn test_has() {
+    let map = map_of(&[Value::Int(1), Value::Null]).unwrap();
+    assert_eq!(has(&[map.clone(), Value::Int(1)]), Ok(Value::Bool(true)));
+    assert_eq!(has(&[map.clone(), s("1")]), Ok(Value::Bool(false)));
+    assert!(matches!(has(&[map]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_keys_and_values_in_insertion_order() {
+    let map = map_of(&[s("z"), Value::Int(1), s("a"), Value::Int(2)]).unwrap();
+    assert_eq!(keys(std::slice::from_ref(&map)), Ok(Value::array(vec![s("z"), s("a")])));
+    assert_eq!(values(std::slice::from_ref(&map)), Ok(ints(&[1, 2])));
+    assert_eq!(keys(&[map_of(&[]).unwrap()]), Ok(ints(&[])));
+}
+
+#[test]
+fn test_remove_map_entry() {
+    let map = scores();
+    assert_eq!(remove(&[map.clone(), s("ann")]), Ok(Value::Int(3)));
+    assert_eq!(remove(&[map.clone(), s("ann")]), Ok(Value::Null));
+    assert_eq!(map, Value::map(vec![(s("bob"), Value::Int(5))]));
+}
+
+#[test]
+fn test_merge_second_map_wins() {
+    let a = scores();
+    let b = map_of(&[s("cat"), Value::Int(1), s("ann"), Value::Int(9)]).unwrap();
+    let merged = merge(&[a.clone(), b]).unwrap();
+    assert_eq!(merged.to_string(), "{\"ann\": 9, \"bob\": 5, \"cat\": 1}");
+    // The inputs are left alone
+    assert_eq!(a, scores());
+    assert!(matches!(merge(&[a, ints(&[])]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+fn assert_double(result: Result<Value, RuntimeError>, expected: f64) {
+    match result {
+        Ok(Value::Double(d)) => assert!((d - expected).abs() <= f64::EPSILON, "expected {}, got {}", expected, d),
+        other => panic!("Expected Double({}), got {:?}", expected, other),
+    }
+}
+
+#[test]
+fn test_trig_builtins() {
+    assert_double(sin(&[Value::Int(0)]), 0.0);
+    assert_double(sin(&[Value::Double(std::f64::consts::FRAC_PI_2)]), 1.0);
+    assert_double(cos(&[Value::Int(0)]), 1.0);
+    assert_double(cos(&[Value::Double(std::f64::consts::PI)]), -1.0);
+}
+
+#[test]
+fn test_sqrt() {
+    assert_double(sqrt(&[Value::Int(16)]), 4.0);
+    assert_double(sqrt(&[Value::Double(2.25)]), 1.5);
+    match sqrt(&[Value::Double(-1.0)]) {
+        Ok(Value::Double(d)) => assert!(d.is_nan()),
+        other => panic!("Expected NaN, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_abs() {
+    assert_eq!(abs(&[Value::Int(-5)]), Ok(Value::Int(5)));
+    assert_double(abs(&[Value::Double(-2.5)]), 2.5);
+    assert!(abs(&[Value::Int(i64::MIN)]).is_err());
+}
+
+#[test]
+fn test_rounding_builtins() {
+    assert_double(floor(&[Value::Double(2.7)]), 2.0);
+    assert_double(floor(&[Value::Double(-2.1)]), -3.0);
+    assert_double(ceil(&[Value::Double(2.1)]), 3.0);
+    assert_double(ceil(&[Value::Int(2)]), 2.0);
+    assert_double(round(&[Value::Double(2.5)]), 3.0);
+    assert_double(round(&[Value::Double(-2.4)]), -2.0);
+}
+
+#[test]
+fn test_log() {
+    assert_double(log(&[Value::Double(std::f64::consts::E)]), 1.0);
+    assert_double(log(&[Value::Int(1)]), 0.0);
+    assert_double(log(&[Value::Int(8), Value::Int(2)]), 3.0);
+    assert_double(log(&[Value::Int(100), Value::Int(10)]), 2.0);
+    assert!(log(&[]).is_err());
+}
+
+#[test]
+fn test_math_rejects_non_numbers() {
+    let result = sqrt(&[Value::Str("4".to_string())]);
+    assert!(matches!(result, Err(RuntimeError::TypeMismatch { .. })));
+    assert!(sin(&[]).is_err());
+}
+
 #[test]
 fn test_rt_concat2() {
     let args = vec![
@@ -279,10 +899,23 @@ fn test_runtime_registration() {
     
     // Check that builtins are registered
     assert!(runtime.is_builtin("print"));
+    assert!(runtime.is_builtin("input"));
     assert!(runtime.is_builtin("len"));
     assert!(runtime.is_builtin("int"));
     assert!(runtime.is_builtin("dub"));
     assert!(runtime.is_builtin("str"));
+    assert!(runtime.is_builtin("parseInt"));
+    assert!(runtime.is_builtin("parseDouble"));
+    assert!(runtime.is_builtin("type"));
+    for name in ["sin", "cos", "sqrt", "abs", "floor", "ceil", "round", "log"] {
+        assert!(runtime.is_builtin(name), "Builtin '{}' should be registered", name);
+    }
+    for name in ["push", "pop", "insert", "remove", "sort", "reverse", "join", "index_of"] {
+        assert!(runtime.is_builtin(name), "Builtin '{}' should be registered", name);
+    }
+    for name in ["map_of", "get", "put", "has", "keys", "values", "merge"] {
+        assert!(runtime.is_builtin(name), "Builtin '{}' should be registered", name);
+    }
     assert!(runtime.is_builtin("rt_concat2"));
     assert!(runtime.is_builtin("rt_concat3"));
     assert!(runtime.is_builtin("rt_concat4"));
@@ -318,3 +951,163 @@ fn test_runtime_call_unknown_builtin() {
     }
 }
 
+#[test]
+fn test_range_counts_by_step() {
+    assert_eq!(range(&[Value::Int(3)]), Ok(ints(&[0, 1, 2])));
+    assert_eq!(range(&[Value::Int(2), Value::Int(5)]), Ok(ints(&[2, 3, 4])));
+    assert_eq!(range(&[Value::Int(10), Value::Int(0), Value::Int(-3)]), Ok(ints(&[10, 7, 4, 1])));
+    assert_eq!(range(&[Value::Int(5), Value::Int(1)]), Ok(ints(&[])));
+    assert_eq!(range(&[Value::Int(1), Value::Int(5), Value::Int(-1)]), Ok(ints(&[])));
+}
+
+#[test]
+fn test_range_rejects_bad_bounds() {
+    let zero_step = range(&[Value::Int(0), Value::Int(3), Value::Int(0)]);
+    assert_eq!(zero_step, Err(RuntimeError::CallError("range step cannot be zero".to_string())));
+    assert!(matches!(range(&[]), Err(RuntimeError::CallError(_))));
+    assert!(matches!(range(&[Value::Double(1.0)]), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+
+fn str_value(s: &str) -> Value {
+    Value::Str(s.to_string())
+}
+
+fn open_file(runtime: &Runtime, path: &std::path::Path, mode: &str) -> Value {
+    let path = str_value(path.to_str().unwrap());
+    runtime.call_builtin("open", &[path, str_value(mode)]).expect("open should succeed")
+}
+
+#[test]
+fn test_file_write_then_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.txt");
+    let runtime = Runtime::new();
+
+    let handle = open_file(&runtime, &path, "w");
+    assert_eq!(runtime.call_builtin("write", &[handle.clone(), str_value("hello")]), Ok(Value::Null));
+    assert_eq!(runtime.call_builtin("close", &[handle]), Ok(Value::Null));
+
+    let handle = open_file(&runtime, &path, "r");
+    assert_eq!(runtime.call_builtin("read", &[handle]), Ok(str_value("hello")));
+}
+
+#[test]
+fn test_file_append() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut file, b"one\n").unwrap();
+    let runtime = Runtime::new();
+
+    let handle = open_file(&runtime, file.path(), "a");
+    runtime.call_builtin("write", &[handle.clone(), str_value("two\n")]).unwrap();
+    runtime.call_builtin("close", &[handle]).unwrap();
+
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "one\ntwo\n");
+}
+
+#[test]
+fn test_file_close_invalidates_handle() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let runtime = Runtime::new();
+
+    let handle = open_file(&runtime, file.path(), "r");
+    runtime.call_builtin("close", std::slice::from_ref(&handle)).unwrap();
+    assert!(runtime.call_builtin("read", std::slice::from_ref(&handle)).is_err());
+    assert!(runtime.call_builtin("close", &[handle]).is_err());
+}
+
+#[test]
+fn test_file_open_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Runtime::new();
+    let missing = str_value(dir.path().join("missing.txt").to_str().unwrap());
+
+    assert!(runtime.call_builtin("open", &[missing.clone(), str_value("r")]).is_err());
+    assert!(runtime.call_builtin("open", &[missing, str_value("x")]).is_err());
+}
+
+#[test]
+fn test_read_write_append_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = str_value(dir.path().join("notes.txt").to_str().unwrap());
+    let runtime = Runtime::new();
+
+    assert_eq!(runtime.call_builtin("file_exists", std::slice::from_ref(&path)), Ok(Value::Bool(false)));
+    assert_eq!(runtime.call_builtin("write_file", &[path.clone(), str_value("one\n")]), Ok(Value::Null));
+    assert_eq!(runtime.call_builtin("append_file", &[path.clone(), str_value("two\n")]), Ok(Value::Null));
+    assert_eq!(runtime.call_builtin("read_file", std::slice::from_ref(&path)), Ok(str_value("one\ntwo\n")));
+    assert_eq!(runtime.call_builtin("file_exists", std::slice::from_ref(&path)), Ok(Value::Bool(true)));
+
+    // write_file replaces, append_file creates missing files
+    runtime.call_builtin("write_file", &[path.clone(), str_value("three")]).unwrap();
+    assert_eq!(runtime.call_builtin("read_file", &[path]), Ok(str_value("three")));
+    let fresh = str_value(dir.path().join("fresh.txt").to_str().unwrap());
+    runtime.call_builtin("append_file", &[fresh.clone(), str_value("x")]).unwrap();
+    assert_eq!(runtime.call_builtin("read_file", &[fresh]), Ok(str_value("x")));
+}
+
+#[test]
+fn test_file_builtin_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.txt");
+    let runtime = Runtime::new();
+
+    match runtime.call_builtin("read_file", &[str_value(missing.to_str().unwrap())]) {
+        Err(RuntimeError::IoError(message)) => assert!(message.contains("missing.txt"), "{}", message),
+        other => panic!("expected IoError, got {:?}", other),
+    }
+    let nested = dir.path().join("no_dir").join("out.txt");
+    let result = runtime.call_builtin("write_file", &[str_value(nested.to_str().unwrap()), str_value("x")]);
+    assert!(matches!(result, Err(RuntimeError::IoError(_))), "{:?}", result);
+    assert!(matches!(runtime.call_builtin("read_file", &[Value::Int(1)]), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(runtime.call_builtin("write_file", &[str_value("a")]), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_file_builtins_respect_allow_fs() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let path = str_value(file.path().to_str().unwrap());
+    let runtime = Runtime::with_options(RuntimeOptions { allow_fs: false, ..RuntimeOptions::default() });
+
+    for (name, args) in [
+        ("read_file", vec![path.clone()]),
+        ("write_file", vec![path.clone(), str_value("x")]),
+        ("append_file", vec![path.clone(), str_value("x")]),
+        ("file_exists", vec![path.clone()]),
+        ("open", vec![path.clone(), str_value("r")]),
+    ] {
+        match runtime.call_builtin(name, &args) {
+            Err(RuntimeError::CallError(message)) => assert!(message.contains("filesystem access disabled"), "{}", message),
+            other => panic!("{} should be disabled, got {:?}", name, other),
+        }
+    }
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "");
+}
+
+#[test]
+fn test_env_and_cwd() {
+    let runtime = Runtime::new();
+    let path = std::env::var("PATH").unwrap();
+    assert_eq!(runtime.call_builtin("env", &[str_value("PATH")]), Ok(str_value(&path)));
+    assert_eq!(runtime.call_builtin("env", &[str_value("BRIEF_SURELY_UNSET_VARIABLE")]), Ok(Value::Null));
+    assert!(matches!(runtime.call_builtin("env", &[Value::Int(1)]), Err(RuntimeError::TypeMismatch { .. })));
+
+    let dir = std::env::current_dir().unwrap().display().to_string();
+    assert_eq!(runtime.call_builtin("cwd", &[]), Ok(str_value(&dir)));
+}
+
+#[test]
+fn test_process_builtins_respect_options() {
+    let runtime = Runtime::with_options(RuntimeOptions { allow_env: false, allow_exit: false, ..RuntimeOptions::default() });
+    for (name, args) in [("env", vec![str_value("PATH")]), ("cwd", vec![])] {
+        match runtime.call_builtin(name, &args) {
+            Err(RuntimeError::CallError(message)) => assert!(message.contains("environment access disabled"), "{}", message),
+            other => panic!("{} should be disabled, got {:?}", name, other),
+        }
+    }
+    match runtime.call_builtin("exit", &[Value::Int(3)]) {
+        Err(RuntimeError::CallError(message)) => assert_eq!(message, "exit: exiting disabled"),
+        other => panic!("exit should be disabled, got {:?}", other),
+    }
+    assert_eq!(Runtime::new().call_builtin("exit", &[Value::Int(3)]), Err(RuntimeError::Exit(3)));
+}
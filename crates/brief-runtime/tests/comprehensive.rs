@@ -99,6 +99,7 @@ fn test_int_cast_negative() {
     }
 }
 
+#[allow(clippy::approx_constant)]
 #[test]
 fn test_dub_cast_negative() {
     let args = vec![Value::Double(-3.14)];
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use brief_runtime::format;
+use brief_runtime::formatter::format_template;
+use brief_vm::{RuntimeError, Value};
+
+fn fmt(template: &str, args: &[Value]) -> String {
+    format_template(template, args).unwrap()
+}
+
+fn fmt_error(template: &str, args: &[Value]) -> String {
+    match format_template(template, args) {
+        Err(RuntimeError::CallError(message)) => message,
+        other => panic!("expected a format error for {:?}, got {:?}", template, other),
+    }
+}
+
+fn s(text: &str) -> Value {
+    Value::Str(text.to_string())
+}
+
+#[test]
+fn test_positional_placeholders_take_arguments_in_order() {
+    assert_eq!(fmt("{} + {} = {}", &[Value::Int(1), Value::Int(2), Value::Int(3)]), "1 + 2 = 3");
+    assert_eq!(fmt("no placeholders", &[]), "no placeholders");
+    assert_eq!(fmt("{}", &[s("héllo")]), "héllo");
+}
+
+#[test]
+fn test_indexed_placeholders_may_repeat_and_reorder() {
+    assert_eq!(fmt("{1} {0} {1}", &[s("a"), s("b")]), "b a b");
+    assert_eq!(fmt("{} {0}", &[s("x")]), "x x");
+}
+
+#[test]
+fn test_values_are_written_as_print_shows_them() {
+    let items = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1), s("two")])));
+    assert_eq!(
+        fmt("{} {} {} {} {}", &[Value::Double(2.0), Value::Bool(true), Value::Null, items, Value::Double(0.1 + 0.2)]),
+        "2.0 true null [1, \"two\"] 0.3"
+    );
+}
+
+#[test]
+fn test_doubled_braces_are_literal() {
+    assert_eq!(fmt("{{}} {{{}}}", &[Value::Int(5)]), "{} {5}");
+}
+
+#[test]
+fn test_precision_sets_decimals_of_numbers() {
+    assert_eq!(fmt("{:.2}", &[Value::Double(12.3456)]), "12.35");
+    assert_eq!(fmt("{:.0}", &[Value::Double(2.7)]), "3");
+    assert_eq!(fmt("{:.3}", &[Value::Int(7)]), "7.000");
+    assert_eq!(fmt("{:.1}", &[Value::Double(-0.25)]), "-0.2");
+}
+
+#[test]
+fn test_precision_truncates_strings() {
+    assert_eq!(fmt("{:.3}", &[s("abcdef")]), "abc");
+    assert_eq!(fmt("{:.10}", &[s("ab")]), "ab");
+}
+
+#[test]
+fn test_width_pads_numbers_right_and_others_left() {
+    assert_eq!(fmt("[{:5}]", &[Value::Int(42)]), "[   42]");
+    assert_eq!(fmt("[{:5}]", &[s("ab")]), "[ab   ]");
+    assert_eq!(fmt("[{:2}]", &[s("longer")]), "[longer]");
+    assert_eq!(fmt("[{:4}]", &[s("né")]), "[né  ]");
+}
+
+#[test]
+fn test_alignment_and_fill() {
+    assert_eq!(fmt("[{:>8}]", &[s("right")]), "[   right]");
+    assert_eq!(fmt("[{:<6}]", &[Value::Int(1)]), "[1     ]");
+    assert_eq!(fmt("[{:^7}]", &[s("mid")]), "[  mid  ]");
+    assert_eq!(fmt("[{:^6}]", &[s("mid")]), "[ mid  ]");
+    assert_eq!(fmt("[{:*>6}]", &[Value::Int(12)]), "[****12]");
+    assert_eq!(fmt("[{:0>5}]", &[Value::Int(7)]), "[00007]");
+    assert_eq!(fmt("[{:->4}]", &[s("x")]), "[---x]");
+}
+
+#[test]
+fn test_width_and_precision_combine() {
+    assert_eq!(fmt("[{:>8.2}]", &[Value::Double(12.3456)]), "[   12.35]");
+    assert_eq!(fmt("[{:8.1}]", &[Value::Int(5)]), "[     5.0]");
+    assert_eq!(fmt("[{1:<6.2}|{0}]", &[s("end"), Value::Double(1.0)]), "[1.00  |end]");
+}
+
+#[test]
+fn test_malformed_templates_are_errors() {
+    assert_eq!(fmt_error("{", &[]), "format: unclosed '{' (write '{{' for a literal brace)");
+    assert_eq!(fmt_error("a } b", &[]), "format: unmatched '}' (write '}}' for a literal brace)");
+    assert_eq!(fmt_error("{x}", &[Value::Int(1)]), "format: invalid placeholder '{x}'");
+    assert_eq!(fmt_error("{:abc}", &[Value::Int(1)]), "format: invalid format spec '{:abc}'");
+    assert_eq!(fmt_error("{:.}", &[Value::Int(1)]), "format: invalid format spec '{:.}'");
+    assert_eq!(fmt_error("{:5.x}", &[Value::Int(1)]), "format: invalid format spec '{:5.x}'");
+    assert_eq!(fmt_error("{:<5>}", &[Value::Int(1)]), "format: invalid format spec '{:<5>}'");
+}
+
+#[test]
+fn test_oversized_width_and_precision_are_errors() {
+    assert_eq!(fmt_error("{:.99999999999}", &[Value::Double(1.5)]), "format: invalid format spec '{:.99999999999}'");
+    assert_eq!(fmt_error("{:>99999999999}", &[Value::Int(1)]), "format: invalid format spec '{:>99999999999}'");
+    assert_eq!(fmt_error("{:.65536}", &[s("abc")]), "format: invalid format spec '{:.65536}'");
+    assert_eq!(fmt("{:65535}", &[Value::Int(1)]).len(), 65535);
+}
+
+#[test]
+fn test_argument_count_must_match_the_template() {
+    assert_eq!(fmt_error("{} {}", &[Value::Int(1)]), "format: placeholder 1 has no argument (1 given)");
+    assert_eq!(fmt_error("{3}", &[Value::Int(1)]), "format: placeholder 3 has no argument (1 given)");
+    assert_eq!(fmt_error("{}", &[Value::Int(1), Value::Int(2)]), "format: 1 of 2 arguments not used by the template");
+}
+
+#[test]
+fn test_precision_rejects_other_values() {
+    assert_eq!(fmt_error("{:.2}", &[Value::Bool(true)]), "format: a precision needs an int, dub or str, got bool");
+}
+
+#[test]
+fn test_format_builtin_needs_a_string_template() {
+    assert_eq!(format(&[s("{}!"), Value::Int(1)]), Ok(s("1!")));
+    assert!(matches!(format(&[Value::Int(1)]), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(format(&[]), Err(RuntimeError::CallError(_))));
+}
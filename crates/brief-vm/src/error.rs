@@ -1,3 +1,5 @@
+use brief_diagnostic::Span;
+
 /// Runtime error
 #[derive(Debug, Clone, PartialEq)]
 pub enum RuntimeError {
@@ -9,10 +11,44 @@ pub enum RuntimeError {
     DivisionByZero,
     UnknownOpcode,
     UndefinedVariable(String),
+    UndefinedField { class: String, field: String },
+    UndefinedMethod { class: String, method: String },
     CallError(String),
+    ParseError(String),
+    ArityMismatch { name: String, min: usize, max: Option<usize>, got: usize },
+    /// Program requested termination via `exit(code)`
+    Exit(i32),
+    /// `assert(cond, msg)` was called with a falsy condition
+    AssertionFailed { message: String },
+    /// `panic(msg)` was called
+    Panic { message: String },
+    /// A file operation failed; the message names the path and the OS error
+    IoError(String),
+    /// A chunk uses a register beyond its `max_regs`
+    MalformedChunk { chunk: String, message: String },
+    /// Another error, tagged with the source of the instruction that raised it
+    Located { error: Box<RuntimeError>, span: Span },
     // Add more error types as needed
 }
 
+impl RuntimeError {
+    /// Where the error was raised, if the failing chunk had a line table
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::Located { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// The error itself, without its location
+    pub fn without_location(&self) -> &RuntimeError {
+        match self {
+            RuntimeError::Located { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
 impl std::fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,7 +62,33 @@ impl std::fmt::Display for RuntimeError {
             RuntimeError::DivisionByZero => write!(f, "Division by zero"),
             RuntimeError::UnknownOpcode => write!(f, "Unknown opcode"),
             RuntimeError::UndefinedVariable(name) => write!(f, "Undefined variable: {}", name),
+            RuntimeError::UndefinedField { class, field } => {
+                write!(f, "Object of class '{}' has no field '{}'", class, field)
+            },
+            RuntimeError::UndefinedMethod { class, method } => {
+                write!(f, "Object of class '{}' has no method '{}'", class, method)
+            },
             RuntimeError::CallError(msg) => write!(f, "Call error: {}", msg),
+            RuntimeError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            RuntimeError::ArityMismatch { name, min, max, got } => {
+                match max {
+                    Some(max) if max == min => {
+                        write!(f, "Function '{}' expects {} arguments, got {}", name, min, got)
+                    }
+                    Some(max) => {
+                        write!(f, "Function '{}' expects {} to {} arguments, got {}", name, min, max, got)
+                    }
+                    None => write!(f, "Function '{}' expects at least {} arguments, got {}", name, min, got),
+                }
+            },
+            RuntimeError::Exit(code) => write!(f, "Exited with code {}", code),
+            RuntimeError::AssertionFailed { message } => write!(f, "Assertion failed: {}", message),
+            RuntimeError::Panic { message } => write!(f, "Panic: {}", message),
+            RuntimeError::IoError(msg) => write!(f, "IO error: {}", msg),
+            RuntimeError::MalformedChunk { chunk, message } => write!(f, "Malformed chunk '{}': {}", chunk, message),
+            RuntimeError::Located { error, span } => {
+                write!(f, "{} at line {} column {}", error, span.start.line, span.start.column)
+            },
         }
     }
 }
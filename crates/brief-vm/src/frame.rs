@@ -9,6 +9,9 @@ pub struct Frame {
     pub ip: usize,              // Instruction pointer
     pub registers: Vec<Value>,  // Register array (size = chunk.max_regs)
     pub base: usize,            // Base register for arguments
+    pub arg_count: usize,       // Number of arguments actually passed
+    pub return_reg: Option<u8>, // Caller register receiving the result (None for entry frames)
+    pub varargs: Vec<Value>,    // Arguments beyond the fixed parameters of a variadic function
 }
 
 impl Frame {
@@ -19,9 +22,35 @@ impl Frame {
             ip: 0,
             registers: vec![Value::Null; register_count],
             base,
+            arg_count: 0,
+            return_reg: None,
+            varargs: Vec::new(),
         }
     }
 
+    /// Create a frame for a call, placing arguments in the first registers.
+    /// For a variadic function the arguments past the fixed parameters are
+    /// held back until VARARG collects them.
+    pub fn for_call(chunk: Rc<Chunk>, mut args: Vec<Value>, return_reg: Option<u8>) -> Self {
+        let fixed = chunk.param_count.saturating_sub(1) as usize;
+        let varargs = if chunk.variadic && args.len() > fixed {
+            args.split_off(fixed)
+        } else {
+            Vec::new()
+        };
+        let mut frame = Self::new(chunk, 0);
+        frame.arg_count = args.len();
+        frame.varargs = varargs;
+        if frame.registers.len() < args.len() {
+            frame.registers.resize(args.len(), Value::Null);
+        }
+        for (idx, arg) in args.into_iter().enumerate() {
+            frame.registers[idx] = arg;
+        }
+        frame.return_reg = return_reg;
+        frame
+    }
+
     /// Get current instruction
     pub fn current_instruction(&self) -> Option<&brief_bytecode::Instruction> {
         self.chunk.code.get(self.ip)
@@ -1,3 +1,71 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use brief_bytecode::{CastType, Chunk};
+use crate::error::RuntimeError;
+
+/// Shared, mutable array storage
+pub type ArrayRef = Rc<RefCell<Vec<Value>>>;
+
+/// Shared, mutable map storage (insertion-ordered key/value pairs)
+pub type MapRef = Rc<RefCell<Vec<(Value, Value)>>>;
+
+/// Shared, mutable class instance
+pub type ObjectRef = Rc<RefCell<Instance>>;
+
+/// Class instance: fields in declaration/assignment order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub class_name: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Instance {
+    pub fn new(class_name: String) -> Self {
+        Self { class_name, fields: Vec::new() }
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields.iter().find(|(name, _)| name == field).map(|(_, value)| value)
+    }
+
+    /// Set a field, adding it if the instance doesn't have it yet
+    pub fn set(&mut self, field: &str, value: Value) {
+        match self.fields.iter_mut().find(|(name, _)| name == field) {
+            Some((_, slot)) => *slot = value,
+            None => self.fields.push((field.to_string(), value)),
+        }
+    }
+}
+
+/// Callable function value
+#[derive(Clone)]
+pub struct Closure {
+    pub chunk: Rc<Chunk>,
+    // TODO: Captured upvalues
+}
+
+impl Closure {
+    pub fn new(chunk: Rc<Chunk>) -> Self {
+        Self { chunk }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.chunk.name
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.chunk, &other.chunk)
+    }
+}
+
+impl std::fmt::Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Closure({})", self.name())
+    }
+}
+
 /// Runtime value representation
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
@@ -6,27 +74,85 @@ pub enum Value {
     Bool(bool),
     Str(String),  // Heap-allocated (GC'd)
     Null,
-    // Obj(ObjPtr),  // For future objects
+    Array(ArrayRef),
+    Map(MapRef),
+    Closure(Rc<Closure>),
+    Object(ObjectRef),
 }
 
 impl Value {
     /// Check truthiness: only false and null are falsey
     pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Bool(false) | Value::Null)
+    }
+
+    /// Name of the value's type, as used in error messages
+    pub fn type_name(&self) -> &'static str {
         match self {
-            Value::Bool(false) => false,
-            Value::Null => false,
-            _ => true,
+            Value::Int(_) => "int",
+            Value::Double(_) => "dub",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "str",
+            Value::Null => "null",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Closure(_) => "function",
+            Value::Object(_) => "object",
         }
     }
 
-    /// Convert to string representation
-    pub fn to_string(&self) -> String {
+    /// The value as a number for an explicit conversion: ints and doubles
+    /// as they are, bools as 1 or 0. Arithmetic never coerces, so `true + 1`
+    /// is still a type mismatch
+    pub fn to_number(&self) -> Option<Value> {
         match self {
-            Value::Int(i) => i.to_string(),
-            Value::Double(d) => d.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::Str(s) => s.clone(),
-            Value::Null => "null".to_string(),
+            Value::Int(_) | Value::Double(_) => Some(self.clone()),
+            Value::Bool(b) => Some(Value::Int(*b as i64)),
+            _ => None,
+        }
+    }
+
+    /// Convert the value as `x int`, `x dub`, ... and the `int`, `dub` and
+    /// `str` builtins do. Doubles truncate toward zero, and strings are
+    /// parsed when cast to a number
+    pub fn cast(&self, target: CastType) -> Result<Value, RuntimeError> {
+        let (name, number) = match target {
+            CastType::Str => return Ok(Value::Str(self.to_string())),
+            CastType::Bool => return Ok(Value::Bool(self.is_truthy())),
+            CastType::Int => ("integer", self.to_number()),
+            CastType::Dub => ("double", self.to_number()),
+        };
+        let parsed = match (self, target) {
+            (Value::Str(s), CastType::Int) => s.parse::<i64>().ok().map(Value::Int),
+            (Value::Str(s), _) => s.parse::<f64>().ok().map(Value::Double),
+            _ => number,
+        };
+        match (parsed, target) {
+            (Some(Value::Double(d)), CastType::Int) => Ok(Value::Int(d as i64)),
+            (Some(Value::Int(n)), CastType::Dub) => Ok(Value::Double(n as f64)),
+            (Some(value), _) => Ok(value),
+            (None, _) => Err(RuntimeError::CallError(match self {
+                Value::Str(s) => format!("Cannot convert string '{}' to {}", s, name),
+                other => format!("Cannot convert {} to {}", other.type_name(), name),
+            })),
+        }
+    }
+
+    /// Create a new array value
+    pub fn array(items: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(items)))
+    }
+
+    /// Create a new map value from key/value pairs
+    pub fn map(entries: Vec<(Value, Value)>) -> Self {
+        Value::Map(Rc::new(RefCell::new(entries)))
+    }
+
+    /// Format for display inside a collection (strings are quoted)
+    fn fmt_nested(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "\"{}\"", s),
+            other => write!(f, "{}", other),
         }
     }
 }
@@ -35,11 +161,69 @@ impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Int(i) => write!(f, "{}", i),
-            Value::Double(d) => write!(f, "{}", d),
+            Value::Double(d) => fmt_double(*d, f),
             Value::Bool(b) => write!(f, "{}", b),
             Value::Str(s) => write!(f, "{}", s),
             Value::Null => write!(f, "null"),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.fmt_nested(f)?;
+                    write!(f, ": ")?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Closure(closure) => write!(f, "<fn {}>", closure.name()),
+            Value::Object(object) => {
+                let object = object.borrow();
+                write!(f, "{}{{", object.class_name)?;
+                for (i, (name, value)) in object.fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", name)?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
+/// Significant digits shown for a double; enough for any value typed in a
+/// literal, while hiding representation noise such as `0.1 + 0.2`
+const DOUBLE_DIGITS: usize = 15;
+
+/// Write a double so it never reads as an int: whole values keep a `.0`,
+/// other values are rounded to `DOUBLE_DIGITS` significant digits, and the
+/// non-finite values print as `inf`, `-inf` and `nan`
+fn fmt_double(d: f64, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if d.is_nan() {
+        return write!(f, "nan");
+    }
+    if d.is_infinite() {
+        return write!(f, "{}", if d > 0.0 { "inf" } else { "-inf" });
+    }
+    // Round through scientific notation, then print the shortest form of the result
+    let rounded: f64 = format!("{:.*e}", DOUBLE_DIGITS - 1, d).parse().unwrap_or(d);
+    let text = rounded.to_string();
+    if text.contains('.') {
+        write!(f, "{}", text)
+    } else {
+        write!(f, "{}.0", text)
+    }
+}
@@ -1,24 +1,62 @@
+use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::collections::HashMap;
-use brief_bytecode::{Chunk, Opcode, Constant};
-use crate::value::Value;
+use brief_bytecode::{CastType, Chunk, Opcode, Constant, MODULE_INIT};
+use crate::value::{Closure, Instance, Value};
 use crate::frame::Frame;
 use crate::heap::Heap;
 use crate::error::RuntimeError;
 
+/// Maximum call depth before reporting a stack overflow
+const MAX_FRAMES: usize = 1024;
+
 /// Virtual Machine for executing Brief bytecode
 pub struct VM {
     frames: Vec<Frame>,
     _heap: Heap,
-    _globals: HashMap<String, Value>,
+    // Module-level variables, indexed by global slot
+    globals: Vec<Value>,
+    // Module initializers still to run before the entry frame, in the
+    // order their modules were registered
+    pending_init: Vec<Rc<Chunk>>,
+    // User-defined functions, keyed by chunk name
+    functions: HashMap<String, Rc<Chunk>>,
+    // Class name -> parent class name, for method lookup
+    parents: HashMap<String, String>,
     // Runtime for builtin functions (optional, stored as trait object to avoid circular dependency)
-    runtime: Option<Box<dyn BuiltinRuntime>>,
+    runtime: Option<Rc<dyn BuiltinRuntime>>,
+    // Chunks registered or pushed since the last run, checked before executing
+    unverified: Vec<Rc<Chunk>>,
 }
 
 /// Trait for builtin function runtime (to avoid circular dependency)
 pub trait BuiltinRuntime: Send + Sync {
     fn call_builtin(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError>;
     fn is_builtin(&self, name: &str) -> bool;
+
+    /// Call a builtin with access to the VM, for builtins that invoke Brief
+    /// callables (e.g. a sort comparator). Defaults to `call_builtin`.
+    fn call_builtin_with_vm(&self, name: &str, args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+        let _ = vm;
+        self.call_builtin(name, args)
+    }
+}
+
+/// A runtime shared with its embedder, who can keep using it (say, to
+/// register more builtins) after handing it to the VM
+impl<T: BuiltinRuntime + ?Sized> BuiltinRuntime for Arc<T> {
+    fn call_builtin(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        (**self).call_builtin(name, args)
+    }
+
+    fn is_builtin(&self, name: &str) -> bool {
+        (**self).is_builtin(name)
+    }
+
+    fn call_builtin_with_vm(&self, name: &str, args: &[Value], vm: &mut VM) -> Result<Value, RuntimeError> {
+        (**self).call_builtin_with_vm(name, args, vm)
+    }
 }
 
 impl VM {
@@ -26,14 +64,52 @@ impl VM {
         Self {
             frames: Vec::new(),
             _heap: Heap::new(),
-            _globals: HashMap::new(),
+            globals: Vec::new(),
+            pending_init: Vec::new(),
+            functions: HashMap::new(),
+            parents: HashMap::new(),
             runtime: None,
+            unverified: Vec::new(),
+        }
+    }
+
+    /// Register a compiled function so CALL can dispatch to it by name
+    pub fn register_function(&mut self, chunk: Rc<Chunk>) {
+        if let Some(parent) = &chunk.parent {
+            self.parents.insert(chunk.name.clone(), parent.clone());
+        }
+        if chunk.name == MODULE_INIT {
+            self.pending_init.push(chunk.clone());
+        }
+        self.unverified.push(chunk.clone());
+        self.functions.insert(chunk.name.clone(), chunk);
+    }
+
+    /// Register every chunk emitted for a program
+    pub fn register_functions(&mut self, chunks: &[Chunk]) {
+        for chunk in chunks {
+            self.register_function(Rc::new(chunk.clone()));
         }
     }
     
+    /// Register the chunks of an imported module, making its functions and
+    /// classes callable by name. Its initializer runs on the next `run`,
+    /// before those of modules registered after it, so a module's globals
+    /// are set before the code that imports it starts
+    pub fn load_module(&mut self, chunks: Vec<Chunk>) {
+        for chunk in chunks {
+            self.register_function(Rc::new(chunk));
+        }
+    }
+    
+    /// Names of the registered chunks: functions, classes and their methods
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
     /// Set the runtime
     pub fn set_runtime(&mut self, runtime: Box<dyn BuiltinRuntime>) {
-        self.runtime = Some(runtime);
+        self.runtime = Some(Rc::from(runtime));
     }
 
     /// Get current frame (mutable)
@@ -48,16 +124,58 @@ impl VM {
 
     /// Push a new frame onto the call stack
     pub fn push_frame(&mut self, chunk: Rc<Chunk>, base: usize) {
+        self.unverified.push(chunk.clone());
         self.frames.push(Frame::new(chunk, base));
     }
 
+    /// Check that every new chunk stays within its registers, so a chunk
+    /// that under-reports `max_regs` fails before it runs rather than
+    /// partway through
+    fn verify_chunks(&mut self) -> Result<(), RuntimeError> {
+        for chunk in std::mem::take(&mut self.unverified) {
+            verify_chunk(&chunk)?;
+        }
+        Ok(())
+    }
+
     /// Pop the current frame from the call stack
     fn pop_frame(&mut self) -> Option<Frame> {
         self.frames.pop()
     }
 
-    /// Run the VM until completion
+    /// Run the VM until completion. Newly registered module initializers
+    /// run first, so module-level variables are set before the entry frame
+    /// (unless an initializer is itself the entry frame).
+    ///
+    /// Errors carry the source location of the failing instruction when
+    /// its chunk has a line table.
     pub fn run(&mut self) -> Result<Value, RuntimeError> {
+        self.verify_chunks()?;
+        self.execute().map_err(|error| self.locate(error))
+    }
+
+    /// Tag `error` with the span of the instruction the current frame just
+    /// ran. An exit request is not a failure and stays as it is
+    fn locate(&self, error: RuntimeError) -> RuntimeError {
+        if matches!(error, RuntimeError::Exit(_) | RuntimeError::Located { .. }) {
+            return error;
+        }
+        let span = self.frames.last()
+            .and_then(|frame| frame.chunk.span_at(frame.ip.saturating_sub(1)));
+        match span {
+            Some(span) => RuntimeError::Located { error: Box::new(error), span },
+            None => error,
+        }
+    }
+
+    fn execute(&mut self) -> Result<Value, RuntimeError> {
+        let pending = std::mem::take(&mut self.pending_init);
+        if self.frames.last().is_none_or(|frame| frame.chunk.name != MODULE_INIT) {
+            for init in pending {
+                self.call_function(init, Vec::new(), None)?;
+                self.execute()?;
+            }
+        }
         loop {
             let frame = self.current_frame_mut()?;
             
@@ -65,9 +183,8 @@ impl VM {
                 Some(inst) => *inst,
                 None => {
                     // End of function - return null
-                    self.pop_frame();
-                    if self.frames.is_empty() {
-                        return Ok(Value::Null);
+                    if let Some(value) = self.finish_frame(Value::Null)? {
+                        return Ok(value);
                     }
                     continue;
                 }
@@ -86,6 +203,16 @@ impl VM {
                     let src = instruction.b();
                     self.move_register(dest, src)?;
                 },
+                Opcode::GET_GLOBAL => {
+                    let dest = instruction.a();
+                    let slot = instruction.b();
+                    self.get_global(dest, slot)?;
+                },
+                Opcode::SET_GLOBAL => {
+                    let slot = instruction.a();
+                    let src = instruction.b();
+                    self.set_global(slot, src)?;
+                },
                 Opcode::ADD => {
                     let dest = instruction.a();
                     let left = instruction.b();
@@ -132,13 +259,13 @@ impl VM {
                     let dest = instruction.a();
                     let left = instruction.b();
                     let right = instruction.c();
-                    self.binary_op_impl(dest, left, right, |a, b| Ok(Value::Bool(a == b)))?;
+                    self.binary_op_impl(dest, left, right, |a, b| Ok(Value::Bool(values_equal(a, b))))?;
                 },
                 Opcode::CMP_NE => {
                     let dest = instruction.a();
                     let left = instruction.b();
                     let right = instruction.c();
-                    self.binary_op_impl(dest, left, right, |a, b| Ok(Value::Bool(a != b)))?;
+                    self.binary_op_impl(dest, left, right, |a, b| Ok(Value::Bool(!values_equal(a, b))))?;
                 },
                 Opcode::CMP_LT => {
                     let dest = instruction.a();
@@ -164,6 +291,14 @@ impl VM {
                     let right = instruction.c();
                     self.binary_op_impl(dest, left, right, Self::cmp_ge_value)?;
                 },
+                Opcode::CONTAINS => {
+                    let dest = instruction.a();
+                    let needle = instruction.b();
+                    let container = instruction.c();
+                    self.binary_op_impl(dest, needle, container, |needle, container| {
+                        contains_value(container, needle).map(Value::Bool)
+                    })?;
+                },
                 Opcode::NEG => {
                     let dest = instruction.a();
                     let src = instruction.b();
@@ -174,6 +309,17 @@ impl VM {
                     let src = instruction.b();
                     self.unary_op_impl(dest, src, |v| Ok(Value::Bool(!v.is_truthy())))?;
                 },
+                Opcode::TOBOOL => {
+                    let dest = instruction.a();
+                    let src = instruction.b();
+                    self.unary_op_impl(dest, src, |v| Ok(Value::Bool(v.is_truthy())))?;
+                },
+                Opcode::CAST => {
+                    let dest = instruction.a();
+                    let src = instruction.b();
+                    let target = CastType::from_u8(instruction.c()).ok_or(RuntimeError::UnknownOpcode)?;
+                    self.unary_op_impl(dest, src, |v| v.cast(target))?;
+                },
                 Opcode::JIF => {
                     let cond_reg = instruction.a();
                     let offset = instruction.offset();
@@ -183,20 +329,93 @@ impl VM {
                     let offset = instruction.offset();
                     self.jump(offset)?;
                 },
+                Opcode::TEST_EQ => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(values_equal(a, b)))?;
+                },
+                Opcode::TEST_NE => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(!values_equal(a, b)))?;
+                },
+                Opcode::TEST_LT => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(Self::cmp_lt_value(a, b)?.is_truthy()))?;
+                },
+                Opcode::TEST_LE => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(Self::cmp_le_value(a, b)?.is_truthy()))?;
+                },
+                Opcode::TEST_GT => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(Self::cmp_gt_value(a, b)?.is_truthy()))?;
+                },
+                Opcode::TEST_GE => {
+                    self.test_impl(instruction.a(), instruction.b(), |a, b| Ok(Self::cmp_ge_value(a, b)?.is_truthy()))?;
+                },
                 Opcode::CALL => {
                     let dest = instruction.a();
                     let callee_reg = instruction.b();
                     let arg_count = instruction.c();
-                    self.call(dest, callee_reg, arg_count)?;
+                    self.call(dest, callee_reg, arg_count, false)?;
+                },
+                Opcode::CALLV => {
+                    let dest = instruction.a();
+                    let callee_reg = instruction.b();
+                    let arg_count = instruction.c();
+                    self.call(dest, callee_reg, arg_count, true)?;
                 },
                 Opcode::RET => {
                     let value_reg = instruction.a();
-                    return self.return_value(value_reg);
+                    if let Some(value) = self.return_value(value_reg)? {
+                        return Ok(value);
+                    }
+                },
+                Opcode::CLOSURE => {
+                    let reg = instruction.a();
+                    let const_idx = instruction.b();
+                    self.load_closure(reg, const_idx)?;
+                },
+                Opcode::NEWOBJ => {
+                    let reg = instruction.a();
+                    let const_idx = instruction.b();
+                    self.new_object(reg, const_idx)?;
+                },
+                Opcode::GETFIELD => {
+                    let dest = instruction.a();
+                    let object_reg = instruction.b();
+                    let const_idx = instruction.c();
+                    self.get_field(dest, object_reg, const_idx)?;
+                },
+                Opcode::SETFIELD => {
+                    let object_reg = instruction.a();
+                    let const_idx = instruction.b();
+                    let value_reg = instruction.c();
+                    self.set_field(object_reg, const_idx, value_reg)?;
+                },
+                Opcode::INVOKE => {
+                    let dest = instruction.a();
+                    let name_reg = instruction.b();
+                    let arg_count = instruction.c();
+                    self.invoke(dest, name_reg, arg_count)?;
+                },
+                Opcode::SUPER => {
+                    let object_reg = instruction.a();
+                    let callee_reg = instruction.b();
+                    let arg_count = instruction.c();
+                    self.call_super(object_reg, callee_reg, arg_count)?;
+                },
+                Opcode::VARARG => {
+                    let reg = instruction.a();
+                    self.collect_varargs(reg)?;
+                },
+                Opcode::JARG => {
+                    let param = instruction.a();
+                    let offset = instruction.offset();
+                    self.jump_if_arg(param, offset)?;
                 },
                 Opcode::PRINT => {
                     let reg = instruction.a();
                     self.print(reg)?;
                 },
+                Opcode::EXIT => {
+                    let reg = instruction.a();
+                    return Err(self.exit(reg));
+                },
                 _ => {
                     return Err(RuntimeError::UnknownOpcode);
                 }
@@ -226,6 +445,87 @@ impl VM {
         Ok(())
     }
 
+    /// Read a name (function, class, or field) from the constant pool
+    fn constant_name(&self, const_idx: u8) -> Result<String, RuntimeError> {
+        match self.current_frame()?.chunk.constants.get(const_idx as usize) {
+            Some(Constant::Str(name)) => Ok(name.clone()),
+            _ => Err(RuntimeError::InvalidConstantIndex(const_idx)),
+        }
+    }
+
+    fn register(&self, reg: u8) -> Result<&Value, RuntimeError> {
+        self.current_frame()?.registers.get(reg as usize)
+            .ok_or(RuntimeError::InvalidRegister(reg))
+    }
+
+    fn set_register(&mut self, reg: u8, value: Value) -> Result<(), RuntimeError> {
+        let slot = self.current_frame_mut()?.registers.get_mut(reg as usize)
+            .ok_or(RuntimeError::InvalidRegister(reg))?;
+        *slot = value;
+        Ok(())
+    }
+
+    fn new_object(&mut self, reg: u8, const_idx: u8) -> Result<(), RuntimeError> {
+        // Run through SUPER: the subclass instance is already in place
+        if matches!(self.register(reg)?, Value::Object(_)) {
+            return Ok(());
+        }
+        let class_name = self.constant_name(const_idx)?;
+        let object = Value::Object(Rc::new(RefCell::new(Instance::new(class_name))));
+        self.set_register(reg, object)
+    }
+
+    fn get_field(&mut self, dest: u8, object_reg: u8, const_idx: u8) -> Result<(), RuntimeError> {
+        let field = self.constant_name(const_idx)?;
+        let value = match self.register(object_reg)? {
+            Value::Object(object) => {
+                let object = object.borrow();
+                object.get(&field).cloned().ok_or_else(|| RuntimeError::UndefinedField {
+                    class: object.class_name.clone(),
+                    field,
+                })?
+            }
+            Value::Map(entries) => map_entry(&entries.borrow(), &field)?,
+            other => {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "object".to_string(),
+                    got: other.type_name().to_string(),
+                });
+            }
+        };
+        self.set_register(dest, value)
+    }
+
+    fn set_field(&mut self, object_reg: u8, const_idx: u8, value_reg: u8) -> Result<(), RuntimeError> {
+        let field = self.constant_name(const_idx)?;
+        let value = self.register(value_reg)?.clone();
+        match self.register(object_reg)? {
+            Value::Object(object) => {
+                object.borrow_mut().set(&field, value);
+                Ok(())
+            }
+            other => Err(RuntimeError::TypeMismatch {
+                expected: "object".to_string(),
+                got: other.type_name().to_string(),
+            }),
+        }
+    }
+
+    fn load_closure(&mut self, reg: u8, const_idx: u8) -> Result<(), RuntimeError> {
+        let name = self.constant_name(const_idx)?;
+        // Names without a chunk (e.g. classes) are kept as plain names for CALL
+        let value = match self.functions.get(&name) {
+            Some(chunk) => Value::Closure(Rc::new(Closure::new(chunk.clone()))),
+            None => Value::Str(name),
+        };
+        let frame = self.current_frame_mut()?;
+        if reg as usize >= frame.registers.len() {
+            return Err(RuntimeError::InvalidRegister(reg));
+        }
+        frame.registers[reg as usize] = value;
+        Ok(())
+    }
+
     fn move_register(&mut self, dest: u8, src: u8) -> Result<(), RuntimeError> {
         let frame = self.current_frame_mut()?;
         if src as usize >= frame.registers.len() || dest as usize >= frame.registers.len() {
@@ -237,6 +537,30 @@ impl VM {
         Ok(())
     }
 
+    /// Slots that were never set read as null
+    fn get_global(&mut self, dest: u8, slot: u8) -> Result<(), RuntimeError> {
+        let value = self.globals.get(slot as usize).cloned().unwrap_or(Value::Null);
+        let frame = self.current_frame_mut()?;
+        if dest as usize >= frame.registers.len() {
+            return Err(RuntimeError::InvalidRegister(dest));
+        }
+        frame.registers[dest as usize] = value;
+        Ok(())
+    }
+
+    fn set_global(&mut self, slot: u8, src: u8) -> Result<(), RuntimeError> {
+        let frame = self.current_frame()?;
+        if src as usize >= frame.registers.len() {
+            return Err(RuntimeError::InvalidRegister(src));
+        }
+        let value = frame.registers[src as usize].clone();
+        if slot as usize >= self.globals.len() {
+            self.globals.resize(slot as usize + 1, Value::Null);
+        }
+        self.globals[slot as usize] = value;
+        Ok(())
+    }
+
     fn binary_op_impl<F>(&mut self, dest: u8, left_reg: u8, right_reg: u8, op: F) -> Result<(), RuntimeError>
     where
         F: FnOnce(&Value, &Value) -> Result<Value, RuntimeError>,
@@ -285,6 +609,28 @@ impl VM {
         Ok(())
     }
 
+    /// Skip the next instruction when `test` holds for the two registers.
+    /// The operands are compared in place, without storing the result
+    fn test_impl<F>(&mut self, left_reg: u8, right_reg: u8, test: F) -> Result<(), RuntimeError>
+    where
+        F: FnOnce(&Value, &Value) -> Result<bool, RuntimeError>,
+    {
+        let frame = self.current_frame_mut()?;
+        let Some(left) = frame.registers.get(left_reg as usize) else {
+            return Err(RuntimeError::InvalidRegister(left_reg));
+        };
+        let Some(right) = frame.registers.get(right_reg as usize) else {
+            return Err(RuntimeError::InvalidRegister(right_reg));
+        };
+        if test(left, right)? {
+            if frame.ip >= frame.chunk.code.len() {
+                return Err(RuntimeError::CallError("Jump out of bounds".to_string()));
+            }
+            frame.ip += 1;
+        }
+        Ok(())
+    }
+
     fn jump(&mut self, offset: i16) -> Result<(), RuntimeError> {
         let frame = self.current_frame_mut()?;
         let new_ip = (frame.ip as i32 + offset as i32) as usize;
@@ -295,19 +641,33 @@ impl VM {
         Ok(())
     }
 
-    fn call(&mut self, dest: u8, callee_reg: u8, arg_count: u8) -> Result<(), RuntimeError> {
-        // Extract all needed data first (function name and args)
-        let (function_name, args) = {
+    fn jump_if_arg(&mut self, param: u8, offset: i16) -> Result<(), RuntimeError> {
+        let passed = (param as usize) < self.current_frame()?.arg_count;
+        if passed {
+            self.jump(offset)?;
+        }
+        Ok(())
+    }
+
+    /// Move the arguments passed beyond the fixed parameters into an array in `reg`
+    fn collect_varargs(&mut self, reg: u8) -> Result<(), RuntimeError> {
+        let frame = self.current_frame_mut()?;
+        if reg as usize >= frame.registers.len() {
+            return Err(RuntimeError::InvalidRegister(reg));
+        }
+        let rest = std::mem::take(&mut frame.varargs);
+        frame.registers[reg as usize] = Value::array(rest);
+        Ok(())
+    }
+
+    fn call(&mut self, dest: u8, callee_reg: u8, arg_count: u8, spread: bool) -> Result<(), RuntimeError> {
+        // Extract all needed data first (callee and args)
+        let (callee, args) = {
             let frame = self.current_frame_mut()?;
             if callee_reg as usize >= frame.registers.len() {
                 return Err(RuntimeError::InvalidRegister(callee_reg));
             }
-            
-            // Extract function name if it's a string
-            let function_name = match &frame.registers[callee_reg as usize] {
-                Value::Str(name) => Some(name.clone()),
-                _ => None,
-            };
+            let callee = frame.registers[callee_reg as usize].clone();
             
             // Collect arguments (starting at callee_reg + 1)
             let mut args = Vec::new();
@@ -319,33 +679,193 @@ impl VM {
                 args.push(frame.registers[arg_reg as usize].clone());
             }
             
-            (function_name, args)
+            // CALLV: expand the trailing array into individual arguments
+            if spread {
+                match args.pop() {
+                    Some(Value::Array(items)) => args.extend(items.borrow().iter().cloned()),
+                    Some(other) => {
+                        return Err(RuntimeError::TypeMismatch {
+                            expected: "array".to_string(),
+                            got: other.type_name().to_string(),
+                        });
+                    }
+                    None => {}
+                }
+            }
+            
+            (callee, args)
         };
+        self.call_callee(dest, callee, args)
+    }
+
+    /// Call `callee` with `args`, leaving the result in `dest`: a closure,
+    /// or a function or builtin named by a string
+    fn call_callee(&mut self, dest: u8, callee: Value, args: Vec<Value>) -> Result<(), RuntimeError> {
+        let function_name = match callee {
+            Value::Closure(closure) => {
+                return self.call_function(closure.chunk.clone(), args, Some(dest));
+            }
+            Value::Str(name) => name,
+            other => {
+                return Err(RuntimeError::CallError(format!("Cannot call a value of type {}", other.type_name())));
+            }
+        };
+
+        // User-defined functions take precedence over builtins
+        if let Some(chunk) = self.functions.get(&function_name).cloned() {
+            return self.call_function(chunk, args, Some(dest));
+        }
+
+        let result = self.call_builtin(&function_name, &args)?;
         
-        // For now, assume callee is a string (function name) for builtin calls
-        // TODO: Support actual function objects when they're implemented
-        if let Some(function_name) = function_name {
-            // Try to call as builtin
-            let result = if let Some(runtime) = &self.runtime {
-                runtime.call_builtin(&function_name, &args)?
-            } else {
-                return Err(RuntimeError::CallError("Runtime not available for builtin calls".to_string()));
-            };
-            
-            // Store result in destination register
-            let frame = self.current_frame_mut()?;
-            if dest as usize >= frame.registers.len() {
-                return Err(RuntimeError::InvalidRegister(dest));
+        // Store result in destination register
+        let frame = self.current_frame_mut()?;
+        if dest as usize >= frame.registers.len() {
+            return Err(RuntimeError::InvalidRegister(dest));
+        }
+        frame.registers[dest as usize] = result;
+        Ok(())
+    }
+
+    /// Call the method named in `name_reg` on the receiver in the next
+    /// register, looking it up on the receiver's class and then its ancestors
+    fn invoke(&mut self, dest: u8, name_reg: u8, arg_count: u8) -> Result<(), RuntimeError> {
+        let method = match self.register(name_reg)? {
+            Value::Str(name) => name.clone(),
+            other => {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "method name".to_string(),
+                    got: other.type_name().to_string(),
+                });
             }
-            frame.registers[dest as usize] = result;
-            Ok(())
-        } else {
-            // TODO: Support function objects
-            Err(RuntimeError::CallError("Function calls not yet fully implemented".to_string()))
+        };
+        let receiver = self.register(name_reg + 1)?.clone();
+        let args = self.call_args(name_reg + 2, arg_count)?;
+        
+        let class_name = match &receiver {
+            Value::Object(object) => object.borrow().class_name.clone(),
+            // An import alias: call the function stored under the name
+            Value::Map(entries) => {
+                let callee = map_entry(&entries.borrow(), &method)?;
+                return self.call_callee(dest, callee, args);
+            }
+            other => {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "object".to_string(),
+                    got: other.type_name().to_string(),
+                });
+            }
+        };
+        let chunk = self.find_method(&class_name, &method)
+            .ok_or(RuntimeError::UndefinedMethod { class: class_name, method })?;
+        self.call_function(chunk, args, Some(dest))?;
+        self.set_receiver(receiver)
+    }
+
+    fn find_method(&self, class_name: &str, method: &str) -> Option<Rc<Chunk>> {
+        let mut class = Some(class_name);
+        while let Some(name) = class {
+            if let Some(chunk) = self.functions.get(&format!("{}::{}", name, method)) {
+                return Some(chunk.clone());
+            }
+            class = self.parents.get(name).map(String::as_str);
+        }
+        None
+    }
+
+    /// Run a parent constructor on an existing instance; the constructor's
+    /// NEWOBJ keeps the instance instead of allocating a new one
+    fn call_super(&mut self, object_reg: u8, callee_reg: u8, arg_count: u8) -> Result<(), RuntimeError> {
+        let chunk = match self.register(callee_reg)? {
+            Value::Closure(closure) => closure.chunk.clone(),
+            other => {
+                return Err(RuntimeError::CallError(format!("Cannot call a value of type {}", other.type_name())));
+            }
+        };
+        let receiver = self.register(object_reg)?.clone();
+        let args = self.call_args(callee_reg + 1, arg_count)?;
+        self.call_function(chunk, args, Some(object_reg))?;
+        self.set_receiver(receiver)
+    }
+
+    /// Copy `count` argument registers starting at `first`
+    fn call_args(&self, first: u8, count: u8) -> Result<Vec<Value>, RuntimeError> {
+        (0..count).map(|i| self.register(first + i).cloned()).collect()
+    }
+
+    /// Hand the receiver to the frame just pushed: it lives in the first
+    /// register after the parameters
+    fn set_receiver(&mut self, receiver: Value) -> Result<(), RuntimeError> {
+        let frame = self.current_frame_mut()?;
+        let reg = frame.chunk.param_count as usize;
+        if frame.registers.len() <= reg {
+            frame.registers.resize(reg + 1, Value::Null);
+        }
+        frame.registers[reg] = receiver;
+        Ok(())
+    }
+
+    fn call_builtin(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        // Clone the handle so the builtin can borrow the VM for callbacks
+        let runtime = self.runtime.clone()
+            .ok_or_else(|| RuntimeError::CallError("Runtime not available for builtin calls".to_string()))?;
+        runtime.call_builtin_with_vm(name, args, self)
+    }
+
+    /// Call a Brief callable from native code (e.g. a builtin invoking a
+    /// comparator) and run it to completion
+    pub fn call_value(&mut self, callee: &Value, args: &[Value]) -> Result<Value, RuntimeError> {
+        let chunk = match callee {
+            Value::Closure(closure) => closure.chunk.clone(),
+            Value::Str(name) => match self.functions.get(name) {
+                Some(chunk) => chunk.clone(),
+                None => return self.call_builtin(name, args),
+            },
+            other => {
+                return Err(RuntimeError::CallError(format!("Cannot call a value of type {}", other.type_name())));
+            }
+        };
+        self.call_function(chunk, args.to_vec(), None)?;
+        self.run()
+    }
+
+    fn call_function(&mut self, chunk: Rc<Chunk>, args: Vec<Value>, dest: Option<u8>) -> Result<(), RuntimeError> {
+        let min = chunk.required_params as usize;
+        // A variadic function accepts any number of extra arguments
+        let max = (!chunk.variadic).then_some(chunk.param_count as usize);
+        if args.len() < min || max.is_some_and(|max| args.len() > max) {
+            return Err(RuntimeError::ArityMismatch {
+                name: chunk.name.clone(),
+                min,
+                max,
+                got: args.len(),
+            });
+        }
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(RuntimeError::StackOverflow);
+        }
+        self.frames.push(Frame::for_call(chunk, args, dest));
+        Ok(())
+    }
+
+    /// Pop the current frame and hand its result to the caller.
+    /// Returns `Some(value)` when `run` should stop (an entry frame finished).
+    fn finish_frame(&mut self, value: Value) -> Result<Option<Value>, RuntimeError> {
+        let frame = self.pop_frame().ok_or(RuntimeError::StackUnderflow)?;
+        match frame.return_reg {
+            Some(dest) if !self.frames.is_empty() => {
+                let caller = self.current_frame_mut()?;
+                if dest as usize >= caller.registers.len() {
+                    return Err(RuntimeError::InvalidRegister(dest));
+                }
+                caller.registers[dest as usize] = value;
+                Ok(None)
+            }
+            _ => Ok(Some(value)),
         }
     }
 
-    fn return_value(&mut self, value_reg: u8) -> Result<Value, RuntimeError> {
+    fn return_value(&mut self, value_reg: u8) -> Result<Option<Value>, RuntimeError> {
         let frame = self.current_frame_mut()?;
         if value_reg as usize >= frame.registers.len() {
             return Err(RuntimeError::InvalidRegister(value_reg));
@@ -354,17 +874,10 @@ impl VM {
         if std::env::var("BRIEF_TRACE_VM").is_ok() {
             eprintln!("Registers at return: {:?}", frame.registers);
         }
-        self.pop_frame();
-        
-        if self.frames.is_empty() {
-            if std::env::var("BRIEF_TRACE_VM").is_ok() {
-                eprintln!("VM returning {:?}", value);
-            }
-            Ok(value)
-        } else {
-            // TODO: Store return value in calling frame
-            Ok(value)
+        if self.frames.len() == 1 && std::env::var("BRIEF_TRACE_VM").is_ok() {
+            eprintln!("VM returning {:?}", value);
         }
+        self.finish_frame(value)
     }
 
     fn print(&mut self, reg: u8) -> Result<(), RuntimeError> {
@@ -372,9 +885,40 @@ impl VM {
         if reg as usize >= frame.registers.len() {
             return Err(RuntimeError::InvalidRegister(reg));
         }
-        let value = &frame.registers[reg as usize];
-        println!("{}", value);
-        Ok(())
+        let value = frame.registers[reg as usize].clone();
+        // The runtime's print writes wherever its embedder pointed it
+        match self.runtime.clone() {
+            Some(runtime) => runtime.call_builtin("print", &[value]).map(|_| ()),
+            None => {
+                println!("{}", value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Unwind every frame; the returned `Exit` error carries the code in `reg`.
+    /// A runtime that doesn't allow exiting fails the call instead
+    fn exit(&mut self, reg: u8) -> RuntimeError {
+        let code = self.current_frame().and_then(|frame| {
+            frame.registers.get(reg as usize)
+                .ok_or(RuntimeError::InvalidRegister(reg))
+                .and_then(exit_code)
+        });
+        let code = code.and_then(|code| match &self.runtime {
+            Some(runtime) => match runtime.call_builtin("exit", &[Value::Int(code as i64)]) {
+                Err(RuntimeError::Exit(code)) => Ok(code),
+                Err(error) => Err(error),
+                Ok(_) => Ok(code),
+            },
+            None => Ok(code),
+        });
+        match code {
+            Ok(code) => {
+                self.frames.clear();
+                RuntimeError::Exit(code)
+            }
+            Err(e) => e,
+        }
     }
 
     // Arithmetic operations (static methods to avoid borrow issues)
@@ -633,3 +1177,84 @@ impl Default for VM {
     }
 }
 
+/// Equality used by `==`/`!=`: arrays compare elementwise, maps compare
+/// their entries regardless of insertion order, functions by identity
+pub fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(x), Value::Array(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return true;
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => Rc::ptr_eq(x, y),
+        (Value::Map(x), Value::Map(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return true;
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len()
+                && x.iter().all(|(key, value)| {
+                    y.iter().any(|(k, v)| values_equal(key, k) && values_equal(value, v))
+                })
+        }
+        _ => a == b,
+    }
+}
+
+/// Membership used by `in` and `contains`: an equal array element, an equal
+/// map key, or a substring (a non-string needle is matched by its display)
+pub fn contains_value(container: &Value, needle: &Value) -> Result<bool, RuntimeError> {
+    match container {
+        Value::Str(s) => match needle {
+            Value::Str(n) => Ok(s.contains(n.as_str())),
+            other => Ok(s.contains(&other.to_string())),
+        },
+        Value::Array(items) => Ok(items.borrow().iter().any(|item| values_equal(item, needle))),
+        Value::Map(entries) => Ok(entries.borrow().iter().any(|(k, _)| values_equal(k, needle))),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "string, array, or map".to_string(),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// The value under the string key `name`, for `map.name`: how an import
+/// alias's map is read
+fn map_entry(entries: &[(Value, Value)], name: &str) -> Result<Value, RuntimeError> {
+    entries.iter()
+        .find(|(key, _)| matches!(key, Value::Str(key) if key == name))
+        .map(|(_, value)| value.clone())
+        .ok_or_else(|| RuntimeError::UndefinedField { class: "map".to_string(), field: name.to_string() })
+}
+
+/// Convert the argument of `exit` into a process exit code
+pub fn exit_code(value: &Value) -> Result<i32, RuntimeError> {
+    match value {
+        Value::Int(n) => i32::try_from(*n)
+            .map_err(|_| RuntimeError::CallError(format!("Exit code {} is out of range", n))),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "int".to_string(),
+            got: other.type_name().to_string(),
+        }),
+    }
+}
+
+/// Reject a chunk whose instructions reach past its `max_regs` registers
+fn verify_chunk(chunk: &Chunk) -> Result<(), RuntimeError> {
+    for (ip, instruction) in chunk.code.iter().enumerate() {
+        if let Some(reg) = instruction.highest_register()
+            && reg >= chunk.max_regs as u16
+        {
+            return Err(RuntimeError::MalformedChunk {
+                chunk: chunk.name.clone(),
+                message: format!(
+                    "instruction {} ({:?}) uses register {} but max_regs is {}",
+                    ip, instruction.opcode(), reg, chunk.max_regs
+                ),
+            });
+        }
+    }
+    Ok(())
+}
@@ -346,6 +346,24 @@ fn test_true_truthiness() {
     }
 }
 
+#[test]
+fn test_tobool_truthiness() {
+    for (constant, expected) in [
+        (Constant::Int(0), true),
+        (Constant::Str(String::new()), true),
+        (Constant::Bool(true), true),
+        (Constant::Bool(false), false),
+        (Constant::Null, false),
+    ] {
+        let mut chunk = create_test_chunk();
+        let idx = chunk.add_constant(constant.clone());
+        chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx));
+        chunk.emit(Instruction::new2(Opcode::TOBOOL, 1, 0));
+        chunk.emit(Instruction::new1(Opcode::RET, 1));
+        assert_eq!(run_chunk(chunk), Ok(Value::Bool(expected)), "{:?}", constant);
+    }
+}
+
 // Error handling tests
 
 #[test]
@@ -356,11 +374,42 @@ fn test_invalid_register() {
     chunk.emit(Instruction::new2(Opcode::LOADK, 10, idx)); // Invalid register
     
     let result = run_chunk(chunk);
-    assert!(result.is_err());
-    if let Err(RuntimeError::InvalidRegister(10)) = result {
-        // Expected
-    } else {
-        panic!("Expected InvalidRegister(10), got {:?}", result);
+    match result {
+        Err(RuntimeError::MalformedChunk { chunk, message }) => {
+            assert_eq!(chunk, "test");
+            assert!(message.contains("register 10"), "{}", message);
+        }
+        other => panic!("Expected MalformedChunk, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_undersized_chunk_rejected_before_running() {
+    // EXIT would stop the program at once, but the bad CALL is caught first
+    let mut chunk = create_test_chunk();
+    chunk.max_regs = 4;
+    let idx = chunk.add_constant(Constant::Int(3));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx));
+    chunk.emit(Instruction::new1(Opcode::EXIT, 0));
+    // Callee in r1 with three arguments in r2..r4, one past the end
+    chunk.emit(Instruction::new(Opcode::CALL, 0, 1, 3));
+    
+    let result = run_chunk(chunk);
+    assert!(matches!(result, Err(RuntimeError::MalformedChunk { .. })), "{:?}", result);
+}
+
+#[test]
+fn test_undersized_registered_function_rejected() {
+    let mut helper = Chunk::new("helper".to_string());
+    helper.max_regs = 1;
+    helper.emit(Instruction::new(Opcode::ADD, 0, 1, 2));
+    
+    let mut vm = VM::new();
+    vm.register_function(Rc::new(helper));
+    vm.push_frame(Rc::new(create_test_chunk()), 0);
+    match vm.run() {
+        Err(RuntimeError::MalformedChunk { chunk, .. }) => assert_eq!(chunk, "helper"),
+        other => panic!("Expected MalformedChunk, got {:?}", other),
     }
 }
 
@@ -418,3 +467,47 @@ fn test_double_negate() {
     }
 }
 
+
+// Cast tests
+
+fn cast_constant(constant: Constant, target: CastType) -> Result<Value, RuntimeError> {
+    let mut chunk = create_test_chunk();
+    let idx = chunk.add_constant(constant);
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx));
+    chunk.emit(Instruction::new(Opcode::CAST, 1, 0, target as u8));
+    chunk.emit(Instruction::new1(Opcode::RET, 1));
+    run_chunk(chunk)
+}
+
+#[test]
+fn test_cast_opcode() {
+    assert_eq!(cast_constant(Constant::Bool(true), CastType::Int), Ok(Value::Int(1)));
+    assert_eq!(cast_constant(Constant::Bool(false), CastType::Dub), Ok(Value::Double(0.0)));
+    assert_eq!(cast_constant(Constant::Double(-2.7), CastType::Int), Ok(Value::Int(-2)));
+    assert_eq!(cast_constant(Constant::Int(3), CastType::Dub), Ok(Value::Double(3.0)));
+    assert_eq!(cast_constant(Constant::Str("42".to_string()), CastType::Int), Ok(Value::Int(42)));
+    assert_eq!(cast_constant(Constant::Double(1.5), CastType::Str), Ok(Value::Str("1.5".to_string())));
+    assert_eq!(cast_constant(Constant::Null, CastType::Bool), Ok(Value::Bool(false)));
+    assert!(matches!(cast_constant(Constant::Null, CastType::Int), Err(RuntimeError::CallError(_))));
+    assert!(matches!(cast_constant(Constant::Str("4x".to_string()), CastType::Dub), Err(RuntimeError::CallError(_))));
+}
+
+#[test]
+fn test_to_number_only_converts_numbers_and_bools() {
+    assert_eq!(Value::Bool(true).to_number(), Some(Value::Int(1)));
+    assert_eq!(Value::Double(0.5).to_number(), Some(Value::Double(0.5)));
+    assert_eq!(Value::Str("1".to_string()).to_number(), None);
+    assert_eq!(Value::Null.to_number(), None);
+}
+
+#[test]
+fn test_bool_arithmetic_stays_strict() {
+    let mut chunk = create_test_chunk();
+    let idx1 = chunk.add_constant(Constant::Bool(true));
+    let idx2 = chunk.add_constant(Constant::Int(1));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx1));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 1, idx2));
+    chunk.emit(Instruction::new(Opcode::ADD, 2, 0, 1));
+    chunk.emit(Instruction::new1(Opcode::RET, 2));
+    assert!(matches!(run_chunk(chunk), Err(RuntimeError::TypeMismatch { .. })));
+}
@@ -1,6 +1,7 @@
 use std::rc::Rc;
 use brief_vm::*;
 use brief_bytecode::*;
+use brief_diagnostic::{FileId, Position, Span};
 
 fn create_test_chunk() -> Chunk {
     let mut chunk = Chunk::new("test".to_string());
@@ -22,6 +23,37 @@ fn test_load_constant() {
     // Frame should be popped after execution
 }
 
+/// Records the builtins called through it, by name and arguments
+struct Recorder(std::sync::Mutex<Vec<String>>);
+
+impl BuiltinRuntime for Recorder {
+    fn call_builtin(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        let args: Vec<String> = args.iter().map(Value::to_string).collect();
+        self.0.lock().unwrap().push(format!("{}({})", name, args.join(", ")));
+        Ok(Value::Null)
+    }
+
+    fn is_builtin(&self, name: &str) -> bool {
+        name == "print"
+    }
+}
+
+#[test]
+fn test_print_opcode_goes_through_the_runtime() {
+    let mut chunk = create_test_chunk();
+    let idx = chunk.add_constant(Constant::Int(42));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, idx));
+    chunk.emit(Instruction::new1(Opcode::PRINT, 0));
+    chunk.emit(Instruction::new1(Opcode::RET, 0));
+
+    let recorder = std::sync::Arc::new(Recorder(std::sync::Mutex::new(Vec::new())));
+    let mut vm = VM::new();
+    vm.set_runtime(Box::new(recorder.clone()));
+    vm.push_frame(Rc::new(chunk), 0);
+    assert_eq!(vm.run(), Ok(Value::Int(42)));
+    assert_eq!(*recorder.0.lock().unwrap(), ["print(42)"]);
+}
+
 #[test]
 fn test_add_integers() {
     let mut chunk = create_test_chunk();
@@ -230,3 +262,228 @@ fn test_move_register() {
     }
 }
 
+
+#[test]
+fn test_values_equal_arrays() {
+    let a = Value::array(vec![Value::Int(1), Value::Int(2)]);
+    assert!(values_equal(&a, &Value::array(vec![Value::Int(1), Value::Int(2)])));
+    assert!(!values_equal(&a, &Value::array(vec![Value::Int(1), Value::Int(3)])));
+    assert!(!values_equal(&a, &Value::array(vec![Value::Int(1)])));
+}
+
+#[test]
+fn test_values_equal_maps_ignore_order() {
+    let a = Value::map(vec![
+        (Value::Str("x".to_string()), Value::Int(1)),
+        (Value::Str("y".to_string()), Value::Int(2)),
+    ]);
+    let b = Value::map(vec![
+        (Value::Str("y".to_string()), Value::Int(2)),
+        (Value::Str("x".to_string()), Value::Int(1)),
+    ]);
+    let c = Value::map(vec![
+        (Value::Str("x".to_string()), Value::Int(1)),
+        (Value::Str("y".to_string()), Value::Int(3)),
+    ]);
+    assert!(values_equal(&a, &b));
+    assert!(!values_equal(&a, &c));
+}
+
+#[test]
+fn test_double_display_keeps_decimal_point() {
+    assert_eq!(Value::Double(3.0).to_string(), "3.0");
+    assert_eq!(Value::Double(-2.0).to_string(), "-2.0");
+    assert_eq!(Value::Double(2.5).to_string(), "2.5");
+    assert_eq!(Value::Double(1e20).to_string(), "100000000000000000000.0");
+    assert_eq!(Value::Int(3).to_string(), "3");
+    assert_eq!(Value::array(vec![Value::Double(1.0), Value::Int(1)]).to_string(), "[1.0, 1]");
+}
+
+#[test]
+fn test_double_display_bounds_precision() {
+    assert_eq!(Value::Double(1.0 / 3.0).to_string(), "0.333333333333333");
+    assert_eq!(Value::Double(2.0 / 3.0).to_string(), "0.666666666666667");
+    assert_eq!(Value::Double(0.1 + 0.2).to_string(), "0.3");
+    assert_eq!(Value::Double(0.000123).to_string(), "0.000123");
+}
+
+#[test]
+fn test_double_display_non_finite() {
+    assert_eq!(Value::Double(f64::INFINITY).to_string(), "inf");
+    assert_eq!(Value::Double(f64::NEG_INFINITY).to_string(), "-inf");
+    assert_eq!(Value::Double(f64::NAN).to_string(), "nan");
+}
+
+#[test]
+fn test_contains_value() {
+    let xs = Value::array(vec![Value::Int(1), Value::Str("two".to_string())]);
+    assert_eq!(contains_value(&xs, &Value::Int(1)), Ok(true));
+    assert_eq!(contains_value(&xs, &Value::Int(2)), Ok(false));
+
+    // Maps are searched by key, not value
+    let map = Value::map(vec![(Value::Str("k".to_string()), Value::Int(9))]);
+    assert_eq!(contains_value(&map, &Value::Str("k".to_string())), Ok(true));
+    assert_eq!(contains_value(&map, &Value::Int(9)), Ok(false));
+
+    assert_eq!(contains_value(&Value::Str("a1b".to_string()), &Value::Int(1)), Ok(true));
+    assert!(contains_value(&Value::Int(5), &Value::Int(5)).is_err());
+}
+
+#[test]
+fn test_contains_opcode() {
+    let mut chunk = create_test_chunk();
+    let needle = chunk.add_constant(Constant::Str("ell".to_string()));
+    let haystack = chunk.add_constant(Constant::Str("hello".to_string()));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, needle));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 1, haystack));
+    chunk.emit(Instruction::new(Opcode::CONTAINS, 2, 0, 1));
+    chunk.emit(Instruction::new1(Opcode::RET, 2));
+
+    let mut vm = VM::new();
+    vm.push_frame(Rc::new(chunk), 0);
+    assert_eq!(vm.run(), Ok(Value::Bool(true)));
+}
+
+/// `i := 0; while (i < n) i += 1; ret i`, with the condition either fused
+/// into TEST_LT or computed by CMP_LT for JIF
+fn counting_loop(n: i64, fused: bool) -> Chunk {
+    let mut chunk = create_test_chunk();
+    let zero = chunk.add_constant(Constant::Int(0));
+    let limit = chunk.add_constant(Constant::Int(n));
+    let one = chunk.add_constant(Constant::Int(1));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, zero));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 1, limit));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 2, one));
+
+    let loop_start = chunk.ip();
+    let mut exit = if fused {
+        chunk.emit(Instruction::new2(Opcode::TEST_LT, 0, 1));
+        Instruction::new1(Opcode::JMP, 0)
+    } else {
+        chunk.emit(Instruction::new(Opcode::CMP_LT, 3, 0, 1));
+        Instruction::new2(Opcode::JIF, 3, 0)
+    };
+    // Skip the body and the back jump
+    exit.set_offset(2);
+    chunk.emit(exit);
+    chunk.emit(Instruction::new(Opcode::ADD, 0, 0, 2));
+    let mut back = Instruction::new1(Opcode::JMP, 0);
+    back.set_offset(loop_start as i16 - chunk.ip() as i16 - 1);
+    chunk.emit(back);
+    chunk.emit(Instruction::new1(Opcode::RET, 0));
+    chunk
+}
+
+fn run_chunk(chunk: Chunk) -> Result<Value, RuntimeError> {
+    let mut vm = VM::new();
+    vm.push_frame(Rc::new(chunk), 0);
+    vm.run()
+}
+
+#[test]
+fn test_fused_tests_agree_with_comparisons() {
+    let pairs = [
+        (Constant::Int(1), Constant::Int(2)),
+        (Constant::Int(2), Constant::Int(2)),
+        (Constant::Int(3), Constant::Double(2.5)),
+        (Constant::Double(2.0), Constant::Int(2)),
+        (Constant::Str("a".to_string()), Constant::Str("a".to_string())),
+    ];
+    let ops = [
+        (Opcode::TEST_EQ, Opcode::CMP_EQ),
+        (Opcode::TEST_NE, Opcode::CMP_NE),
+        (Opcode::TEST_LT, Opcode::CMP_LT),
+        (Opcode::TEST_LE, Opcode::CMP_LE),
+        (Opcode::TEST_GT, Opcode::CMP_GT),
+        (Opcode::TEST_GE, Opcode::CMP_GE),
+    ];
+    for (test_op, cmp_op) in ops {
+        for (left, right) in &pairs {
+            let mut compare = create_test_chunk();
+            let l = compare.add_constant(left.clone());
+            let r = compare.add_constant(right.clone());
+            let yes = compare.add_constant(Constant::Bool(true));
+            let no = compare.add_constant(Constant::Bool(false));
+            compare.emit(Instruction::new2(Opcode::LOADK, 0, l));
+            compare.emit(Instruction::new2(Opcode::LOADK, 1, r));
+            let mut fused = compare.clone();
+
+            compare.emit(Instruction::new(cmp_op, 2, 0, 1));
+            compare.emit(Instruction::new1(Opcode::RET, 2));
+
+            // The instruction skipped when the test holds overwrites true with false
+            fused.emit(Instruction::new2(Opcode::LOADK, 2, yes));
+            fused.emit(Instruction::new2(test_op, 0, 1));
+            fused.emit(Instruction::new2(Opcode::LOADK, 2, no));
+            fused.emit(Instruction::new1(Opcode::RET, 2));
+
+            let label = format!("{:?} {:?} {:?}", test_op, left, right);
+            assert_eq!(run_chunk(fused), run_chunk(compare), "{}", label);
+        }
+    }
+}
+
+#[test]
+fn test_fused_test_rejects_non_numbers() {
+    let mut chunk = create_test_chunk();
+    let s = chunk.add_constant(Constant::Str("a".to_string()));
+    let n = chunk.add_constant(Constant::Int(1));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, s));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 1, n));
+    chunk.emit(Instruction::new2(Opcode::TEST_LT, 0, 1));
+    chunk.emit(Instruction::new1(Opcode::RET, 0));
+    assert!(matches!(run_chunk(chunk), Err(RuntimeError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_fused_loop_matches_unfused() {
+    for n in [0, 1, 250] {
+        assert_eq!(run_chunk(counting_loop(n, true)), Ok(Value::Int(n)));
+        assert_eq!(run_chunk(counting_loop(n, true)), run_chunk(counting_loop(n, false)));
+    }
+}
+
+/// Compare dispatch cost of the two loop forms:
+/// `cargo test -p brief-vm --release -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_fused_compare_loop() {
+    use std::time::Instant;
+
+    const ITERATIONS: i64 = 1_000_000;
+    for fused in [false, true] {
+        let chunk = counting_loop(ITERATIONS, fused);
+        let start = Instant::now();
+        assert_eq!(run_chunk(chunk), Ok(Value::Int(ITERATIONS)));
+        println!("{} loop: {:?}", if fused { "TEST_LT/JMP" } else { "CMP_LT/JIF" }, start.elapsed());
+    }
+}
+
+#[test]
+fn test_errors_carry_line_table_span() {
+    let at_line = |line| Span::single(FileId(0), Position::new(line, 2));
+    let mut chunk = create_test_chunk();
+    let one = chunk.add_constant(Constant::Int(1));
+    let zero = chunk.add_constant(Constant::Int(0));
+    chunk.mark_span(0, at_line(1));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, one));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 1, zero));
+    chunk.mark_span(2, at_line(2));
+    chunk.emit(Instruction::new(Opcode::DIVI, 2, 0, 1));
+    assert_eq!(chunk.span_at(1), Some(at_line(1)));
+
+    let err = run_chunk(chunk).unwrap_err();
+    assert_eq!(err.without_location(), &RuntimeError::DivisionByZero);
+    assert_eq!(err.span(), Some(at_line(2)));
+    assert_eq!(err.to_string(), "Division by zero at line 2 column 2");
+}
+
+#[test]
+fn test_exit_is_not_located() {
+    let mut chunk = create_test_chunk();
+    let code = chunk.add_constant(Constant::Int(3));
+    chunk.mark_span(0, Span::single(FileId(0), Position::new(1, 1)));
+    chunk.emit(Instruction::new2(Opcode::LOADK, 0, code));
+    chunk.emit(Instruction::new1(Opcode::EXIT, 0));
+    assert_eq!(run_chunk(chunk), Err(RuntimeError::Exit(3)));
+}
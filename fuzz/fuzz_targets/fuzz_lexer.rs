@@ -0,0 +1,12 @@
+#![no_main]
+
+use brief_diagnostic::FileId;
+use brief_lexer::lex;
+use libfuzzer_sys::fuzz_target;
+
+// Lexing never panics: malformed input only produces lex errors
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = lex(source, FileId(0));
+    }
+});
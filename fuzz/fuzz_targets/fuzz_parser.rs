@@ -0,0 +1,15 @@
+#![no_main]
+
+use brief_diagnostic::FileId;
+use brief_lexer::lex;
+use brief_parser::parse;
+use libfuzzer_sys::fuzz_target;
+
+// Parsing never panics, whatever tokens the lexer recovered: malformed
+// input only produces lex and parse errors
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let (tokens, _) = lex(source, FileId(0));
+        let _ = parse(tokens, FileId(0));
+    }
+});
@@ -1,35 +1,54 @@
 use anyhow::Result;
 use brief_diagnostic::FileId;
-use brief_lexer::lex;
-use brief_parser::parse;
-use brief_hir::{lower, emit_bytecode};
-use brief_vm::VM;
-use brief_runtime::Runtime;
-use std::rc::Rc;
+use brief_driver::{compile, Runtime, VM};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 
 pub fn run_source(source: &str) -> Result<()> {
-    let file_id = FileId(0);
-    let (tokens, lex_errors) = lex(source, file_id);
-    if !lex_errors.is_empty() {
-        anyhow::bail!("Lex errors: {:?}", lex_errors);
-    }
+    run_with_runtime(source, Runtime::new())
+}
 
-    let (program, parse_errors) = parse(tokens, file_id);
-    if !parse_errors.is_empty() {
-        anyhow::bail!("Parse errors: {:?}", parse_errors);
-    }
+/// Run `source` like `run_source`, then check that what it printed is
+/// exactly `expected`
+pub fn run_source_expect_output(source: &str, expected: &str) -> Result<()> {
+    let output = SharedBuffer::default();
+    let runtime = Runtime::new();
+    runtime.set_output(Box::new(output.clone()));
+    run_with_runtime(source, runtime)?;
 
-    let hir = lower(program).map_err(|errs| anyhow::anyhow!("HIR errors: {:?}", errs))?;
-    let chunks = emit_bytecode(&hir);
-    if chunks.is_empty() {
-        return Ok(());
+    let printed = output.contents();
+    if printed != expected {
+        anyhow::bail!("Output mismatch\n--- expected\n{}\n--- printed\n{}", expected, printed);
     }
+    Ok(())
+}
 
+/// Run `source` like `run_source`, calling builtins through `runtime`
+pub fn run_with_runtime(source: &str, runtime: Runtime) -> Result<()> {
+    let program = compile(source, FileId(0)).map_err(|errs| anyhow::anyhow!("Compile errors: {:?}", errs))?;
     let mut vm = VM::new();
-    vm.set_runtime(Box::new(Runtime::new()));
-    let chunk = Rc::new(chunks[0].clone());
-    vm.push_frame(chunk, 0);
-    vm.run().map(|_| ())?;
+    vm.set_runtime(Box::new(runtime));
+    program.run(&mut vm)?;
     Ok(())
 }
 
+/// Bytes written through any of its clones, for capturing a runtime's output
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
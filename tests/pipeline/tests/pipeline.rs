@@ -4,10 +4,12 @@ use brief_bytecode::Chunk;
 use brief_diagnostic::FileId;
 use brief_lexer::lex;
 use brief_parser::parse;
-use brief_hir::{lower, emit_bytecode};
-use brief_vm::VM;
-use brief_runtime::Runtime;
-use std::rc::Rc;
+use brief_hir::{lower, emit_bytecode, KnownGlobals};
+use brief_driver::{compile, new_vm, Compiler};
+use brief_vm::{RuntimeError, Value, VM};
+use brief_runtime::{Runtime, RuntimeOptions};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 fn snapshot_bytecode(source: &str) -> Vec<String> {
     let file_id = FileId(0);
@@ -36,25 +38,31 @@ fn format_chunk(chunk: &Chunk) -> String {
     lines.join("\n")
 }
 
-fn run_vm(source: &str) -> Result<(), String> {
+fn run_vm(source: &str) -> Result<Value, String> {
     let snapshots = snapshot_bytecode(source);
     // Keep a snapshot for debugging even if execution succeeds
     assert_snapshot!(format!("bytecode_{}", hash(source.as_bytes())), snapshots.join("\n\n"));
 
+    let program = compile(source, FileId(0)).map_err(|e| format!("HIR error: {:?}", e))?;
+    // Prefer a `test` entry point so helpers can be declared before it
+    program.run_from(&mut new_vm(), "test").map_err(|e| format!("Runtime error: {:?}", e))
+}
+
+/// Run a program that should fail at runtime, returning the error
+fn runtime_error(source: &str) -> RuntimeError {
+    let program = compile(source, FileId(0)).expect("compilation failed");
+    program.run_from(&mut new_vm(), "test").expect_err("expected a runtime error")
+}
+
+/// Lower a program that should be rejected before bytecode generation
+fn lower_error(source: &str) -> String {
     let file_id = FileId(0);
     let (tokens, _) = lex(source, file_id);
     let (program, _) = parse(tokens, file_id);
-    let hir = lower(program).map_err(|e| format!("HIR error: {:?}", e))?;
-    let chunks = emit_bytecode(&hir);
-    if chunks.is_empty() {
-        return Ok(());
+    match lower(program) {
+        Ok(_) => panic!("expected lowering to fail"),
+        Err(errors) => format!("{:?}", errors),
     }
-
-    let mut vm = VM::new();
-    vm.set_runtime(Box::new(Runtime::new()));
-    let chunk = Rc::new(chunks[0].clone());
-    vm.push_frame(chunk, 0);
-    vm.run().map(|_| ()).map_err(|e| format!("Runtime error: {:?}", e))
 }
 
 #[test]
@@ -69,6 +77,758 @@ fn pipeline_handles_builtin_calls() {
 
 #[test]
 fn pipeline_runs_loop() {
-    run_vm("def test()\n\tx := 0\n\twhile (x < 3)\n\t\tx := x + 1\n\tret x").expect("while loop should run");
+    run_vm("def test()\n\tx := 0\n\twhile (x < 3)\n\t\tx = x + 1\n\tret x").expect("while loop should run");
+}
+
+#[test]
+fn pipeline_while_else_runs_only_when_body_is_skipped() {
+    let count = "def count(x)\n\tsteps := 0\n\twhile (x < 3)\n\t\tx++\n\t\tsteps++\n\telse\n\t\tsteps = -1\n\tret steps";
+    // The body runs, so the else does not, even once the condition fails
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret count(0)", count)), Ok(Value::Int(3)));
+    // The condition is false on the first check
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret count(5)", count)), Ok(Value::Int(-1)));
+}
+
+const LOOP_TAIL: &str = "def run(x)\n\tif (x < 1)\n\t\twhile (x < 3)\n\t\t\tx = x + 1\n\telse\n\t\tfor (i := 0; i < 2; i++)\n\t\t\tx = x + i";
+
+#[test]
+fn pipeline_if_tail_ending_in_loops_is_null() {
+    for arg in ["0", "5"] {
+        let source = format!("{}\ndef test()\n\tret run({})", LOOP_TAIL, arg);
+        assert_eq!(run_vm(&source), Ok(Value::Null), "branch taken for run({})", arg);
+    }
+}
+
+#[test]
+fn pipeline_if_tail_branch_ret_returns() {
+    let pick = "def pick(c)\n\tif (c)\n\t\tret 1\n\telse\n\t\twhile (false)\n\t\t\tprint(1)";
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret pick(true)", pick)), Ok(Value::Int(1)));
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret pick(false)", pick)), Ok(Value::Null));
+}
+
+#[test]
+fn pipeline_if_block_shadowing_leaves_outer_unchanged() {
+    let shadowed = "def test()\n\tx := 1\n\tif (true)\n\t\tx := x + 10\n\tret x";
+    assert_eq!(run_vm(shadowed), Ok(Value::Int(1)));
+    let assigned = "def test()\n\tx := 1\n\tif (true)\n\t\tx = x + 10\n\tret x";
+    assert_eq!(run_vm(assigned), Ok(Value::Int(11)));
+}
+
+const GREET: &str = "def greet(name, greeting = \"Hello\")\n\tret greeting + \", \" + name";
+
+#[test]
+fn pipeline_default_param_omitted() {
+    let source = format!("{}\ndef test()\n\tret greet(\"Sam\")", GREET);
+    let value = run_vm(&source).expect("call with default should succeed");
+    assert_eq!(value, Value::Str("Hello, Sam".into()));
+}
+
+#[test]
+fn pipeline_default_param_overridden() {
+    let source = format!("{}\ndef test()\n\tret greet(\"Sam\", \"Hi\")", GREET);
+    let value = run_vm(&source).expect("call overriding default should succeed");
+    assert_eq!(value, Value::Str("Hi, Sam".into()));
+}
+
+#[test]
+fn pipeline_default_param_refers_to_earlier_param() {
+    let value = run_vm("def scale(x, factor = x)\n\tret x * factor\ndef test()\n\tret scale(4)")
+        .expect("default referring to earlier param should succeed");
+    assert_eq!(value, Value::Int(16));
+}
+
+#[test]
+fn pipeline_default_param_arity_error() {
+    let source = format!("{}\ndef test()\n\tret greet()", GREET);
+    let err = run_vm(&source).expect_err("missing required argument should fail");
+    assert!(err.contains("ArityMismatch"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_named_args_reordered() {
+    let source = format!("{}\ndef test()\n\tret greet(greeting: \"Hi\", name: \"Sam\")", GREET);
+    let value = run_vm(&source).expect("named arguments should be reordered");
+    assert_eq!(value, Value::Str("Hi, Sam".into()));
+}
+
+#[test]
+fn pipeline_named_args_after_positional() {
+    let value = run_vm("def sub(a, b, c = 0)\n\tret a - b - c\ndef test()\n\tret sub(10, c: 1, b: 2)")
+        .expect("named arguments may follow positional ones");
+    assert_eq!(value, Value::Int(7));
 }
 
+#[test]
+fn pipeline_named_arg_leaves_default() {
+    let source = format!("{}\ndef test()\n\tret greet(name: \"Sam\")", GREET);
+    let value = run_vm(&source).expect("omitted named parameter should use its default");
+    assert_eq!(value, Value::Str("Hello, Sam".into()));
+}
+
+#[test]
+fn pipeline_named_arg_unknown_label() {
+    let err = lower_error(&format!("{}\ndef test()\n\tret greet(nme: \"Sam\")", GREET));
+    assert!(err.contains("Unknown parameter 'nme'"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_named_arg_duplicate_label() {
+    let err = lower_error(&format!("{}\ndef test()\n\tret greet(\"Sam\", name: \"Al\")", GREET));
+    assert!(err.contains("given more than once"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_calls_function_value() {
+    let value = run_vm("def twice(x)\n\tret x * 2\ndef test()\n\tf := twice\n\tret f(4)")
+        .expect("calling a function stored in a variable should succeed");
+    assert_eq!(value, Value::Int(8));
+}
+
+#[test]
+fn pipeline_exit_halts_with_code() {
+    let err = run_vm("def test()\n\texit(3)\n\tret 1").expect_err("exit should stop the program");
+    assert_eq!(err, "Runtime error: Exit(3)");
+}
+
+const SUM: &str = "def add_all(total, next = null, rest...)\n\tif (next == null)\n\t\tret total\n\tret add_all(total + next, rest...)\ndef sum(nums...)\n\tret add_all(0, nums...)";
+
+#[test]
+fn pipeline_assert_failure_stops_program() {
+    let err = run_vm("def test()\n\tassert(1 < 2)\n\tassert(2 < 1, \"x must be positive\")\n\tret 1")
+        .expect_err("failed assertion should stop the program");
+    assert!(err.contains("AssertionFailed"), "unexpected error: {}", err);
+    assert!(err.contains("x must be positive"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_registered_builtin_flows_into_print() {
+    let source = "def test()\n\tname := get_config(\"name\")\n\tprint(\"hello \" + name)\n\tret get_config(\"missing\") ?? \"default\"";
+    let mut globals = KnownGlobals::default();
+    globals.add_builtin("get_config");
+    let mut compiler = Compiler::with_globals(globals);
+    let file_id = compiler.add_source("<test>", source);
+    let program = compiler.compile(file_id).expect("compilation failed");
+
+    // Registered after the VM has the runtime, through the shared handle
+    let runtime = Arc::new(Runtime::new());
+    let mut vm = VM::new();
+    vm.set_runtime(Box::new(runtime.clone()));
+    let config: HashMap<String, String> = [("name".to_string(), "brief".to_string())].into();
+    runtime.register("get_config", 1, move |args| match args {
+        [Value::Str(key)] => Ok(config.get(key).cloned().map_or(Value::Null, Value::Str)),
+        _ => Err(RuntimeError::CallError("get_config needs a str key".to_string())),
+    });
+    let printed = Arc::new(Mutex::new(Vec::new()));
+    let sink = printed.clone();
+    runtime.register("print", 1, move |args| {
+        sink.lock().unwrap().push(args[0].to_string());
+        Ok(Value::Null)
+    });
+
+    assert_eq!(program.run_from(&mut vm, "test"), Ok(Value::Str("default".to_string())));
+    assert_eq!(*printed.lock().unwrap(), ["hello brief"]);
+}
+
+#[test]
+fn pipeline_match_range_patterns_test_bounds() {
+    let source = "def bucket(n)\n\tmatch(n)\n\t\tcase 0..10\n\t\t\tret 1\n\t\tcase 10..=20, 99\n\t\t\tret 2\n\t\telse\n\t\t\tret 3\ndef test()\n\tret bucket(0) * 10000 + bucket(9) * 1000 + bucket(10) * 100 + bucket(20) * 10 + bucket(21) + bucket(99) * 100000";
+    assert_eq!(run_vm(source), Ok(Value::Int(211223)));
+}
+
+#[test]
+fn pipeline_format_builds_an_aligned_table() {
+    let source = "def row(name, price)\n\tline := format(\"{:<10}|{:>7.2}\", name, price)\n\tprint(line)\n\tret \"\\n\" + line\ndef test()\n\ttable := format(\"{:<10}|{:>7}\", \"item\", \"price\")\n\ttable = table + row(\"apple\", 1.5)\n\ttable = table + row(\"kiwi\", 0.25)\n\ttable = table + row(\"watermelon\", 12)\n\tret table";
+    assert_eq!(
+        run_vm(source),
+        Ok(Value::Str("item      |  price\napple     |   1.50\nkiwi      |   0.25\nwatermelon|  12.00".to_string()))
+    );
+}
+
+#[test]
+fn pipeline_assert_and_panic_errors_carry_their_line() {
+    assert_eq!(run_vm("def test()\n\tassert(true)\n\tassert(1, \"truthy\")\n\tret 1"), Ok(Value::Int(1)));
+
+    let err = runtime_error("def test()\n\tx := 3\n\tassert(x < 2, \"x is too big\")");
+    assert_eq!(err.without_location(), &RuntimeError::AssertionFailed { message: "x is too big".to_string() });
+    assert_eq!(err.span().map(|span| span.start.line), Some(3));
+
+    let err = runtime_error("def check(n)\n\tif (n > 1)\n\t\tpanic(\"n was \" + str(n))\ndef test()\n\tcheck(5)");
+    assert_eq!(err.without_location(), &RuntimeError::Panic { message: "n was 5".to_string() });
+    assert_eq!(err.span().map(|span| span.start.line), Some(3));
+}
+
+#[test]
+fn pipeline_null_coalesce_uses_right_for_null() {
+    let value = run_vm("def test()\n\tret null ?? 5").expect("?? should fall back on null");
+    assert_eq!(value, Value::Int(5));
+}
+
+#[test]
+fn pipeline_null_coalesce_short_circuits() {
+    let value = run_vm("def crash()\n\tassert(false, \"right side evaluated\")\n\tret 0\ndef test()\n\tret 3 ?? crash()")
+        .expect("?? should not evaluate the right side for a non-null left");
+    assert_eq!(value, Value::Int(3));
+}
+
+#[test]
+fn pipeline_variadic_sum_no_args() {
+    let source = format!("{}\ndef test()\n\tret sum()", SUM);
+    assert_eq!(run_vm(&source), Ok(Value::Int(0)));
+}
+
+#[test]
+fn pipeline_variadic_sum_one_arg() {
+    let source = format!("{}\ndef test()\n\tret sum(7)", SUM);
+    assert_eq!(run_vm(&source), Ok(Value::Int(7)));
+}
+
+#[test]
+fn pipeline_variadic_sum_five_args() {
+    let source = format!("{}\ndef test()\n\tret sum(1, 2, 3, 4, 5)", SUM);
+    assert_eq!(run_vm(&source), Ok(Value::Int(15)));
+}
+
+#[test]
+fn pipeline_variadic_requires_fixed_params() {
+    let source = "def tag(label, rest...)\n\tret label\ndef test()\n\tret tag()";
+    let err = run_vm(source).expect_err("missing fixed parameter should fail");
+    assert!(err.contains("ArityMismatch"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_map_filter_reduce() {
+    let source = "def list(xs...)\n\tret xs\ndef square(x)\n\tret x * x\ndef odd(x)\n\tret x % 2 == 1\ndef add(acc, x)\n\tret acc + x\ndef test()\n\tsquares := map(list(1, 2, 3, 4), square)\n\tret reduce(filter(squares, odd), add, 0)";
+    assert_eq!(run_vm(source), Ok(Value::Int(10)));
+}
+
+#[test]
+fn pipeline_parse_and_type_builtins() {
+    let source = "def test()\n\tn := parseInt(\"40\") + 2\n\tret type(n) + \":\" + str(n)";
+    assert_eq!(run_vm(source), Ok(Value::Str("int:42".into())));
+}
+
+#[test]
+fn pipeline_double_to_string_keeps_decimal() {
+    let source = "def test()\n\tret \"half: \" + 4 / 2 + \", \" + str(1 / 3.0)";
+    assert_eq!(run_vm(source), Ok(Value::Str("half: 2.0, 0.333333333333333".into())));
+}
+
+const LIST: &str = "def list(xs...)\n\tret xs";
+
+#[test]
+fn pipeline_array_equality_is_structural() {
+    let source = format!("{}\ndef test()\n\tret list(1, 2) == list(1, 2)", LIST);
+    assert_eq!(run_vm(&source), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_array_inequality() {
+    let source = format!("{}\ndef test()\n\tret list(1, 2) == list(1, 3)", LIST);
+    assert_eq!(run_vm(&source), Ok(Value::Bool(false)));
+    let source = format!("{}\ndef test()\n\tret list(1, 2) != list(1, 3)", LIST);
+    assert_eq!(run_vm(&source), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_class_field_default() {
+    let source = "cls Dog\n\tint age = 3\n\tobj Dog(name)\n\t\tprint(name)\ndef test()\n\td := Dog(\"rex\")\n\tret d.age";
+    assert_eq!(run_vm(source), Ok(Value::Int(3)));
+}
+
+#[test]
+fn pipeline_class_field_assignment() {
+    let source = "cls Dog\n\tint age = 3\n\tobj Dog(name)\ndef test()\n\td := Dog(\"rex\")\n\td.age = d.age + 1\n\tret d.name + str(d.age)";
+    assert_eq!(run_vm(source), Ok(Value::Str("rex4".into())));
+}
+
+const OWNERS: &str = "cls Person\n\tobj Person(name, pet)\ncls Pet\n\tobj Pet(name)";
+
+#[test]
+fn pipeline_safe_member_chain() {
+    let source = format!("{}\ndef test()\n\tp := Person(\"Sam\", Pet(\"rex\"))\n\tret p?.pet?.name", OWNERS);
+    assert_eq!(run_vm(&source), Ok(Value::Str("rex".into())));
+}
+
+#[test]
+fn pipeline_safe_member_chain_stops_at_null() {
+    let source = format!("{}\ndef test()\n\tp := Person(\"Sam\", null)\n\tret p?.pet?.name ?? \"none\"", OWNERS);
+    assert_eq!(run_vm(&source), Ok(Value::Str("none".into())));
+}
+
+#[test]
+fn pipeline_safe_member_on_null() {
+    assert_eq!(run_vm("def test()\n\tp := null\n\tret p?.name"), Ok(Value::Null));
+}
+
+#[test]
+fn pipeline_safe_member_on_null_literal() {
+    assert_eq!(run_vm("def test()\n\tret null?.x"), Ok(Value::Null));
+}
+
+#[test]
+fn pipeline_safe_member_reads_field() {
+    let source = format!("{}\ndef test()\n\tret Pet(\"rex\")?.name", OWNERS);
+    assert_eq!(run_vm(&source), Ok(Value::Str("rex".into())));
+}
+
+const ANIMALS: &str = "cls Dog\n\tobj Dog(name)\n\tobj def speak()\n\t\tret \"woof\"\n\tobj def describe()\n\t\tret obj.name + \" says \" + obj.speak()\ncls Puppy : Dog\n\tobj Puppy(name)\n\t\tsuper(name)\n\tobj def speak()\n\t\tret \"yip\"";
+
+#[test]
+fn pipeline_overridden_method_uses_child() {
+    let source = format!("{}\ndef test()\n\tp := Puppy(\"rex\")\n\tret p.speak()", ANIMALS);
+    assert_eq!(run_vm(&source), Ok(Value::Str("yip".into())));
+}
+
+#[test]
+fn pipeline_inherited_method_falls_back_to_parent() {
+    let source = format!("{}\ndef test()\n\tp := Puppy(\"rex\")\n\tret p.describe()", ANIMALS);
+    assert_eq!(run_vm(&source), Ok(Value::Str("rex says yip".into())));
+}
+
+#[test]
+fn pipeline_implicit_super_initializes_parent_fields() {
+    let source = "cls Animal\n\tint legs = 4\ncls Bird extends Animal\n\tobj def legCount()\n\t\tret obj.legs\ndef test()\n\tb := Bird()\n\tret b.legCount()";
+    assert_eq!(run_vm(source), Ok(Value::Int(4)));
+}
+
+#[test]
+fn pipeline_unknown_method_is_runtime_error() {
+    let source = format!("{}\ndef test()\n\tp := Puppy(\"rex\")\n\tret p.fetch()", ANIMALS);
+    let err = run_vm(&source).unwrap_err();
+    assert!(err.contains("UndefinedMethod"), "unexpected error: {}", err);
+}
+
+const MATH: &str = "cls Math\n\tconst TWO := 2\n\tdef square(x)\n\t\tret x * x";
+
+#[test]
+fn pipeline_static_method_call() {
+    let source = format!("{}\ndef test()\n\tret Math.square(4)", MATH);
+    assert_eq!(run_vm(&source), Ok(Value::Int(16)));
+}
+
+#[test]
+fn pipeline_class_constant() {
+    let source = format!("{}\ndef test()\n\tret Math.square(Math.TWO) + Math.TWO", MATH);
+    assert_eq!(run_vm(&source), Ok(Value::Int(6)));
+}
+
+#[test]
+fn pipeline_static_method_inherited() {
+    let source = format!("{}\ncls Geometry : Math\n\tdef cube(x)\n\t\tret x * Geometry.square(x)\ndef test()\n\tret Geometry.cube(3)", MATH);
+    assert_eq!(run_vm(&source), Ok(Value::Int(27)));
+}
+
+#[test]
+fn pipeline_module_constants_do_not_collide_with_locals() {
+    let source = "const A := 10\nconst B := 32\ndef test()\n\tx := 1\n\tret A + B + x";
+    assert_eq!(run_vm(source), Ok(Value::Int(43)));
+}
+
+#[test]
+fn pipeline_module_variable_shared_between_functions() {
+    let source = "count := 0\ndef bump()\n\tcount += 1\ndef test()\n\tbump()\n\tbump()\n\tcount = count * 10\n\tret count";
+    assert_eq!(run_vm(source), Ok(Value::Int(20)));
+}
+
+#[test]
+fn pipeline_mutual_recursion_between_hoisted_functions() {
+    let source = "def test()\n\tret is_even(10) && is_odd(7)\ndef is_even(n)\n\tif (n == 0)\n\t\tret true\n\tret is_odd(n - 1)\ndef is_odd(n)\n\tif (n == 0)\n\t\tret false\n\tret is_even(n - 1)";
+    assert_eq!(run_vm(source), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_in_operator() {
+    let source = format!("{}\ndef test()\n\tret 2 in list(1, 2, 3)", LIST);
+    assert_eq!(run_vm(&source), Ok(Value::Bool(true)));
+    let source = format!("{}\ndef test()\n\tret 4 in list(1, 2, 3)", LIST);
+    assert_eq!(run_vm(&source), Ok(Value::Bool(false)));
+    assert_eq!(run_vm("def test()\n\tret \"ell\" in \"hello\""), Ok(Value::Bool(true)));
+    assert_eq!(run_vm("def test()\n\tret \"xyz\" in \"hello\""), Ok(Value::Bool(false)));
+}
+
+#[test]
+fn pipeline_for_in_inclusive_range() {
+    let source = "def test()\n\ttotal := 0\n\tfor (i in 1..=5)\n\t\ttotal += i\n\tret total";
+    assert_eq!(run_vm(source), Ok(Value::Int(15)));
+}
+
+#[test]
+fn pipeline_for_in_exclusive_range_with_computed_end() {
+    // The end is evaluated once, before the first iteration
+    let source = "def sum_below(n)\n\ttotal := 0\n\tfor (i in 0..n)\n\t\ttotal += i\n\t\tn = 0\n\tret total\ndef test()\n\tret sum_below(4)";
+    assert_eq!(run_vm(source), Ok(Value::Int(6)));
+}
+
+#[test]
+fn pipeline_range_outside_loop_is_an_array() {
+    assert_eq!(run_vm("def test()\n\txs := 2..5\n\tret 5 in xs"), Ok(Value::Bool(false)));
+    assert_eq!(run_vm("def test()\n\tret 5 in 1..=5"), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_for_in_range_call_sums() {
+    let source = "def test()\n\ttotal := 0\n\tfor (i in range(1, 101))\n\t\ttotal += i\n\tret total";
+    assert_eq!(run_vm(source), Ok(Value::Int(5050)));
+    let source = "def test()\n\ttotal := 0\n\tfor (i in range(4))\n\t\ttotal = total * 10 + i\n\tret total";
+    assert_eq!(run_vm(source), Ok(Value::Int(123)));
+}
+
+#[test]
+fn pipeline_for_in_range_call_counts_down_and_skips_empty_ranges() {
+    let source = "def test()\n\ts := \"\"\n\tfor (i in range(10, 0, -3))\n\t\ts = s + str(i)\n\tfor (i in range(5, 1))\n\t\ts = s + \"up\"\n\tfor (i in range(1, 5, -1))\n\t\ts = s + \"down\"\n\tret s";
+    assert_eq!(run_vm(source), Ok(Value::Str("10741".to_string())));
+    // `continue` still steps the index
+    let source = "def test()\n\tn := 0\n\tfor (i in range(0, 10, 2))\n\t\tif (i == 4)\n\t\t\tcontinue\n\t\tn += i\n\tret n";
+    assert_eq!(run_vm(source), Ok(Value::Int(16)));
+}
+
+#[test]
+fn pipeline_range_call_checks_its_bounds() {
+    let error = runtime_error("def test()\n\tfor (i in range(0, 3, 0))\n\t\tprint(i)");
+    assert_eq!(error.without_location(), &RuntimeError::CallError("range step cannot be zero".to_string()));
+    let error = runtime_error("def test()\n\tfor (i in range(\"3\"))\n\t\tprint(i)");
+    assert!(matches!(error.without_location(), RuntimeError::TypeMismatch { .. }), "{:?}", error);
+}
+
+#[test]
+fn pipeline_range_call_outside_loop_and_user_defined_range() {
+    assert_eq!(run_vm("def test()\n\tret str(range(2, 8, 2))"), Ok(Value::Str("[2, 4, 6]".to_string())));
+    // A program's own `range` is called like any function
+    assert_eq!(run_vm("def range(n)\n\tret n * 2\ndef test()\n\tret range(3)"), Ok(Value::Int(6)));
+}
+
+#[test]
+fn pipeline_fused_conditions_match_materialized_comparisons() {
+    // `if (i OP 5)` compiles to a TEST_* instruction; `c := i OP 5` computes
+    // the boolean first. Both must count the same iterations
+    // Iterations out of 0..10 for which `i OP 5` holds
+    for (op, expected) in [("==", 1), ("!=", 9), ("<", 5), ("<=", 6), (">", 4), (">=", 5)] {
+        let source = format!(
+            "def test()\n\tfused := 0\n\tplain := 0\n\ti := 0\n\twhile (i < 10)\n\t\tif (i {op} 5)\n\t\t\tfused++\n\t\tc := i {op} 5\n\t\tif (c)\n\t\t\tplain++\n\t\ti++\n\tret fused * 100 + plain"
+        );
+        assert_eq!(run_vm(&source), Ok(Value::Int(expected * 101)), "for {}", op);
+    }
+}
+
+#[test]
+fn pipeline_fused_loop_conditions_with_mixed_operands() {
+    // Counts down against a double bound, and stops when a string matches
+    let source = "def count(n)\n\tsteps := 0\n\twhile (n >= 0.5)\n\t\tn--\n\t\tsteps++\n\tret steps\ndef test()\n\tret count(3)";
+    assert_eq!(run_vm(source), Ok(Value::Int(3)));
+    let source = "def test()\n\ts := \"\"\n\twhile (s != \"aaa\")\n\t\ts = s + \"a\"\n\tret s";
+    assert_eq!(run_vm(source), Ok(Value::Str("aaa".into())));
+}
+
+/// Counts its calls, to show whether a short-circuited operand ran
+const TOUCH: &str = "calls := 0\ndef touch(v)\n\tcalls += 1\n\tret v";
+
+fn run_logic(expr: &str) -> Result<Value, String> {
+    run_vm(&format!("{}\ndef test()\n\tret {}", TOUCH, expr))
+}
+
+fn calls_after(expr: &str) -> Result<Value, String> {
+    run_vm(&format!("{}\ndef test()\n\tr := {}\n\tret calls", TOUCH, expr))
+}
+
+#[test]
+fn pipeline_logical_operators_return_bools() {
+    assert_eq!(run_logic("5 && 3"), Ok(Value::Bool(true)));
+    assert_eq!(run_logic("null || touch(\"default\")"), Ok(Value::Bool(true)));
+    assert_eq!(run_logic("touch(\"set\") || \"default\""), Ok(Value::Bool(true)));
+    assert_eq!(run_logic("touch(null) && 1"), Ok(Value::Bool(false)));
+    assert_eq!(run_logic("touch(1) && touch(null)"), Ok(Value::Bool(false)));
+    assert_eq!(run_logic("touch(false) || null"), Ok(Value::Bool(false)));
+    // 0 and "" are truthy
+    assert_eq!(run_logic("touch(0) || 5"), Ok(Value::Bool(true)));
+    assert_eq!(run_logic("touch(\"\") && 5"), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_logical_operators_short_circuit() {
+    assert_eq!(calls_after("false && touch(1)"), Ok(Value::Int(0)));
+    assert_eq!(calls_after("null && touch(1)"), Ok(Value::Int(0)));
+    assert_eq!(calls_after("touch(true) || touch(1)"), Ok(Value::Int(1)));
+    assert_eq!(calls_after("touch(0) || touch(1)"), Ok(Value::Int(1)));
+    // 0 does not stop &&, unlike false
+    assert_eq!(calls_after("touch(0) && touch(1)"), Ok(Value::Int(2)));
+}
+
+#[test]
+fn pipeline_logical_operator_chains() {
+    assert_eq!(run_logic("null || false || touch(\"x\")"), Ok(Value::Bool(true)));
+    assert_eq!(run_logic("touch(1) && null && touch(2)"), Ok(Value::Bool(false)));
+    assert_eq!(run_logic("false && touch(1) || touch(\"fallback\")"), Ok(Value::Bool(true)));
+    assert_eq!(calls_after("false && touch(1) || touch(2) || touch(3)"), Ok(Value::Int(1)));
+    assert_eq!(run_logic("touch(1) && (touch(null) || touch(3))"), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn pipeline_repeat_runs_body_count_times() {
+    let source = "def test()\n\tn := 0\n\trepeat 2 + 3\n\t\tn += 2\n\tret n";
+    assert_eq!(run_vm(source), Ok(Value::Int(10)));
+    // A count of zero or less skips the body
+    let source = "def test()\n\tn := 0\n\trepeat -1\n\t\tn++\n\tret n";
+    assert_eq!(run_vm(source), Ok(Value::Int(0)));
+}
+
+#[test]
+fn pipeline_repeat_rejects_non_integer_count() {
+    let source = "def test()\n\trepeat 2.5\n\t\tprint(1)";
+    let err = run_vm(source).unwrap_err();
+    assert!(err.contains("TypeMismatch"), "{}", err);
+}
+
+const PICK: &str = "def pick(c, a, b)\n\tx := c ? a : b\n\tret x";
+
+#[test]
+fn pipeline_ternary_takes_either_branch() {
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret pick(true, 1, 2)", PICK)), Ok(Value::Int(1)));
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret pick(false, 1, 2)", PICK)), Ok(Value::Int(2)));
+    assert_eq!(run_vm(&format!("{}\ndef test()\n\tret pick(null, 1, 2)", PICK)), Ok(Value::Int(2)));
+}
+
+#[test]
+fn pipeline_nested_ternary() {
+    let source = "def sign(n)\n\tret n < 0 ? -1 : n == 0 ? 0 : 1\ndef test()\n\tret sign(-5) * 100 + sign(0) * 10 + sign(7)";
+    assert_eq!(run_vm(source), Ok(Value::Int(-99)));
+    let source = "def grade(n)\n\tret n >= 5 ? n >= 8 ? \"high\" : \"mid\" : \"low\"\ndef test()\n\tret grade(9) + grade(6) + grade(1)";
+    assert_eq!(run_vm(source), Ok(Value::Str("highmidlow".into())));
+}
+
+#[test]
+fn pipeline_ternary_branches_with_different_register_pressure() {
+    // The then-branch needs several temporaries, the else-branch none
+    let source = "def f(c, a, b)\n\tx := c ? (a + b) * (a - b) + a * b : a\n\tret x + 1\ndef test()\n\tret f(true, 5, 3) * 1000 + f(false, 5, 3)";
+    assert_eq!(run_vm(source), Ok(Value::Int(32006)));
+}
+
+#[test]
+fn pipeline_ternary_bytecode() {
+    let source = "def sign(n)\n\tret n < 0 ? -1 : n == 0 ? 0 : 1";
+    assert_snapshot!("ternary_nested", snapshot_bytecode(source).join("\n\n"));
+}
+
+#[test]
+fn pipeline_long_loop_body_reuses_registers() {
+    let mut source = String::from("def run(n)\n\ttotal := 0\n\tfor (i := 0; i < n; i++)\n");
+    let mut expected = 0i64;
+    for k in 0..30 {
+        source.push_str(&format!("\t\tstep{k} := (i + {k}) * (i - {k}) + i * {k}\n\t\ttotal = total + step{k}\n"));
+        expected += (0..4).map(|i| (i + k) * (i - k) + i * k).sum::<i64>();
+    }
+    source.push_str("\tret total\ndef test()\n\tret run(4)");
+    assert_eq!(run_vm(&source), Ok(Value::Int(expected)));
+}
+
+#[test]
+fn pipeline_loop_locals_survive_temporaries() {
+    // `last` is declared inside the body and read on the next iteration
+    // after the condition and other statements have reused temporaries
+    let source = "def test()\n\tprev := 0\n\tsum := 0\n\ti := 0\n\twhile (i * 2 + 1 < 12)\n\t\tlast := prev + i * 3\n\t\tsum = sum + last * 2 - prev\n\t\tprev = last\n\t\ti++\n\tret sum * 1000 + prev";
+    assert_eq!(run_vm(source), Ok(Value::Int(150045)));
+}
+
+#[test]
+fn pipeline_runtime_error_reports_line() {
+    let source = "def test()\n\tx := 10\n\ty := x - 10\n\tprint(x)\n\tret x / y";
+    let err = runtime_error(source);
+    assert_eq!(err.without_location(), &RuntimeError::DivisionByZero);
+    let span = err.span().expect("error should carry a location");
+    assert_eq!((span.start.line, span.start.column), (5, 6));
+}
+
+#[test]
+fn pipeline_runtime_error_in_callee_reports_callee_line() {
+    let source = "def half(n)\n\tret n / 0\ndef test()\n\ta := 1\n\tret half(a)";
+    let span = runtime_error(source).span().expect("error should carry a location");
+    assert_eq!(span.start.line, 2);
+}
+
+const DESCRIBE: &str = "def describe(x)\n\tmatch(type(x))\n\t\tcase \"int\", \"dub\"\n\t\t\tret \"number \" + str(x)\n\t\tcase \"str\", \"array\"\n\t\t\tret \"length \" + str(len(x))\n\t\telse\n\t\t\tret type(x)\ncls Box\n\tint size = 1";
+
+#[test]
+fn pipeline_type_dispatch_in_match() {
+    for (arg, expected) in [
+        ("3", "number 3"),
+        ("2.0", "number 2.0"),
+        ("\"héllo\"", "length 5"),
+        ("1..3", "length 2"),
+        ("null", "null"),
+        ("Box()", "object:Box"),
+    ] {
+        let source = format!("{}\ndef test()\n\tret describe({})", DESCRIBE, arg);
+        assert_eq!(run_vm(&source), Ok(Value::Str(expected.to_string())), "describe({})", arg);
+    }
+}
+
+#[test]
+fn pipeline_if_else_statement_runs_else_branch() {
+    let source = "def pick(x)\n\ts := \"\"\n\tif (x == 1)\n\t\ts = \"one\"\n\telse\n\t\ts = \"other\"\n\tret s\ndef test()\n\tret pick(1) + \"-\" + pick(2)";
+    assert_eq!(run_vm(source), Ok(Value::Str("one-other".to_string())));
+}
+
+#[test]
+fn pipeline_match_fall_runs_into_next_case() {
+    let source = "def steps(x)\n\ts := \"\"\n\tmatch(x)\n\t\tcase 1\n\t\t\ts += \"a\"\n\t\t\tfall\n\t\tcase 2\n\t\t\ts += \"b\"\n\t\tcase 3\n\t\t\ts += \"c\"\n\t\t\tfall\n\t\telse\n\t\t\ts += \"z\"\n\tret s\ndef test()\n\tret steps(1) + \"-\" + steps(2) + \"-\" + steps(3) + \"-\" + steps(4)";
+    assert_eq!(run_vm(source), Ok(Value::Str("ab-b-cz-z".to_string())));
+}
+
+#[test]
+fn pipeline_string_builtins_chain() {
+    let source = "def test()\n\tname := upper(trim(\"  brief lang \"))\n\twords := split(name, \" \")\n\tret replace(name, \"LANG\", \"\") + str(len(words)) + str(index_of(name, \"LANG\")) + substring(name, 1, 3)";
+    assert_eq!(run_vm(source), Ok(Value::Str("BRIEF 26RI".to_string())));
+}
+
+#[test]
+fn pipeline_interpolates_expressions() {
+    let source = "cls P\n\tint x = 3\ndef test()\n\ta := 2\n\tb := 5\n\tp := P()\n\tret \"sum is &(a + b), &a&&&b, x=&p.x, half &(b / 2.0)\"";
+    assert_eq!(run_vm(source), Ok(Value::Str("sum is 7, 2&5, x=3, half 2.5".to_string())));
+}
+
+#[test]
+fn pipeline_interpolates_lone_value() {
+    assert_eq!(run_vm("def test()\n\tn := 4\n\tret \"&n\""), Ok(Value::Str("4".to_string())));
+}
+
+#[test]
+fn pipeline_array_builtins_build_sort_and_join() {
+    let source = "def test()\n\twords := split(\"pear fig apple\", \" \")\n\tpush(words, \"kiwi\")\n\tinsert(words, 0, \"plum\")\n\tremove(words, index_of(words, \"fig\"))\n\tsort(words)\n\tlast := pop(words)\n\treverse(words)\n\tret join(words, \", \") + \" / \" + last";
+    assert_eq!(run_vm(source), Ok(Value::Str("pear, kiwi, apple / plum".to_string())));
+}
+
+#[test]
+fn pipeline_sort_with_inconsistent_comparator() {
+    // A comparator that isn't a total order leaves the order unspecified,
+    // but must not crash the interpreter
+    let source = "def bad(a, b)\n\tret random_int(-1, 1)\ndef add(a, b)\n\tret a + b\ndef test()\n\tseed_random(1)\n\txs := range(0, 200)\n\tsort(xs, bad)\n\tret str(len(xs)) + \" \" + str(reduce(xs, add, 0))";
+    assert_eq!(run_vm(source), Ok(Value::Str("200 19900".to_string())));
+}
+
+#[test]
+fn pipeline_sort_mixed_types_is_runtime_error() {
+    let source = "def test()\n\titems := split(\"b a\", \" \")\n\tpush(items, 1)\n\tsort(items)";
+    let err = runtime_error(source);
+    assert!(matches!(err.without_location(), RuntimeError::TypeMismatch { .. }), "got {:?}", err);
+}
+
+#[test]
+fn pipeline_counts_word_frequencies_with_maps() {
+    let source = "def test()\n\twords := split(\"the cat saw the dog and the cat\", \" \")\n\treverse(words)\n\tcounts := map_of()\n\twhile (len(words) > 0)\n\t\tword := pop(words)\n\t\tput(counts, word, get(counts, word, 0) + 1)\n\tremove(counts, \"and\")\n\tcounts = merge(counts, map_of(\"bird\", 0))\n\tret join(keys(counts), \",\") + \" \" + join(values(counts), \",\") + \" \" + str(has(counts, \"and\"))";
+    assert_eq!(run_vm(source), Ok(Value::Str("the,cat,saw,dog,bird 3,2,1,1,0 false".to_string())));
+}
+
+#[test]
+fn pipeline_bool_cast_to_int() {
+    assert_eq!(run_vm("def test()\n\tret true int + 1"), Ok(Value::Int(2)));
+    assert_eq!(run_vm("def test()\n\tflag := false\n\tret flag dub + 0.5"), Ok(Value::Double(0.5)));
+}
+
+#[test]
+fn pipeline_bool_arithmetic_without_cast_is_runtime_error() {
+    let err = runtime_error("def test()\n\tret true + 1");
+    assert!(matches!(err.without_location(), RuntimeError::TypeMismatch { .. }), "got {:?}", err);
+}
+
+#[test]
+fn pipeline_file_round_trip() {
+    // Relative to the package directory, so the bytecode snapshot is stable
+    let path = "pipeline_file_round_trip.txt";
+    let _ = std::fs::remove_file(path);
+    let source = format!(
+        "def test()\n\tbefore := file_exists(\"{0}\")\n\twrite_file(\"{0}\", \"one\\n\")\n\tappend_file(\"{0}\", \"two\")\n\tret str(before) + \" \" + str(file_exists(\"{0}\")) + \" \" + read_file(\"{0}\")",
+        path
+    );
+    let result = run_vm(&source);
+    let _ = std::fs::remove_file(path);
+    assert_eq!(result, Ok(Value::Str("false true one\ntwo".to_string())));
+}
+
+#[test]
+fn pipeline_read_missing_file_is_io_error() {
+    let err = runtime_error("def test()\n\tret read_file(\"no/such/dir/file.txt\")");
+    match err.without_location() {
+        RuntimeError::IoError(message) => assert!(message.contains("no/such/dir/file.txt"), "{}", message),
+        other => panic!("expected IoError, got {:?}", other),
+    }
+}
+
+const ADD: &str = "def add(a, b)\n\tret a + b";
+
+#[test]
+fn pipeline_call_result_as_argument() {
+    let source = format!("{}\ndef test()\n\tret add(add(1, 2), add(3, add(4, 5)))", ADD);
+    assert_eq!(run_vm(&source), Ok(Value::Int(15)));
+    let source = format!("{}\ndef test()\n\tx := 7\n\tret add(x, add(x, x)) * add(1, 1)", ADD);
+    assert_eq!(run_vm(&source), Ok(Value::Int(42)));
+}
+
+#[test]
+fn pipeline_call_result_as_builtin_argument() {
+    let source = format!("{}\ndef test()\n\tret str(add(1, 2)) + str(len(str(add(40, 2))))", ADD);
+    assert_eq!(run_vm(&source), Ok(Value::Str("32".to_string())));
+}
+
+#[test]
+fn pipeline_nested_calls_with_named_args_and_receivers() {
+    let source = format!(
+        "{}\ndef pick(a, b = 10, c = 100)\n\tret a * 10000 + b * 100 + c\ndef test()\n\tret pick(add(1, 1), c: add(2, 3), b: add(3, 4))",
+        ADD
+    );
+    assert_eq!(run_vm(&source), Ok(Value::Int(20705)));
+    let source = format!(
+        "{}\ncls Box\n\tobj Box(v)\n\t\tobj.v = v\n\tobj def add(a, b)\n\t\tret obj.v + a + b\ndef test()\n\tb := Box(1)\n\tret b.add(add(1, 2), b.add(add(3, 4), 5))",
+        ADD
+    );
+    assert_eq!(run_vm(&source), Ok(Value::Int(17)));
+    let source = format!("{}\ndef test()\n\tret Math.square(Math.square(2))", MATH);
+    assert_eq!(run_vm(&source), Ok(Value::Int(16)));
+}
+
+#[test]
+fn pipeline_output_of_print_variants() {
+    let source = "def main()\n\tprint(\"a\", 1, 2.5, true)\n\tprints(\"no\", \"newline\")\n\tprints(\"!\")\n\tprintln(format(\"{:>4}|{:<3}|\", 7, \"x\"))";
+    brief_pipeline_tests::run_source_expect_output(source, "a 1 2.5 true\nno newline!   7|x  |\n").unwrap();
+}
+
+#[test]
+fn pipeline_output_of_loops_and_interpolation() {
+    let source = "def main()\n\tfor (i in 1..=15)\n\t\tif (i % 15 == 0)\n\t\t\tprint(\"fizzbuzz\")\n\t\telse if (i % 5 == 0)\n\t\t\tprint(\"buzz\")\n\t\telse if (i % 3 == 0)\n\t\t\tprint(\"fizz\")\n\t\telse\n\t\t\tprint(\"n=&i\")";
+    let expected = "n=1\nn=2\nfizz\nn=4\nbuzz\nfizz\nn=7\nn=8\nfizz\nbuzz\nn=11\nfizz\nn=13\nn=14\nfizzbuzz\n";
+    brief_pipeline_tests::run_source_expect_output(source, expected).unwrap();
+}
+
+#[test]
+fn pipeline_output_of_methods_and_inheritance() {
+    let source = "def main()\n\tpup := puppy(\"rex\")\n\tpup.greet()\n\tpup.bark()\ncls dog\n\tobj dog(name)\n\tobj def greet()\n\t\tprint(\"&obj.name says hi\")\n\tobj def bark()\n\t\tprint(\"woof\")\ncls puppy : dog\n\tobj puppy(name)\n\t\tsuper(name)\n\tobj def bark()\n\t\tprint(\"yip\")";
+    brief_pipeline_tests::run_source_expect_output(source, "rex says hi\nyip\n").unwrap();
+}
+
+#[test]
+fn pipeline_labeled_break_leaves_outer_loop() {
+    let source = "def main()\n\touter: for (i := 0; i < 3; i++)\n\t\tj := 0\n\t\twhile (true)\n\t\t\tif (i == 1 && j == 2)\n\t\t\t\tbreak outer\n\t\t\tif (j == 3)\n\t\t\t\tbreak\n\t\t\tprintln(i, j)\n\t\t\tj++\n\tprintln(\"done\")";
+    brief_pipeline_tests::run_source_expect_output(source, "0 0\n0 1\n0 2\n1 0\n1 1\ndone\n").unwrap();
+}
+
+#[test]
+fn pipeline_continue_runs_loop_increment() {
+    // `continue` in a counting loop still steps its index
+    let source = "def main()\n\tfor (i := 0; i < 5; i++)\n\t\tif (i % 2 == 0)\n\t\t\tcontinue\n\t\tprint(i)\n\tfor (k in 0..4)\n\t\tif (k == 1)\n\t\t\tcontinue\n\t\tprint(k)\n\tn := 0\n\trow: repeat 2\n\t\trepeat 3\n\t\t\tn++\n\t\t\tcontinue row\n\tprint(n)";
+    brief_pipeline_tests::run_source_expect_output(source, "1\n3\n0\n2\n3\n2\n").unwrap();
+}
+
+#[test]
+fn pipeline_exit_respects_runtime_options() {
+    let source = "def main()\n\texit(3)";
+    let err = brief_pipeline_tests::run_source(source).unwrap_err();
+    assert!(matches!(err.downcast_ref::<RuntimeError>(), Some(RuntimeError::Exit(3))), "{:?}", err);
+
+    let runtime = Runtime::with_options(RuntimeOptions { allow_exit: false, ..RuntimeOptions::default() });
+    let err = brief_pipeline_tests::run_with_runtime(source, runtime).unwrap_err();
+    assert!(err.to_string().contains("exit: exiting disabled"), "unexpected error: {}", err);
+}
+
+#[test]
+fn pipeline_output_mismatch_is_an_error() {
+    let err = brief_pipeline_tests::run_source_expect_output("def main()\n\tprint(1)", "2\n").unwrap_err();
+    assert!(err.to_string().contains("--- printed\n1\n"), "unexpected error: {}", err);
+}